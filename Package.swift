@@ -20,8 +20,7 @@ let package = Package(
             linkerSettings: [
                 .unsafeFlags([
                     "-L", "target/release",
-                    "-lreaper_cpu_monitor",
-                    "-lreaper_memory_monitor"
+                    "-lreaper"
                 ])
             ]
         ),
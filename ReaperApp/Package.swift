@@ -22,12 +22,7 @@ let package = Package(
             linkerSettings: [
                 .unsafeFlags([
                     "-L../target/release",
-                    "-lreaper_core",
-                    "-lreaper_cpu_monitor",
-                    "-lreaper_memory_monitor",
-                    "-lreaper_hardware_monitor",
-                    "-lreaper_network_monitor",
-                    "-lreaper_disk_monitor",
+                    "-lreaper",
                     "-framework", "Security",
                     "-framework", "CoreFoundation",
                     "-framework", "IOKit"
@@ -24,8 +24,7 @@ let package = Package(
             linkerSettings: [
                 .unsafeFlags([
                     "-L../target/release",
-                    "-lreaper_cpu_monitor",
-                    "-lreaper_disk_monitor",
+                    "-lreaper",
                     "-framework", "IOKit",
                     "-framework", "CoreFoundation"
                 ])
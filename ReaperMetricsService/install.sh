@@ -48,20 +48,11 @@ fi
 echo "Fixing library paths..."
 PROJECT_ROOT="$SCRIPT_DIR/.."
 
-# Fix all possible paths for cpu_monitor
+# Fix all possible paths for libreaper
 for old_path in \
-    "$PROJECT_ROOT/target/release/deps/libreaper_cpu_monitor.dylib" \
-    "$PROJECT_ROOT/target/release/libreaper_cpu_monitor.dylib" \
-    "/Volumes/WD_BLACK/repos/BelowZero/ReaperSuite/ReaperDesktop/target/release/deps/libreaper_cpu_monitor.dylib"; do
-    sudo install_name_tool -change "$old_path" /usr/local/lib/libreaper_cpu_monitor.dylib "$INSTALL_BIN" 2>/dev/null || true
-done
-
-# Fix all possible paths for disk_monitor
-for old_path in \
-    "$PROJECT_ROOT/target/release/deps/libreaper_disk_monitor.dylib" \
-    "$PROJECT_ROOT/target/release/libreaper_disk_monitor.dylib" \
-    "/Volumes/WD_BLACK/repos/BelowZero/ReaperSuite/ReaperDesktop/target/release/deps/libreaper_disk_monitor.dylib"; do
-    sudo install_name_tool -change "$old_path" /usr/local/lib/libreaper_disk_monitor.dylib "$INSTALL_BIN" 2>/dev/null || true
+    "$PROJECT_ROOT/target/release/deps/libreaper.dylib" \
+    "$PROJECT_ROOT/target/release/libreaper.dylib"; do
+    sudo install_name_tool -change "$old_path" /usr/local/lib/libreaper.dylib "$INSTALL_BIN" 2>/dev/null || true
 done
 
 # Re-sign the binary after modification
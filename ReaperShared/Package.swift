@@ -38,5 +38,5 @@ let package = Package(
 //
 // To use RustMetricsProvider in your app:
 // 1. Build Rust libraries: cd monitors && cargo build --release
-// 2. Copy libreaper_cpu_monitor.dylib and libreaper_disk_monitor.dylib to app bundle
+// 2. Copy libreaper.dylib to app bundle
 // 3. Include RustMetricsProvider.swift directly in your app target
@@ -63,16 +63,12 @@ nice -n 19 cargo build --release -j $MAX_CORES
 
 # Verify Rust libraries
 echo "Verifying Rust libraries..."
-verify_file "target/release/libreaper_cpu_monitor.dylib" 400000 || exit 1
-verify_file "target/release/libreaper_memory_monitor.dylib" 400000 || exit 1
-verify_file "target/release/libreaper_hardware_monitor.dylib" 400000 || exit 1
-verify_file "target/release/libreaper_network_monitor.dylib" 400000 || exit 1
-verify_file "target/release/libreaper_disk_monitor.dylib" 300000 || exit 1
+verify_file "target/release/libreaper.dylib" 1000000 || exit 1
 
-# Build Swift executable with all monitor libraries and CPU limit
+# Build Swift executable against libreaper, which contains every monitor
 echo "Building Swift executable (limited to $MAX_CORES cores with minimal priority)..."
 cd ReaperApp
-nice -n 19 swift build -c release -j $MAX_CORES -Xlinker -lreaper_memory_monitor -Xlinker -lreaper_hardware_monitor -Xlinker -lreaper_disk_monitor
+nice -n 19 swift build -c release -j $MAX_CORES
 
 # Verify Swift executable
 verify_file ".build/release/ReaperApp" 1000000 || exit 1
@@ -117,7 +113,7 @@ done
 
 # Fix library paths in executable to use @executable_path
 echo "Fixing library paths..."
-for lib in libreaper_core libreaper_cpu_monitor libreaper_memory_monitor libreaper_hardware_monitor libreaper_network_monitor libreaper_disk_monitor; do
+for lib in libreaper; do
     if [ -f "$APP_BUNDLE/Contents/Frameworks/${lib}.dylib" ]; then
         # Fix the path in the main executable
         install_name_tool -change "$PWD/target/release/deps/${lib}.dylib" "@executable_path/../Frameworks/${lib}.dylib" "$APP_BUNDLE/Contents/MacOS/ReaperApp" 2>/dev/null || true
@@ -174,11 +170,7 @@ fi
 echo ""
 echo "Final verification:"
 verify_file "$APP_BUNDLE/Contents/MacOS/ReaperApp" 1000000 || exit 1
-verify_file "$APP_BUNDLE/Contents/Frameworks/libreaper_cpu_monitor.dylib" 400000 || exit 1
-verify_file "$APP_BUNDLE/Contents/Frameworks/libreaper_memory_monitor.dylib" 400000 || exit 1
-verify_file "$APP_BUNDLE/Contents/Frameworks/libreaper_hardware_monitor.dylib" 400000 || exit 1
-verify_file "$APP_BUNDLE/Contents/Frameworks/libreaper_network_monitor.dylib" 400000 || exit 1
-verify_file "$APP_BUNDLE/Contents/Frameworks/libreaper_disk_monitor.dylib" 300000 || exit 1
+verify_file "$APP_BUNDLE/Contents/Frameworks/libreaper.dylib" 1000000 || exit 1
 
 echo ""
 echo -e "${GREEN}✓ Reaper.app bundle created successfully!${NC}"
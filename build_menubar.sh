@@ -15,14 +15,9 @@ echo -e "${YELLOW}Building ReaperMenuBar.app...${NC}"
 echo -e "${BLUE}Building Rust libraries...${NC}"
 cargo build --release
 
-# Verify Rust libraries exist
-if [ ! -f "target/release/libreaper_cpu_monitor.dylib" ]; then
-    echo -e "${RED}Error: libreaper_cpu_monitor.dylib not found${NC}"
-    exit 1
-fi
-
-if [ ! -f "target/release/libreaper_disk_monitor.dylib" ]; then
-    echo -e "${RED}Error: libreaper_disk_monitor.dylib not found${NC}"
+# Verify the Rust library exists
+if [ ! -f "target/release/libreaper.dylib" ]; then
+    echo -e "${RED}Error: libreaper.dylib not found${NC}"
     exit 1
 fi
 
@@ -33,8 +28,7 @@ cd ReaperMenuBar
 echo -e "${BLUE}Building ReaperMenuBar...${NC}"
 swift build -c release \
     -Xlinker -L../target/release \
-    -Xlinker -lreaper_cpu_monitor \
-    -Xlinker -lreaper_disk_monitor \
+    -Xlinker -lreaper \
     -Xlinker -rpath \
     -Xlinker @executable_path/../Frameworks
 
@@ -82,8 +76,7 @@ fi
 
 # Copy Rust libraries
 echo -e "${BLUE}Copying libraries...${NC}"
-cp ../target/release/libreaper_cpu_monitor.dylib "$APP_BUNDLE/Contents/Frameworks/"
-cp ../target/release/libreaper_disk_monitor.dylib "$APP_BUNDLE/Contents/Frameworks/"
+cp ../target/release/libreaper.dylib "$APP_BUNDLE/Contents/Frameworks/"
 
 # Update library paths
 echo -e "${BLUE}Updating library paths...${NC}"
@@ -93,17 +86,9 @@ PROJECT_ROOT="$(cd .. && pwd)"
 # Fix the library paths to use @rpath instead of absolute paths
 # The linker may use either deps/ or direct release/ paths
 for old_path in \
-    "$PROJECT_ROOT/target/release/deps/libreaper_cpu_monitor.dylib" \
-    "$PROJECT_ROOT/target/release/libreaper_cpu_monitor.dylib" \
-    "/Volumes/WD_BLACK/repos/BelowZero/ReaperSuite/ReaperDesktop/target/release/deps/libreaper_cpu_monitor.dylib"; do
-    install_name_tool -change "$old_path" @rpath/libreaper_cpu_monitor.dylib "$APP_BUNDLE/Contents/MacOS/ReaperMenuBar" 2>/dev/null || true
-done
-
-for old_path in \
-    "$PROJECT_ROOT/target/release/deps/libreaper_disk_monitor.dylib" \
-    "$PROJECT_ROOT/target/release/libreaper_disk_monitor.dylib" \
-    "/Volumes/WD_BLACK/repos/BelowZero/ReaperSuite/ReaperDesktop/target/release/deps/libreaper_disk_monitor.dylib"; do
-    install_name_tool -change "$old_path" @rpath/libreaper_disk_monitor.dylib "$APP_BUNDLE/Contents/MacOS/ReaperMenuBar" 2>/dev/null || true
+    "$PROJECT_ROOT/target/release/deps/libreaper.dylib" \
+    "$PROJECT_ROOT/target/release/libreaper.dylib"; do
+    install_name_tool -change "$old_path" @rpath/libreaper.dylib "$APP_BUNDLE/Contents/MacOS/ReaperMenuBar" 2>/dev/null || true
 done
 
 # Add rpath if not already present
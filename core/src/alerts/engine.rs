@@ -0,0 +1,222 @@
+use super::{AlertRule, Condition, Severity};
+use crate::metrics::{MetricSample, SeriesKey};
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use std::sync::mpsc::{channel, Receiver, Sender};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum AlertState {
+    /// The condition holds but not yet for the rule's `for` duration, or
+    /// the rule is still cooling down
+    Pending,
+    Firing,
+    Resolved,
+}
+
+/// A rule's alert for one series. Published on every transition to
+/// `Firing` or `Resolved`; `active_alerts` also reports `Pending` ones.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Alert {
+    pub rule: String,
+    pub severity: Severity,
+    pub series: SeriesKey,
+    pub state: AlertState,
+    pub value: f64,
+    /// Unix time the alert entered `state`
+    pub since: u64,
+}
+
+struct Tracked {
+    state: AlertState,
+    since: u64,
+    value: f64,
+}
+
+/// Evaluates alert rules against each batch of monitor readings
+///
+/// Every rule is tracked separately for each series its first condition
+/// matches, so `process.cpu > 90` raises one alert per process. A batch is
+/// authoritative for the metric names it contains: a series missing from
+/// the latest batch for its name is gone, and its alerts resolve.
+#[derive(Default)]
+pub struct AlertEngine {
+    rules: Vec<AlertRule>,
+    latest: HashMap<SeriesKey, f64>,
+    tracked: HashMap<(usize, SeriesKey), Tracked>,
+    last_fired: HashMap<(usize, SeriesKey), u64>,
+    subscribers: Vec<Sender<Alert>>,
+}
+
+impl AlertEngine {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_rules(rules: Vec<AlertRule>) -> Self {
+        Self {
+            rules,
+            ..Self::default()
+        }
+    }
+
+    pub fn rules(&self) -> &[AlertRule] {
+        &self.rules
+    }
+
+    /// Replace all rules. Alerts that were firing are published as resolved.
+    pub fn set_rules(&mut self, rules: Vec<AlertRule>, now: u64) {
+        let resolved: Vec<Alert> = self.tracked
+            .drain()
+            .filter(|(_, tracked)| tracked.state == AlertState::Firing)
+            .map(|((index, series), tracked)| Alert {
+                rule: self.rules[index].name.clone(),
+                severity: self.rules[index].severity,
+                series,
+                state: AlertState::Resolved,
+                value: tracked.value,
+                since: now,
+            })
+            .collect();
+
+        self.rules = rules;
+        self.last_fired.clear();
+        self.dispatch(&resolved);
+    }
+
+    /// Receive every alert transition from now on
+    pub fn subscribe(&mut self) -> Receiver<Alert> {
+        let (sender, receiver) = channel();
+        self.subscribers.push(sender);
+        receiver
+    }
+
+    /// Pending and firing alerts, ordered by rule and series
+    pub fn active_alerts(&self) -> Vec<Alert> {
+        let mut alerts: Vec<Alert> = self.tracked
+            .iter()
+            .map(|((index, series), tracked)| Alert {
+                rule: self.rules[*index].name.clone(),
+                severity: self.rules[*index].severity,
+                series: series.clone(),
+                state: tracked.state,
+                value: tracked.value,
+                since: tracked.since,
+            })
+            .collect();
+        alerts.sort_by(|a, b| (&a.rule, &a.series).cmp(&(&b.rule, &b.series)));
+        alerts
+    }
+
+    /// Take in one monitor's readings at `now` (Unix seconds), re-evaluate
+    /// the rules that read them and publish the resulting transitions
+    pub fn observe(&mut self, now: u64, samples: &[MetricSample]) -> Vec<Alert> {
+        let names: HashSet<&str> = samples.iter().map(|s| s.key.name.as_str()).collect();
+        if names.is_empty() {
+            return Vec::new();
+        }
+
+        self.latest.retain(|key, _| !names.contains(key.name.as_str()));
+        self.latest.extend(samples.iter().map(|s| (s.key.clone(), s.value)));
+
+        let mut events = Vec::new();
+        for index in 0..self.rules.len() {
+            if self.rules[index].expression.metric_names().any(|name| names.contains(name)) {
+                self.evaluate(index, now, &mut events);
+            }
+        }
+
+        let rules = &self.rules;
+        self.last_fired.retain(|(index, _), at| now.saturating_sub(*at) < rules[*index].cooldown.as_secs());
+
+        self.dispatch(&events);
+        events
+    }
+
+    fn evaluate(&mut self, index: usize, now: u64, events: &mut Vec<Alert>) {
+        let Self { rules, latest, tracked, last_fired, .. } = self;
+        let rule = &rules[index];
+        let (first, joined) = rule.expression.conditions.split_first().expect("rule without conditions");
+
+        let alert = |series: &SeriesKey, state, value, since| Alert {
+            rule: rule.name.clone(),
+            severity: rule.severity,
+            series: series.clone(),
+            state,
+            value,
+            since,
+        };
+
+        let mut seen = HashSet::new();
+        for (series, &value) in latest.iter().filter(|(key, _)| first.selector.matches(key)) {
+            seen.insert(series);
+            let id = (index, series.clone());
+            let firing = tracked.get(&id).is_some_and(|t| t.state == AlertState::Firing);
+            let holds = first.holds(value, firing, rule.hysteresis)
+                && joined.iter().all(|c| joined_holds(latest, c, series, firing, rule.hysteresis));
+
+            if !holds {
+                if let Some(previous) = tracked.remove(&id) {
+                    if previous.state == AlertState::Firing {
+                        events.push(alert(series, AlertState::Resolved, value, now));
+                    }
+                }
+                continue;
+            }
+
+            let entry = tracked.entry(id.clone()).or_insert(Tracked {
+                state: AlertState::Pending,
+                since: now,
+                value,
+            });
+            entry.value = value;
+
+            let held_long_enough = now.saturating_sub(entry.since) >= rule.expression.for_duration.as_secs();
+            let cooled_down = last_fired
+                .get(&id)
+                .is_none_or(|&at| now.saturating_sub(at) >= rule.cooldown.as_secs());
+            if entry.state == AlertState::Pending && held_long_enough && cooled_down {
+                entry.state = AlertState::Firing;
+                entry.since = now;
+                last_fired.insert(id, now);
+                events.push(alert(series, AlertState::Firing, value, now));
+            }
+        }
+
+        // Series that disappeared, e.g. an exited process
+        let gone: Vec<SeriesKey> = tracked
+            .keys()
+            .filter(|(i, series)| *i == index && !seen.contains(series))
+            .map(|(_, series)| series.clone())
+            .collect();
+        for series in gone {
+            if let Some(previous) = tracked.remove(&(index, series.clone())) {
+                if previous.state == AlertState::Firing {
+                    events.push(alert(&series, AlertState::Resolved, previous.value, now));
+                }
+            }
+        }
+    }
+
+    fn dispatch(&mut self, events: &[Alert]) {
+        if events.is_empty() {
+            return;
+        }
+        self.subscribers.retain(|subscriber| events.iter().all(|event| subscriber.send(event.clone()).is_ok()));
+    }
+}
+
+/// Whether a series for `condition` that agrees with `entity` on their
+/// shared labels satisfies it
+fn joined_holds(
+    latest: &HashMap<SeriesKey, f64>,
+    condition: &Condition,
+    entity: &SeriesKey,
+    firing: bool,
+    hysteresis: f64,
+) -> bool {
+    latest.iter().any(|(key, &value)| {
+        condition.selector.matches(key)
+            && key.labels.iter().all(|(k, v)| entity.labels.get(k).is_none_or(|other| other == v))
+            && condition.holds(value, firing, hysteresis)
+    })
+}
@@ -0,0 +1,51 @@
+//! Alert rules evaluated against live monitor readings
+//!
+//! Rules are expressions over the series monitors report through
+//! `Monitor::metric_samples`, such as `process.cpu > 90 for 2m`,
+//! `disk["/"].usage > 95` or `memory.pressure == Critical`, loaded from
+//! TOML or JSON. The sampling engine hands every batch of readings to an
+//! `AlertEngine`, which moves each rule through pending, firing and
+//! resolved per series and publishes the transitions to subscribers.
+
+mod engine;
+mod rule;
+
+pub use engine::{Alert, AlertEngine, AlertState};
+pub use rule::{
+    parse_duration, AlertError, AlertRule, CompareOp, Condition, Expression, RuleSet, Selector, Severity,
+};
+
+use once_cell::sync::Lazy;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+static GLOBAL_ENGINE: Lazy<Arc<Mutex<AlertEngine>>> = Lazy::new(|| {
+    let engine = Arc::new(Mutex::new(AlertEngine::new()));
+    crate::sampling::global_engine()
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .set_alert_engine(Arc::clone(&engine));
+    engine
+});
+
+/// The process-wide alert engine, fed by the global sampling engine
+pub fn global_engine() -> Arc<Mutex<AlertEngine>> {
+    Arc::clone(&GLOBAL_ENGINE)
+}
+
+/// Replace the global engine's rules with those in a `.toml` or `.json`
+/// file; returns how many were loaded
+pub fn load_global_rules(path: &Path) -> Result<usize, AlertError> {
+    let rule_set = RuleSet::load(path)?;
+    let count = rule_set.rules.len();
+
+    global_engine()
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .set_rules(rule_set.rules, crate::metrics::unix_now());
+
+    Ok(count)
+}
+
+#[cfg(test)]
+mod tests;
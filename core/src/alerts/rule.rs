@@ -0,0 +1,559 @@
+use crate::metrics::SeriesKey;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::Path;
+use std::time::Duration;
+
+/// Short names accepted in rule expressions for the series monitors report
+const ALIASES: &[(&str, &str)] = &[
+    ("cpu.usage", "cpu.usage_percent"),
+    ("process.cpu", "process.cpu_percent"),
+    ("process.memory", "process.resident_bytes"),
+    ("memory.usage", "memory.usage_percent"),
+    ("disk.usage", "disk.usage_percent"),
+];
+
+/// Named levels for series that report an enum as its position in this list
+const LEVELS: &[(&str, &[&str])] = &[
+    ("memory.pressure", &["Low", "Normal", "High", "Critical"]),
+    ("thermal.state", &["Normal", "Warm", "Hot", "Throttling"]),
+];
+
+/// Error loading or parsing alert rules
+#[derive(Debug, Clone, PartialEq)]
+pub enum AlertError {
+    /// A rule's expression or one of its settings is invalid
+    InvalidRule { rule: String, message: String },
+    /// The rules file could not be read or decoded
+    InvalidFile(String),
+}
+
+impl fmt::Display for AlertError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidRule { rule, message } => write!(f, "Invalid alert rule '{}': {}", rule, message),
+            Self::InvalidFile(msg) => write!(f, "Invalid alert rules file: {}", msg),
+        }
+    }
+}
+
+impl std::error::Error for AlertError {}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Info,
+    #[default]
+    Warning,
+    Critical,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CompareOp {
+    Gt,
+    Ge,
+    Lt,
+    Le,
+    Eq,
+    Ne,
+}
+
+impl CompareOp {
+    fn symbol(self) -> &'static str {
+        match self {
+            CompareOp::Gt => ">",
+            CompareOp::Ge => ">=",
+            CompareOp::Lt => "<",
+            CompareOp::Le => "<=",
+            CompareOp::Eq => "==",
+            CompareOp::Ne => "!=",
+        }
+    }
+
+    fn from_symbol(symbol: &str) -> Option<Self> {
+        [CompareOp::Gt, CompareOp::Ge, CompareOp::Lt, CompareOp::Le, CompareOp::Eq, CompareOp::Ne]
+            .into_iter()
+            .find(|op| op.symbol() == symbol)
+    }
+
+    pub fn holds(self, value: f64, threshold: f64) -> bool {
+        match self {
+            CompareOp::Gt => value > threshold,
+            CompareOp::Ge => value >= threshold,
+            CompareOp::Lt => value < threshold,
+            CompareOp::Le => value <= threshold,
+            CompareOp::Eq => value == threshold,
+            CompareOp::Ne => value != threshold,
+        }
+    }
+}
+
+/// The series a condition applies to: a metric name and required labels
+#[derive(Debug, Clone, PartialEq)]
+pub struct Selector {
+    pub name: String,
+    pub labels: BTreeMap<String, String>,
+}
+
+impl Selector {
+    pub fn matches(&self, key: &SeriesKey) -> bool {
+        key.matches(&self.name, &self.labels)
+    }
+}
+
+/// One `selector op threshold` comparison
+#[derive(Debug, Clone, PartialEq)]
+pub struct Condition {
+    pub selector: Selector,
+    pub op: CompareOp,
+    pub threshold: f64,
+}
+
+impl Condition {
+    /// Whether `value` satisfies the condition. While the alert is firing
+    /// the threshold moves back by `hysteresis`, so a value hovering around
+    /// it doesn't flap between firing and resolved.
+    pub fn holds(&self, value: f64, firing: bool, hysteresis: f64) -> bool {
+        let threshold = match (firing, self.op) {
+            (true, CompareOp::Gt | CompareOp::Ge) => self.threshold - hysteresis,
+            (true, CompareOp::Lt | CompareOp::Le) => self.threshold + hysteresis,
+            _ => self.threshold,
+        };
+        self.op.holds(value, threshold)
+    }
+}
+
+/// A parsed rule expression such as `process["node"].cpu > 300 for 5m`
+///
+/// Conditions joined with `and` are matched on shared labels, so
+/// `process.memory_leak == 1 and process.memory > 8GB` applies to one
+/// process at a time.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Expression {
+    pub conditions: Vec<Condition>,
+    pub for_duration: Duration,
+}
+
+impl Expression {
+    pub fn parse(input: &str) -> Result<Self, String> {
+        let mut parser = Parser { tokens: tokenize(input)?, position: 0 };
+        let expression = parser.expression()?;
+        match parser.peek() {
+            None => Ok(expression),
+            Some(token) => Err(format!("unexpected {}", token)),
+        }
+    }
+
+    /// Metric names this expression reads
+    pub fn metric_names(&self) -> impl Iterator<Item = &str> {
+        self.conditions.iter().map(|c| c.selector.name.as_str())
+    }
+}
+
+/// Rule as written in a rules file
+#[derive(Debug, Clone, Deserialize)]
+struct RuleDefinition {
+    name: String,
+    expr: String,
+    #[serde(default)]
+    severity: Severity,
+    #[serde(default)]
+    cooldown: Option<String>,
+    #[serde(default)]
+    hysteresis: f64,
+    #[serde(default)]
+    description: Option<String>,
+}
+
+/// A named alert rule
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(try_from = "RuleDefinition")]
+pub struct AlertRule {
+    pub name: String,
+    pub expression: Expression,
+    pub severity: Severity,
+    /// Minimum time between two firings for the same series
+    pub cooldown: Duration,
+    /// How far past the threshold a firing alert's value must move back
+    /// before it resolves
+    pub hysteresis: f64,
+    pub description: Option<String>,
+}
+
+impl AlertRule {
+    pub fn new(name: impl Into<String>, expression: &str) -> Result<Self, AlertError> {
+        let name = name.into();
+        let expression = Expression::parse(expression)
+            .map_err(|message| AlertError::InvalidRule { rule: name.clone(), message })?;
+
+        Ok(Self {
+            name,
+            expression,
+            severity: Severity::default(),
+            cooldown: Duration::ZERO,
+            hysteresis: 0.0,
+            description: None,
+        })
+    }
+
+    pub fn with_severity(mut self, severity: Severity) -> Self {
+        self.severity = severity;
+        self
+    }
+
+    pub fn with_cooldown(mut self, cooldown: Duration) -> Self {
+        self.cooldown = cooldown;
+        self
+    }
+
+    pub fn with_hysteresis(mut self, hysteresis: f64) -> Self {
+        self.hysteresis = hysteresis;
+        self
+    }
+}
+
+impl TryFrom<RuleDefinition> for AlertRule {
+    type Error = AlertError;
+
+    fn try_from(definition: RuleDefinition) -> Result<Self, AlertError> {
+        let invalid = |message: String| AlertError::InvalidRule { rule: definition.name.clone(), message };
+
+        let cooldown = match &definition.cooldown {
+            Some(cooldown) => parse_duration(cooldown).map_err(invalid)?,
+            None => Duration::ZERO,
+        };
+        if definition.hysteresis < 0.0 {
+            return Err(invalid("hysteresis must not be negative".to_string()));
+        }
+
+        let mut rule = AlertRule::new(definition.name.clone(), &definition.expr)?
+            .with_severity(definition.severity)
+            .with_cooldown(cooldown)
+            .with_hysteresis(definition.hysteresis);
+        rule.description = definition.description;
+        Ok(rule)
+    }
+}
+
+/// The rules in a TOML (`[[rule]]` tables) or JSON (`{"rules": [...]}`) file
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+pub struct RuleSet {
+    #[serde(alias = "rule", default)]
+    pub rules: Vec<AlertRule>,
+}
+
+impl RuleSet {
+    pub fn from_toml(input: &str) -> Result<Self, AlertError> {
+        toml::from_str(input).map_err(|e| AlertError::InvalidFile(e.to_string()))
+    }
+
+    pub fn from_json(input: &str) -> Result<Self, AlertError> {
+        serde_json::from_str(input).map_err(|e| AlertError::InvalidFile(e.to_string()))
+    }
+
+    /// Load a `.toml` or `.json` rules file
+    pub fn load(path: &Path) -> Result<Self, AlertError> {
+        let contents = std::fs::read_to_string(path)
+            .map_err(|e| AlertError::InvalidFile(format!("{}: {}", path.display(), e)))?;
+
+        match path.extension().and_then(|e| e.to_str()) {
+            Some("toml") => Self::from_toml(&contents),
+            Some("json") => Self::from_json(&contents),
+            _ => Err(AlertError::InvalidFile(format!("{}: expected a .toml or .json file", path.display()))),
+        }
+    }
+}
+
+/// Parse a duration such as `90s`, `2m`, `1h` or `1d`
+pub fn parse_duration(input: &str) -> Result<Duration, String> {
+    let mut parser = Parser { tokens: tokenize(input)?, position: 0 };
+    let duration = parser.duration()?;
+    match parser.peek() {
+        None => Ok(duration),
+        Some(token) => Err(format!("unexpected {} in duration", token)),
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Ident(String),
+    Number(f64),
+    Str(String),
+    Symbol(&'static str),
+}
+
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Token::Ident(ident) => write!(f, "'{}'", ident),
+            Token::Number(number) => write!(f, "'{}'", number),
+            Token::Str(string) => write!(f, "\"{}\"", string),
+            Token::Symbol(symbol) => write!(f, "'{}'", symbol),
+        }
+    }
+}
+
+const SYMBOLS: &[&str] = &[">=", "<=", "==", "!=", ">", "<", "=", "[", "]", "{", "}", ".", ",", "%", "-"];
+
+fn tokenize(input: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = Vec::new();
+    let mut rest = input;
+
+    while let Some(c) = rest.chars().next() {
+        if c.is_whitespace() {
+            rest = &rest[c.len_utf8()..];
+        } else if c.is_ascii_digit() {
+            let end = rest.find(|c: char| !c.is_ascii_digit() && c != '.').unwrap_or(rest.len());
+            let number = rest[..end].parse().map_err(|_| format!("invalid number '{}'", &rest[..end]))?;
+            tokens.push(Token::Number(number));
+            rest = &rest[end..];
+        } else if c.is_alphabetic() || c == '_' {
+            let end = rest.find(|c: char| !c.is_alphanumeric() && c != '_').unwrap_or(rest.len());
+            tokens.push(Token::Ident(rest[..end].to_string()));
+            rest = &rest[end..];
+        } else if c == '"' || c == '\'' {
+            let end = rest[1..].find(c).ok_or("unterminated string")? + 1;
+            tokens.push(Token::Str(rest[1..end].to_string()));
+            rest = &rest[end + 1..];
+        } else if let Some(symbol) = SYMBOLS.iter().find(|s| rest.starts_with(*s)) {
+            tokens.push(Token::Symbol(symbol));
+            rest = &rest[symbol.len()..];
+        } else {
+            return Err(format!("unexpected character '{}'", c));
+        }
+    }
+
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    position: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.position).cloned();
+        self.position += 1;
+        token
+    }
+
+    fn eat_symbol(&mut self, symbol: &str) -> bool {
+        let found = matches!(self.peek(), Some(Token::Symbol(s)) if *s == symbol);
+        if found {
+            self.position += 1;
+        }
+        found
+    }
+
+    fn eat_keyword(&mut self, keyword: &str) -> bool {
+        let found = matches!(self.peek(), Some(Token::Ident(i)) if i.eq_ignore_ascii_case(keyword));
+        if found {
+            self.position += 1;
+        }
+        found
+    }
+
+    fn expect_symbol(&mut self, symbol: &str) -> Result<(), String> {
+        if self.eat_symbol(symbol) {
+            Ok(())
+        } else {
+            Err(self.unexpected(&format!("'{}'", symbol)))
+        }
+    }
+
+    fn unexpected(&self, expected: &str) -> String {
+        match self.peek() {
+            Some(token) => format!("expected {}, found {}", expected, token),
+            None => format!("expected {}, found end of expression", expected),
+        }
+    }
+
+    fn expression(&mut self) -> Result<Expression, String> {
+        let mut conditions = vec![self.condition()?];
+        while self.eat_keyword("and") {
+            conditions.push(self.condition()?);
+        }
+
+        let for_duration = if self.eat_keyword("for") {
+            self.duration()?
+        } else {
+            Duration::ZERO
+        };
+
+        Ok(Expression { conditions, for_duration })
+    }
+
+    fn condition(&mut self) -> Result<Condition, String> {
+        let selector = self.selector()?;
+        let op = match self.next() {
+            Some(Token::Symbol(symbol)) => CompareOp::from_symbol(symbol),
+            _ => None,
+        };
+        let Some(op) = op else {
+            self.position -= 1;
+            return Err(self.unexpected("a comparison operator"));
+        };
+        let threshold = self.value(&selector.name)?;
+
+        Ok(Condition { selector, op, threshold })
+    }
+
+    /// `disk["/"].usage`, `process[1234].cpu` or `net.upload_bps{interface="en0"}`
+    fn selector(&mut self) -> Result<Selector, String> {
+        let mut segments = Vec::new();
+        let mut bracket = None;
+
+        loop {
+            match self.next() {
+                Some(Token::Ident(segment)) => segments.push(segment),
+                _ => {
+                    self.position -= 1;
+                    return Err(self.unexpected("a metric name"));
+                }
+            }
+
+            if self.eat_symbol("[") {
+                let value = match self.next() {
+                    Some(Token::Str(value)) | Some(Token::Ident(value)) => value,
+                    Some(Token::Number(number)) => number.to_string(),
+                    _ => return Err("expected a value inside [..]".to_string()),
+                };
+                self.expect_symbol("]")?;
+                if bracket.replace(value).is_some() {
+                    return Err("only one [..] selector is allowed".to_string());
+                }
+            }
+
+            if !self.eat_symbol(".") {
+                break;
+            }
+        }
+
+        let mut labels = BTreeMap::new();
+        if let Some(value) = bracket {
+            let label = default_label(&segments[0], &value)
+                .ok_or_else(|| format!("'{}' does not take a [..] selector", segments[0]))?;
+            labels.insert(label.to_string(), value);
+        }
+
+        if self.eat_symbol("{") {
+            while !self.eat_symbol("}") {
+                let Some(Token::Ident(key)) = self.next() else {
+                    return Err("expected a label name".to_string());
+                };
+                self.expect_symbol("=")?;
+                let Some(Token::Str(value)) = self.next() else {
+                    return Err(format!("expected a quoted value for label '{}'", key));
+                };
+                labels.insert(key, value);
+                if !self.eat_symbol(",") {
+                    self.expect_symbol("}")?;
+                    break;
+                }
+            }
+        }
+
+        let name = segments.join(".");
+        let name = ALIASES
+            .iter()
+            .find(|(alias, _)| *alias == name)
+            .map_or(name.clone(), |(_, series)| series.to_string());
+
+        Ok(Selector { name, labels })
+    }
+
+    /// A number with an optional `%` or byte unit, or a named level
+    fn value(&mut self, metric: &str) -> Result<f64, String> {
+        let negative = self.eat_symbol("-");
+        match self.next() {
+            Some(Token::Number(number)) => {
+                let unit = match self.peek() {
+                    Some(Token::Ident(unit)) => byte_unit(unit),
+                    _ => None,
+                };
+                let scale = if let Some(scale) = unit {
+                    self.position += 1;
+                    scale
+                } else {
+                    self.eat_symbol("%");
+                    1.0
+                };
+                Ok(if negative { -number * scale } else { number * scale })
+            }
+            Some(Token::Ident(level)) if !negative => {
+                let levels = LEVELS
+                    .iter()
+                    .find(|(name, _)| *name == metric)
+                    .map(|(_, levels)| *levels)
+                    .ok_or_else(|| format!("'{}' has no named levels", metric))?;
+                levels
+                    .iter()
+                    .position(|l| l.eq_ignore_ascii_case(&level))
+                    .map(|index| index as f64)
+                    .ok_or_else(|| format!("unknown level '{}' for '{}', expected one of {}", level, metric, levels.join(", ")))
+            }
+            _ => {
+                self.position -= 1;
+                Err(self.unexpected("a number or level"))
+            }
+        }
+    }
+
+    fn duration(&mut self) -> Result<Duration, String> {
+        let Some(Token::Number(amount)) = self.next() else {
+            self.position -= 1;
+            return Err(self.unexpected("a duration"));
+        };
+
+        let unit = match self.peek() {
+            Some(Token::Ident(unit)) => {
+                let unit = unit.clone();
+                self.position += 1;
+                unit
+            }
+            _ if amount == 0.0 => "s".to_string(),
+            _ => return Err("duration needs a unit (s, m, h or d)".to_string()),
+        };
+
+        let seconds = match unit.as_str() {
+            "ms" => amount / 1000.0,
+            "s" => amount,
+            "m" => amount * 60.0,
+            "h" => amount * 3600.0,
+            "d" => amount * 86400.0,
+            _ => return Err(format!("unknown duration unit '{}'", unit)),
+        };
+        Duration::try_from_secs_f64(seconds).map_err(|_| format!("duration {}{} is out of range", amount, unit))
+    }
+}
+
+/// Label a `[..]` selector refers to for each metric family
+fn default_label(family: &str, value: &str) -> Option<&'static str> {
+    match family {
+        "process" if value.chars().all(|c| c.is_ascii_digit()) => Some("pid"),
+        "process" => Some("process"),
+        "disk" => Some("mount_point"),
+        "net" => Some("interface"),
+        "thermal" => Some("sensor"),
+        "cpu" => Some("core"),
+        _ => None,
+    }
+}
+
+fn byte_unit(unit: &str) -> Option<f64> {
+    let power = match unit.to_ascii_uppercase().as_str() {
+        "B" => 0,
+        "KB" | "KIB" => 1,
+        "MB" | "MIB" => 2,
+        "GB" | "GIB" => 3,
+        "TB" | "TIB" => 4,
+        _ => return None,
+    };
+    Some(1024f64.powi(power))
+}
@@ -0,0 +1,164 @@
+use super::*;
+use crate::metrics::{MetricSample, SeriesKey};
+use std::time::Duration;
+
+fn process_cpu(pid: u32, name: &str, value: f64) -> MetricSample {
+    MetricSample::new(
+        SeriesKey::new("process.cpu_percent").with_label("pid", pid).with_label("process", name),
+        value,
+    )
+}
+
+fn states(events: &[Alert]) -> Vec<AlertState> {
+    events.iter().map(|e| e.state).collect()
+}
+
+#[test]
+fn test_parse_rule_expressions() {
+    let cpu = Expression::parse("process.cpu > 90 for 2m").unwrap();
+    assert_eq!(cpu.for_duration, Duration::from_secs(120));
+    assert_eq!(cpu.conditions[0].selector.name, "process.cpu_percent");
+    assert_eq!(cpu.conditions[0].op, CompareOp::Gt);
+    assert_eq!(cpu.conditions[0].threshold, 90.0);
+
+    let disk = Expression::parse(r#"disk["/"].usage > 95"#).unwrap();
+    assert_eq!(disk.conditions[0].selector.name, "disk.usage_percent");
+    assert_eq!(disk.conditions[0].selector.labels.get("mount_point").map(String::as_str), Some("/"));
+
+    let pressure = Expression::parse("memory.pressure == Critical").unwrap();
+    assert_eq!(pressure.conditions[0].threshold, 3.0);
+
+    let leak = Expression::parse("process[1234].memory > 8GB and process.memory_leak == 1").unwrap();
+    assert_eq!(leak.conditions[0].selector.labels.get("pid").map(String::as_str), Some("1234"));
+    assert_eq!(leak.conditions[0].threshold, 8.0 * 1024.0 * 1024.0 * 1024.0);
+    assert_eq!(leak.conditions[1].selector.name, "process.memory_leak");
+
+    let labelled = Expression::parse(r#"net.upload_bps{interface="en0"} >= 1MB"#).unwrap();
+    assert_eq!(labelled.conditions[0].selector.labels.get("interface").map(String::as_str), Some("en0"));
+
+    assert!(Expression::parse("memory.pressure == Scorching").unwrap_err().contains("unknown level"));
+    assert!(Expression::parse("process.cpu 90").is_err());
+    assert!(Expression::parse("process.cpu > 90 for 2 fortnights").is_err());
+    assert!(Expression::parse("process.cpu > 90 for 300000000000000d").unwrap_err().contains("out of range"));
+    assert!(parse_duration(&"9".repeat(400)).is_err());
+    assert!(parse_duration(&format!("{}s", "9".repeat(400))).is_err());
+}
+
+#[test]
+fn test_fires_after_for_duration() {
+    let rule = AlertRule::new("hot", "process.cpu > 90 for 2m").unwrap();
+    let mut engine = AlertEngine::with_rules(vec![rule]);
+    let receiver = engine.subscribe();
+
+    assert!(engine.observe(1000, &[process_cpu(1, "node", 95.0)]).is_empty());
+    assert_eq!(engine.active_alerts()[0].state, AlertState::Pending);
+    assert!(engine.observe(1060, &[process_cpu(1, "node", 97.0)]).is_empty());
+
+    let events = engine.observe(1120, &[process_cpu(1, "node", 99.0)]);
+    assert_eq!(states(&events), vec![AlertState::Firing]);
+    assert_eq!(events[0].series.labels.get("process").map(String::as_str), Some("node"));
+
+    // A dip below the threshold while pending starts the wait over
+    let mut engine = AlertEngine::with_rules(engine.rules().to_vec());
+    engine.observe(0, &[process_cpu(1, "node", 95.0)]);
+    engine.observe(60, &[process_cpu(1, "node", 50.0)]);
+    assert!(engine.observe(120, &[process_cpu(1, "node", 95.0)]).is_empty());
+
+    assert_eq!(receiver.try_recv().unwrap().state, AlertState::Firing);
+}
+
+#[test]
+fn test_hysteresis_prevents_flapping() {
+    let rule = AlertRule::new("hot", "process.cpu > 90").unwrap().with_hysteresis(5.0);
+    let mut engine = AlertEngine::with_rules(vec![rule]);
+
+    assert_eq!(states(&engine.observe(0, &[process_cpu(1, "node", 91.0)])), vec![AlertState::Firing]);
+    assert!(engine.observe(1, &[process_cpu(1, "node", 88.0)]).is_empty());
+    assert_eq!(states(&engine.observe(2, &[process_cpu(1, "node", 84.0)])), vec![AlertState::Resolved]);
+}
+
+#[test]
+fn test_cooldown_holds_alert_pending() {
+    let rule = AlertRule::new("hot", "process.cpu > 90").unwrap().with_cooldown(Duration::from_secs(600));
+    let mut engine = AlertEngine::with_rules(vec![rule]);
+
+    engine.observe(0, &[process_cpu(1, "node", 95.0)]);
+    engine.observe(10, &[process_cpu(1, "node", 10.0)]);
+
+    assert!(engine.observe(20, &[process_cpu(1, "node", 95.0)]).is_empty());
+    assert_eq!(engine.active_alerts()[0].state, AlertState::Pending);
+    assert_eq!(states(&engine.observe(600, &[process_cpu(1, "node", 95.0)])), vec![AlertState::Firing]);
+}
+
+#[test]
+fn test_vanished_series_resolves() {
+    let rule = AlertRule::new("hot", "process.cpu > 90").unwrap();
+    let mut engine = AlertEngine::with_rules(vec![rule]);
+
+    let events = engine.observe(0, &[process_cpu(1, "node", 95.0), process_cpu(2, "cargo", 99.0)]);
+    assert_eq!(events.len(), 2);
+
+    // Process 2 exited
+    let events = engine.observe(1, &[process_cpu(1, "node", 95.0)]);
+    assert_eq!(states(&events), vec![AlertState::Resolved]);
+    assert_eq!(events[0].series.labels.get("pid").map(String::as_str), Some("2"));
+
+    // Readings for other metrics don't touch process alerts
+    let memory = MetricSample::new(SeriesKey::new("memory.used_bytes"), 1.0);
+    assert!(engine.observe(2, &[memory]).is_empty());
+    assert_eq!(engine.active_alerts().len(), 1);
+}
+
+#[test]
+fn test_joined_conditions_match_same_process() {
+    let rule = AlertRule::new("leak", "process.memory_leak == 1 and process.memory > 8GB").unwrap();
+    let mut engine = AlertEngine::with_rules(vec![rule]);
+    let gb = 1024.0 * 1024.0 * 1024.0;
+
+    let resident = |pid: u32, bytes: f64| MetricSample::new(
+        SeriesKey::new("process.resident_bytes").with_label("pid", pid).with_label("process", "app"),
+        bytes,
+    );
+    let leaking = |pid: u32| MetricSample::new(
+        SeriesKey::new("process.memory_leak").with_label("pid", pid).with_label("process", "app"),
+        1.0,
+    );
+
+    // Process 1 is big but not leaking, process 2 is leaking but small
+    engine.observe(0, &[resident(1, 9.0 * gb), resident(2, 1.0 * gb)]);
+    assert!(engine.observe(0, &[leaking(2)]).is_empty());
+
+    let events = engine.observe(1, &[resident(1, 9.0 * gb), resident(2, 10.0 * gb)]);
+    assert_eq!(states(&events), vec![AlertState::Firing]);
+    assert_eq!(events[0].series.labels.get("pid").map(String::as_str), Some("2"));
+}
+
+#[test]
+fn test_load_rules_from_toml_and_json() {
+    let toml = r#"
+        [[rule]]
+        name = "disk-full"
+        expr = 'disk["/"].usage > 95'
+        severity = "critical"
+        cooldown = "30m"
+
+        [[rule]]
+        name = "memory-pressure"
+        expr = "memory.pressure == Critical for 1m"
+        hysteresis = 1
+    "#;
+    let rules = RuleSet::from_toml(toml).unwrap().rules;
+    assert_eq!(rules.len(), 2);
+    assert_eq!(rules[0].severity, Severity::Critical);
+    assert_eq!(rules[0].cooldown, Duration::from_secs(1800));
+    assert_eq!(rules[1].severity, Severity::Warning);
+    assert_eq!(rules[1].hysteresis, 1.0);
+
+    let json = r#"{"rules": [{"name": "hot", "expr": "cpu.usage > 90 for 30s"}]}"#;
+    let rules = RuleSet::from_json(json).unwrap().rules;
+    assert_eq!(rules[0].expression.for_duration, Duration::from_secs(30));
+
+    let invalid = r#"{"rules": [{"name": "broken", "expr": "cpu.usage >"}]}"#;
+    let error = RuleSet::from_json(invalid).unwrap_err().to_string();
+    assert!(error.contains("broken"), "{}", error);
+}
@@ -0,0 +1,212 @@
+use super::record::{AuditRecord, GENESIS_HASH};
+use std::fmt;
+use std::fs::{File, OpenOptions};
+use std::io::{self, BufRead, BufReader, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+
+/// Error reading or verifying an audit log
+#[derive(Debug, Clone, PartialEq)]
+pub enum AuditError {
+    Io(String),
+    /// A line that isn't a valid record
+    Malformed { line: usize, message: String },
+    /// A record whose hash or link to the previous record doesn't match,
+    /// i.e. the log was edited, truncated in the middle or reordered
+    Tampered { sequence: u64, reason: String },
+}
+
+impl fmt::Display for AuditError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(msg) => write!(f, "Audit log I/O error: {}", msg),
+            Self::Malformed { line, message } => write!(f, "Malformed audit record on line {}: {}", line, message),
+            Self::Tampered { sequence, reason } => write!(f, "Audit record {} was tampered with: {}", sequence, reason),
+        }
+    }
+}
+
+impl std::error::Error for AuditError {}
+
+impl From<io::Error> for AuditError {
+    fn from(error: io::Error) -> Self {
+        Self::Io(error.to_string())
+    }
+}
+
+/// Records to return from `AuditLog::query`; unset bounds match everything
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct AuditQuery {
+    /// Inclusive Unix time in seconds
+    pub start: Option<u64>,
+    /// Exclusive Unix time in seconds
+    pub end: Option<u64>,
+    pub pid: Option<u32>,
+}
+
+impl AuditQuery {
+    pub fn between(mut self, start: u64, end: u64) -> Self {
+        self.start = Some(start);
+        self.end = Some(end);
+        self
+    }
+
+    pub fn for_pid(mut self, pid: u32) -> Self {
+        self.pid = Some(pid);
+        self
+    }
+
+    pub fn matches(&self, record: &AuditRecord) -> bool {
+        self.start.is_none_or(|start| record.timestamp >= start)
+            && self.end.is_none_or(|end| record.timestamp < end)
+            && self.pid.is_none_or(|pid| record.pid == pid)
+    }
+}
+
+/// Append-only, hash-chained file of audit records, one JSON object per line.
+/// Several handles, in one process or many, may append to the same file.
+#[derive(Debug)]
+pub struct AuditLog {
+    path: PathBuf,
+    torn_line: Option<usize>,
+}
+
+impl AuditLog {
+    /// Open or create the log at `path`. Unparsable lines after the last
+    /// valid record, e.g. from a write torn by a crash, are left in place and
+    /// reported by `torn_line`; new records chain from the valid one.
+    pub fn open(path: impl Into<PathBuf>) -> io::Result<Self> {
+        let path = path.into();
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let mut file = OpenOptions::new().create(true).read(true).append(true).open(&path)?;
+        file.lock_shared()?;
+
+        let mut log = Self { path, torn_line: None };
+        let mut contents = Vec::new();
+        file.read_to_end(&mut contents)?;
+        for (index, line) in contents.split(|&byte| byte == b'\n').enumerate() {
+            if line.iter().all(u8::is_ascii_whitespace) {
+                continue;
+            }
+            if serde_json::from_slice::<AuditRecord>(line).is_ok() {
+                log.torn_line = None;
+            } else {
+                log.torn_line.get_or_insert(index + 1);
+            }
+        }
+        Ok(log)
+    }
+
+    /// First line after the last valid record that failed to parse when the
+    /// log was opened, if any. The chain continues from the valid record, but
+    /// `verify` reports the damaged line until it is removed.
+    pub fn torn_line(&self) -> Option<usize> {
+        self.torn_line
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Chain `record` onto the last record in the file and write it; returns
+    /// the record as stored. The file is locked while its tail is read and
+    /// the record written, so concurrent appends form one chain.
+    pub fn append(&mut self, mut record: AuditRecord) -> io::Result<AuditRecord> {
+        let mut file = OpenOptions::new().read(true).append(true).open(&self.path)?;
+        // Released when `file` is closed
+        file.lock()?;
+
+        let (last, terminated) = read_tail(&mut file)?;
+        (record.sequence, record.prev_hash) = match last {
+            Some(last) => (last.sequence + 1, last.hash),
+            None => (0, GENESIS_HASH.to_string()),
+        };
+        record.hash = record.compute_hash();
+
+        // Terminate a torn final line so the record starts on its own
+        let newline = if terminated { "" } else { "\n" };
+        file.write_all(format!("{}{}\n", newline, serde_json::to_string(&record)?).as_bytes())?;
+        file.sync_data()?;
+        Ok(record)
+    }
+
+    pub fn records(&self) -> Result<Vec<AuditRecord>, AuditError> {
+        BufReader::new(File::open(&self.path)?)
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| line.as_ref().map_or(true, |line| !line.trim().is_empty()))
+            .map(|(index, line)| {
+                serde_json::from_str(&line?)
+                    .map_err(|e| AuditError::Malformed { line: index + 1, message: e.to_string() })
+            })
+            .collect()
+    }
+
+    pub fn query(&self, query: &AuditQuery) -> Result<Vec<AuditRecord>, AuditError> {
+        Ok(self.records()?.into_iter().filter(|record| query.matches(record)).collect())
+    }
+
+    /// Check every record's hash and link; returns how many were verified
+    pub fn verify(&self) -> Result<usize, AuditError> {
+        let records = self.records()?;
+        let mut prev_hash = GENESIS_HASH.to_string();
+
+        for (expected, record) in (0u64..).zip(&records) {
+            let tampered = |reason: &str| AuditError::Tampered { sequence: record.sequence, reason: reason.to_string() };
+
+            if record.sequence != expected {
+                return Err(tampered(&format!("expected sequence {}", expected)));
+            }
+            if record.prev_hash != prev_hash {
+                return Err(tampered("does not link to the previous record"));
+            }
+            if record.hash != record.compute_hash() {
+                return Err(tampered("contents do not match its hash"));
+            }
+            prev_hash = record.hash.clone();
+        }
+
+        Ok(records.len())
+    }
+}
+
+/// Bytes read at a time when looking for the last record
+const TAIL_CHUNK: u64 = 64 * 1024;
+
+/// The last record in `file` that parses, reading backwards from the end, and
+/// whether the file ends in a newline
+fn read_tail(file: &mut File) -> io::Result<(Option<AuditRecord>, bool)> {
+    let len = file.seek(SeekFrom::End(0))?;
+    let mut start = len;
+    let mut tail = Vec::new();
+    let mut terminated = true;
+
+    while start > 0 {
+        let chunk_start = start.saturating_sub(TAIL_CHUNK);
+        let mut chunk = vec![0; (start - chunk_start) as usize];
+        file.seek(SeekFrom::Start(chunk_start))?;
+        file.read_exact(&mut chunk)?;
+        if start == len {
+            terminated = chunk.last() == Some(&b'\n');
+        }
+        chunk.extend_from_slice(&tail);
+        tail = chunk;
+        start = chunk_start;
+
+        // Everything up to the first newline may continue in an earlier chunk
+        let complete = match tail.iter().position(|&byte| byte == b'\n') {
+            _ if start == 0 => 0,
+            Some(newline) => newline + 1,
+            None => continue,
+        };
+        for line in tail[complete..].rsplit(|&byte| byte == b'\n') {
+            if let Ok(record) = serde_json::from_slice(line) {
+                return Ok((Some(record), terminated));
+            }
+        }
+        tail.truncate(complete);
+    }
+
+    Ok((None, terminated))
+}
@@ -0,0 +1,76 @@
+//! Append-only log of actions Reaper takes on other processes
+//!
+//! Every terminate, kill, suspend, resume, priority change or limit, whether
+//! requested by the user or applied by a remediation policy, is appended as
+//! one JSON line recording who acted, on what, and with what result. Each
+//! record carries the SHA-256 of the one before it, so `AuditLog::verify`
+//! detects records that were edited, removed or reordered.
+
+mod log;
+mod pending;
+mod record;
+
+pub use log::{AuditError, AuditLog, AuditQuery};
+pub use pending::{AuditResult, PendingAudit};
+pub use record::{AuditRecord, GENESIS_HASH};
+
+use once_cell::sync::Lazy;
+use std::io;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+/// `~/.reaper/audit.jsonl`
+pub fn default_path() -> PathBuf {
+    let home = std::env::var("HOME").unwrap_or_else(|_| "/tmp".to_string());
+    PathBuf::from(home).join(".reaper").join("audit.jsonl")
+}
+
+/// Login of the user running Reaper, for `user:<login>` actors
+pub fn current_user() -> String {
+    std::env::var("USER")
+        .or_else(|_| std::env::var("LOGNAME"))
+        .unwrap_or_else(|_| "unknown".to_string())
+}
+
+static GLOBAL_LOG: Lazy<Option<Arc<Mutex<AuditLog>>>> = Lazy::new(|| match AuditLog::open(default_path()) {
+    Ok(log) => {
+        if let Some(line) = log.torn_line() {
+            eprintln!("Audit log {} has a damaged record on line {}", log.path().display(), line);
+        }
+        Some(Arc::new(Mutex::new(log)))
+    }
+    Err(e) => {
+        eprintln!("Failed to open audit log {}: {}", default_path().display(), e);
+        None
+    }
+});
+
+static WRITE_FAILURES: AtomicU64 = AtomicU64::new(0);
+
+/// The process-wide log at `default_path()`, if it could be opened
+pub fn global_log() -> Option<Arc<Mutex<AuditLog>>> {
+    GLOBAL_LOG.clone()
+}
+
+/// Append to the process-wide log; returns the record as stored. Failures,
+/// including a log that couldn't be opened, are also counted in
+/// `write_failures` for callers that can't return them.
+pub fn record(record: AuditRecord) -> io::Result<AuditRecord> {
+    let result = match global_log() {
+        Some(log) => log.lock().unwrap_or_else(|e| e.into_inner()).append(record),
+        None => Err(io::Error::new(io::ErrorKind::NotFound, "audit log could not be opened")),
+    };
+    if result.is_err() {
+        WRITE_FAILURES.fetch_add(1, Ordering::Relaxed);
+    }
+    result
+}
+
+/// How many `record` calls failed since startup
+pub fn write_failures() -> u64 {
+    WRITE_FAILURES.load(Ordering::Relaxed)
+}
+
+#[cfg(test)]
+mod tests;
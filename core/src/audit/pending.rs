@@ -0,0 +1,67 @@
+use super::{current_user, record, AuditRecord};
+use std::fmt;
+use sysinfo::{Pid, System};
+
+/// An error that can be written as the result of an audited call
+pub trait AuditResult: fmt::Debug {
+    /// Kind of failure, such as `PermissionDenied`, for the record's `result`
+    fn audit_result(&self) -> &'static str;
+
+    /// Description for the record's `outcome`
+    fn audit_outcome(&self) -> String {
+        format!("{:?}", self)
+    }
+}
+
+/// Audit record for a call that acts on a process, opened before the call so
+/// it captures the target while the process still exists
+pub struct PendingAudit {
+    record: AuditRecord,
+    priority: Option<fn(u32) -> Option<i32>>,
+}
+
+impl PendingAudit {
+    /// The current user doing `action` to `pid`
+    pub fn begin(action: &str, pid: u32) -> Self {
+        let mut record = AuditRecord::new(format!("user:{}", current_user()), action, pid);
+
+        let mut system = System::new();
+        let target = Pid::from_u32(pid);
+        if system.refresh_process(target) {
+            if let Some(process) = system.process(target) {
+                let exe = process.exe().map(|exe| exe.display().to_string());
+                record = record.with_process(process.name()).with_command(exe, process.cmd().to_vec());
+            }
+        }
+
+        Self { record, priority: None }
+    }
+
+    /// Record the target's priority, as `read` reports it, before the call
+    /// and again when it finishes
+    pub fn with_priority(mut self, read: fn(u32) -> Option<i32>) -> Self {
+        self.record.priority_before = read(self.record.pid);
+        self.priority = Some(read);
+        self
+    }
+
+    /// Write the record with the call's result. The action has already
+    /// happened, so a failed write can't fail the call; it is reported on
+    /// stderr and counted in `write_failures`.
+    pub fn finish(self, result: &str, outcome: impl Into<String>) {
+        let pid = self.record.pid;
+        let mut finished = self.record.with_result(result, outcome);
+        finished.priority_after = self.priority.and_then(|read| read(pid));
+        if let Err(e) = record(finished) {
+            eprintln!("Failed to write audit record for process {}: {}", pid, e);
+        }
+    }
+
+    /// `finish` with `Success` and `success`, or with the error
+    pub fn finish_result<E: AuditResult>(self, result: &Result<(), E>, success: String) {
+        match result {
+            Ok(()) => self.finish("Success", success),
+            Err(error) => self.finish(error.audit_result(), error.audit_outcome()),
+        }
+    }
+}
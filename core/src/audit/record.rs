@@ -0,0 +1,105 @@
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+/// `prev_hash` of the first record in a log
+pub const GENESIS_HASH: &str = "0000000000000000000000000000000000000000000000000000000000000000";
+
+/// One action taken, or simulated in dry-run mode, against a process
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AuditRecord {
+    /// Position in the log, starting at 0
+    #[serde(default)]
+    pub sequence: u64,
+    /// Unix time in seconds
+    pub timestamp: u64,
+    /// Who asked: `user:<login>` for direct requests, `policy:<name>` for
+    /// remediation
+    pub actor: String,
+    pub action: String,
+    pub pid: u32,
+    pub process: Option<String>,
+    #[serde(default)]
+    pub exe: Option<String>,
+    #[serde(default)]
+    pub cmdline: Vec<String>,
+    /// Kind of result, such as `Success` or `PermissionDenied`
+    #[serde(default)]
+    pub result: String,
+    pub outcome: String,
+    #[serde(default)]
+    pub priority_before: Option<i32>,
+    #[serde(default)]
+    pub priority_after: Option<i32>,
+    #[serde(default)]
+    pub dry_run: bool,
+    /// `hash` of the previous record
+    #[serde(default)]
+    pub prev_hash: String,
+    /// SHA-256 over this record's other fields, including `prev_hash`
+    #[serde(default)]
+    pub hash: String,
+}
+
+impl AuditRecord {
+    pub fn new(actor: impl Into<String>, action: impl Into<String>, pid: u32) -> Self {
+        Self {
+            sequence: 0,
+            timestamp: crate::metrics::unix_now(),
+            actor: actor.into(),
+            action: action.into(),
+            pid,
+            process: None,
+            exe: None,
+            cmdline: Vec::new(),
+            result: String::new(),
+            outcome: String::new(),
+            priority_before: None,
+            priority_after: None,
+            dry_run: false,
+            prev_hash: String::new(),
+            hash: String::new(),
+        }
+    }
+
+    pub fn with_process(mut self, process: impl Into<String>) -> Self {
+        self.process = Some(process.into());
+        self
+    }
+
+    /// Executable path and command line of the target
+    pub fn with_command(mut self, exe: Option<String>, cmdline: Vec<String>) -> Self {
+        self.exe = exe;
+        self.cmdline = cmdline;
+        self
+    }
+
+    pub fn with_result(mut self, result: impl Into<String>, outcome: impl Into<String>) -> Self {
+        self.result = result.into();
+        self.outcome = outcome.into();
+        self
+    }
+
+    pub fn with_outcome(mut self, outcome: impl Into<String>) -> Self {
+        self.outcome = outcome.into();
+        self
+    }
+
+    pub fn with_priority(mut self, before: Option<i32>, after: Option<i32>) -> Self {
+        self.priority_before = before;
+        self.priority_after = after;
+        self
+    }
+
+    pub fn with_dry_run(mut self, dry_run: bool) -> Self {
+        self.dry_run = dry_run;
+        self
+    }
+
+    /// The hash this record should carry given its contents
+    pub fn compute_hash(&self) -> String {
+        let unhashed = AuditRecord { hash: String::new(), ..self.clone() };
+        let json = serde_json::to_vec(&unhashed).unwrap_or_default();
+
+        Sha256::digest(&json).iter().map(|byte| format!("{:02x}", byte)).collect()
+    }
+}
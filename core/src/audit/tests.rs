@@ -0,0 +1,152 @@
+use super::*;
+
+fn kill(pid: u32, timestamp: u64) -> AuditRecord {
+    let mut record = AuditRecord::new("user:alice", "kill", pid)
+        .with_process("node")
+        .with_command(Some("/usr/bin/node".to_string()), vec!["node".to_string(), "server.js".to_string()])
+        .with_result("Success", "Process killed successfully")
+        .with_priority(Some(0), None);
+    record.timestamp = timestamp;
+    record
+}
+
+#[test]
+fn test_records_are_chained() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("audit.jsonl");
+
+    let mut log = AuditLog::open(&path).unwrap();
+    let first = log.append(kill(10, 100)).unwrap();
+    let second = log.append(kill(11, 200)).unwrap();
+    assert_eq!(first.sequence, 0);
+    assert_eq!(first.prev_hash, GENESIS_HASH);
+    assert_eq!(second.prev_hash, first.hash);
+
+    // Reopening continues the chain
+    let mut log = AuditLog::open(&path).unwrap();
+    let third = log.append(kill(12, 300)).unwrap();
+    assert_eq!(third.sequence, 2);
+    assert_eq!(third.prev_hash, second.hash);
+    assert_eq!(log.verify().unwrap(), 3);
+    assert_eq!(log.records().unwrap()[0].cmdline, vec!["node", "server.js"]);
+}
+
+#[test]
+fn test_verify_detects_tampering() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("audit.jsonl");
+    let mut log = AuditLog::open(&path).unwrap();
+    for pid in 1..=3 {
+        log.append(kill(pid, 100)).unwrap();
+    }
+    let original = std::fs::read_to_string(&path).unwrap();
+    let lines: Vec<&str> = original.lines().collect();
+
+    // Edited outcome
+    std::fs::write(&path, original.replacen("killed successfully", "was left alone", 1)).unwrap();
+    assert!(matches!(log.verify(), Err(AuditError::Tampered { sequence: 0, .. })));
+
+    // Removed record
+    std::fs::write(&path, format!("{}\n{}\n", lines[0], lines[2])).unwrap();
+    assert!(matches!(log.verify(), Err(AuditError::Tampered { sequence: 2, .. })));
+
+    // Garbage line
+    std::fs::write(&path, format!("{}\nnot json\n", lines[0])).unwrap();
+    assert!(matches!(log.verify(), Err(AuditError::Malformed { line: 2, .. })));
+}
+
+#[test]
+fn test_query_by_time_and_pid() {
+    let dir = tempfile::tempdir().unwrap();
+    let mut log = AuditLog::open(dir.path().join("audit.jsonl")).unwrap();
+    log.append(kill(1, 100)).unwrap();
+    log.append(kill(2, 200)).unwrap();
+    log.append(kill(1, 300)).unwrap();
+
+    let pids = |query: AuditQuery| -> Vec<(u32, u64)> {
+        log.query(&query).unwrap().iter().map(|r| (r.pid, r.timestamp)).collect()
+    };
+    assert_eq!(pids(AuditQuery::default()).len(), 3);
+    assert_eq!(pids(AuditQuery::default().for_pid(1)), vec![(1, 100), (1, 300)]);
+    assert_eq!(pids(AuditQuery::default().between(100, 300)), vec![(1, 100), (2, 200)]);
+    assert_eq!(pids(AuditQuery::default().between(150, 400).for_pid(1)), vec![(1, 300)]);
+}
+
+#[test]
+fn test_open_recovers_from_torn_write() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("audit.jsonl");
+    let mut log = AuditLog::open(&path).unwrap();
+    log.append(kill(1, 100)).unwrap();
+    let second = log.append(kill(2, 200)).unwrap();
+    assert_eq!(log.torn_line(), None);
+
+    // A crash mid-write leaves half a record without its newline
+    let torn = serde_json::to_string(&kill(3, 300)).unwrap();
+    let mut contents = std::fs::read_to_string(&path).unwrap();
+    contents.push_str(&torn[..torn.len() / 2]);
+    std::fs::write(&path, contents).unwrap();
+
+    // The chain continues from the last valid record instead of restarting
+    let mut log = AuditLog::open(&path).unwrap();
+    assert_eq!(log.torn_line(), Some(3));
+    let third = log.append(kill(4, 400)).unwrap();
+    assert_eq!(third.sequence, 2);
+    assert_eq!(third.prev_hash, second.hash);
+    assert_ne!(third.prev_hash, GENESIS_HASH);
+
+    // The new record got its own line; only the torn one is unreadable
+    assert!(matches!(log.verify(), Err(AuditError::Malformed { line: 3, .. })));
+    let lines: Vec<String> = std::fs::read_to_string(&path).unwrap().lines().map(String::from).collect();
+    assert_eq!(lines.len(), 4);
+    assert_eq!(serde_json::from_str::<AuditRecord>(&lines[3]).unwrap(), third);
+
+    // Once the damaged line is removed the chain verifies
+    std::fs::write(&path, format!("{}\n{}\n{}\n", lines[0], lines[1], lines[3])).unwrap();
+    assert_eq!(AuditLog::open(&path).unwrap().torn_line(), None);
+    assert_eq!(log.verify().unwrap(), 3);
+}
+
+#[test]
+fn test_handles_share_one_chain() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("audit.jsonl");
+    let mut first = AuditLog::open(&path).unwrap();
+    let mut second = AuditLog::open(&path).unwrap();
+    first.append(kill(1, 100)).unwrap();
+    let appended = second.append(kill(2, 200)).unwrap();
+    assert_eq!(appended.sequence, 1);
+    first.append(kill(3, 300)).unwrap();
+    assert_eq!(first.verify().unwrap(), 3);
+
+    // Threads with their own handles, as separate processes would have
+    let threads: Vec<_> = (0..4)
+        .map(|thread| {
+            let path = path.clone();
+            std::thread::spawn(move || {
+                let mut log = AuditLog::open(&path).unwrap();
+                for i in 0..25 {
+                    log.append(kill(thread * 100 + i, 400)).unwrap();
+                }
+            })
+        })
+        .collect();
+    for thread in threads {
+        thread.join().unwrap();
+    }
+    assert_eq!(first.verify().unwrap(), 103);
+}
+
+#[test]
+fn test_chains_from_records_longer_than_a_read() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("audit.jsonl");
+    let mut log = AuditLog::open(&path).unwrap();
+    let mut long = kill(1, 100);
+    long.cmdline = vec!["x".repeat(200 * 1024)];
+    let long = log.append(long).unwrap();
+    let next = log.append(kill(2, 200)).unwrap();
+    assert_eq!(next.prev_hash, long.hash);
+    log.append(kill(3, 300)).unwrap();
+    assert_eq!(log.verify().unwrap(), 3);
+}
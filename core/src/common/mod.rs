@@ -15,10 +15,16 @@ pub enum MonitorType {
     Memory,
     Disk,
     Network,
+    Hardware,
 }
 
 pub trait Monitor {
     fn name(&self) -> &str;
     fn monitor_type(&self) -> MonitorType;
     fn refresh(&mut self);
+
+    /// Readings to persist in the metrics store after each refresh
+    fn metric_samples(&self) -> Vec<crate::metrics::MetricSample> {
+        Vec::new()
+    }
 }
\ No newline at end of file
@@ -1,5 +1,7 @@
-use std::ffi::CString;
+use once_cell::sync::Lazy;
+use std::ffi::{c_void, CStr, CString};
 use std::os::raw::c_char;
+use std::sync::{Mutex, Once};
 
 #[no_mangle]
 pub extern "C" fn free_rust_string(s: *mut c_char) {
@@ -14,4 +16,182 @@ pub fn string_to_c(s: String) -> *mut c_char {
     CString::new(s)
         .unwrap_or_else(|_| CString::new("").unwrap())
         .into_raw()
-}
\ No newline at end of file
+}
+
+/// Start the shared sampling loop that refreshes all registered monitors
+#[no_mangle]
+pub extern "C" fn reaper_sampling_start() {
+    crate::sampling::start_global();
+}
+
+#[no_mangle]
+pub extern "C" fn reaper_sampling_stop() {
+    crate::sampling::stop_global();
+}
+
+#[no_mangle]
+pub extern "C" fn reaper_sampling_is_running() -> bool {
+    crate::sampling::is_global_running()
+}
+
+/// Persist monitor readings in the metrics store under `data_directory`,
+/// or the default `~/.reaper/metrics` when it is null
+///
+/// # Safety
+/// `data_directory` must be null or a valid NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn reaper_metrics_enable(data_directory: *const c_char) -> bool {
+    let mut config = crate::metrics::MetricsStoreConfig::default();
+    if !data_directory.is_null() {
+        config.data_directory = CStr::from_ptr(data_directory).to_string_lossy().into_owned().into();
+    }
+    crate::metrics::enable_global(config).is_ok()
+}
+
+/// Run a JSON-encoded `RangeQuery` and return the matching series as JSON,
+/// or null when the store is disabled or the query is invalid
+///
+/// # Safety
+/// `query_json` must be a valid NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn reaper_metrics_query(query_json: *const c_char) -> *mut c_char {
+    if query_json.is_null() {
+        return std::ptr::null_mut();
+    }
+    let Ok(query) = serde_json::from_slice::<crate::metrics::RangeQuery>(CStr::from_ptr(query_json).to_bytes()) else {
+        return std::ptr::null_mut();
+    };
+    let Some(store) = crate::metrics::global_store() else {
+        return std::ptr::null_mut();
+    };
+
+    let result = match store.lock() {
+        Ok(store) => store.query(&query),
+        Err(_) => return std::ptr::null_mut(),
+    };
+    match result.ok().and_then(|series| serde_json::to_string(&series).ok()) {
+        Some(json) => string_to_c(json),
+        None => std::ptr::null_mut(),
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn reaper_metrics_flush() -> bool {
+    match crate::metrics::global_store() {
+        Some(store) => store.lock().map(|mut s| s.flush().is_ok()).unwrap_or(false),
+        None => false,
+    }
+}
+
+/// Receives each alert transition as a JSON-encoded `alerts::Alert`. The
+/// string is only valid for the duration of the call.
+pub type AlertCallback = extern "C" fn(alert_json: *const c_char, user_data: *mut c_void);
+
+#[derive(Clone, Copy)]
+struct RegisteredCallback {
+    callback: AlertCallback,
+    user_data: *mut c_void,
+}
+
+// The caller guarantees `user_data` may be used from the dispatch thread
+unsafe impl Send for RegisteredCallback {}
+
+static ALERT_CALLBACK: Lazy<Mutex<Option<RegisteredCallback>>> = Lazy::new(|| Mutex::new(None));
+static ALERT_DISPATCHER: Once = Once::new();
+
+/// Load alert rules from a `.toml` or `.json` file, replacing the current
+/// ones. Returns the number of rules, or -1 if the file is invalid.
+///
+/// # Safety
+/// `path` must be a valid NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn reaper_alerts_load_rules(path: *const c_char) -> i32 {
+    if path.is_null() {
+        return -1;
+    }
+    let path = CStr::from_ptr(path).to_string_lossy().into_owned();
+    match crate::alerts::load_global_rules(std::path::Path::new(&path)) {
+        Ok(count) => count as i32,
+        Err(_) => -1,
+    }
+}
+
+/// Register `callback` for alert transitions, or clear it with null.
+/// Callbacks run on a dedicated background thread, one at a time.
+#[no_mangle]
+pub extern "C" fn reaper_alerts_set_callback(callback: Option<AlertCallback>, user_data: *mut c_void) {
+    *ALERT_CALLBACK.lock().unwrap_or_else(|e| e.into_inner()) =
+        callback.map(|callback| RegisteredCallback { callback, user_data });
+
+    ALERT_DISPATCHER.call_once(|| {
+        let receiver = crate::alerts::global_engine()
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .subscribe();
+
+        let _ = std::thread::Builder::new()
+            .name("reaper-alerts".to_string())
+            .spawn(move || {
+                for alert in receiver {
+                    let Ok(json) = serde_json::to_string(&alert) else {
+                        continue;
+                    };
+                    let json = CString::new(json).unwrap_or_default();
+                    // Copied out so the callback may re-register itself
+                    let registered = *ALERT_CALLBACK.lock().unwrap_or_else(|e| e.into_inner());
+                    if let Some(registered) = registered {
+                        (registered.callback)(json.as_ptr(), registered.user_data);
+                    }
+                }
+            });
+    });
+}
+
+/// Pending and firing alerts as a JSON array
+#[no_mangle]
+pub extern "C" fn reaper_alerts_active() -> *mut c_char {
+    let alerts = crate::alerts::global_engine()
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .active_alerts();
+    string_to_c(serde_json::to_string(&alerts).unwrap_or_else(|_| "[]".to_string()))
+}
+
+/// Audit records as a JSON array, filtered to `[start, end)` Unix seconds
+/// and to `pid`. Pass 0 for any bound or pid to leave it open. Returns null
+/// if the log can't be read.
+#[no_mangle]
+pub extern "C" fn reaper_audit_query(start: u64, end: u64, pid: u32) -> *mut c_char {
+    let Some(log) = crate::audit::global_log() else {
+        return std::ptr::null_mut();
+    };
+    let query = crate::audit::AuditQuery {
+        start: (start != 0).then_some(start),
+        end: (end != 0).then_some(end),
+        pid: (pid != 0).then_some(pid),
+    };
+
+    let records = log.lock().unwrap_or_else(|e| e.into_inner()).query(&query);
+    match records {
+        Ok(records) => string_to_c(serde_json::to_string(&records).unwrap_or_else(|_| "[]".to_string())),
+        Err(_) => std::ptr::null_mut(),
+    }
+}
+
+/// Verify the audit log's hash chain. Returns the number of records, or -1
+/// if any record was altered, removed or is unreadable.
+#[no_mangle]
+pub extern "C" fn reaper_audit_verify() -> i64 {
+    let Some(log) = crate::audit::global_log() else {
+        return -1;
+    };
+    let verified = log.lock().unwrap_or_else(|e| e.into_inner()).verify();
+    verified.map_or(-1, |count| count as i64)
+}
+
+/// How many audit records failed to be written since startup, so the app can
+/// warn that actions went unrecorded
+#[no_mangle]
+pub extern "C" fn reaper_audit_write_failures() -> u64 {
+    crate::audit::write_failures()
+}
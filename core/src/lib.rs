@@ -1,6 +1,10 @@
 pub mod ffi;
+pub mod alerts;
+pub mod audit;
 pub mod common;
+pub mod metrics;
 pub mod platform;
+pub mod sampling;
 
 pub use common::*;
 pub use ffi::*;
@@ -0,0 +1,38 @@
+//! Persisted time-series store shared by all monitors
+//!
+//! Monitors report readings through `Monitor::metric_samples`; the sampling
+//! engine records them into a `MetricsStore`, which keeps raw, per-minute
+//! and per-hour tiers on disk and answers range queries with aligned
+//! buckets.
+
+mod series;
+mod store;
+
+pub use series::{Aggregate, MetricSample, SeriesKey, Tier};
+pub use store::{unix_now, MetricsStore, MetricsStoreConfig, RangeBucket, RangeQuery, SeriesRange, MAX_QUERY_BUCKETS};
+
+use once_cell::sync::Lazy;
+use std::sync::{Arc, Mutex};
+
+static GLOBAL_STORE: Lazy<Mutex<Option<Arc<Mutex<MetricsStore>>>>> = Lazy::new(|| Mutex::new(None));
+
+/// Open the process-wide store and have the global sampling engine record
+/// into it. Replaces a previously enabled store.
+pub fn enable_global(config: MetricsStoreConfig) -> std::io::Result<Arc<Mutex<MetricsStore>>> {
+    let store = Arc::new(Mutex::new(MetricsStore::new(config)?));
+    *GLOBAL_STORE.lock().unwrap_or_else(|e| e.into_inner()) = Some(Arc::clone(&store));
+
+    crate::sampling::global_engine()
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .set_metrics_store(Arc::clone(&store));
+
+    Ok(store)
+}
+
+pub fn global_store() -> Option<Arc<Mutex<MetricsStore>>> {
+    GLOBAL_STORE.lock().unwrap_or_else(|e| e.into_inner()).clone()
+}
+
+#[cfg(test)]
+mod tests;
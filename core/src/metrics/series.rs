@@ -0,0 +1,126 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+
+/// Identifies one time series: a metric name plus labels such as `pid`,
+/// `interface`, `mount_point` or `sensor`
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct SeriesKey {
+    pub name: String,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub labels: BTreeMap<String, String>,
+}
+
+impl SeriesKey {
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            labels: BTreeMap::new(),
+        }
+    }
+
+    pub fn with_label(mut self, key: impl Into<String>, value: impl ToString) -> Self {
+        self.labels.insert(key.into(), value.to_string());
+        self
+    }
+
+    /// Whether this series has `name` and carries every label in `labels`
+    pub fn matches(&self, name: &str, labels: &BTreeMap<String, String>) -> bool {
+        self.name == name && labels.iter().all(|(k, v)| self.labels.get(k) == Some(v))
+    }
+}
+
+impl fmt::Display for SeriesKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name)?;
+        if !self.labels.is_empty() {
+            let labels: Vec<String> = self.labels
+                .iter()
+                .map(|(k, v)| format!("{}=\"{}\"", k, v))
+                .collect();
+            write!(f, "{{{}}}", labels.join(","))?;
+        }
+        Ok(())
+    }
+}
+
+/// One reading reported by a monitor
+#[derive(Debug, Clone, PartialEq)]
+pub struct MetricSample {
+    pub key: SeriesKey,
+    pub value: f64,
+}
+
+impl MetricSample {
+    pub fn new(key: SeriesKey, value: f64) -> Self {
+        Self { key, value }
+    }
+}
+
+/// Summary of the readings that fell into one bucket
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Aggregate {
+    pub count: u64,
+    pub sum: f64,
+    pub min: f64,
+    pub max: f64,
+    pub last: f64,
+}
+
+impl Aggregate {
+    pub fn single(value: f64) -> Self {
+        Self {
+            count: 1,
+            sum: value,
+            min: value,
+            max: value,
+            last: value,
+        }
+    }
+
+    /// Fold `other`, which covers a later time span, into this aggregate
+    pub fn merge(&mut self, other: &Aggregate) {
+        self.count += other.count;
+        self.sum += other.sum;
+        self.min = self.min.min(other.min);
+        self.max = self.max.max(other.max);
+        self.last = other.last;
+    }
+
+    pub fn mean(&self) -> f64 {
+        if self.count == 0 {
+            0.0
+        } else {
+            self.sum / self.count as f64
+        }
+    }
+}
+
+/// Storage resolution. Raw keeps every reading; the rollup tiers keep one
+/// aggregate per series per minute or hour.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum Tier {
+    Raw,
+    Minute,
+    Hour,
+}
+
+impl Tier {
+    pub const ALL: [Tier; 3] = [Tier::Raw, Tier::Minute, Tier::Hour];
+
+    pub fn resolution_secs(self) -> u64 {
+        match self {
+            Tier::Raw => 1,
+            Tier::Minute => 60,
+            Tier::Hour => 3600,
+        }
+    }
+
+    pub fn dir_name(self) -> &'static str {
+        match self {
+            Tier::Raw => "raw",
+            Tier::Minute => "1m",
+            Tier::Hour => "1h",
+        }
+    }
+}
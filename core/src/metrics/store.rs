@@ -0,0 +1,389 @@
+use super::{Aggregate, MetricSample, SeriesKey, Tier};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::fs::{File, OpenOptions};
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const SECONDS_PER_DAY: u64 = 86400;
+
+/// Most buckets a single query may ask for
+pub const MAX_QUERY_BUCKETS: u64 = 100_000;
+
+/// Configuration for the metrics store
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MetricsStoreConfig {
+    pub data_directory: PathBuf,
+    pub raw_retention_days: u32,
+    pub minute_retention_days: u32,
+    pub hour_retention_days: u32,
+    pub flush_interval_seconds: u64,
+}
+
+impl Default for MetricsStoreConfig {
+    fn default() -> Self {
+        let home = std::env::var("HOME").unwrap_or_else(|_| "/tmp".to_string());
+
+        Self {
+            data_directory: PathBuf::from(home).join(".reaper").join("metrics"),
+            raw_retention_days: 2,
+            minute_retention_days: 30,
+            hour_retention_days: 365,
+            flush_interval_seconds: 60,
+        }
+    }
+}
+
+impl MetricsStoreConfig {
+    pub fn retention_secs(&self, tier: Tier) -> u64 {
+        let days = match tier {
+            Tier::Raw => self.raw_retention_days,
+            Tier::Minute => self.minute_retention_days,
+            Tier::Hour => self.hour_retention_days,
+        };
+        days as u64 * SECONDS_PER_DAY
+    }
+}
+
+/// One persisted line: a raw reading, or a finished rollup bucket
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct StoredPoint {
+    key: SeriesKey,
+    timestamp: u64,
+    aggregate: Aggregate,
+}
+
+/// A range query over all series with a given name and labels
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RangeQuery {
+    pub name: String,
+    #[serde(default)]
+    pub labels: BTreeMap<String, String>,
+    pub start: u64,
+    pub end: u64,
+    pub step: u64,
+}
+
+impl RangeQuery {
+    pub fn new(name: impl Into<String>, start: u64, end: u64, step: u64) -> Self {
+        Self {
+            name: name.into(),
+            labels: BTreeMap::new(),
+            start,
+            end,
+            step: step.max(1),
+        }
+    }
+
+    pub fn with_label(mut self, key: impl Into<String>, value: impl ToString) -> Self {
+        self.labels.insert(key.into(), value.to_string());
+        self
+    }
+
+    /// First bucket start: `start` rounded down to a multiple of `step`
+    pub fn aligned_start(&self) -> u64 {
+        self.start - self.start.checked_rem(self.step).unwrap_or(0)
+    }
+
+    pub fn bucket_count(&self) -> usize {
+        usize::try_from(self.buckets()).unwrap_or(usize::MAX)
+    }
+
+    fn buckets(&self) -> u64 {
+        self.end.checked_sub(self.aligned_start())
+            .and_then(|span| span.checked_div(self.step))
+            .map_or(0, |steps| steps.saturating_add(1))
+    }
+
+    /// Rejects a zero step and ranges of more than `MAX_QUERY_BUCKETS` steps.
+    /// Queries come straight from JSON, so `new`'s clamping can't be relied on
+    pub fn validate(&self) -> io::Result<()> {
+        if self.step == 0 {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, "query step must be positive"));
+        }
+        if self.buckets() > MAX_QUERY_BUCKETS {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("query spans more than {} steps", MAX_QUERY_BUCKETS),
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// One step of a query result; `aggregate` is `None` when no data fell in it
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RangeBucket {
+    pub start: u64,
+    pub aggregate: Option<Aggregate>,
+}
+
+/// Query result for one series, with one bucket per step
+#[derive(Debug, Clone, Serialize)]
+pub struct SeriesRange {
+    pub key: SeriesKey,
+    pub tier: Tier,
+    pub buckets: Vec<RangeBucket>,
+}
+
+/// Persisted, downsampled store for time series from any monitor
+///
+/// Every reading goes to the raw tier and is rolled up into per-minute and
+/// per-hour aggregates. Each tier is appended to daily JSONL files under
+/// `<data_directory>/<tier>/` and pruned after its own retention period.
+pub struct MetricsStore {
+    config: MetricsStoreConfig,
+    pending: HashMap<Tier, Vec<StoredPoint>>,
+    open_rollups: HashMap<(Tier, SeriesKey), (u64, Aggregate)>,
+    last_flush_time: SystemTime,
+}
+
+impl MetricsStore {
+    pub fn new(config: MetricsStoreConfig) -> io::Result<Self> {
+        for tier in Tier::ALL {
+            std::fs::create_dir_all(config.data_directory.join(tier.dir_name()))?;
+        }
+
+        Ok(Self {
+            config,
+            pending: HashMap::new(),
+            open_rollups: HashMap::new(),
+            last_flush_time: SystemTime::now(),
+        })
+    }
+
+    pub fn config(&self) -> &MetricsStoreConfig {
+        &self.config
+    }
+
+    /// Record one reading taken at `timestamp` (Unix seconds)
+    pub fn record(&mut self, timestamp: u64, key: SeriesKey, value: f64) {
+        let reading = Aggregate::single(value);
+
+        for tier in [Tier::Minute, Tier::Hour] {
+            let bucket = timestamp - timestamp % tier.resolution_secs();
+            match self.open_rollups.get_mut(&(tier, key.clone())) {
+                Some((start, aggregate)) if *start == bucket => aggregate.merge(&reading),
+                Some((start, aggregate)) => {
+                    let finished = StoredPoint { key: key.clone(), timestamp: *start, aggregate: *aggregate };
+                    self.pending.entry(tier).or_default().push(finished);
+                    *start = bucket;
+                    *aggregate = reading;
+                }
+                None => {
+                    self.open_rollups.insert((tier, key.clone()), (bucket, reading));
+                }
+            }
+        }
+
+        self.pending.entry(Tier::Raw).or_default().push(StoredPoint {
+            key,
+            timestamp,
+            aggregate: reading,
+        });
+    }
+
+    /// Record a batch of readings and flush if the flush interval has passed
+    pub fn record_all(&mut self, timestamp: u64, samples: Vec<MetricSample>) -> io::Result<()> {
+        for sample in samples {
+            self.record(timestamp, sample.key, sample.value);
+        }
+        self.finish_rollups(timestamp);
+
+        let should_flush = self.last_flush_time
+            .elapsed()
+            .unwrap_or_default()
+            .as_secs() >= self.config.flush_interval_seconds;
+
+        if should_flush {
+            self.flush()?;
+            self.apply_retention(unix_now())?;
+        }
+
+        Ok(())
+    }
+
+    /// Move rollup buckets that ended by `timestamp` to `pending`. `record`
+    /// only finishes a bucket when its series reports again, which series
+    /// that stop, such as those of exited processes, never do
+    fn finish_rollups(&mut self, timestamp: u64) {
+        let pending = &mut self.pending;
+        self.open_rollups.retain(|(tier, key), (start, aggregate)| {
+            let open = start.saturating_add(tier.resolution_secs()) > timestamp;
+            if !open {
+                let finished = StoredPoint { key: key.clone(), timestamp: *start, aggregate: *aggregate };
+                pending.entry(*tier).or_default().push(finished);
+            }
+            open
+        });
+    }
+
+    /// Append finished points to their daily files
+    pub fn flush(&mut self) -> io::Result<()> {
+        for (tier, points) in self.pending.iter_mut() {
+            let mut by_day: BTreeMap<String, Vec<&StoredPoint>> = BTreeMap::new();
+            for point in points.iter() {
+                by_day.entry(date_string(point.timestamp)).or_default().push(point);
+            }
+
+            for (date, day_points) in by_day {
+                let path = self.config.data_directory
+                    .join(tier.dir_name())
+                    .join(format!("{}.jsonl", date));
+                let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+                for point in day_points {
+                    writeln!(file, "{}", serde_json::to_string(point)?)?;
+                }
+                file.flush()?;
+            }
+
+            points.clear();
+        }
+
+        self.last_flush_time = SystemTime::now();
+        Ok(())
+    }
+
+    /// Finish all rollup buckets that are still open and flush everything.
+    /// A bucket finished early is merged with the rest of its readings at
+    /// query time.
+    pub fn close(&mut self) -> io::Result<()> {
+        for ((tier, key), (start, aggregate)) in self.open_rollups.drain() {
+            self.pending.entry(tier).or_default().push(StoredPoint { key, timestamp: start, aggregate });
+        }
+        self.flush()
+    }
+
+    /// Delete daily files older than their tier's retention; returns how many
+    pub fn apply_retention(&mut self, now: u64) -> io::Result<usize> {
+        let mut removed = 0;
+
+        for tier in Tier::ALL {
+            let cutoff = date_string(now.saturating_sub(self.config.retention_secs(tier)));
+            let dir = self.config.data_directory.join(tier.dir_name());
+
+            for entry in std::fs::read_dir(&dir)? {
+                let path = entry?.path();
+                let Some(date) = path.file_name()
+                    .and_then(|n| n.to_str())
+                    .and_then(|n| n.strip_suffix(".jsonl"))
+                else {
+                    continue;
+                };
+                if date < cutoff.as_str() {
+                    std::fs::remove_file(&path)?;
+                    removed += 1;
+                }
+            }
+        }
+
+        Ok(removed)
+    }
+
+    /// Tier used to answer `query`: the coarsest one no coarser than the
+    /// step whose retention still reaches back to the query start
+    pub fn tier_for(&self, query: &RangeQuery, now: u64) -> Tier {
+        let covers = |tier: Tier| now.saturating_sub(self.config.retention_secs(tier)) <= query.start;
+
+        Tier::ALL.into_iter()
+            .rev()
+            .find(|&tier| tier.resolution_secs() <= query.step && covers(tier))
+            .or_else(|| Tier::ALL.into_iter().find(|&tier| covers(tier)))
+            .unwrap_or(Tier::Hour)
+    }
+
+    /// Aligned buckets for every series matching the query's name and labels
+    pub fn query(&self, query: &RangeQuery) -> io::Result<Vec<SeriesRange>> {
+        let tier = self.tier_for(query, unix_now());
+        self.query_tier(query, tier)
+    }
+
+    pub fn query_tier(&self, query: &RangeQuery, tier: Tier) -> io::Result<Vec<SeriesRange>> {
+        query.validate()?;
+        let aligned_start = query.aligned_start();
+        let bucket_count = query.bucket_count();
+        let mut series: BTreeMap<SeriesKey, Vec<Option<Aggregate>>> = BTreeMap::new();
+
+        let mut add = |key: &SeriesKey, timestamp: u64, aggregate: &Aggregate| {
+            if timestamp < aligned_start || timestamp > query.end || !key.matches(&query.name, &query.labels) {
+                return;
+            }
+            let index = ((timestamp - aligned_start) / query.step) as usize;
+            let buckets = series.entry(key.clone()).or_insert_with(|| vec![None; bucket_count]);
+            match &mut buckets[index] {
+                Some(existing) => existing.merge(aggregate),
+                slot => *slot = Some(*aggregate),
+            }
+        };
+
+        // Files are per UTC day, so scan every file the range touches
+        let (first_day, last_day) = (date_string(aligned_start), date_string(query.end));
+        let mut paths: Vec<PathBuf> = match std::fs::read_dir(self.config.data_directory.join(tier.dir_name())) {
+            Ok(entries) => entries.filter_map(|entry| entry.ok().map(|e| e.path())).collect(),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Vec::new(),
+            Err(e) => return Err(e),
+        };
+        paths.retain(|path| {
+            path.file_name()
+                .and_then(|n| n.to_str())
+                .and_then(|n| n.strip_suffix(".jsonl"))
+                .is_some_and(|date| date >= first_day.as_str() && date <= last_day.as_str())
+        });
+        paths.sort();
+        for path in paths {
+            for line in BufReader::new(File::open(path)?).lines() {
+                if let Ok(point) = serde_json::from_str::<StoredPoint>(&line?) {
+                    add(&point.key, point.timestamp, &point.aggregate);
+                }
+            }
+        }
+
+        for point in self.pending.get(&tier).into_iter().flatten() {
+            add(&point.key, point.timestamp, &point.aggregate);
+        }
+        for ((open_tier, key), (start, aggregate)) in &self.open_rollups {
+            if *open_tier == tier {
+                add(key, *start, aggregate);
+            }
+        }
+
+        Ok(series.into_iter()
+            .map(|(key, buckets)| SeriesRange {
+                key,
+                tier,
+                buckets: buckets.into_iter()
+                    .enumerate()
+                    .map(|(i, aggregate)| RangeBucket {
+                        start: aligned_start + i as u64 * query.step,
+                        aggregate,
+                    })
+                    .collect(),
+            })
+            .collect())
+    }
+}
+
+impl Drop for MetricsStore {
+    fn drop(&mut self) {
+        let _ = self.close();
+    }
+}
+
+pub fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+/// UTC `YYYY-MM-DD` of a Unix timestamp. Dates past year 9999 are clamped
+/// so the strings still sort like the dates
+fn date_string(timestamp: u64) -> String {
+    let epoch_date = chrono::NaiveDate::from_ymd_opt(1970, 1, 1).unwrap();
+    let last_date = chrono::NaiveDate::from_ymd_opt(9999, 12, 31).unwrap();
+    let date = epoch_date
+        .checked_add_days(chrono::Days::new(timestamp / SECONDS_PER_DAY))
+        .map_or(last_date, |date| date.min(last_date));
+    date.format("%Y-%m-%d").to_string()
+}
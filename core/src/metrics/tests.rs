@@ -0,0 +1,202 @@
+use super::*;
+use tempfile::TempDir;
+
+/// 2023-11-14 22:00:00 UTC, on an hour boundary
+const BASE: u64 = 1_699_999_200;
+
+fn store_in(dir: &TempDir) -> MetricsStore {
+    MetricsStore::new(MetricsStoreConfig {
+        data_directory: dir.path().to_path_buf(),
+        ..MetricsStoreConfig::default()
+    })
+    .unwrap()
+}
+
+fn memory_key() -> SeriesKey {
+    SeriesKey::new("memory.used_bytes")
+}
+
+#[test]
+fn test_series_key_labels() {
+    let key = SeriesKey::new("net.upload_bps").with_label("interface", "eth0");
+    let mut labels = std::collections::BTreeMap::new();
+
+    assert!(key.matches("net.upload_bps", &labels));
+    labels.insert("interface".to_string(), "eth0".to_string());
+    assert!(key.matches("net.upload_bps", &labels));
+    labels.insert("interface".to_string(), "wlan0".to_string());
+    assert!(!key.matches("net.upload_bps", &labels));
+
+    assert_eq!(key.to_string(), "net.upload_bps{interface=\"eth0\"}");
+}
+
+#[test]
+fn test_minute_rollup_buckets() {
+    let dir = TempDir::new().unwrap();
+    let mut store = store_in(&dir);
+
+    // Three minutes of readings every 10 seconds, value = minute index
+    for i in 0..18u64 {
+        store.record(BASE + i * 10, memory_key(), (i / 6) as f64 * 100.0 + i as f64);
+    }
+
+    let query = RangeQuery::new("memory.used_bytes", BASE, BASE + 179, 60);
+    let result = store.query_tier(&query, Tier::Minute).unwrap();
+    assert_eq!(result.len(), 1);
+
+    let buckets = &result[0].buckets;
+    assert_eq!(buckets.len(), 3);
+    assert_eq!(buckets[1].start, BASE + 60);
+
+    let second = buckets[1].aggregate.unwrap();
+    assert_eq!(second.count, 6);
+    assert_eq!(second.min, 106.0);
+    assert_eq!(second.max, 111.0);
+    assert_eq!(second.last, 111.0);
+    assert_eq!(second.mean(), 108.5);
+}
+
+#[test]
+fn test_query_aligns_and_fills_gaps() {
+    let dir = TempDir::new().unwrap();
+    let mut store = store_in(&dir);
+    store.record(BASE + 5, memory_key(), 1.0);
+    store.record(BASE + 125, memory_key(), 3.0);
+
+    // Unaligned start is rounded down to the step
+    let query = RangeQuery::new("memory.used_bytes", BASE + 30, BASE + 179, 60);
+    let buckets = &store.query_tier(&query, Tier::Raw).unwrap()[0].buckets;
+
+    let starts: Vec<u64> = buckets.iter().map(|b| b.start).collect();
+    assert_eq!(starts, vec![BASE, BASE + 60, BASE + 120]);
+    assert_eq!(buckets[0].aggregate.map(|a| a.last), Some(1.0));
+    assert_eq!(buckets[1].aggregate, None);
+    assert_eq!(buckets[2].aggregate.map(|a| a.last), Some(3.0));
+}
+
+#[test]
+fn test_label_filter_returns_each_series() {
+    let dir = TempDir::new().unwrap();
+    let mut store = store_in(&dir);
+    for (mount, used) in [("/", 10.0), ("/home", 20.0)] {
+        store.record(BASE, SeriesKey::new("disk.used_bytes").with_label("mount_point", mount), used);
+    }
+
+    let all = RangeQuery::new("disk.used_bytes", BASE, BASE, 1);
+    assert_eq!(store.query_tier(&all, Tier::Raw).unwrap().len(), 2);
+
+    let home = all.with_label("mount_point", "/home");
+    let result = store.query_tier(&home, Tier::Raw).unwrap();
+    assert_eq!(result.len(), 1);
+    assert_eq!(result[0].buckets[0].aggregate.map(|a| a.sum), Some(20.0));
+}
+
+#[test]
+fn test_persists_across_restart() {
+    let dir = TempDir::new().unwrap();
+    {
+        let mut store = store_in(&dir);
+        for i in 0..120u64 {
+            store.record(BASE + i, memory_key(), 1.0);
+        }
+        // Dropping closes the still-open rollup buckets
+    }
+
+    let mut store = store_in(&dir);
+    // Readings after the restart land in the hour bucket written at close
+    store.record(BASE + 200, memory_key(), 1.0);
+
+    let query = RangeQuery::new("memory.used_bytes", BASE, BASE + 3599, 3600);
+    let hour = store.query_tier(&query, Tier::Hour).unwrap()[0].buckets[0].aggregate.unwrap();
+    assert_eq!(hour.count, 121);
+
+    let raw = store.query_tier(&RangeQuery::new("memory.used_bytes", BASE, BASE + 119, 60), Tier::Raw).unwrap();
+    assert_eq!(raw[0].buckets.iter().map(|b| b.aggregate.unwrap().count).sum::<u64>(), 120);
+}
+
+#[test]
+fn test_stopped_series_are_written() {
+    let dir = TempDir::new().unwrap();
+    let mut store = MetricsStore::new(MetricsStoreConfig {
+        data_directory: dir.path().to_path_buf(),
+        raw_retention_days: 100_000,
+        minute_retention_days: 100_000,
+        hour_retention_days: 100_000,
+        flush_interval_seconds: 0,
+    })
+    .unwrap();
+    let process = |pid: u32| MetricSample::new(SeriesKey::new("process.cpu_percent").with_label("pid", pid), pid as f64);
+
+    // Process 2 exits after the first minute
+    store.record_all(BASE, vec![process(1), process(2)]).unwrap();
+    store.record_all(BASE + 60, vec![process(1)]).unwrap();
+
+    // A second store only sees what has been written out
+    let written = |tier: Tier| {
+        let query = RangeQuery::new("process.cpu_percent", BASE, BASE + 3599, tier.resolution_secs()).with_label("pid", 2);
+        store_in(&dir).query_tier(&query, tier).unwrap()
+    };
+    assert_eq!(written(Tier::Minute)[0].buckets[0].aggregate.map(|a| a.last), Some(2.0));
+    assert!(written(Tier::Hour).is_empty());
+
+    store.record_all(BASE + 3600, vec![process(1)]).unwrap();
+    assert_eq!(written(Tier::Hour)[0].buckets[0].aggregate.map(|a| a.count), Some(1));
+}
+
+#[test]
+fn test_retention_per_tier() {
+    let dir = TempDir::new().unwrap();
+    let mut store = store_in(&dir);
+    store.record(BASE, memory_key(), 1.0);
+    store.close().unwrap();
+
+    // Ten days later raw files (2 days) are gone, minute and hour files remain
+    let removed = store.apply_retention(BASE + 10 * 86400).unwrap();
+    assert_eq!(removed, 1);
+
+    let query = RangeQuery::new("memory.used_bytes", BASE, BASE + 59, 60);
+    assert!(store.query_tier(&query, Tier::Raw).unwrap().is_empty());
+    assert_eq!(store.query_tier(&query, Tier::Minute).unwrap().len(), 1);
+}
+
+#[test]
+fn test_tier_selection() {
+    let dir = TempDir::new().unwrap();
+    let store = store_in(&dir);
+    let now = BASE + 7 * 86400;
+
+    let last_hour = RangeQuery::new("m", now - 3600, now, 10);
+    assert_eq!(store.tier_for(&last_hour, now), Tier::Raw);
+
+    let last_day = RangeQuery::new("m", now - 86400, now, 300);
+    assert_eq!(store.tier_for(&last_day, now), Tier::Minute);
+
+    // Raw only reaches back two days, so a fine-grained week falls back to minutes
+    let last_week = RangeQuery::new("m", now - 7 * 86400, now, 10);
+    assert_eq!(store.tier_for(&last_week, now), Tier::Minute);
+
+    let last_year = RangeQuery::new("m", now - 200 * 86400, now, 86400);
+    assert_eq!(store.tier_for(&last_year, now), Tier::Hour);
+}
+
+#[test]
+fn test_rejects_invalid_queries() {
+    let dir = TempDir::new().unwrap();
+    let mut store = store_in(&dir);
+    store.record(BASE, memory_key(), 1.0);
+
+    // `new` clamps the step, but queries from JSON aren't built with it
+    let zero_step: RangeQuery =
+        serde_json::from_str(r#"{"name": "memory.used_bytes", "start": 0, "end": 100, "step": 0}"#).unwrap();
+    assert_eq!(zero_step.bucket_count(), 0);
+    assert_eq!(store.query_tier(&zero_step, Tier::Raw).unwrap_err().kind(), std::io::ErrorKind::InvalidInput);
+
+    let huge = RangeQuery::new("memory.used_bytes", 0, u64::MAX, 1);
+    assert!(store.query(&huge).is_err());
+    let too_many = RangeQuery::new("memory.used_bytes", BASE, BASE + MAX_QUERY_BUCKETS, 1);
+    assert!(store.query_tier(&too_many, Tier::Raw).is_err());
+
+    // A huge step keeps the bucket count small however far the range reaches
+    let wide = RangeQuery::new("memory.used_bytes", 0, u64::MAX, u64::MAX / 4);
+    assert_eq!(store.query_tier(&wide, Tier::Raw).unwrap()[0].buckets.len(), 5);
+}
@@ -0,0 +1,331 @@
+//! Linux process analysis implementation
+//!
+//! Unlike the macOS analyzer, nothing here needs to send real signals to
+//! the target: `/proc` exposes the scheduler state, wait channel, pending
+//! signal mask and per-thread states directly.
+
+use super::procfs;
+use crate::platform::{
+    ContextSwitchInfo, DeadlockInfo, DeadlockType, IoWaitInfo, PlatformError, PlatformResult,
+    ProcessAnalyzer, ProcessResponsiveness, ProcessState, StackFrame, StackTrace,
+};
+use std::collections::{HashMap, HashSet};
+use std::sync::RwLock;
+use std::time::Instant;
+
+pub struct LinuxProcessAnalyzer {
+    // First time each PID was observed in D state, for unkillable detection
+    blocked_since: RwLock<HashMap<u32, Instant>>,
+    // Cache for context switch rate tracking
+    context_switch_cache: RwLock<HashMap<u32, (Instant, u64)>>,
+}
+
+/// How long a task must stay in D state before it is considered stuck
+const STUCK_THRESHOLD_SECS: u64 = 10;
+
+impl LinuxProcessAnalyzer {
+    pub fn new() -> Self {
+        Self {
+            blocked_since: RwLock::new(HashMap::new()),
+            context_switch_cache: RwLock::new(HashMap::new()),
+        }
+    }
+
+    /// Record the D-state observation and return how long the task has been blocked
+    fn track_blocked(&self, pid: u32, in_d_state: bool) -> u64 {
+        let Ok(mut blocked) = self.blocked_since.write() else {
+            return 0;
+        };
+
+        if in_d_state {
+            blocked.entry(pid).or_insert_with(Instant::now).elapsed().as_secs()
+        } else {
+            blocked.remove(&pid);
+            0
+        }
+    }
+
+    /// Number of threads of `pid` currently in uninterruptible sleep
+    fn count_blocked_threads(&self, pid: u32) -> u32 {
+        procfs::list_tids(pid)
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|&tid| {
+                procfs::read_pid_file(pid, &format!("task/{}/stat", tid))
+                    .ok()
+                    .and_then(|c| procfs::parse_stat(&c))
+                    .map(|s| s.state == 'D')
+                    .unwrap_or(false)
+            })
+            .count() as u32
+    }
+
+    /// Resolve the file behind the fd the task is currently blocked on, if any
+    fn blocked_fd_target(&self, pid: u32) -> Option<String> {
+        let (_, args) = procfs::read_pid_file(pid, "syscall").ok()
+            .and_then(|c| procfs::parse_syscall(&c))?;
+        let fd = *args.first()?;
+        // The first argument is only meaningful as an fd for fd-based syscalls;
+        // anything that does not resolve under /proc/<pid>/fd is ignored
+        if fd > i32::MAX as u64 {
+            return None;
+        }
+        std::fs::read_link(format!("{}/{}/fd/{}", procfs::PROC_ROOT, pid, fd))
+            .ok()
+            .map(|p| p.to_string_lossy().into_owned())
+    }
+
+    fn classify_deadlock_type(&self, wchan: &str) -> DeadlockType {
+        if wchan.contains("sk_") || wchan.contains("sock") || wchan.contains("tcp")
+            || wchan.contains("udp") || wchan.contains("inet") || wchan.contains("nfs")
+        {
+            DeadlockType::NetworkDeadlock
+        } else if wchan.contains("bio") || wchan.contains("blk") || wchan.contains("io_schedule")
+            || wchan.contains("folio") || wchan.contains("page") || wchan.contains("jbd2")
+            || wchan.contains("ext4") || wchan.contains("xfs")
+        {
+            DeadlockType::IoDeadlock
+        } else if wchan.contains("mutex") || wchan.contains("rwsem") || wchan.contains("futex") {
+            DeadlockType::ResourceDeadlock
+        } else {
+            DeadlockType::Unknown
+        }
+    }
+
+    /// Other D-state processes blocked on the same wait channel or sharing open files
+    fn find_related_waiting_processes(&self, pid: u32, wchan: Option<&str>) -> Vec<u32> {
+        let mut related = vec![pid];
+        let Ok(blocked) = self.find_uninterruptible_processes() else {
+            return related;
+        };
+
+        let target_files: HashSet<String> = procfs::read_fd_targets(pid)
+            .unwrap_or_default()
+            .into_iter()
+            .map(|(_, target)| target)
+            .filter(|t| t.starts_with('/'))
+            .collect();
+
+        for other in blocked.into_iter().filter(|&p| p != pid) {
+            let same_wchan = wchan.is_some() && procfs::read_wchan(other).as_deref() == wchan;
+            let shares_files = !target_files.is_empty() && procfs::read_fd_targets(other)
+                .unwrap_or_default()
+                .iter()
+                .any(|(_, t)| target_files.contains(t));
+
+            if same_wchan || shares_files {
+                related.push(other);
+            }
+        }
+
+        related
+    }
+}
+
+impl Default for LinuxProcessAnalyzer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ProcessAnalyzer for LinuxProcessAnalyzer {
+    fn analyze_unkillable(&self, pid: u32) -> PlatformResult<bool> {
+        let stat = procfs::read_stat(pid)?;
+        let status = procfs::read_status(pid)?;
+        let blocked_secs = self.track_blocked(pid, stat.state == 'D');
+
+        // A queued SIGKILL that has not been acted on is direct evidence; a long
+        // D-state stay is the next best signal
+        Ok(stat.state == 'D'
+            && (status.has_pending_sigkill() || blocked_secs >= STUCK_THRESHOLD_SECS))
+    }
+
+    fn get_process_state(&self, pid: u32) -> PlatformResult<ProcessState> {
+        let stat = procfs::read_stat(pid)?;
+        let status = procfs::read_status(pid)?;
+
+        Ok(ProcessState {
+            state_char: stat.state,
+            wchan: procfs::read_wchan(pid),
+            flags: stat.flags,
+            nice: stat.nice,
+            num_threads: stat.num_threads,
+            tgid: status.tgid,
+            blocked_signals: status.blocked_signals,
+            pending_signals: status.pending_signals,
+        })
+    }
+
+    fn find_uninterruptible_processes(&self) -> PlatformResult<Vec<u32>> {
+        Ok(procfs::list_pids()?
+            .into_iter()
+            .filter(|&pid| {
+                procfs::read_stat(pid)
+                    .map(|stat| stat.state == 'D')
+                    .unwrap_or(false)
+            })
+            .collect())
+    }
+
+    fn analyze_io_wait(&self, pid: u32) -> PlatformResult<IoWaitInfo> {
+        let stat = procfs::read_stat(pid)?;
+
+        let total_wait_time_ms = procfs::read_sched(pid).ok()
+            .and_then(|s| s.iowait_sum_ms)
+            .map(|ms| ms as u64)
+            .unwrap_or_else(|| stat.delayacct_blkio_ticks * 1000 / procfs::clock_ticks());
+
+        let blocked_on_device = if stat.state == 'D' {
+            self.blocked_fd_target(pid)
+        } else {
+            None
+        };
+
+        Ok(IoWaitInfo {
+            total_wait_time_ms,
+            current_wait_operation: procfs::read_wchan(pid),
+            blocked_on_device,
+            io_operations_pending: self.count_blocked_threads(pid),
+        })
+    }
+
+    fn test_process_responsiveness(&self, pid: u32) -> PlatformResult<ProcessResponsiveness> {
+        let start_time = Instant::now();
+
+        // Signal 0 only checks existence and permissions; nothing is delivered
+        let exists = unsafe { libc::kill(pid as libc::pid_t, 0) } == 0
+            || procfs::last_errno() == libc::EPERM;
+        if !exists {
+            return Err(PlatformError::ProcessNotFound(pid));
+        }
+
+        let stat = procfs::read_stat(pid)?;
+        let status = procfs::read_status(pid)?;
+        let blocked_secs = self.track_blocked(pid, stat.state == 'D');
+
+        // Pending signals that are not blocked should be delivered promptly
+        // unless the task is stuck in the kernel
+        let undelivered = status.pending_signals & !status.blocked_signals;
+        let responds_to_signals = !(stat.state == 'D' && undelivered != 0);
+
+        let mut signal_test_results = HashMap::new();
+        signal_test_results.insert(0, true);
+        signal_test_results.insert(libc::SIGKILL, !status.has_pending_sigkill());
+
+        Ok(ProcessResponsiveness {
+            responds_to_signals,
+            last_response_time_ms: responds_to_signals.then(|| start_time.elapsed().as_millis() as u64),
+            signal_test_results,
+            is_likely_unkillable: stat.state == 'D'
+                && (status.has_pending_sigkill() || blocked_secs >= STUCK_THRESHOLD_SECS),
+        })
+    }
+
+    fn get_context_switches(&self, pid: u32) -> PlatformResult<ContextSwitchInfo> {
+        let status = procfs::read_status(pid)?;
+        let current = status.context_switches();
+        let now = Instant::now();
+
+        let switches_per_second = {
+            let mut cache = self.context_switch_cache.write()
+                .map_err(|_| PlatformError::SystemCallFailed("Lock poisoned".to_string()))?;
+
+            let (last_time, last_count) = cache.get(&pid).copied().unwrap_or((now, current));
+            cache.insert(pid, (now, current));
+
+            let elapsed = now.duration_since(last_time).as_secs_f64();
+            if elapsed > 0.0 {
+                current.saturating_sub(last_count) as f64 / elapsed
+            } else {
+                0.0
+            }
+        };
+
+        Ok(ContextSwitchInfo {
+            voluntary_switches: status.voluntary_ctxt_switches,
+            involuntary_switches: status.nonvoluntary_ctxt_switches,
+            switches_per_second,
+            // Consider high frequency if > 100 switches/second
+            is_high_frequency: switches_per_second > 100.0,
+        })
+    }
+
+    fn detect_deadlock(&self, pid: u32) -> PlatformResult<Option<DeadlockInfo>> {
+        let stat = procfs::read_stat(pid)?;
+        let status = procfs::read_status(pid)?;
+        let wchan = procfs::read_wchan(pid);
+
+        if stat.state != 'D' {
+            self.track_blocked(pid, false);
+            return Ok(None);
+        }
+
+        let mut detection_confidence: f32 = 0.4;
+        let blocked_secs = self.track_blocked(pid, true);
+
+        if status.has_pending_sigkill() {
+            detection_confidence += 0.3;
+        }
+        if blocked_secs > 60 {
+            detection_confidence += 0.2;
+        } else if blocked_secs > STUCK_THRESHOLD_SECS {
+            detection_confidence += 0.1;
+        }
+
+        // Stuck processes barely context switch
+        if let Ok(context_info) = self.get_context_switches(pid) {
+            if context_info.switches_per_second < 0.1 && blocked_secs > 0 {
+                detection_confidence += 0.1;
+            }
+        }
+
+        let mut involved_processes = vec![pid];
+        if detection_confidence > 0.4 {
+            let related = self.find_related_waiting_processes(pid, wchan.as_deref());
+            if related.len() > 1 {
+                detection_confidence += 0.2;
+                involved_processes = related;
+            }
+        }
+
+        if detection_confidence > 0.6 {
+            Ok(Some(DeadlockInfo {
+                involved_processes,
+                deadlock_type: wchan.as_deref()
+                    .map(|w| self.classify_deadlock_type(w))
+                    .unwrap_or(DeadlockType::Unknown),
+                resource_info: wchan.unwrap_or_else(|| "unknown".to_string()),
+                detection_confidence: detection_confidence.min(1.0),
+            }))
+        } else {
+            Ok(None)
+        }
+    }
+
+    fn collect_stack_trace(&self, pid: u32, duration_ms: u64) -> PlatformResult<StackTrace> {
+        let timestamp = std::time::SystemTime::now();
+
+        // Kernel stack of the main thread; reading it requires CAP_SYS_ADMIN
+        let content = procfs::read_pid_file(pid, "stack")?;
+        let frames: Vec<StackFrame> = procfs::parse_kernel_stack(&content)
+            .into_iter()
+            .map(|(symbol, offset)| StackFrame {
+                address: 0, // Addresses are masked by the kernel
+                symbol: Some(symbol),
+                module: Some("[kernel]".to_string()),
+                file: None,
+                line: None,
+                offset,
+            })
+            .collect();
+
+        Ok(StackTrace {
+            pid,
+            thread_id: Some(pid as u64),
+            timestamp,
+            is_complete: !frames.is_empty(),
+            frames,
+            sample_duration_ms: duration_ms,
+        })
+    }
+}
@@ -0,0 +1,243 @@
+//! cgroup v2 helpers for the limiters
+//!
+//! Reaper keeps the cgroups it creates under one `reaper` directory at the
+//! top of the unified hierarchy, one child per limited process. Controllers
+//! are enabled on the way down through `cgroup.subtree_control`; the
+//! `reaper` directory itself never holds processes, as cgroup v2 requires
+//! of any cgroup that delegates controllers.
+//!
+//! A process can only be in one cgroup, so the CPU, memory and I/O limiters
+//! share `reaper/pid-<pid>` through `attach` and `detach`. The last limiter
+//! to detach moves the process back to where it came from; the limit files
+//! tell `detach` about limiters in other Reaper processes.
+
+use super::procfs;
+use crate::platform::{PlatformError, PlatformResult};
+use once_cell::sync::Lazy;
+use std::collections::{BTreeSet, HashMap};
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+/// Directory under the hierarchy root that holds Reaper's cgroups
+pub const REAPER_CGROUP: &str = "reaper";
+
+/// A mounted cgroup v2 hierarchy
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CgroupFs {
+    root: PathBuf,
+}
+
+impl CgroupFs {
+    /// Find the cgroup2 mount from `/proc/self/mountinfo`
+    pub fn detect() -> Option<Self> {
+        let mountinfo = std::fs::read_to_string(Path::new(procfs::PROC_ROOT).join("self/mountinfo")).ok()?;
+        parse_cgroup2_mount(&mountinfo).map(Self::at)
+    }
+
+    /// Use the hierarchy mounted at `root`
+    pub fn at(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Whether the root cgroup offers `controller` (`cpu`, `memory`, `io`, …)
+    pub fn has_controller(&self, controller: &str) -> bool {
+        std::fs::read_to_string(self.root.join("cgroup.controllers"))
+            .map(|c| c.split_whitespace().any(|name| name == controller))
+            .unwrap_or(false)
+    }
+
+    /// Absolute directory of a cgroup path as `/proc/<pid>/cgroup` names it
+    pub fn path_of(&self, cgroup: &str) -> PathBuf {
+        self.root.join(cgroup.trim_start_matches('/'))
+    }
+
+    /// The cgroup `pid` is in, relative to the hierarchy root (`/user.slice/…`)
+    pub fn process_cgroup(&self, pid: u32) -> PlatformResult<String> {
+        let content = procfs::read_pid_file(pid, "cgroup")?;
+        parse_cgroup_v2_path(&content)
+            .ok_or_else(|| PlatformError::NotSupported(format!("Process {} is not in a cgroup v2 hierarchy", pid)))
+    }
+
+    /// Create (or reuse) `reaper/<name>` with `controllers` enabled for it
+    pub fn create_managed(&self, name: &str, controllers: &[&str]) -> PlatformResult<PathBuf> {
+        let parent = self.root.join(REAPER_CGROUP);
+        create_dir(&parent)?;
+
+        let enable: String = controllers.iter().map(|c| format!("+{} ", c)).collect();
+        for dir in [&self.root, &parent] {
+            let enabled = std::fs::read_to_string(dir.join("cgroup.subtree_control")).unwrap_or_default();
+            if controllers.iter().all(|c| enabled.split_whitespace().any(|e| e == *c)) {
+                continue;
+            }
+            write_file(&dir.join("cgroup.subtree_control"), enable.trim_end())?;
+        }
+
+        let cgroup = parent.join(name);
+        create_dir(&cgroup)?;
+        Ok(cgroup)
+    }
+
+    /// Move every thread of `pid` into the cgroup at `dir`
+    pub fn move_process(&self, pid: u32, dir: &Path) -> PlatformResult<()> {
+        let path = dir.join("cgroup.procs");
+        std::fs::write(&path, pid.to_string()).map_err(|e| match e.raw_os_error() {
+            Some(libc::ESRCH) => PlatformError::ProcessNotFound(pid),
+            _ => io_error(&path, e),
+        })
+    }
+
+    /// Remove a cgroup created by `create_managed`; it must be empty
+    pub fn remove(&self, dir: &Path) -> PlatformResult<()> {
+        match std::fs::remove_dir(dir) {
+            Ok(()) => Ok(()),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(()),
+            Err(e) => Err(io_error(dir, e)),
+        }
+    }
+}
+
+/// A process in its Reaper cgroup and the controllers limiting it there
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Attachment {
+    /// cgroup the process came from, relative to the hierarchy root
+    pub original: String,
+    pub dir: PathBuf,
+    pub controllers: BTreeSet<String>,
+}
+
+static ATTACHED: Lazy<Mutex<HashMap<u32, Attachment>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+impl CgroupFs {
+    /// Put `pid` in its Reaper cgroup with `controller` enabled, joining
+    /// whatever other limiters already attached it
+    pub fn attach(&self, pid: u32, controller: &str) -> PlatformResult<Attachment> {
+        let mut attached = ATTACHED.lock().unwrap_or_else(|e| e.into_inner());
+        let name = format!("pid-{}", pid);
+
+        if let Some(attachment) = attached.get_mut(&pid) {
+            self.create_managed(&name, &[controller])?;
+            attachment.controllers.insert(controller.to_string());
+            return Ok(attachment.clone());
+        }
+
+        // Already here if another Reaper process limits it; where it came
+        // from is only known there, so it goes back to the root
+        let current = self.process_cgroup(pid)?;
+        let original = if current == format!("/{}/{}", REAPER_CGROUP, name) { "/".to_string() } else { current };
+        let dir = self.create_managed(&name, &[controller])?;
+        if let Err(e) = self.move_process(pid, &dir) {
+            let _ = self.remove(&dir);
+            return Err(e);
+        }
+
+        let attachment = Attachment { original, dir, controllers: BTreeSet::from([controller.to_string()]) };
+        attached.insert(pid, attachment.clone());
+        Ok(attachment)
+    }
+
+    /// Drop `controller`'s hold on `pid`. Callers reset their own interface
+    /// files first. The process stays while a limiter here still holds it or
+    /// the cgroup's limit files show one elsewhere does; otherwise it moves
+    /// back to where it came from and the cgroup is removed. A process that
+    /// has exited only needs the cgroup removed
+    pub fn detach(&self, pid: u32, controller: &str) -> PlatformResult<()> {
+        let mut attached = ATTACHED.lock().unwrap_or_else(|e| e.into_inner());
+        let dir = self.root.join(REAPER_CGROUP).join(format!("pid-{}", pid));
+        if let Some(attachment) = attached.get_mut(&pid) {
+            attachment.controllers.remove(controller);
+            if !attachment.controllers.is_empty() {
+                return Ok(());
+            }
+        }
+        if !dir.exists() {
+            attached.remove(&pid);
+            return Ok(());
+        }
+        if !limiting_controllers(&dir).is_empty() {
+            return Ok(());
+        }
+
+        let original = attached.remove(&pid).map_or_else(|| "/".to_string(), |attachment| attachment.original);
+        let moved = match self.move_process(pid, &self.path_of(&original)) {
+            Err(PlatformError::ProcessNotFound(_)) => Ok(()),
+            other => other,
+        };
+        moved.and(self.remove(&dir))
+    }
+
+    /// The attachment of `pid`, if any limiter has it in a Reaper cgroup
+    pub fn attachment(pid: u32) -> Option<Attachment> {
+        ATTACHED.lock().unwrap_or_else(|e| e.into_inner()).get(&pid).cloned()
+    }
+}
+
+/// Controllers whose interface files in `dir` hold a limit. Missing or
+/// unreadable files count as unlimited
+pub fn limiting_controllers(dir: &Path) -> BTreeSet<&'static str> {
+    let read = |file: &str| read_file(&dir.join(file)).unwrap_or_default();
+    let limited = |value: &str| !value.is_empty() && value != "max";
+
+    let mut controllers = BTreeSet::new();
+    // `cpu.max` is "<quota> <period>"
+    if limited(read("cpu.max").split_whitespace().next().unwrap_or_default()) {
+        controllers.insert("cpu");
+    }
+    if ["memory.max", "memory.high", "memory.swap.max"].iter().any(|file| limited(&read(file))) {
+        controllers.insert("memory");
+    }
+    // `io.max` has a "<major>:<minor> rbps=… wbps=… riops=… wiops=…" line per device
+    let io_max = read("io.max");
+    if io_max.split_whitespace().filter_map(|field| field.split_once('=')).any(|(_, value)| limited(value)) {
+        controllers.insert("io");
+    }
+    controllers
+}
+
+/// Write a cgroup interface file such as `cpu.max`
+pub fn write_file(path: &Path, value: &str) -> PlatformResult<()> {
+    std::fs::write(path, value).map_err(|e| io_error(path, e))
+}
+
+/// Read a cgroup interface file, trimmed
+pub fn read_file(path: &Path) -> PlatformResult<String> {
+    std::fs::read_to_string(path).map(|s| s.trim().to_string()).map_err(|e| io_error(path, e))
+}
+
+fn create_dir(path: &Path) -> PlatformResult<()> {
+    match std::fs::create_dir(path) {
+        Ok(()) => Ok(()),
+        Err(e) if e.kind() == ErrorKind::AlreadyExists => Ok(()),
+        Err(e) => Err(io_error(path, e)),
+    }
+}
+
+fn io_error(path: &Path, err: std::io::Error) -> PlatformError {
+    match err.kind() {
+        ErrorKind::PermissionDenied => PlatformError::PermissionDenied(format!("Cannot write {}", path.display())),
+        _ => PlatformError::SystemCallFailed(format!("{}: {}", path.display(), err)),
+    }
+}
+
+/// Mount point of the cgroup2 filesystem in `mountinfo` content
+pub fn parse_cgroup2_mount(mountinfo: &str) -> Option<PathBuf> {
+    mountinfo.lines().find_map(|line| {
+        // Optional fields end at " - ", followed by the filesystem type
+        let (mount, fs) = line.split_once(" - ")?;
+        if fs.split_whitespace().next()? != "cgroup2" {
+            return None;
+        }
+        mount.split_whitespace().nth(4).map(PathBuf::from)
+    })
+}
+
+/// The unified hierarchy entry (`0::/path`) of `/proc/<pid>/cgroup`
+pub fn parse_cgroup_v2_path(content: &str) -> Option<String> {
+    content.lines()
+        .find_map(|line| line.strip_prefix("0::"))
+        .map(|path| path.trim().trim_end_matches(" (deleted)").to_string())
+}
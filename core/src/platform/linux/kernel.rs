@@ -0,0 +1,120 @@
+//! Linux kernel operations implementation
+
+use super::procfs;
+use crate::platform::{KernelOperations, PlatformError, PlatformResult};
+use libc::{c_int, kill, pid_t, SIGCONT, SIGKILL, SIGSTOP};
+
+pub struct LinuxKernelOps;
+
+impl LinuxKernelOps {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl Default for LinuxKernelOps {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Send `sig` to `pid` via kill(2), translating errno into a platform error
+pub(super) fn send_raw_signal(pid: u32, sig: c_int, action: &str) -> PlatformResult<()> {
+    let result = unsafe { kill(pid as pid_t, sig) };
+
+    if result == 0 {
+        Ok(())
+    } else {
+        let errno = procfs::last_errno();
+        match errno {
+            libc::ESRCH => Err(PlatformError::ProcessNotFound(pid)),
+            libc::EPERM => Err(PlatformError::PermissionDenied(
+                format!("Cannot {} process {}", action, pid)
+            )),
+            _ => Err(PlatformError::SystemCallFailed(
+                format!("kill({}) failed with errno {}", sig, errno)
+            )),
+        }
+    }
+}
+
+impl KernelOperations for LinuxKernelOps {
+    fn force_kill(&self, pid: u32) -> PlatformResult<()> {
+        if self.is_kernel_process(pid) {
+            return Err(PlatformError::ProcessUnkillable(
+                "Kernel process cannot be killed".to_string()
+            ));
+        }
+
+        send_raw_signal(pid, SIGKILL, "kill")
+    }
+
+    fn suspend_process(&self, pid: u32) -> PlatformResult<()> {
+        send_raw_signal(pid, SIGSTOP, "suspend")
+    }
+
+    fn resume_process(&self, pid: u32) -> PlatformResult<()> {
+        send_raw_signal(pid, SIGCONT, "resume")
+    }
+
+    fn is_kernel_process(&self, pid: u32) -> bool {
+        // PID 1 is init; kernel threads carry PF_KTHREAD and descend from kthreadd (PID 2)
+        if pid <= 2 {
+            return true;
+        }
+
+        procfs::read_stat(pid)
+            .map(|stat| stat.is_kernel_thread() || stat.ppid == 2)
+            .unwrap_or(false)
+    }
+
+    fn get_process_priority(&self, pid: u32) -> PlatformResult<i32> {
+        // getpriority may legitimately return -1, so errno must be cleared first
+        let priority = unsafe {
+            *libc::__errno_location() = 0;
+            libc::getpriority(libc::PRIO_PROCESS, pid as libc::id_t)
+        };
+
+        let errno = procfs::last_errno();
+        if errno != 0 && priority == -1 {
+            match errno {
+                libc::ESRCH => Err(PlatformError::ProcessNotFound(pid)),
+                libc::EINVAL => Err(PlatformError::SystemCallFailed(
+                    "Invalid priority class".to_string()
+                )),
+                _ => Err(PlatformError::SystemCallFailed(
+                    format!("getpriority() failed with errno {}", errno)
+                )),
+            }
+        } else {
+            Ok(priority)
+        }
+    }
+
+    fn set_process_priority(&self, pid: u32, priority: i32) -> PlatformResult<()> {
+        // Priority on Unix ranges from -20 (highest) to 19 (lowest)
+        let clamped_priority = priority.clamp(-20, 19);
+
+        let result = unsafe {
+            libc::setpriority(libc::PRIO_PROCESS, pid as libc::id_t, clamped_priority)
+        };
+
+        if result == 0 {
+            Ok(())
+        } else {
+            let errno = procfs::last_errno();
+            match errno {
+                libc::ESRCH => Err(PlatformError::ProcessNotFound(pid)),
+                libc::EPERM => Err(PlatformError::PermissionDenied(
+                    format!("Cannot set priority for process {}", pid)
+                )),
+                libc::EACCES => Err(PlatformError::PermissionDenied(
+                    "Insufficient privileges to raise priority (requires CAP_SYS_NICE)".to_string()
+                )),
+                _ => Err(PlatformError::SystemCallFailed(
+                    format!("setpriority() failed with errno {}", errno)
+                )),
+            }
+        }
+    }
+}
@@ -0,0 +1,59 @@
+//! Linux platform implementation
+//!
+//! Everything is read from `/proc` and `/sys`; process control uses kill(2)
+//! and setpriority(2) directly.
+
+pub mod procfs;
+pub mod cgroup;
+mod process;
+mod system;
+mod kernel;
+mod analyzer;
+
+pub use process::LinuxProcessManager;
+pub use system::LinuxSystemMonitor;
+pub use kernel::LinuxKernelOps;
+pub use analyzer::LinuxProcessAnalyzer;
+
+use super::{ProcessManager, SystemMonitor, KernelOperations, ProcessAnalyzer};
+
+/// Main platform implementation for Linux
+pub struct LinuxPlatform {
+    process_manager: LinuxProcessManager,
+    system_monitor: LinuxSystemMonitor,
+    kernel_ops: LinuxKernelOps,
+    process_analyzer: LinuxProcessAnalyzer,
+}
+
+impl LinuxPlatform {
+    pub fn new() -> Self {
+        Self {
+            process_manager: LinuxProcessManager::new(),
+            system_monitor: LinuxSystemMonitor::new(),
+            kernel_ops: LinuxKernelOps::new(),
+            process_analyzer: LinuxProcessAnalyzer::new(),
+        }
+    }
+
+    pub fn process_manager(&self) -> &dyn ProcessManager {
+        &self.process_manager
+    }
+
+    pub fn system_monitor(&self) -> &dyn SystemMonitor {
+        &self.system_monitor
+    }
+
+    pub fn kernel_ops(&self) -> &dyn KernelOperations {
+        &self.kernel_ops
+    }
+
+    pub fn process_analyzer(&self) -> &dyn ProcessAnalyzer {
+        &self.process_analyzer
+    }
+}
+
+impl Default for LinuxPlatform {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,217 @@
+//! Linux process management implementation backed by `/proc`
+
+use super::kernel::send_raw_signal;
+use super::procfs::{self, ProcStat};
+use crate::platform::{
+    ProcessInfo, ProcessManager, ProcessStatus, PlatformError, PlatformResult, Signal,
+};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::Instant;
+
+pub struct LinuxProcessManager {
+    // Previous CPU tick counts for computing usage between calls
+    cpu_samples: Mutex<HashMap<u32, (u64, Instant)>>,
+    clock_ticks: u64,
+    page_size: u64,
+}
+
+impl LinuxProcessManager {
+    pub fn new() -> Self {
+        Self {
+            cpu_samples: Mutex::new(HashMap::new()),
+            clock_ticks: procfs::clock_ticks(),
+            page_size: procfs::page_size(),
+        }
+    }
+
+    fn read_process_info(&self, pid: u32, uptime_secs: f64) -> PlatformResult<ProcessInfo> {
+        let stat = procfs::read_stat(pid)?;
+        // status may disappear between reads if the process exits
+        let status = procfs::read_status(pid).unwrap_or_default();
+        let ticks = self.clock_ticks as f64;
+
+        let cpu_usage = self.compute_cpu_usage(pid, &stat);
+        let start_secs = stat.starttime as f64 / ticks;
+
+        let io_wait_time_ms = procfs::read_sched(pid).ok()
+            .and_then(|s| s.iowait_sum_ms)
+            .map(|ms| ms as u64)
+            .unwrap_or_else(|| stat.delayacct_blkio_ticks * 1000 / self.clock_ticks);
+
+        let executable_path = std::fs::read_link(format!("{}/{}/exe", procfs::PROC_ROOT, pid))
+            .ok()
+            .map(|p| p.to_string_lossy().into_owned());
+
+        let command_line = procfs::read_pid_bytes(pid, "cmdline")
+            .map(|c| procfs::parse_cmdline(&c))
+            .unwrap_or_default();
+
+        // environ is only readable by the owner or root
+        let environment = procfs::read_pid_bytes(pid, "environ")
+            .map(|c| procfs::parse_environ(&c))
+            .unwrap_or_default();
+
+        let state = convert_state(stat.state);
+
+        Ok(ProcessInfo {
+            pid,
+            name: stat.comm.clone(),
+            cpu_usage,
+            memory_bytes: stat.rss_pages * self.page_size,
+            virtual_memory_bytes: stat.vsize,
+            is_unkillable: state == ProcessStatus::UninterruptibleSleep && status.has_pending_sigkill(),
+            status: state,
+            parent_pid: if stat.ppid == 0 { None } else { Some(stat.ppid) },
+            thread_count: stat.num_threads,
+            run_time_seconds: (uptime_secs - start_secs).max(0.0) as u64,
+            user_time_seconds: (stat.utime as f64 / ticks) as f32,
+            system_time_seconds: (stat.stime as f64 / ticks) as f32,
+            executable_path,
+            command_line,
+            environment,
+            io_wait_time_ms,
+            context_switches: status.context_switches(),
+            minor_faults: stat.minflt,
+            major_faults: stat.majflt,
+            priority: stat.nice,
+            last_signal_response_ms: None,
+        })
+    }
+
+    /// CPU usage in percent of one core since the previous sample of this PID
+    fn compute_cpu_usage(&self, pid: u32, stat: &ProcStat) -> f32 {
+        let now = Instant::now();
+        let total = stat.total_ticks();
+        let mut samples = match self.cpu_samples.lock() {
+            Ok(samples) => samples,
+            Err(poisoned) => poisoned.into_inner(),
+        };
+
+        let usage = match samples.get(&pid) {
+            Some(&(prev_total, prev_time)) => {
+                let elapsed = now.duration_since(prev_time).as_secs_f64();
+                if elapsed > 0.0 {
+                    let cpu_secs = total.saturating_sub(prev_total) as f64 / self.clock_ticks as f64;
+                    (cpu_secs / elapsed * 100.0) as f32
+                } else {
+                    0.0
+                }
+            }
+            None => 0.0,
+        };
+
+        samples.insert(pid, (total, now));
+        usage
+    }
+
+    fn uptime_secs() -> f64 {
+        procfs::read_proc_file("uptime")
+            .map(|c| procfs::parse_uptime(&c))
+            .unwrap_or(0.0)
+    }
+}
+
+/// Map the single-character state from `/proc/<pid>/stat`
+pub(super) fn convert_state(state: char) -> ProcessStatus {
+    match state {
+        'R' => ProcessStatus::Running,
+        'S' => ProcessStatus::Sleeping,
+        'D' => ProcessStatus::UninterruptibleSleep,
+        'Z' => ProcessStatus::Zombie,
+        'T' | 't' => ProcessStatus::Stopped,
+        'I' => ProcessStatus::Idle,
+        'W' | 'P' => ProcessStatus::Waiting,
+        _ => ProcessStatus::Unknown,
+    }
+}
+
+impl ProcessManager for LinuxProcessManager {
+    fn list_processes(&self) -> PlatformResult<Vec<ProcessInfo>> {
+        let uptime = Self::uptime_secs();
+        let pids = procfs::list_pids()?;
+
+        let processes: Vec<ProcessInfo> = pids.iter()
+            .filter_map(|&pid| self.read_process_info(pid, uptime).ok())
+            .collect();
+
+        // Drop CPU samples of processes that have exited
+        if let Ok(mut samples) = self.cpu_samples.lock() {
+            samples.retain(|pid, _| pids.contains(pid));
+        }
+
+        Ok(processes)
+    }
+
+    fn get_process_info(&self, pid: u32) -> PlatformResult<ProcessInfo> {
+        self.read_process_info(pid, Self::uptime_secs())
+    }
+
+    fn send_signal(&self, pid: u32, signal: Signal) -> PlatformResult<()> {
+        let sig = match signal {
+            Signal::Terminate => libc::SIGTERM,
+            Signal::Kill => libc::SIGKILL,
+            Signal::Stop => libc::SIGSTOP,
+            Signal::Continue => libc::SIGCONT,
+            Signal::Interrupt => libc::SIGINT,
+        };
+
+        send_raw_signal(pid, sig, "send signal to")
+    }
+
+    fn is_process_responsive(&self, pid: u32) -> PlatformResult<bool> {
+        let stat = procfs::read_stat(pid)?;
+        let status = procfs::read_status(pid)?;
+
+        // A task stuck in D state with a fatal signal queued cannot act on it
+        Ok(!(stat.state == 'D' && status.has_pending_sigkill()))
+    }
+
+    fn get_child_processes(&self, parent_pid: u32) -> PlatformResult<Vec<u32>> {
+        // /proc/<pid>/task/<tid>/children is only present with CONFIG_PROC_CHILDREN
+        let path = format!("{}/{}/task/{}/children", procfs::PROC_ROOT, parent_pid, parent_pid);
+        if let Ok(content) = std::fs::read_to_string(path) {
+            return Ok(content.split_whitespace().filter_map(|p| p.parse().ok()).collect());
+        }
+
+        let children = procfs::list_pids()?
+            .into_iter()
+            .filter(|&pid| {
+                procfs::read_stat(pid)
+                    .map(|stat| stat.ppid == parent_pid)
+                    .unwrap_or(false)
+            })
+            .collect();
+
+        Ok(children)
+    }
+
+    fn can_terminate_process(&self, pid: u32) -> PlatformResult<bool> {
+        // PID 0 does not exist as a process, PID 1 is init, PID 2 is kthreadd
+        if pid <= 2 {
+            return Ok(false);
+        }
+
+        let stat = procfs::read_stat(pid)?;
+        if stat.is_kernel_thread() {
+            return Ok(false);
+        }
+
+        let result = unsafe { libc::kill(pid as libc::pid_t, 0) };
+
+        if result == 0 {
+            Ok(true)
+        } else {
+            match procfs::last_errno() {
+                libc::ESRCH => Err(PlatformError::ProcessNotFound(pid)),
+                _ => Ok(false),
+            }
+        }
+    }
+}
+
+impl Default for LinuxProcessManager {
+    fn default() -> Self {
+        Self::new()
+    }
+}
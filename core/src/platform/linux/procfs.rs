@@ -0,0 +1,551 @@
+//! Parsers for the Linux `/proc` filesystem
+//!
+//! Every parser takes the raw file contents so it can be exercised against
+//! fixture text; the `read_*` helpers do the actual filesystem access and
+//! map I/O errors onto `PlatformError`.
+
+use crate::platform::{PlatformError, PlatformResult};
+use std::collections::HashMap;
+use std::io::ErrorKind;
+use std::path::Path;
+
+/// Root of the proc filesystem
+pub const PROC_ROOT: &str = "/proc";
+
+/// Parsed `/proc/<pid>/stat`
+#[derive(Debug, Clone, Default)]
+pub struct ProcStat {
+    pub pid: u32,
+    pub comm: String,
+    pub state: char,
+    pub ppid: u32,
+    pub pgrp: i32,
+    pub flags: u64,
+    pub minflt: u64,
+    pub majflt: u64,
+    /// User time in clock ticks
+    pub utime: u64,
+    /// System time in clock ticks
+    pub stime: u64,
+    pub priority: i64,
+    pub nice: i32,
+    pub num_threads: usize,
+    /// Start time after boot in clock ticks
+    pub starttime: u64,
+    pub vsize: u64,
+    pub rss_pages: u64,
+    pub processor: i32,
+    /// Aggregated block I/O delay in clock ticks
+    pub delayacct_blkio_ticks: u64,
+}
+
+/// Kernel thread flag from `include/linux/sched.h`
+pub const PF_KTHREAD: u64 = 0x0020_0000;
+
+impl ProcStat {
+    /// Total CPU time (user + system) in clock ticks
+    pub fn total_ticks(&self) -> u64 {
+        self.utime + self.stime
+    }
+
+    pub fn is_kernel_thread(&self) -> bool {
+        self.flags & PF_KTHREAD != 0
+    }
+}
+
+/// Parsed subset of `/proc/<pid>/status`
+#[derive(Debug, Clone, Default)]
+pub struct ProcStatus {
+    pub name: String,
+    pub tgid: u32,
+    pub uid: u32,
+    pub voluntary_ctxt_switches: u64,
+    pub nonvoluntary_ctxt_switches: u64,
+    /// Signals pending for the thread or the whole thread group
+    pub pending_signals: u64,
+    pub blocked_signals: u64,
+}
+
+impl ProcStatus {
+    pub fn context_switches(&self) -> u64 {
+        self.voluntary_ctxt_switches + self.nonvoluntary_ctxt_switches
+    }
+
+    /// Whether a SIGKILL has been queued but not yet delivered
+    pub fn has_pending_sigkill(&self) -> bool {
+        self.pending_signals & (1 << (libc::SIGKILL - 1)) != 0
+    }
+}
+
+/// Parsed `/proc/<pid>/io`
+#[derive(Debug, Clone, Default)]
+pub struct ProcIo {
+    pub rchar: u64,
+    pub wchar: u64,
+    pub syscr: u64,
+    pub syscw: u64,
+    pub read_bytes: u64,
+    pub write_bytes: u64,
+    pub cancelled_write_bytes: u64,
+}
+
+/// Parsed subset of `/proc/<pid>/sched`
+#[derive(Debug, Clone, Default)]
+pub struct ProcSched {
+    pub nr_switches: u64,
+    pub nr_voluntary_switches: u64,
+    pub nr_involuntary_switches: u64,
+    /// Cumulative I/O wait in milliseconds (requires schedstats)
+    pub iowait_sum_ms: Option<f64>,
+    pub iowait_count: Option<u64>,
+}
+
+/// Aggregate CPU times from the first line of `/proc/stat`
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CpuTimes {
+    pub user: u64,
+    pub nice: u64,
+    pub system: u64,
+    pub idle: u64,
+    pub iowait: u64,
+    pub irq: u64,
+    pub softirq: u64,
+    pub steal: u64,
+}
+
+impl CpuTimes {
+    pub fn total(&self) -> u64 {
+        self.user + self.nice + self.system + self.idle + self.iowait + self.irq + self.softirq + self.steal
+    }
+
+    pub fn idle_total(&self) -> u64 {
+        self.idle + self.iowait
+    }
+}
+
+/// Per-interface counters from `/proc/net/dev`
+#[derive(Debug, Clone, Default)]
+pub struct NetDevCounters {
+    pub rx_bytes: u64,
+    pub rx_packets: u64,
+    pub rx_errors: u64,
+    pub rx_dropped: u64,
+    pub tx_bytes: u64,
+    pub tx_packets: u64,
+    pub tx_errors: u64,
+    pub tx_dropped: u64,
+}
+
+/// Per-device counters from `/proc/diskstats`
+#[derive(Debug, Clone, Default)]
+pub struct DiskStats {
+    pub reads_completed: u64,
+    pub sectors_read: u64,
+    pub writes_completed: u64,
+    pub sectors_written: u64,
+    pub io_in_progress: u64,
+    pub io_time_ms: u64,
+}
+
+/// Sector size used by `/proc/diskstats` regardless of the device
+pub const DISKSTATS_SECTOR_SIZE: u64 = 512;
+
+/// Parse `/proc/<pid>/stat`. The command name may contain spaces and
+/// parentheses, so fields are located relative to the last `)`.
+pub fn parse_stat(content: &str) -> Option<ProcStat> {
+    let open = content.find('(')?;
+    let close = content.rfind(')')?;
+    let pid = content[..open].trim().parse().ok()?;
+    let comm = content[open + 1..close].to_string();
+    let rest: Vec<&str> = content[close + 1..].split_whitespace().collect();
+
+    // rest[0] is field 3 (state) in proc(5) numbering
+    let field = |n: usize| rest.get(n - 3).copied();
+    let num = |n: usize| field(n).and_then(|s| s.parse::<u64>().ok()).unwrap_or(0);
+    let signed = |n: usize| field(n).and_then(|s| s.parse::<i64>().ok()).unwrap_or(0);
+
+    Some(ProcStat {
+        pid,
+        comm,
+        state: field(3)?.chars().next()?,
+        ppid: num(4) as u32,
+        pgrp: signed(5) as i32,
+        flags: num(9),
+        minflt: num(10),
+        majflt: num(12),
+        utime: num(14),
+        stime: num(15),
+        priority: signed(18),
+        nice: signed(19) as i32,
+        num_threads: num(20) as usize,
+        starttime: num(22),
+        vsize: num(23),
+        rss_pages: signed(24).max(0) as u64,
+        processor: signed(39) as i32,
+        delayacct_blkio_ticks: num(42),
+    })
+}
+
+/// Parse `/proc/<pid>/status`
+pub fn parse_status(content: &str) -> ProcStatus {
+    let mut status = ProcStatus::default();
+    let mut sig_pnd = 0;
+    let mut shd_pnd = 0;
+
+    for line in content.lines() {
+        let Some((key, value)) = line.split_once(':') else { continue };
+        let value = value.trim();
+        match key {
+            "Name" => status.name = value.to_string(),
+            "Tgid" => status.tgid = value.parse().unwrap_or(0),
+            "Uid" => {
+                status.uid = value.split_whitespace().next()
+                    .and_then(|v| v.parse().ok())
+                    .unwrap_or(0)
+            }
+            "voluntary_ctxt_switches" => status.voluntary_ctxt_switches = value.parse().unwrap_or(0),
+            "nonvoluntary_ctxt_switches" => status.nonvoluntary_ctxt_switches = value.parse().unwrap_or(0),
+            "SigPnd" => sig_pnd = u64::from_str_radix(value, 16).unwrap_or(0),
+            "ShdPnd" => shd_pnd = u64::from_str_radix(value, 16).unwrap_or(0),
+            "SigBlk" => status.blocked_signals = u64::from_str_radix(value, 16).unwrap_or(0),
+            _ => {}
+        }
+    }
+
+    status.pending_signals = sig_pnd | shd_pnd;
+    status
+}
+
+/// Parse `/proc/<pid>/io`
+pub fn parse_io(content: &str) -> ProcIo {
+    let values = parse_key_values(content, ':');
+    let get = |key: &str| values.get(key).copied().unwrap_or(0);
+
+    ProcIo {
+        rchar: get("rchar"),
+        wchar: get("wchar"),
+        syscr: get("syscr"),
+        syscw: get("syscw"),
+        read_bytes: get("read_bytes"),
+        write_bytes: get("write_bytes"),
+        cancelled_write_bytes: get("cancelled_write_bytes"),
+    }
+}
+
+/// Parse `/proc/<pid>/sched`. Key names changed across kernel versions
+/// (`se.statistics.iowait_sum` vs `iowait_sum`), so keys are matched by suffix.
+pub fn parse_sched(content: &str) -> ProcSched {
+    let mut sched = ProcSched::default();
+
+    for line in content.lines() {
+        let Some((key, value)) = line.split_once(':') else { continue };
+        let key = key.trim();
+        let value = value.trim();
+        let short = key.rsplit('.').next().unwrap_or(key);
+
+        match short {
+            "nr_switches" => sched.nr_switches = value.parse().unwrap_or(0),
+            "nr_voluntary_switches" => sched.nr_voluntary_switches = value.parse().unwrap_or(0),
+            "nr_involuntary_switches" => sched.nr_involuntary_switches = value.parse().unwrap_or(0),
+            "iowait_sum" => sched.iowait_sum_ms = value.parse().ok(),
+            "iowait_count" => sched.iowait_count = value.parse().ok(),
+            _ => {}
+        }
+    }
+
+    sched
+}
+
+/// Parse the aggregate `cpu` line of `/proc/stat`
+pub fn parse_cpu_times(content: &str) -> Option<CpuTimes> {
+    let line = content.lines().find(|l| l.starts_with("cpu "))?;
+    let values: Vec<u64> = line.split_whitespace()
+        .skip(1)
+        .map(|v| v.parse().unwrap_or(0))
+        .collect();
+    let get = |i: usize| values.get(i).copied().unwrap_or(0);
+
+    Some(CpuTimes {
+        user: get(0),
+        nice: get(1),
+        system: get(2),
+        idle: get(3),
+        iowait: get(4),
+        irq: get(5),
+        softirq: get(6),
+        steal: get(7),
+    })
+}
+
+/// Count the per-CPU `cpuN` lines of `/proc/stat`
+pub fn count_cpus(content: &str) -> usize {
+    content.lines()
+        .filter(|l| l.starts_with("cpu") && l.as_bytes().get(3).is_some_and(|b| b.is_ascii_digit()))
+        .count()
+}
+
+/// Parse `/proc/meminfo` into a map of field name to value in bytes
+pub fn parse_meminfo(content: &str) -> HashMap<String, u64> {
+    content.lines()
+        .filter_map(|line| {
+            let (key, rest) = line.split_once(':')?;
+            let mut parts = rest.split_whitespace();
+            let value: u64 = parts.next()?.parse().ok()?;
+            let multiplier = match parts.next() {
+                Some("kB") => 1024,
+                _ => 1,
+            };
+            Some((key.trim().to_string(), value * multiplier))
+        })
+        .collect()
+}
+
+/// Parse `/proc/vmstat` into a map of counter name to value
+pub fn parse_vmstat(content: &str) -> HashMap<String, u64> {
+    content.lines()
+        .filter_map(|line| {
+            let (key, value) = line.split_once(' ')?;
+            Some((key.to_string(), value.trim().parse().ok()?))
+        })
+        .collect()
+}
+
+/// Parse `/proc/loadavg` into the 1, 5 and 15 minute averages
+pub fn parse_loadavg(content: &str) -> (f64, f64, f64) {
+    let mut parts = content.split_whitespace().map(|v| v.parse().unwrap_or(0.0));
+    (
+        parts.next().unwrap_or(0.0),
+        parts.next().unwrap_or(0.0),
+        parts.next().unwrap_or(0.0),
+    )
+}
+
+/// Parse `/proc/uptime` into seconds since boot, with the fraction kept
+pub fn parse_uptime(content: &str) -> f64 {
+    content.split_whitespace()
+        .next()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(0.0)
+}
+
+/// Parse `/proc/net/dev` into per-interface counters
+pub fn parse_net_dev(content: &str) -> HashMap<String, NetDevCounters> {
+    content.lines()
+        .skip(2) // Two header lines
+        .filter_map(|line| {
+            let (name, rest) = line.split_once(':')?;
+            let values: Vec<u64> = rest.split_whitespace()
+                .map(|v| v.parse().unwrap_or(0))
+                .collect();
+            if values.len() < 16 {
+                return None;
+            }
+            Some((name.trim().to_string(), NetDevCounters {
+                rx_bytes: values[0],
+                rx_packets: values[1],
+                rx_errors: values[2],
+                rx_dropped: values[3],
+                tx_bytes: values[8],
+                tx_packets: values[9],
+                tx_errors: values[10],
+                tx_dropped: values[11],
+            }))
+        })
+        .collect()
+}
+
+/// Parse `/proc/diskstats` into per-device counters
+pub fn parse_diskstats(content: &str) -> HashMap<String, DiskStats> {
+    content.lines()
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            if fields.len() < 14 {
+                return None;
+            }
+            let num = |i: usize| fields[i].parse::<u64>().unwrap_or(0);
+            Some((fields[2].to_string(), DiskStats {
+                reads_completed: num(3),
+                sectors_read: num(5),
+                writes_completed: num(7),
+                sectors_written: num(9),
+                io_in_progress: num(11),
+                io_time_ms: num(12),
+            }))
+        })
+        .collect()
+}
+
+/// Parse the NUL-separated `/proc/<pid>/cmdline`
+pub fn parse_cmdline(content: &[u8]) -> Vec<String> {
+    content.split(|&b| b == 0)
+        .filter(|arg| !arg.is_empty())
+        .map(|arg| String::from_utf8_lossy(arg).into_owned())
+        .collect()
+}
+
+/// Parse the NUL-separated `/proc/<pid>/environ`
+pub fn parse_environ(content: &[u8]) -> HashMap<String, String> {
+    content.split(|&b| b == 0)
+        .filter(|entry| !entry.is_empty())
+        .map(|entry| {
+            let entry = String::from_utf8_lossy(entry);
+            match entry.split_once('=') {
+                Some((key, value)) => (key.to_string(), value.to_string()),
+                None => (entry.into_owned(), String::new()),
+            }
+        })
+        .collect()
+}
+
+/// Parse `/proc/<pid>/stack` lines such as `[<0>] do_select+0x5c8/0x780`
+/// into `(symbol, offset)` pairs
+pub fn parse_kernel_stack(content: &str) -> Vec<(String, Option<u64>)> {
+    content.lines()
+        .filter_map(|line| {
+            let symbol = line.split_once(']').map(|(_, s)| s).unwrap_or(line).trim();
+            if symbol.is_empty() {
+                return None;
+            }
+            match symbol.split_once('+') {
+                Some((name, offsets)) => {
+                    let offset = offsets.split('/').next()
+                        .and_then(|o| u64::from_str_radix(o.trim_start_matches("0x"), 16).ok());
+                    Some((name.to_string(), offset))
+                }
+                None => Some((symbol.to_string(), None)),
+            }
+        })
+        .collect()
+}
+
+/// Parse `/proc/<pid>/syscall` into the syscall number and its arguments.
+/// Returns `None` when the task is running (`running`) or not in a syscall (`-1`).
+pub fn parse_syscall(content: &str) -> Option<(i64, Vec<u64>)> {
+    let mut parts = content.split_whitespace();
+    let nr: i64 = parts.next()?.parse().ok()?;
+    if nr < 0 {
+        return None;
+    }
+    let args = parts.take(6)
+        .filter_map(|a| u64::from_str_radix(a.trim_start_matches("0x"), 16).ok())
+        .collect();
+    Some((nr, args))
+}
+
+fn parse_key_values(content: &str, separator: char) -> HashMap<&str, u64> {
+    content.lines()
+        .filter_map(|line| {
+            let (key, value) = line.split_once(separator)?;
+            Some((key.trim(), value.trim().parse().ok()?))
+        })
+        .collect()
+}
+
+/// Clock ticks per second used by `/proc` time fields
+pub fn clock_ticks() -> u64 {
+    let ticks = unsafe { libc::sysconf(libc::_SC_CLK_TCK) };
+    if ticks > 0 { ticks as u64 } else { 100 }
+}
+
+/// System page size in bytes
+pub fn page_size() -> u64 {
+    let size = unsafe { libc::sysconf(libc::_SC_PAGESIZE) };
+    if size > 0 { size as u64 } else { 4096 }
+}
+
+/// Map an I/O error on a per-process file to a platform error
+pub fn map_io_error(pid: u32, path: &Path, err: std::io::Error) -> PlatformError {
+    match err.kind() {
+        ErrorKind::NotFound => PlatformError::ProcessNotFound(pid),
+        ErrorKind::PermissionDenied => PlatformError::PermissionDenied(
+            format!("Cannot read {}", path.display())
+        ),
+        _ => PlatformError::SystemCallFailed(format!("read {}: {}", path.display(), err)),
+    }
+}
+
+/// Read `/proc/<pid>/<name>` as text
+pub fn read_pid_file(pid: u32, name: &str) -> PlatformResult<String> {
+    let path = Path::new(PROC_ROOT).join(pid.to_string()).join(name);
+    std::fs::read_to_string(&path).map_err(|e| map_io_error(pid, &path, e))
+}
+
+/// Read `/proc/<pid>/<name>` as raw bytes
+pub fn read_pid_bytes(pid: u32, name: &str) -> PlatformResult<Vec<u8>> {
+    let path = Path::new(PROC_ROOT).join(pid.to_string()).join(name);
+    std::fs::read(&path).map_err(|e| map_io_error(pid, &path, e))
+}
+
+/// Read a system-wide file below `/proc`
+pub fn read_proc_file(name: &str) -> PlatformResult<String> {
+    let path = Path::new(PROC_ROOT).join(name);
+    std::fs::read_to_string(&path)
+        .map_err(|e| PlatformError::SystemCallFailed(format!("read {}: {}", path.display(), e)))
+}
+
+pub fn read_stat(pid: u32) -> PlatformResult<ProcStat> {
+    let content = read_pid_file(pid, "stat")?;
+    parse_stat(&content).ok_or_else(|| {
+        PlatformError::SystemCallFailed(format!("Malformed /proc/{}/stat", pid))
+    })
+}
+
+pub fn read_status(pid: u32) -> PlatformResult<ProcStatus> {
+    read_pid_file(pid, "status").map(|c| parse_status(&c))
+}
+
+pub fn read_io(pid: u32) -> PlatformResult<ProcIo> {
+    read_pid_file(pid, "io").map(|c| parse_io(&c))
+}
+
+pub fn read_sched(pid: u32) -> PlatformResult<ProcSched> {
+    read_pid_file(pid, "sched").map(|c| parse_sched(&c))
+}
+
+/// Read `/proc/<pid>/wchan`; `0` or an empty file means not waiting
+pub fn read_wchan(pid: u32) -> Option<String> {
+    read_pid_file(pid, "wchan").ok()
+        .map(|w| w.trim().to_string())
+        .filter(|w| !w.is_empty() && w != "0")
+}
+
+/// List all numeric entries of `/proc`
+pub fn list_pids() -> PlatformResult<Vec<u32>> {
+    list_numeric_dirs(Path::new(PROC_ROOT))
+}
+
+/// List thread IDs from `/proc/<pid>/task`
+pub fn list_tids(pid: u32) -> PlatformResult<Vec<u32>> {
+    let path = Path::new(PROC_ROOT).join(pid.to_string()).join("task");
+    list_numeric_dirs(&path).map_err(|_| PlatformError::ProcessNotFound(pid))
+}
+
+fn list_numeric_dirs(path: &Path) -> PlatformResult<Vec<u32>> {
+    let entries = std::fs::read_dir(path)
+        .map_err(|e| PlatformError::SystemCallFailed(format!("read_dir {}: {}", path.display(), e)))?;
+
+    Ok(entries
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| entry.file_name().to_str()?.parse().ok())
+        .collect())
+}
+
+/// Resolve the targets of all open file descriptors of a process
+pub fn read_fd_targets(pid: u32) -> PlatformResult<Vec<(u32, String)>> {
+    let path = Path::new(PROC_ROOT).join(pid.to_string()).join("fd");
+    let entries = std::fs::read_dir(&path).map_err(|e| map_io_error(pid, &path, e))?;
+
+    Ok(entries
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| {
+            let fd = entry.file_name().to_str()?.parse().ok()?;
+            let target = std::fs::read_link(entry.path()).ok()?;
+            Some((fd, target.to_string_lossy().into_owned()))
+        })
+        .collect())
+}
+
+/// Last OS error number for the calling thread
+pub fn last_errno() -> i32 {
+    std::io::Error::last_os_error().raw_os_error().unwrap_or(0)
+}
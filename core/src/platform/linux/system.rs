@@ -0,0 +1,161 @@
+//! Linux system monitoring implementation backed by `/proc` and `/sys`
+
+use super::procfs::{self, CpuTimes};
+use crate::platform::{PlatformError, PlatformResult, SystemMetrics, SystemMonitor};
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::Mutex;
+
+const THERMAL_ROOT: &str = "/sys/class/thermal";
+
+/// Thermal zone types that report package or core temperature
+const CPU_THERMAL_ZONES: &[&str] = &["x86_pkg_temp", "cpu", "soc", "coretemp", "k10temp", "acpitz"];
+
+pub struct LinuxSystemMonitor {
+    // CPU times from the previous call, used to compute usage over the interval
+    last_cpu_times: Mutex<Option<CpuTimes>>,
+}
+
+impl LinuxSystemMonitor {
+    pub fn new() -> Self {
+        Self {
+            last_cpu_times: Mutex::new(None),
+        }
+    }
+
+    fn cpu_usage_percent(&self, current: CpuTimes) -> f32 {
+        let mut last = match self.last_cpu_times.lock() {
+            Ok(last) => last,
+            Err(poisoned) => poisoned.into_inner(),
+        };
+
+        // Without a previous sample, report the average since boot
+        let previous = last.unwrap_or_default();
+        *last = Some(current);
+
+        let total = current.total().saturating_sub(previous.total());
+        let idle = current.idle_total().saturating_sub(previous.idle_total());
+        if total == 0 {
+            return 0.0;
+        }
+
+        (total.saturating_sub(idle) as f64 / total as f64 * 100.0) as f32
+    }
+
+    fn cpu_frequency_mhz() -> f64 {
+        let scaling = "/sys/devices/system/cpu/cpu0/cpufreq/scaling_cur_freq";
+        if let Some(khz) = std::fs::read_to_string(scaling).ok()
+            .and_then(|c| c.trim().parse::<f64>().ok())
+        {
+            return khz / 1000.0;
+        }
+
+        procfs::read_proc_file("cpuinfo").ok()
+            .and_then(|info| {
+                info.lines()
+                    .find(|l| l.starts_with("cpu MHz"))
+                    .and_then(|l| l.split_once(':'))
+                    .and_then(|(_, v)| v.trim().parse().ok())
+            })
+            .unwrap_or(0.0)
+    }
+}
+
+impl Default for LinuxSystemMonitor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SystemMonitor for LinuxSystemMonitor {
+    fn get_system_metrics(&self) -> PlatformResult<SystemMetrics> {
+        let stat = procfs::read_proc_file("stat")?;
+        let cpu_times = procfs::parse_cpu_times(&stat)
+            .ok_or_else(|| PlatformError::SystemCallFailed("Malformed /proc/stat".to_string()))?;
+
+        let meminfo = procfs::parse_meminfo(&procfs::read_proc_file("meminfo")?);
+        let mem = |key: &str| meminfo.get(key).copied().unwrap_or(0);
+
+        let (load_1, load_5, load_15) = procfs::read_proc_file("loadavg")
+            .map(|c| procfs::parse_loadavg(&c))
+            .unwrap_or((0.0, 0.0, 0.0));
+
+        let uptime = procfs::read_proc_file("uptime")
+            .map(|c| procfs::parse_uptime(&c))
+            .unwrap_or(0.0);
+
+        let memory_total = mem("MemTotal");
+        let memory_available = mem("MemAvailable");
+
+        Ok(SystemMetrics {
+            cpu_count: procfs::count_cpus(&stat),
+            cpu_frequency_mhz: Self::cpu_frequency_mhz(),
+            cpu_usage_percent: self.cpu_usage_percent(cpu_times),
+            memory_total_bytes: memory_total,
+            memory_used_bytes: memory_total.saturating_sub(memory_available),
+            memory_available_bytes: memory_available,
+            swap_total_bytes: mem("SwapTotal"),
+            swap_used_bytes: mem("SwapTotal").saturating_sub(mem("SwapFree")),
+            load_average_1min: load_1,
+            load_average_5min: load_5,
+            load_average_15min: load_15,
+            uptime_seconds: uptime as u64,
+        })
+    }
+
+    fn get_cpu_temperature(&self) -> PlatformResult<Option<f32>> {
+        let Ok(entries) = std::fs::read_dir(THERMAL_ROOT) else {
+            return Ok(None);
+        };
+
+        let mut fallback = None;
+        for entry in entries.filter_map(|e| e.ok()) {
+            let zone = entry.path();
+            if !entry.file_name().to_string_lossy().starts_with("thermal_zone") {
+                continue;
+            }
+
+            let Some(millidegrees) = read_trimmed(&zone.join("temp"))
+                .and_then(|t| t.parse::<f32>().ok())
+            else {
+                continue;
+            };
+            let celsius = millidegrees / 1000.0;
+
+            let zone_type = read_trimmed(&zone.join("type")).unwrap_or_default();
+            if CPU_THERMAL_ZONES.iter().any(|t| zone_type.contains(t)) {
+                return Ok(Some(celsius));
+            }
+            fallback.get_or_insert(celsius);
+        }
+
+        Ok(fallback)
+    }
+
+    fn get_disk_io_stats(&self) -> PlatformResult<HashMap<String, (u64, u64)>> {
+        let stats = procfs::parse_diskstats(&procfs::read_proc_file("diskstats")?);
+
+        Ok(stats.into_iter()
+            .filter(|(name, _)| !name.starts_with("loop") && !name.starts_with("ram"))
+            .map(|(name, s)| (
+                name,
+                (
+                    s.sectors_read * procfs::DISKSTATS_SECTOR_SIZE,
+                    s.sectors_written * procfs::DISKSTATS_SECTOR_SIZE,
+                ),
+            ))
+            .collect())
+    }
+
+    fn get_network_io_stats(&self) -> PlatformResult<HashMap<String, (u64, u64)>> {
+        let counters = procfs::parse_net_dev(&procfs::read_proc_file("net/dev")?);
+
+        Ok(counters.into_iter()
+            .map(|(name, c)| (name, (c.rx_bytes, c.tx_bytes)))
+            .collect())
+    }
+}
+
+fn read_trimmed(path: &Path) -> Option<String> {
+    std::fs::read_to_string(path).ok().map(|s| s.trim().to_string())
+}
@@ -15,6 +15,9 @@ pub mod macos;
 #[cfg(target_os = "windows")]
 pub mod windows;
 
+#[cfg(target_os = "linux")]
+pub mod linux;
+
 // Re-export the current platform implementation
 #[cfg(target_os = "macos")]
 pub use macos::*;
@@ -22,6 +25,9 @@ pub use macos::*;
 #[cfg(target_os = "windows")]
 pub use windows::*;
 
+#[cfg(target_os = "linux")]
+pub use linux::*;
+
 /// Result type for platform operations
 pub type PlatformResult<T> = Result<T, PlatformError>;
 
@@ -374,7 +380,19 @@ pub fn get_platform_capabilities() -> PlatformCapabilities {
         }
     }
     
-    #[cfg(not(any(target_os = "macos", target_os = "windows")))]
+    #[cfg(target_os = "linux")]
+    {
+        PlatformCapabilities {
+            can_kill_processes: true,
+            can_suspend_processes: true,
+            can_set_priority: true,
+            has_temperature_sensors: true,  // Via /sys/class/thermal
+            supports_process_groups: true,
+            requires_elevation: false,  // None for the user's own processes; others and kernel stacks need root or CAP_SYS_ADMIN
+        }
+    }
+    
+    #[cfg(not(any(target_os = "macos", target_os = "windows", target_os = "linux")))]
     {
         PlatformCapabilities::default()
     }
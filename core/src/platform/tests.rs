@@ -95,4 +95,218 @@ mod tests {
         assert!(metrics.memory_used_bytes > 0);
         assert!(metrics.memory_used_bytes <= metrics.memory_total_bytes);
     }
-}
\ No newline at end of file
+    
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_linux_platform_creation() {
+        use super::super::linux::LinuxPlatform;
+        
+        let platform = LinuxPlatform::new();
+        
+        let _pm = platform.process_manager();
+        let _sm = platform.system_monitor();
+        let _ko = platform.kernel_ops();
+        let _pa = platform.process_analyzer();
+    }
+    
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_linux_parse_stat_with_spaces_in_comm() {
+        use super::super::linux::procfs::parse_stat;
+        
+        let content = "1234 (Web Content (x)) S 1000 1234 1000 0 -1 4194560 5000 0 12 0 \
+            250 75 0 0 20 0 31 0 98765 4096000000 51200 18446744073709551615 1 1 0 0 0 0 \
+            0 4096 17663 0 0 0 17 3 0 0 42 0 0";
+        let stat = parse_stat(content).unwrap();
+        
+        assert_eq!(stat.pid, 1234);
+        assert_eq!(stat.comm, "Web Content (x)");
+        assert_eq!(stat.state, 'S');
+        assert_eq!(stat.ppid, 1000);
+        assert_eq!(stat.minflt, 5000);
+        assert_eq!(stat.majflt, 12);
+        assert_eq!(stat.total_ticks(), 325);
+        assert_eq!(stat.nice, 0);
+        assert_eq!(stat.num_threads, 31);
+        assert_eq!(stat.starttime, 98765);
+        assert_eq!(stat.rss_pages, 51200);
+        assert_eq!(stat.processor, 3);
+        assert_eq!(stat.delayacct_blkio_ticks, 42);
+        assert!(!stat.is_kernel_thread());
+    }
+    
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_linux_parse_status_signals() {
+        use super::super::linux::procfs::parse_status;
+        
+        let content = "Name:\tcargo\nTgid:\t4321\nUid:\t1000\t1000\t1000\t1000\n\
+            SigPnd:\t0000000000000000\nShdPnd:\t0000000000000100\n\
+            SigBlk:\t0000000000010000\n\
+            voluntary_ctxt_switches:\t150\nnonvoluntary_ctxt_switches:\t25\n";
+        let status = parse_status(content);
+        
+        assert_eq!(status.name, "cargo");
+        assert_eq!(status.tgid, 4321);
+        assert_eq!(status.uid, 1000);
+        assert_eq!(status.context_switches(), 175);
+        assert!(status.has_pending_sigkill());
+        assert_eq!(status.blocked_signals, 0x10000);
+    }
+    
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_linux_parse_io_and_sched() {
+        use super::super::linux::procfs::{parse_io, parse_sched};
+        
+        let io = parse_io("rchar: 100\nwchar: 200\nsyscr: 3\nsyscw: 4\n\
+            read_bytes: 4096\nwrite_bytes: 8192\ncancelled_write_bytes: 0\n");
+        assert_eq!(io.rchar, 100);
+        assert_eq!(io.write_bytes, 8192);
+        
+        let sched = parse_sched("cargo (4321, #threads: 1)\n\
+            -------------------------------------------------------------------\n\
+            se.exec_start                                :      12345.678901\n\
+            se.statistics.iowait_sum                     :        250.500000\n\
+            se.statistics.iowait_count                   :                 7\n\
+            nr_switches                                  :               175\n");
+        assert_eq!(sched.iowait_sum_ms, Some(250.5));
+        assert_eq!(sched.iowait_count, Some(7));
+        assert_eq!(sched.nr_switches, 175);
+    }
+    
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_linux_parse_system_files() {
+        use super::super::linux::procfs::*;
+        
+        let stat = "cpu  100 0 50 800 50 0 0 0 0 0\ncpu0 50 0 25 400 25 0 0 0 0 0\n\
+            cpu1 50 0 25 400 25 0 0 0 0 0\nintr 0\n";
+        let times = parse_cpu_times(stat).unwrap();
+        assert_eq!(times.total(), 1000);
+        assert_eq!(times.idle_total(), 850);
+        assert_eq!(count_cpus(stat), 2);
+        
+        let meminfo = parse_meminfo("MemTotal:       16384 kB\nMemAvailable:    8192 kB\nHugePages_Total:       0\n");
+        assert_eq!(meminfo["MemTotal"], 16384 * 1024);
+        assert_eq!(meminfo["HugePages_Total"], 0);
+        
+        let vmstat = parse_vmstat("nr_free_pages 1024\npgmajfault 14730\noom_kill 2\n");
+        assert_eq!(vmstat["pgmajfault"], 14730);
+        assert_eq!(vmstat["oom_kill"], 2);
+        
+        let net = parse_net_dev("Inter-|   Receive\n face |bytes\n  eth0: 1000 10 1 2 0 0 0 0 2000 20 3 4 0 0 0 0\n");
+        assert_eq!(net["eth0"].rx_bytes, 1000);
+        assert_eq!(net["eth0"].tx_packets, 20);
+        assert_eq!(net["eth0"].tx_dropped, 4);
+        
+        let disks = parse_diskstats("   8       0 sda 100 0 2048 10 50 0 4096 20 0 30 40\n");
+        assert_eq!(disks["sda"].sectors_read, 2048);
+        assert_eq!(disks["sda"].sectors_written, 4096);
+        
+        let frames = parse_kernel_stack("[<0>] do_select+0x5c8/0x780\n[<0>] entry_SYSCALL_64_after_hwframe+0x76/0x7e\n");
+        assert_eq!(frames[0], ("do_select".to_string(), Some(0x5c8)));
+        assert_eq!(frames.len(), 2);
+        
+        assert_eq!(parse_syscall("running\n"), None);
+        assert_eq!(parse_syscall("0 0x3 0x7ffd 0x1000 0x0 0x0 0x0 0x7ffc 0x7f00\n").unwrap().1[0], 3);
+    }
+    
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_linux_cgroup_paths() {
+        use super::super::linux::cgroup::*;
+
+        let mountinfo = "24 1 0:22 / /sys rw - sysfs sysfs rw\n\
+            42 32 0:38 / /sys/fs/cgroup/unified rw,relatime shared:9 - cgroup2 cgroup2 rw\n";
+        assert_eq!(parse_cgroup2_mount(mountinfo), Some("/sys/fs/cgroup/unified".into()));
+        assert_eq!(parse_cgroup2_mount("33 32 0:29 / /sys/fs/cgroup/cpu rw - cgroup cgroup rw,cpu\n"), None);
+
+        assert_eq!(parse_cgroup_v2_path("4:memory:/a\n0::/user.slice/app.scope\n").as_deref(), Some("/user.slice/app.scope"));
+        assert_eq!(parse_cgroup_v2_path("1:cpu:/\n"), None);
+
+        let dir = tempfile::tempdir().unwrap();
+        let fs = CgroupFs::at(dir.path());
+        std::fs::write(dir.path().join("cgroup.controllers"), "cpuset cpu io memory pids\n").unwrap();
+        std::fs::write(dir.path().join("cgroup.subtree_control"), "memory\n").unwrap();
+        assert!(fs.has_controller("cpu"));
+        assert!(!fs.has_controller("hugetlb"));
+        assert_eq!(fs.path_of("/user.slice"), dir.path().join("user.slice"));
+
+        let cgroup = fs.create_managed("pid-42", &["cpu"]).unwrap();
+        assert_eq!(cgroup, dir.path().join(REAPER_CGROUP).join("pid-42"));
+        assert_eq!(read_file(&dir.path().join("cgroup.subtree_control")).unwrap(), "+cpu");
+        fs.move_process(42, &cgroup).unwrap();
+        assert_eq!(read_file(&cgroup.join("cgroup.procs")).unwrap(), "42");
+
+        std::fs::remove_file(cgroup.join("cgroup.procs")).unwrap();
+        fs.remove(&cgroup).unwrap();
+        fs.remove(&cgroup).unwrap();
+        assert!(!cgroup.exists());
+
+        // Limiters share one cgroup per process; the last to leave restores it
+        let pid = std::process::id();
+        let attached = fs.attach(pid, "cpu").unwrap();
+        assert_eq!(attached.original, parse_cgroup_v2_path(&std::fs::read_to_string("/proc/self/cgroup").unwrap()).unwrap());
+        assert_eq!(read_file(&attached.dir.join("cgroup.procs")).unwrap(), pid.to_string());
+        let shared = fs.attach(pid, "memory").unwrap();
+        assert_eq!(shared.dir, attached.dir);
+        assert_eq!(shared.controllers.len(), 2);
+        assert!(read_file(&dir.path().join(REAPER_CGROUP).join("cgroup.subtree_control")).unwrap().contains("+memory"));
+
+        fs.detach(pid, "cpu").unwrap();
+        assert_eq!(CgroupFs::attachment(pid).unwrap().controllers.len(), 1);
+        // Interface files on a real cgroupfs don't stop rmdir; plain files here do
+        std::fs::remove_file(attached.dir.join("cgroup.procs")).unwrap();
+        std::fs::create_dir_all(fs.path_of(&attached.original)).unwrap();
+        fs.detach(pid, "memory").unwrap();
+        assert!(!attached.dir.exists());
+        assert!(CgroupFs::attachment(pid).is_none());
+        assert_eq!(read_file(&fs.path_of(&attached.original).join("cgroup.procs")).unwrap(), pid.to_string());
+
+        // A limit set by another Reaper process keeps the process in place
+        let attached = fs.attach(pid, "cpu").unwrap();
+        std::fs::write(attached.dir.join("cpu.max"), "max 100000").unwrap();
+        std::fs::write(attached.dir.join("io.max"), "8:0 rbps=max wbps=max riops=max wiops=max").unwrap();
+        std::fs::write(attached.dir.join("memory.max"), "1073741824").unwrap();
+        assert_eq!(limiting_controllers(&attached.dir).into_iter().collect::<Vec<_>>(), vec!["memory"]);
+        fs.detach(pid, "cpu").unwrap();
+        assert!(attached.dir.exists());
+        assert_eq!(read_file(&attached.dir.join("cgroup.procs")).unwrap(), pid.to_string());
+
+        std::fs::write(attached.dir.join("io.max"), "8:0 rbps=max wbps=1048576 riops=max wiops=max").unwrap();
+        std::fs::write(attached.dir.join("memory.max"), "max").unwrap();
+        assert_eq!(limiting_controllers(&attached.dir).into_iter().collect::<Vec<_>>(), vec!["io"]);
+        for file in ["cgroup.procs", "cpu.max", "io.max", "memory.max"] {
+            std::fs::remove_file(attached.dir.join(file)).unwrap();
+        }
+        fs.detach(pid, "memory").unwrap();
+        assert!(!attached.dir.exists());
+        assert!(CgroupFs::attachment(pid).is_none());
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_linux_current_process_info() {
+        use super::super::linux::LinuxPlatform;
+        
+        let platform = LinuxPlatform::new();
+        let pid = std::process::id();
+        
+        let info = platform.process_manager().get_process_info(pid).unwrap();
+        assert_eq!(info.pid, pid);
+        assert!(info.memory_bytes > 0);
+        assert!(info.thread_count >= 1);
+        assert!(info.context_switches > 0);
+        
+        let state = platform.process_analyzer().get_process_state(pid).unwrap();
+        assert_eq!(state.tgid, pid);
+        
+        assert!(!platform.kernel_ops().is_kernel_process(pid));
+        assert!(platform.kernel_ops().is_kernel_process(1));
+        
+        let metrics = platform.system_monitor().get_system_metrics().unwrap();
+        assert!(metrics.cpu_count > 0);
+        assert!(metrics.memory_used_bytes <= metrics.memory_total_bytes);
+    }
+}
@@ -0,0 +1,224 @@
+use super::{CpuThrottler, Metric, SharedSystem};
+use crate::alerts::AlertEngine;
+use crate::common::Monitor;
+use crate::metrics::{unix_now, MetricsStore};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+use sysinfo::Pid;
+
+/// Shortest sleep between two engine cycles
+const MIN_CYCLE: Duration = Duration::from_millis(50);
+
+struct RegisteredMonitor {
+    monitor: Arc<Mutex<dyn Monitor + Send>>,
+    interval: Duration,
+    last_refresh: Option<Instant>,
+}
+
+/// Drives all registered monitors from one refresh loop
+///
+/// Each cycle refreshes the shared system metrics that are due, then the
+/// monitors that are due. The engine measures its own process's CPU usage
+/// after every cycle and feeds it to a `CpuThrottler`, whose slowdown
+/// factor stretches every interval while Reaper is over budget.
+pub struct SamplingEngine {
+    system: SharedSystem,
+    monitors: Vec<RegisteredMonitor>,
+    throttler: CpuThrottler,
+    own_pid: Option<Pid>,
+    metrics_store: Option<Arc<Mutex<MetricsStore>>>,
+    alert_engine: Option<Arc<Mutex<AlertEngine>>>,
+}
+
+/// Summary of one engine cycle
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TickReport {
+    pub refreshed_metrics: Vec<Metric>,
+    pub refreshed_monitors: usize,
+}
+
+impl SamplingEngine {
+    pub fn new(system: SharedSystem) -> Self {
+        Self {
+            system,
+            monitors: Vec::new(),
+            throttler: CpuThrottler::default(),
+            own_pid: sysinfo::get_current_pid().ok(),
+            metrics_store: None,
+            alert_engine: None,
+        }
+    }
+
+    pub fn system(&self) -> SharedSystem {
+        self.system.clone()
+    }
+
+    pub fn throttler(&self) -> &CpuThrottler {
+        &self.throttler
+    }
+
+    pub fn throttler_mut(&mut self) -> &mut CpuThrottler {
+        &mut self.throttler
+    }
+
+    /// Record each monitor's `metric_samples` into `store` after it refreshes
+    pub fn set_metrics_store(&mut self, store: Arc<Mutex<MetricsStore>>) {
+        self.metrics_store = Some(store);
+    }
+
+    /// Evaluate alert rules against each monitor's `metric_samples`
+    pub fn set_alert_engine(&mut self, alerts: Arc<Mutex<AlertEngine>>) {
+        self.alert_engine = Some(alerts);
+    }
+
+    /// Refresh `monitor` every `interval`, before slowdown
+    pub fn register<M: Monitor + Send + 'static>(&mut self, monitor: Arc<Mutex<M>>, interval: Duration) {
+        self.monitors.push(RegisteredMonitor {
+            monitor,
+            interval,
+            last_refresh: None,
+        });
+    }
+
+    pub fn monitor_names(&self) -> Vec<String> {
+        self.monitors
+            .iter()
+            .filter_map(|m| m.monitor.lock().ok().map(|m| m.name().to_string()))
+            .collect()
+    }
+
+    /// Run one cycle at `now`: refresh due metrics, then due monitors
+    pub fn tick(&mut self, now: Instant) -> TickReport {
+        let slowdown = self.throttler.slowdown_factor();
+        self.system.set_slowdown(slowdown);
+
+        let refreshed_metrics = Metric::ALL
+            .into_iter()
+            .filter(|&metric| self.system.ensure_fresh_at(metric, now))
+            .collect();
+
+        let mut refreshed_monitors = 0;
+        for registered in &mut self.monitors {
+            let due = registered.last_refresh
+                .is_none_or(|last| now.saturating_duration_since(last) >= registered.interval * slowdown);
+            if !due {
+                continue;
+            }
+
+            if let Ok(mut monitor) = registered.monitor.lock() {
+                monitor.refresh();
+                refreshed_monitors += 1;
+
+                if self.metrics_store.is_some() || self.alert_engine.is_some() {
+                    let samples = monitor.metric_samples();
+                    let timestamp = unix_now();
+
+                    if let Some(alerts) = &self.alert_engine {
+                        if let Ok(mut alerts) = alerts.lock() {
+                            alerts.observe(timestamp, &samples);
+                        }
+                    }
+                    if let Some(store) = &self.metrics_store {
+                        if let Ok(mut store) = store.lock() {
+                            let _ = store.record_all(timestamp, samples);
+                        }
+                    }
+                }
+            }
+            registered.last_refresh = Some(now);
+        }
+
+        TickReport { refreshed_metrics, refreshed_monitors }
+    }
+
+    /// Time from `now` until the next metric or monitor is due
+    pub fn next_delay(&self, now: Instant) -> Duration {
+        let slowdown = self.throttler.slowdown_factor();
+        let metric_due = Metric::ALL.iter().map(|&metric| self.system.next_due(metric));
+        let monitor_due = self.monitors
+            .iter()
+            .map(|m| m.last_refresh.map(|last| last + m.interval * slowdown));
+
+        metric_due
+            .chain(monitor_due)
+            .map(|due| due.map_or(Duration::ZERO, |due| due.saturating_duration_since(now)))
+            .min()
+            .unwrap_or_else(|| self.throttler.get_refresh_interval())
+            .max(MIN_CYCLE)
+    }
+
+    /// One iteration of the background loop; returns how long to sleep
+    fn step(&mut self) -> Duration {
+        if self.throttler.should_skip_update() {
+            self.record_own_usage();
+            return self.throttler.get_refresh_interval();
+        }
+
+        let now = Instant::now();
+        self.tick(now);
+        self.record_own_usage();
+        self.next_delay(Instant::now())
+    }
+
+    fn record_own_usage(&mut self) {
+        let Some(pid) = self.own_pid else {
+            return;
+        };
+        if !self.system.refresh_process(pid) {
+            return;
+        }
+        let usage = self.system.read().process(pid).map(|p| p.cpu_usage());
+        if let Some(usage) = usage {
+            self.throttler.update_usage(usage);
+        }
+    }
+
+    /// Run the engine on a background thread until the handle is stopped
+    pub fn spawn(engine: Arc<Mutex<SamplingEngine>>) -> SamplingHandle {
+        let stop = Arc::new(AtomicBool::new(false));
+        let thread_stop = Arc::clone(&stop);
+
+        let thread = std::thread::Builder::new()
+            .name("reaper-sampling".to_string())
+            .spawn(move || {
+                while !thread_stop.load(Ordering::Relaxed) {
+                    let delay = match engine.lock() {
+                        Ok(mut engine) => engine.step(),
+                        Err(_) => break,
+                    };
+                    std::thread::park_timeout(delay);
+                }
+            })
+            .expect("failed to spawn sampling thread");
+
+        SamplingHandle { stop, thread: Some(thread) }
+    }
+}
+
+/// A running `SamplingEngine` loop; stopped when dropped
+pub struct SamplingHandle {
+    stop: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl SamplingHandle {
+    pub fn stop(mut self) {
+        self.shutdown();
+    }
+
+    fn shutdown(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        if let Some(thread) = self.thread.take() {
+            thread.thread().unpark();
+            let _ = thread.join();
+        }
+    }
+}
+
+impl Drop for SamplingHandle {
+    fn drop(&mut self) {
+        self.shutdown();
+    }
+}
@@ -0,0 +1,92 @@
+//! Unified sampling engine
+//!
+//! Monitors read from one `SharedSystem` instead of each owning a
+//! `sysinfo::System`, and a `SamplingEngine` refreshes the system and the
+//! registered monitors on a single loop with per-metric intervals.
+
+mod engine;
+mod system;
+mod throttler;
+
+pub use engine::{SamplingEngine, SamplingHandle, TickReport};
+pub use system::{SharedSystem, SystemHandle, SystemRef};
+pub use throttler::{CpuThrottler, ThrottleStats};
+
+use crate::common::Monitor;
+use once_cell::sync::Lazy;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use sysinfo::System;
+
+/// A part of the sysinfo state that is refreshed as a unit
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Metric {
+    Cpu,
+    Memory,
+    Processes,
+}
+
+impl Metric {
+    pub const ALL: [Metric; 3] = [Metric::Cpu, Metric::Memory, Metric::Processes];
+
+    pub fn default_interval(self) -> Duration {
+        match self {
+            Metric::Cpu => Duration::from_secs(1),
+            Metric::Memory => Duration::from_secs(2),
+            Metric::Processes => Duration::from_secs(2),
+        }
+    }
+
+    fn refresh(self, system: &mut System) {
+        match self {
+            Metric::Cpu => system.refresh_cpu(),
+            Metric::Memory => system.refresh_memory(),
+            Metric::Processes => system.refresh_processes(),
+        }
+    }
+}
+
+static SHARED_SYSTEM: Lazy<SharedSystem> = Lazy::new(SharedSystem::new);
+
+static GLOBAL_ENGINE: Lazy<Arc<Mutex<SamplingEngine>>> = Lazy::new(|| {
+    Arc::new(Mutex::new(SamplingEngine::new(shared_system())))
+});
+
+static GLOBAL_HANDLE: Lazy<Mutex<Option<SamplingHandle>>> = Lazy::new(|| Mutex::new(None));
+
+/// The process-wide system snapshot used by the FFI monitors
+pub fn shared_system() -> SharedSystem {
+    SHARED_SYSTEM.clone()
+}
+
+/// The process-wide engine driving `shared_system()`
+pub fn global_engine() -> Arc<Mutex<SamplingEngine>> {
+    Arc::clone(&GLOBAL_ENGINE)
+}
+
+/// Have the global engine refresh `monitor` every `interval`
+pub fn register_global<M: Monitor + Send + 'static>(monitor: Arc<Mutex<M>>, interval: Duration) {
+    GLOBAL_ENGINE.lock().unwrap_or_else(|e| e.into_inner()).register(monitor, interval);
+}
+
+/// Start the global engine's background loop; no-op if already running
+pub fn start_global() {
+    let mut handle = GLOBAL_HANDLE.lock().unwrap_or_else(|e| e.into_inner());
+    if handle.is_none() {
+        *handle = Some(SamplingEngine::spawn(global_engine()));
+    }
+}
+
+pub fn stop_global() {
+    let handle = GLOBAL_HANDLE.lock().unwrap_or_else(|e| e.into_inner()).take();
+    if let Some(handle) = handle {
+        handle.stop();
+    }
+}
+
+pub fn is_global_running() -> bool {
+    GLOBAL_HANDLE.lock().map(|h| h.is_some()).unwrap_or(false)
+}
+
+#[cfg(test)]
+mod tests;
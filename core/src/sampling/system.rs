@@ -0,0 +1,174 @@
+use super::Metric;
+use std::ops::Deref;
+use std::sync::{Arc, Mutex, RwLock, RwLockReadGuard, RwLockWriteGuard};
+use std::time::{Duration, Instant};
+use sysinfo::{Pid, System};
+
+struct Freshness {
+    interval: Duration,
+    refreshed_at: Option<Instant>,
+}
+
+struct Inner {
+    system: RwLock<System>,
+    freshness: Mutex<[Freshness; Metric::ALL.len()]>,
+    slowdown: Mutex<u32>,
+}
+
+/// A `sysinfo::System` shared between monitors
+///
+/// Each metric is refreshed at most once per interval no matter how many
+/// monitors ask for it, so the process table is scanned once per cycle
+/// instead of once per monitor.
+#[derive(Clone)]
+pub struct SharedSystem {
+    inner: Arc<Inner>,
+}
+
+impl SharedSystem {
+    pub fn new() -> Self {
+        Self {
+            inner: Arc::new(Inner {
+                system: RwLock::new(System::new()),
+                freshness: Mutex::new(Metric::ALL.map(|metric| Freshness {
+                    interval: metric.default_interval(),
+                    refreshed_at: None,
+                })),
+                slowdown: Mutex::new(1),
+            }),
+        }
+    }
+
+    pub fn read(&self) -> RwLockReadGuard<'_, System> {
+        self.inner.system.read().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn write(&self) -> RwLockWriteGuard<'_, System> {
+        self.inner.system.write().unwrap_or_else(|e| e.into_inner())
+    }
+
+    pub fn interval(&self, metric: Metric) -> Duration {
+        self.freshness()[metric as usize].interval
+    }
+
+    pub fn set_interval(&self, metric: Metric, interval: Duration) {
+        self.freshness()[metric as usize].interval = interval;
+    }
+
+    /// Stretch every interval by `factor`, used by the engine when Reaper
+    /// itself is using too much CPU
+    pub fn set_slowdown(&self, factor: u32) {
+        *self.inner.slowdown.lock().unwrap_or_else(|e| e.into_inner()) = factor.max(1);
+    }
+
+    /// Interval of `metric` after applying the current slowdown
+    pub fn effective_interval(&self, metric: Metric) -> Duration {
+        let slowdown = *self.inner.slowdown.lock().unwrap_or_else(|e| e.into_inner());
+        self.interval(metric) * slowdown
+    }
+
+    /// Refresh `metric` unless it was refreshed within its interval.
+    /// Returns whether a refresh happened.
+    pub fn ensure_fresh(&self, metric: Metric) -> bool {
+        self.ensure_fresh_at(metric, Instant::now())
+    }
+
+    pub fn ensure_fresh_at(&self, metric: Metric, now: Instant) -> bool {
+        let interval = self.effective_interval(metric);
+        {
+            let mut freshness = self.freshness();
+            let entry = &mut freshness[metric as usize];
+            if entry.refreshed_at.is_some_and(|at| now.saturating_duration_since(at) < interval) {
+                return false;
+            }
+            // Claim the refresh before scanning so concurrent callers skip it
+            entry.refreshed_at = Some(now);
+        }
+
+        metric.refresh(&mut self.write());
+        true
+    }
+
+    /// Refresh `metric` regardless of its age
+    pub fn force_refresh(&self, metric: Metric) {
+        self.freshness()[metric as usize].refreshed_at = Some(Instant::now());
+        metric.refresh(&mut self.write());
+    }
+
+    /// When `metric` next needs refreshing, `None` if it never was
+    pub fn next_due(&self, metric: Metric) -> Option<Instant> {
+        let interval = self.effective_interval(metric);
+        self.freshness()[metric as usize].refreshed_at.map(|at| at + interval)
+    }
+
+    /// Refresh a single process, e.g. Reaper's own for self-monitoring
+    pub fn refresh_process(&self, pid: Pid) -> bool {
+        self.write().refresh_process(pid)
+    }
+
+    fn freshness(&self) -> std::sync::MutexGuard<'_, [Freshness; Metric::ALL.len()]> {
+        self.inner.freshness.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl Default for SharedSystem {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The `System` a monitor reads from: its own, or one shared through a
+/// `SharedSystem`
+pub enum SystemHandle {
+    Owned(Box<System>),
+    Shared(SharedSystem),
+}
+
+impl SystemHandle {
+    pub fn read(&self) -> SystemRef<'_> {
+        match self {
+            SystemHandle::Owned(system) => SystemRef::Owned(system),
+            SystemHandle::Shared(shared) => SystemRef::Shared(shared.read()),
+        }
+    }
+
+    /// Bring `metric` up to date. An owned system runs `refresh` as is; a
+    /// shared one refreshes `metric` only if it is older than its interval.
+    pub fn refresh(&mut self, metric: Metric, refresh: impl FnOnce(&mut System)) {
+        match self {
+            SystemHandle::Owned(system) => refresh(system),
+            SystemHandle::Shared(shared) => {
+                shared.ensure_fresh(metric);
+            }
+        }
+    }
+
+    /// Like `refresh`, but also refreshes a shared system that is still fresh.
+    /// For callers sampling faster than the shared interval.
+    pub fn force_refresh(&mut self, metric: Metric, refresh: impl FnOnce(&mut System)) {
+        match self {
+            SystemHandle::Owned(system) => refresh(system),
+            SystemHandle::Shared(shared) => shared.force_refresh(metric),
+        }
+    }
+
+    pub fn is_shared(&self) -> bool {
+        matches!(self, SystemHandle::Shared(_))
+    }
+}
+
+pub enum SystemRef<'a> {
+    Owned(&'a System),
+    Shared(RwLockReadGuard<'a, System>),
+}
+
+impl Deref for SystemRef<'_> {
+    type Target = System;
+
+    fn deref(&self) -> &System {
+        match self {
+            SystemRef::Owned(system) => system,
+            SystemRef::Shared(guard) => guard,
+        }
+    }
+}
@@ -0,0 +1,130 @@
+use super::*;
+use crate::common::{Monitor, MonitorType};
+use crate::metrics::{MetricSample, MetricsStore, MetricsStoreConfig, RangeQuery, SeriesKey, Tier};
+use std::time::Instant;
+
+struct CountingMonitor {
+    refreshes: usize,
+}
+
+impl Monitor for CountingMonitor {
+    fn name(&self) -> &str {
+        "counting"
+    }
+
+    fn monitor_type(&self) -> MonitorType {
+        MonitorType::CPU
+    }
+
+    fn refresh(&mut self) {
+        self.refreshes += 1;
+    }
+
+    fn metric_samples(&self) -> Vec<MetricSample> {
+        vec![MetricSample::new(SeriesKey::new("test.refreshes"), self.refreshes as f64)]
+    }
+}
+
+#[test]
+fn test_shared_system_refreshes_once_per_interval() {
+    let system = SharedSystem::new();
+    system.set_interval(Metric::Memory, Duration::from_secs(2));
+    let start = Instant::now();
+
+    assert!(system.ensure_fresh_at(Metric::Memory, start));
+    assert!(!system.ensure_fresh_at(Metric::Memory, start + Duration::from_secs(1)));
+    assert!(system.ensure_fresh_at(Metric::Memory, start + Duration::from_secs(2)));
+    assert!(system.read().total_memory() > 0);
+}
+
+#[test]
+fn test_shared_handle_skips_fresh_metrics() {
+    let shared = SharedSystem::new();
+    let mut first = SystemHandle::Shared(shared.clone());
+    let mut second = SystemHandle::Shared(shared.clone());
+
+    first.refresh(Metric::Processes, |_| unreachable!());
+    let refreshed_at = shared.next_due(Metric::Processes);
+    second.refresh(Metric::Processes, |_| unreachable!());
+
+    assert!(refreshed_at.is_some());
+    assert_eq!(shared.next_due(Metric::Processes), refreshed_at);
+    assert!(!second.read().processes().is_empty());
+}
+
+#[test]
+fn test_engine_per_monitor_intervals() {
+    let mut engine = SamplingEngine::new(SharedSystem::new());
+    let fast = Arc::new(Mutex::new(CountingMonitor { refreshes: 0 }));
+    let slow = Arc::new(Mutex::new(CountingMonitor { refreshes: 0 }));
+    engine.register(Arc::clone(&fast), Duration::from_secs(1));
+    engine.register(Arc::clone(&slow), Duration::from_secs(3));
+
+    let start = Instant::now();
+    let first = engine.tick(start);
+    assert_eq!(first.refreshed_metrics, Metric::ALL.to_vec());
+    assert_eq!(first.refreshed_monitors, 2);
+
+    for second in 1..=3 {
+        engine.tick(start + Duration::from_secs(second));
+    }
+
+    assert_eq!(fast.lock().unwrap().refreshes, 4);
+    assert_eq!(slow.lock().unwrap().refreshes, 2);
+    assert_eq!(engine.monitor_names(), vec!["counting", "counting"]);
+}
+
+#[test]
+fn test_throttler_stretches_intervals() {
+    let mut engine = SamplingEngine::new(SharedSystem::new());
+    let monitor = Arc::new(Mutex::new(CountingMonitor { refreshes: 0 }));
+    engine.register(Arc::clone(&monitor), Duration::from_secs(1));
+
+    let start = Instant::now();
+    engine.tick(start);
+    // Over the 2% budget: intervals stretch fivefold
+    engine.throttler_mut().update_usage(3.0);
+
+    let report = engine.tick(start + Duration::from_secs(1));
+    assert!(report.refreshed_metrics.is_empty());
+    assert_eq!(report.refreshed_monitors, 0);
+    assert_eq!(engine.next_delay(start + Duration::from_secs(1)), Duration::from_secs(4));
+
+    engine.tick(start + Duration::from_secs(5));
+    assert_eq!(monitor.lock().unwrap().refreshes, 2);
+}
+
+#[test]
+fn test_spawned_engine_refreshes_monitors() {
+    let engine = Arc::new(Mutex::new(SamplingEngine::new(SharedSystem::new())));
+    let monitor = Arc::new(Mutex::new(CountingMonitor { refreshes: 0 }));
+    engine.lock().unwrap().register(Arc::clone(&monitor), Duration::from_millis(50));
+
+    let handle = SamplingEngine::spawn(Arc::clone(&engine));
+    std::thread::sleep(Duration::from_millis(300));
+    handle.stop();
+
+    assert!(monitor.lock().unwrap().refreshes >= 1);
+}
+
+#[test]
+fn test_engine_records_monitor_samples() {
+    let dir = tempfile::TempDir::new().unwrap();
+    let store = MetricsStore::new(MetricsStoreConfig {
+        data_directory: dir.path().to_path_buf(),
+        ..MetricsStoreConfig::default()
+    })
+    .unwrap();
+    let store = Arc::new(Mutex::new(store));
+
+    let mut engine = SamplingEngine::new(SharedSystem::new());
+    engine.set_metrics_store(Arc::clone(&store));
+    engine.register(Arc::new(Mutex::new(CountingMonitor { refreshes: 0 })), Duration::from_secs(1));
+    engine.tick(Instant::now());
+
+    let now = crate::metrics::unix_now();
+    let query = RangeQuery::new("test.refreshes", now - 60, now, 60);
+    let result = store.lock().unwrap().query_tier(&query, Tier::Raw).unwrap();
+    let recorded: Vec<f64> = result[0].buckets.iter().filter_map(|b| b.aggregate).map(|a| a.last).collect();
+    assert_eq!(recorded, vec![1.0]);
+}
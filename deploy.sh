@@ -84,9 +84,7 @@ build_rust() {
     
     # Verify library sizes
     libraries=(
-        "libreaper_core.dylib:5000"
-        "libreaper_cpu_monitor.dylib:400000"
-        "libreaper_memory_monitor.dylib:400000"
+        "libreaper.dylib:1000000"
     )
     
     for lib_spec in "${libraries[@]}"; do
@@ -105,7 +103,7 @@ build_reaper_app() {
     print_status "Building ReaperApp..."
     
     cd ReaperApp
-    swift build -c release -Xlinker -lreaper_memory_monitor
+    swift build -c release
     
     if ! verify_file_size ".build/release/ReaperApp" 1000000; then
         print_error "ReaperApp executable is too small"
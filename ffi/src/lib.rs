@@ -0,0 +1,97 @@
+//! Single C library for the Reaper apps
+//!
+//! Re-exports reaper-core and every monitor so their `#[no_mangle]` functions
+//! end up in one `libreaper` with one copy of reaper-core's process-wide
+//! state. Linking the monitors as separate dylibs would give each its own
+//! sampling loop, alert engine and audit log.
+
+pub use reaper_core;
+pub use reaper_cpu_monitor;
+pub use reaper_disk_monitor;
+pub use reaper_hardware_monitor;
+pub use reaper_memory_monitor;
+pub use reaper_network_monitor;
+
+#[cfg(test)]
+mod tests {
+    use reaper_core::alerts::{AlertEngine, AlertRule};
+    use reaper_core::sampling::{SamplingEngine, SharedSystem};
+    use reaper_cpu_monitor::remediation::{
+        OutcomeStatus, ProcessController, ProcessIdentity, RemediationAction, RemediationEngine, RemediationPolicy,
+    };
+    use reaper_cpu_monitor::{ActionResult, LimitPreset, ProcessAction};
+    use reaper_memory_monitor::{LeakDetectorConfig, MemoryMonitor};
+    use std::sync::{Arc, Mutex};
+    use std::time::{Duration, Instant};
+
+    /// Knows only the test process, and records actions instead of taking them
+    struct ThisProcess {
+        calls: Arc<Mutex<Vec<String>>>,
+    }
+
+    impl ProcessController for ThisProcess {
+        fn identify(&mut self, pid: u32) -> Option<ProcessIdentity> {
+            (pid == std::process::id()).then(|| ProcessIdentity {
+                pid,
+                name: "reaper-test".to_string(),
+                exe: None,
+                cmdline: vec![],
+            })
+        }
+
+        fn execute(&mut self, pid: u32, action: ProcessAction) -> ActionResult {
+            self.calls.lock().unwrap().push(format!("{:?} {}", action, pid));
+            ActionResult::Success(format!("{:?} sent", action))
+        }
+
+        fn limit(&mut self, pid: u32, preset: LimitPreset) -> Result<String, String> {
+            self.calls.lock().unwrap().push(format!("Limit {:?} {}", preset, pid));
+            Ok("limited".to_string())
+        }
+    }
+
+    #[test]
+    fn test_memory_leak_alert_reaches_remediation() {
+        let config = LeakDetectorConfig {
+            min_samples: 5,
+            min_window: Duration::ZERO,
+            min_confidence: 0.5,
+            ..LeakDetectorConfig::default()
+        };
+        let monitor = Arc::new(Mutex::new(MemoryMonitor::new().with_leak_config(config)));
+        let rule = AlertRule::new("leak", "process.memory_leak == 1").unwrap();
+        let alerts = Arc::new(Mutex::new(AlertEngine::with_rules(vec![rule])));
+        let receiver = alerts.lock().unwrap().subscribe();
+        let mut sampling = SamplingEngine::new(SharedSystem::new());
+        sampling.set_alert_engine(Arc::clone(&alerts));
+        sampling.register(monitor, Duration::ZERO);
+
+        let calls = Arc::default();
+        let action = RemediationAction::Terminate { kill_after: Some(Duration::from_secs(10)) };
+        let mut remediation = RemediationEngine::new(Box::new(ThisProcess { calls: Arc::clone(&calls) }));
+        remediation.set_policies(vec![RemediationPolicy::new("reap-leaks", "leak", action)]);
+
+        // Leak 8 MiB, filled so it is resident, before every refresh
+        let pid = std::process::id();
+        let mut leaked = Vec::new();
+        let mut outcomes = Vec::new();
+        for _ in 0..30 {
+            leaked.push(vec![1u8; 8 << 20]);
+            std::thread::sleep(Duration::from_millis(20));
+            sampling.tick(Instant::now());
+            for alert in receiver.try_iter() {
+                outcomes.extend(remediation.handle_alert(&alert, Instant::now()));
+            }
+            if outcomes.iter().any(|outcome| outcome.pid == Some(pid)) {
+                break;
+            }
+        }
+
+        let outcome = outcomes.iter().find(|outcome| outcome.pid == Some(pid)).expect("no leak alert for this process");
+        assert_eq!(outcome.policy, "reap-leaks");
+        assert!(matches!(outcome.status, OutcomeStatus::Executed(_)), "{:?}", outcome.status);
+        assert_eq!(*calls.lock().unwrap(), vec![format!("Terminate {}", pid)]);
+        // SIGKILL follows if it's still running after the grace period
+        assert!(remediation.next_due().is_some());
+    }
+}
@@ -0,0 +1,22 @@
+use crate::{LimitError, ProcessCpuLimiter};
+use reaper_core::audit::{AuditResult, PendingAudit};
+
+/// Audit a process-control call, with the target's nice value before and after
+pub(crate) fn begin(action: &str, pid: u32) -> PendingAudit {
+    PendingAudit::begin(action, pid).with_priority(current_priority)
+}
+
+impl AuditResult for LimitError {
+    fn audit_result(&self) -> &'static str {
+        match self {
+            LimitError::PermissionDenied => "PermissionDenied",
+            LimitError::ProcessNotFound => "ProcessNotFound",
+            LimitError::InvalidLimit => "InvalidLimit",
+            LimitError::SystemError(_) => "SystemError",
+        }
+    }
+}
+
+fn current_priority(pid: u32) -> Option<i32> {
+    ProcessCpuLimiter::new().get_nice_value(pid).ok()
+}
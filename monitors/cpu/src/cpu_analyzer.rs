@@ -1,6 +1,9 @@
 use serde::{Deserialize, Serialize};
 use std::time::{Instant, Duration};
 use std::collections::VecDeque;
+use reaper_core::common::{Monitor, MonitorType};
+use reaper_core::metrics::{MetricSample, SeriesKey};
+use reaper_core::sampling::{Metric, SharedSystem, SystemHandle};
 use sysinfo::System;
 use std::process::Command;
 
@@ -69,7 +72,7 @@ pub enum BottleneckType {
 }
 
 pub struct CpuAnalyzer {
-    system: System,
+    system: SystemHandle,
     last_update: Instant,
     history: Vec<CpuMetrics>,
     max_history_size: usize,
@@ -155,6 +158,15 @@ impl CpuAnalyzer {
         system.refresh_cpu();
         system.refresh_memory();
         
+        Self::with_handle(SystemHandle::Owned(Box::new(system)))
+    }
+    
+    /// Analyze CPU metrics from a system shared with other monitors
+    pub fn with_system(system: SharedSystem) -> Self {
+        Self::with_handle(SystemHandle::Shared(system))
+    }
+    
+    fn with_handle(system: SystemHandle) -> Self {
         CpuAnalyzer {
             system,
             last_update: Instant::now(),
@@ -179,8 +191,8 @@ impl CpuAnalyzer {
             return;
         }
         
-        self.system.refresh_cpu();
-        self.system.refresh_memory();
+        self.system.refresh(Metric::Cpu, |system| system.refresh_cpu());
+        self.system.refresh(Metric::Memory, |system| system.refresh_memory());
         
         let metrics = self.get_current_metrics();
         
@@ -204,11 +216,12 @@ impl CpuAnalyzer {
     }
     
     pub fn collect_realtime_sample(&mut self) {
-        self.system.refresh_cpu();
+        // Sampled faster than the shared CPU interval
+        self.system.force_refresh(Metric::Cpu, |system| system.refresh_cpu());
         
         let timestamp = Instant::now();
-        let total_usage = self.system.global_cpu_info().cpu_usage();
-        let per_core_usage: Vec<f32> = self.system.cpus().iter()
+        let total_usage = self.system.read().global_cpu_info().cpu_usage();
+        let per_core_usage: Vec<f32> = self.system.read().cpus().iter()
             .map(|cpu| cpu.cpu_usage())
             .collect();
         
@@ -256,8 +269,8 @@ impl CpuAnalyzer {
     
     fn estimate_context_switches(&self) -> u64 {
         // Rough estimation based on CPU usage and process count
-        let cpu_usage = self.system.global_cpu_info().cpu_usage();
-        let process_count = self.system.processes().len() as u64;
+        let cpu_usage = self.system.read().global_cpu_info().cpu_usage();
+        let process_count = self.system.read().processes().len() as u64;
         
         // Higher CPU usage and more processes = more context switches
         ((cpu_usage as u64) * process_count * 10) / 100
@@ -265,7 +278,7 @@ impl CpuAnalyzer {
     
     fn estimate_interrupts(&self) -> u64 {
         // Simplified estimation
-        let cpu_usage = self.system.global_cpu_info().cpu_usage();
+        let cpu_usage = self.system.read().global_cpu_info().cpu_usage();
         (cpu_usage as u64) * 50
     }
     
@@ -275,7 +288,7 @@ impl CpuAnalyzer {
         
         // This is a simplified version - real implementation would
         // parse process states from system calls
-        for process in self.system.processes().values() {
+        for process in self.system.read().processes().values() {
             // sysinfo doesn't provide detailed process states on macOS
             // so we estimate based on CPU usage
             if process.cpu_usage() > 0.1 {
@@ -299,17 +312,19 @@ impl CpuAnalyzer {
     
     pub fn get_current_metrics(&self) -> CpuMetrics {
         let load_avg = System::load_average();
+        let temperature = self.get_cpu_temperature();
+        let system = self.system.read();
 
         CpuMetrics {
-            total_usage: self.system.global_cpu_info().cpu_usage(),
-            per_core_usage: self.system.cpus().iter().map(|cpu| cpu.cpu_usage()).collect(),
+            total_usage: system.global_cpu_info().cpu_usage(),
+            per_core_usage: system.cpus().iter().map(|cpu| cpu.cpu_usage()).collect(),
             load_average: LoadAverage {
                 one_minute: load_avg.one,
                 five_minutes: load_avg.five,
                 fifteen_minutes: load_avg.fifteen,
             },
-            frequency_mhz: self.system.global_cpu_info().frequency(),
-            temperature: self.get_cpu_temperature(),
+            frequency_mhz: system.global_cpu_info().frequency(),
+            temperature,
             timestamp: Instant::now(),
         }
     }
@@ -359,13 +374,14 @@ impl CpuAnalyzer {
 
         // Fallback: simulate temperature based on CPU usage (for development)
         let base_temp = 35.0; // Base temperature in Celsius
-        let usage_temp = self.system.global_cpu_info().cpu_usage() * 0.5; // Scale factor
+        let usage_temp = self.system.read().global_cpu_info().cpu_usage() * 0.5; // Scale factor
         Some(base_temp + usage_temp)
     }
     
     pub fn detect_bottlenecks(&self) -> Vec<CpuBottleneck> {
         let mut bottlenecks = Vec::new();
         let metrics = self.get_current_metrics();
+        let system = self.system.read();
         
         if metrics.total_usage > 90.0 {
             bottlenecks.push(CpuBottleneck {
@@ -376,20 +392,20 @@ impl CpuAnalyzer {
             });
         }
         
-        if metrics.load_average.one_minute > self.system.cpus().len() as f64 * 2.0 {
+        if metrics.load_average.one_minute > system.cpus().len() as f64 * 2.0 {
             bottlenecks.push(CpuBottleneck {
                 bottleneck_type: BottleneckType::ExcessiveContextSwitching,
-                severity: ((metrics.load_average.one_minute / self.system.cpus().len() as f64) / 3.0) as f32,
+                severity: ((metrics.load_average.one_minute / system.cpus().len() as f64) / 3.0) as f32,
                 affected_processes: vec![],
                 description: format!(
                     "System load ({:.2}) is significantly higher than CPU count ({})",
                     metrics.load_average.one_minute,
-                    self.system.cpus().len()
+                    system.cpus().len()
                 ),
             });
         }
         
-        let memory_usage = (self.system.used_memory() as f64 / self.system.total_memory() as f64) * 100.0;
+        let memory_usage = (system.used_memory() as f64 / system.total_memory() as f64) * 100.0;
         if memory_usage > 90.0 {
             bottlenecks.push(CpuBottleneck {
                 bottleneck_type: BottleneckType::MemoryPressure,
@@ -422,4 +438,34 @@ impl CpuAnalyzer {
         
         Some(recent_avg - older_avg)
     }
-}
\ No newline at end of file
+}
+
+impl Monitor for CpuAnalyzer {
+    fn name(&self) -> &str {
+        "cpu"
+    }
+    
+    fn monitor_type(&self) -> MonitorType {
+        MonitorType::CPU
+    }
+    
+    fn refresh(&mut self) {
+        CpuAnalyzer::refresh(self);
+    }
+    
+    fn metric_samples(&self) -> Vec<MetricSample> {
+        let Some(metrics) = self.history.last() else {
+            return Vec::new();
+        };
+        
+        let mut samples = vec![
+            MetricSample::new(SeriesKey::new("cpu.usage_percent"), metrics.total_usage as f64),
+            MetricSample::new(SeriesKey::new("cpu.load_1m"), metrics.load_average.one_minute),
+            MetricSample::new(SeriesKey::new("cpu.frequency_mhz"), metrics.frequency_mhz as f64),
+        ];
+        samples.extend(metrics.per_core_usage.iter().enumerate().map(|(core, usage)| {
+            MetricSample::new(SeriesKey::new("cpu.core_usage_percent").with_label("core", core), *usage as f64)
+        }));
+        samples
+    }
+}
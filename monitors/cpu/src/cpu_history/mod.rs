@@ -1,3 +1,4 @@
+use std::cell::Cell;
 use std::collections::{BTreeMap, VecDeque};
 use std::fs::{File, OpenOptions};
 use std::io::{BufRead, BufReader, Write};
@@ -8,6 +9,12 @@ use serde::{Deserialize, Serialize};
 use crate::cpu_analyzer::CpuMetrics;
 use crate::process_monitor::ProcessInfo;
 
+mod segment;
+
+const FILE_PREFIX: &str = "cpu_history_";
+const JSONL_EXTENSION: &str = "jsonl";
+const SEGMENT_EXTENSION: &str = "seg";
+
 /// Historical CPU data point for persistence
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CpuHistoryPoint {
@@ -26,6 +33,8 @@ pub struct CpuHistoryConfig {
     pub data_directory: PathBuf,
     pub max_points_in_memory: usize,
     pub max_days_to_keep: u32,
+    /// Write compressed binary segments instead of JSON lines. Existing
+    /// `.jsonl` files are migrated when the store opens.
     pub compression_enabled: bool,
     pub auto_cleanup_enabled: bool,
     pub flush_interval_seconds: u64,
@@ -54,9 +63,10 @@ impl Default for CpuHistoryConfig {
 pub struct CpuHistoryStore {
     config: CpuHistoryConfig,
     memory_buffer: VecDeque<CpuHistoryPoint>,
-    daily_files: BTreeMap<String, PathBuf>, // date -> file path
+    unflushed_points: usize, // at the back of memory_buffer
+    daily_files: BTreeMap<String, Vec<PathBuf>>, // date -> segment and/or JSONL file
     last_flush_time: SystemTime,
-    current_day: String,
+    corrupt_records: Cell<usize>,
 }
 
 impl CpuHistoryStore {
@@ -67,14 +77,19 @@ impl CpuHistoryStore {
         let mut store = Self {
             config,
             memory_buffer: VecDeque::new(),
+            unflushed_points: 0,
             daily_files: BTreeMap::new(),
             last_flush_time: SystemTime::now(),
-            current_day: Self::current_date_string(),
+            corrupt_records: Cell::new(0),
         };
 
         // Discover existing history files
         store.discover_existing_files()?;
 
+        if store.config.compression_enabled {
+            store.migrate_jsonl_files()?;
+        }
+
         // Load recent data into memory
         store.load_recent_data()?;
 
@@ -108,11 +123,13 @@ impl CpuHistoryStore {
 
         // Add to memory buffer
         self.memory_buffer.push_back(history_point);
+        self.unflushed_points += 1;
 
         // Trim buffer if too large
         while self.memory_buffer.len() > self.config.max_points_in_memory {
             self.memory_buffer.pop_front();
         }
+        self.unflushed_points = self.unflushed_points.min(self.memory_buffer.len());
 
         // Check if we need to flush to disk
         let should_flush = self.last_flush_time
@@ -163,14 +180,10 @@ impl CpuHistoryStore {
         let start_date = Self::timestamp_to_date_string(start_timestamp);
         let end_date = Self::timestamp_to_date_string(end_timestamp);
 
-        for (date, file_path) in &self.daily_files {
-            if date >= &start_date && date <= &end_date {
-                let file_data = self.load_data_from_file(file_path)?;
-                for point in file_data {
-                    if point.timestamp >= start_timestamp && point.timestamp <= end_timestamp {
-                        results.push(point);
-                    }
-                }
+        for files in self.daily_files.range(start_date..=end_date).map(|(_, files)| files) {
+            for file_path in files {
+                let file_data = self.load_range_from_file(file_path, start_timestamp, end_timestamp)?;
+                results.extend(file_data);
             }
         }
 
@@ -215,30 +228,35 @@ impl CpuHistoryStore {
         }
     }
 
-    pub fn flush_to_disk(&mut self) -> std::io::Result<()> {
-        let current_date = Self::current_date_string();
+    /// Number of stored points that could not be read back, from corrupt
+    /// segment blocks or unparseable JSON lines
+    pub fn corrupt_records(&self) -> usize {
+        self.corrupt_records.get()
+    }
 
-        // Check if we've moved to a new day
-        if current_date != self.current_day {
-            self.current_day = current_date.clone();
+    pub fn flush_to_disk(&mut self) -> std::io::Result<()> {
+        // Only write points that haven't been written yet, each to the file
+        // for its own day
+        let first_unflushed = self.memory_buffer.len() - self.unflushed_points;
+        let mut by_date: BTreeMap<String, Vec<CpuHistoryPoint>> = BTreeMap::new();
+        for point in self.memory_buffer.range(first_unflushed..) {
+            by_date
+                .entry(Self::timestamp_to_date_string(point.timestamp))
+                .or_default()
+                .push(point.clone());
         }
 
-        let file_path = self.get_file_path_for_date(&current_date);
-        self.daily_files.insert(current_date, file_path.clone());
+        for (date, points) in by_date {
+            let file_path = self.get_file_path_for_date(&date);
+            self.write_points(&file_path, &points)?;
 
-        // Append new data to today's file
-        let mut file = OpenOptions::new()
-            .create(true)
-            .append(true)
-            .open(&file_path)?;
-
-        // Only write points that haven't been written yet
-        for point in &self.memory_buffer {
-            let json_line = serde_json::to_string(point)?;
-            writeln!(file, "{}", json_line)?;
+            let files = self.daily_files.entry(date).or_default();
+            if !files.contains(&file_path) {
+                files.push(file_path);
+            }
         }
 
-        file.flush()?;
+        self.unflushed_points = 0;
         self.last_flush_time = SystemTime::now();
 
         Ok(())
@@ -254,9 +272,9 @@ impl CpuHistoryStore {
         let cutoff_date_string = Self::timestamp_to_date_string(cutoff_date);
 
         let mut files_to_remove = Vec::new();
-        for (date, file_path) in &self.daily_files {
+        for (date, file_paths) in &self.daily_files {
             if date < &cutoff_date_string {
-                if file_path.exists() {
+                for file_path in file_paths.iter().filter(|p| p.exists()) {
                     std::fs::remove_file(file_path)?;
                 }
                 files_to_remove.push(date.clone());
@@ -279,19 +297,58 @@ impl CpuHistoryStore {
             let entry = entry?;
             let path = entry.path();
 
-            if let Some(filename) = path.file_name().and_then(|n| n.to_str()) {
-                if filename.starts_with("cpu_history_") && filename.ends_with(".jsonl") {
-                    // Extract date from filename: cpu_history_2024-03-15.jsonl
-                    if let Some(date) = filename
-                        .strip_prefix("cpu_history_")
-                        .and_then(|s| s.strip_suffix(".jsonl"))
-                    {
-                        self.daily_files.insert(date.to_string(), path);
-                    }
-                }
+            // Extract date from filename: cpu_history_2024-03-15.seg or .jsonl
+            let date = path.file_name()
+                .and_then(|n| n.to_str())
+                .and_then(|n| n.strip_prefix(FILE_PREFIX))
+                .and_then(|n| {
+                    n.strip_suffix(&format!(".{}", SEGMENT_EXTENSION))
+                        .or_else(|| n.strip_suffix(&format!(".{}", JSONL_EXTENSION)))
+                });
+
+            if let Some(date) = date {
+                self.daily_files.entry(date.to_string()).or_default().push(path);
             }
         }
 
+        for files in self.daily_files.values_mut() {
+            files.sort();
+        }
+
+        Ok(())
+    }
+
+    /// Rewrite each `.jsonl` file as a segment. The JSON file is deleted
+    /// once everything in it has been migrated; a file with lines that
+    /// don't parse is kept as `.jsonl.corrupt` for inspection.
+    fn migrate_jsonl_files(&mut self) -> std::io::Result<()> {
+        for (date, files) in self.daily_files.iter_mut() {
+            let Some(position) = files.iter().position(|p| is_jsonl(p)) else {
+                continue;
+            };
+            let jsonl_path = files.remove(position);
+
+            let (mut points, bad_lines) = read_jsonl(&jsonl_path)?;
+            // Earlier versions rewrote the whole buffer on every flush
+            points.sort_by_key(|p| p.timestamp);
+            points.dedup_by_key(|p| p.timestamp);
+
+            let segment_path = self.config.data_directory
+                .join(format!("{}{}.{}", FILE_PREFIX, date, SEGMENT_EXTENSION));
+            segment::append(&segment_path, &points)?;
+            if !files.contains(&segment_path) && segment_path.exists() {
+                files.push(segment_path);
+            }
+
+            if bad_lines == 0 {
+                std::fs::remove_file(&jsonl_path)?;
+            } else {
+                self.corrupt_records.set(self.corrupt_records.get() + bad_lines);
+                std::fs::rename(&jsonl_path, jsonl_path.with_extension("jsonl.corrupt"))?;
+            }
+        }
+
+        self.daily_files.retain(|_, files| !files.is_empty());
         Ok(())
     }
 
@@ -304,8 +361,8 @@ impl CpuHistoryStore {
         recent_dates.reverse();
 
         for date in recent_dates {
-            if let Some(file_path) = self.daily_files.get(date) {
-                let data = self.load_data_from_file(file_path)?;
+            for file_path in &self.daily_files[date] {
+                let data = self.load_range_from_file(file_path, 0, u64::MAX)?;
                 for point in data {
                     if self.memory_buffer.len() >= self.config.max_points_in_memory {
                         break;
@@ -318,36 +375,53 @@ impl CpuHistoryStore {
         Ok(())
     }
 
-    fn load_data_from_file(&self, file_path: &Path) -> std::io::Result<Vec<CpuHistoryPoint>> {
+    /// Points in `[start, end]` from a segment or JSONL file. Records that
+    /// can't be read are added to `corrupt_records` instead of failing the
+    /// whole read.
+    fn load_range_from_file(&self, file_path: &Path, start: u64, end: u64) -> std::io::Result<Vec<CpuHistoryPoint>> {
         if !file_path.exists() {
             return Ok(Vec::new());
         }
 
-        let file = File::open(file_path)?;
-        let reader = BufReader::new(file);
-        let mut data = Vec::new();
+        let (mut data, corrupt) = if is_jsonl(file_path) {
+            read_jsonl(file_path)?
+        } else {
+            let read = segment::read_range(file_path, start, end)?;
+            (read.points, read.corrupt_records)
+        };
 
-        for line in reader.lines() {
-            let line = line?;
-            if let Ok(point) = serde_json::from_str::<CpuHistoryPoint>(&line) {
-                data.push(point);
-            }
+        self.corrupt_records.set(self.corrupt_records.get() + corrupt);
+        data.retain(|p| p.timestamp >= start && p.timestamp <= end);
+        Ok(data)
+    }
+
+    fn write_points(&self, file_path: &Path, points: &[CpuHistoryPoint]) -> std::io::Result<()> {
+        if !is_jsonl(file_path) {
+            return segment::append(file_path, points);
         }
 
-        Ok(data)
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(file_path)?;
+
+        for point in points {
+            let json_line = serde_json::to_string(point)?;
+            writeln!(file, "{}", json_line)?;
+        }
+
+        file.flush()
     }
 
     fn get_file_path_for_date(&self, date: &str) -> PathBuf {
-        self.config.data_directory
-            .join(format!("cpu_history_{}.jsonl", date))
-    }
+        let extension = if self.config.compression_enabled {
+            SEGMENT_EXTENSION
+        } else {
+            JSONL_EXTENSION
+        };
 
-    fn current_date_string() -> String {
-        let now = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .unwrap_or_default()
-            .as_secs();
-        Self::timestamp_to_date_string(now)
+        self.config.data_directory
+            .join(format!("{}{}.{}", FILE_PREFIX, date, extension))
     }
 
     fn timestamp_to_date_string(timestamp: u64) -> String {
@@ -359,6 +433,31 @@ impl CpuHistoryStore {
     }
 }
 
+fn is_jsonl(path: &Path) -> bool {
+    path.extension().is_some_and(|e| e == JSONL_EXTENSION)
+}
+
+/// Parse a JSONL history file, returning the points and the number of
+/// lines that failed to parse
+fn read_jsonl(file_path: &Path) -> std::io::Result<(Vec<CpuHistoryPoint>, usize)> {
+    let reader = BufReader::new(File::open(file_path)?);
+    let mut data = Vec::new();
+    let mut bad_lines = 0;
+
+    for line in reader.lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        match serde_json::from_str::<CpuHistoryPoint>(&line) {
+            Ok(point) => data.push(point),
+            Err(_) => bad_lines += 1,
+        }
+    }
+
+    Ok((data, bad_lines))
+}
+
 /// Statistics computed from historical CPU data
 #[derive(Debug, Clone)]
 pub struct CpuHistoryStatistics {
@@ -440,4 +539,61 @@ mod tests {
         assert_eq!(point.total_usage, deserialized.total_usage);
         assert_eq!(point.per_core_usage, deserialized.per_core_usage);
     }
+
+    fn config_in(dir: &tempfile::TempDir) -> CpuHistoryConfig {
+        CpuHistoryConfig {
+            data_directory: dir.path().to_path_buf(),
+            auto_cleanup_enabled: false,
+            ..CpuHistoryConfig::default()
+        }
+    }
+
+    fn point_at(timestamp: u64) -> CpuHistoryPoint {
+        CpuHistoryPoint {
+            timestamp,
+            total_usage: 42.0,
+            per_core_usage: vec![40.0, 44.0],
+            load_average: (1.0, 1.0, 1.0),
+            frequency_mhz: 2400,
+            temperature: None,
+            top_processes: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_migrates_jsonl_and_reports_bad_lines() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let jsonl = dir.path().join("cpu_history_2022-01-01.jsonl");
+        let line = |t| serde_json::to_string(&point_at(t)).unwrap();
+        // Older flushes wrote the same points more than once
+        let contents = [line(1640995200), line(1640995201), line(1640995200), "{\"timestamp\":".to_string()];
+        std::fs::write(&jsonl, contents.join("\n")).unwrap();
+
+        let store = CpuHistoryStore::new(config_in(&dir)).unwrap();
+        assert_eq!(store.corrupt_records(), 1);
+        assert!(!jsonl.exists());
+        assert!(dir.path().join("cpu_history_2022-01-01.jsonl.corrupt").exists());
+        assert!(dir.path().join("cpu_history_2022-01-01.seg").exists());
+
+        let start = UNIX_EPOCH + Duration::from_secs(1640995200);
+        let data = store.get_historical_data(start, start + Duration::from_secs(60)).unwrap();
+        let timestamps: Vec<u64> = data.iter().map(|p| p.timestamp).collect();
+        assert_eq!(timestamps, vec![1640995200, 1640995201]);
+    }
+
+    #[test]
+    fn test_flush_writes_each_point_once() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let mut store = CpuHistoryStore::new(config_in(&dir)).unwrap();
+
+        for t in 0..3 {
+            store.memory_buffer.push_back(point_at(1640995200 + t));
+            store.unflushed_points += 1;
+            store.flush_to_disk().unwrap();
+        }
+
+        let segment = dir.path().join("cpu_history_2022-01-01.seg");
+        let read = segment::read_all(&segment).unwrap();
+        assert_eq!(read.points.len(), 3);
+    }
 }
\ No newline at end of file
@@ -0,0 +1,743 @@
+//! Binary segment files for CPU history
+//!
+//! A segment holds one day of points as a sequence of blocks, one per
+//! flush, each with its own checksum, followed by an index footer:
+//!
+//! ```text
+//! header  "RCPUSEG" + version byte
+//! block*  payload length (u32) | CRC-32 of payload (u32) | payload
+//! footer  index entries (32 bytes each) | entry count (u32) | CRC-32 of entries (u32) | "RCPUIDX1"
+//! ```
+//!
+//! Payloads are columnar: delta-of-delta timestamps, then one XOR-compressed
+//! stream per float column, so a steady 1 Hz series costs a bit per
+//! timestamp and only the changed bits of each value. Range reads use the
+//! footer to seek straight to the blocks they need. When the footer is
+//! missing, e.g. after a crash mid-flush, blocks are recovered by scanning
+//! and the next append writes a fresh footer.
+
+use super::CpuHistoryPoint;
+use crate::process_monitor::ProcessInfo;
+use std::fs::{File, OpenOptions};
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::path::Path;
+
+const HEADER: &[u8; 8] = b"RCPUSEG\x01";
+const FOOTER_MAGIC: &[u8; 8] = b"RCPUIDX1";
+const FOOTER_TAIL_LEN: u64 = 16;
+const BLOCK_HEADER_LEN: u64 = 8;
+const INDEX_ENTRY_LEN: usize = 32;
+
+/// Footer entry describing one block
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct BlockInfo {
+    pub offset: u64,
+    pub len: u32,
+    pub count: u32,
+    pub first_timestamp: u64,
+    pub last_timestamp: u64,
+}
+
+impl BlockInfo {
+    fn overlaps(&self, start: u64, end: u64) -> bool {
+        self.first_timestamp <= end && self.last_timestamp >= start
+    }
+}
+
+/// Points read from a segment, and how many were lost to corrupt blocks
+#[derive(Debug, Default)]
+pub(crate) struct SegmentRead {
+    pub points: Vec<CpuHistoryPoint>,
+    pub corrupt_records: usize,
+}
+
+/// Append `points` to the segment at `path` as one new block
+pub(crate) fn append(path: &Path, points: &[CpuHistoryPoint]) -> io::Result<()> {
+    if points.is_empty() {
+        return Ok(());
+    }
+
+    let mut sorted: Vec<&CpuHistoryPoint> = points.iter().collect();
+    sorted.sort_by_key(|p| p.timestamp);
+
+    let mut file = OpenOptions::new().read(true).write(true).create(true).truncate(false).open(path)?;
+    let (mut index, data_end) = if file.metadata()?.len() == 0 {
+        file.write_all(HEADER)?;
+        (Vec::new(), HEADER.len() as u64)
+    } else {
+        check_header(&mut file)?;
+        load_index(&mut file)?
+    };
+
+    let payload = encode_block(&sorted)?;
+    index.push(BlockInfo {
+        offset: data_end,
+        len: payload.len() as u32,
+        count: sorted.len() as u32,
+        first_timestamp: sorted[0].timestamp,
+        last_timestamp: sorted[sorted.len() - 1].timestamp,
+    });
+
+    // Drop the old footer and write the new block and footer in its place
+    file.set_len(data_end)?;
+    file.seek(SeekFrom::Start(data_end))?;
+    file.write_all(&(payload.len() as u32).to_le_bytes())?;
+    file.write_all(&crc32fast::hash(&payload).to_le_bytes())?;
+    file.write_all(&payload)?;
+    write_footer(&mut file, &index)?;
+    file.flush()
+}
+
+/// Points with `start <= timestamp <= end`, reading only overlapping blocks
+pub(crate) fn read_range(path: &Path, start: u64, end: u64) -> io::Result<SegmentRead> {
+    let mut file = File::open(path)?;
+    check_header(&mut file)?;
+    let (index, _) = load_index(&mut file)?;
+
+    let mut result = SegmentRead::default();
+    for block in index.iter().filter(|b| b.overlaps(start, end)) {
+        match read_block(&mut file, block) {
+            Ok(points) => result.points.extend(
+                points.into_iter().filter(|p| p.timestamp >= start && p.timestamp <= end),
+            ),
+            Err(e) if e.kind() == io::ErrorKind::InvalidData => {
+                result.corrupt_records += block.count as usize;
+            }
+            Err(e) => return Err(e),
+        }
+    }
+
+    Ok(result)
+}
+
+#[cfg(test)]
+pub(crate) fn read_all(path: &Path) -> io::Result<SegmentRead> {
+    read_range(path, 0, u64::MAX)
+}
+
+fn corrupt(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.to_string())
+}
+
+fn check_header(file: &mut File) -> io::Result<()> {
+    let mut header = [0u8; 8];
+    file.seek(SeekFrom::Start(0))?;
+    file.read_exact(&mut header)?;
+    if &header != HEADER {
+        return Err(corrupt("not a CPU history segment"));
+    }
+    Ok(())
+}
+
+fn le_u32(bytes: &[u8]) -> u32 {
+    u32::from_le_bytes(bytes[..4].try_into().unwrap())
+}
+
+fn le_u64(bytes: &[u8]) -> u64 {
+    u64::from_le_bytes(bytes[..8].try_into().unwrap())
+}
+
+/// Block index and the offset where block data ends
+fn load_index(file: &mut File) -> io::Result<(Vec<BlockInfo>, u64)> {
+    match read_footer(file)? {
+        Some(found) => Ok(found),
+        None => scan_blocks(file),
+    }
+}
+
+fn read_footer(file: &mut File) -> io::Result<Option<(Vec<BlockInfo>, u64)>> {
+    let file_len = file.metadata()?.len();
+    if file_len < HEADER.len() as u64 + FOOTER_TAIL_LEN {
+        return Ok(None);
+    }
+
+    let mut tail = [0u8; FOOTER_TAIL_LEN as usize];
+    file.seek(SeekFrom::Start(file_len - FOOTER_TAIL_LEN))?;
+    file.read_exact(&mut tail)?;
+    if &tail[8..] != FOOTER_MAGIC {
+        return Ok(None);
+    }
+
+    let index_len = le_u32(&tail[0..4]) as u64 * INDEX_ENTRY_LEN as u64;
+    let Some(data_end) = (file_len - FOOTER_TAIL_LEN).checked_sub(index_len) else {
+        return Ok(None);
+    };
+    if data_end < HEADER.len() as u64 {
+        return Ok(None);
+    }
+
+    let mut entries = vec![0u8; index_len as usize];
+    file.seek(SeekFrom::Start(data_end))?;
+    file.read_exact(&mut entries)?;
+    if crc32fast::hash(&entries) != le_u32(&tail[4..8]) {
+        return Ok(None);
+    }
+
+    let index: Vec<BlockInfo> = entries
+        .chunks_exact(INDEX_ENTRY_LEN)
+        .map(|entry| BlockInfo {
+            offset: le_u64(&entry[0..]),
+            len: le_u32(&entry[8..]),
+            count: le_u32(&entry[12..]),
+            first_timestamp: le_u64(&entry[16..]),
+            last_timestamp: le_u64(&entry[24..]),
+        })
+        .collect();
+
+    let in_bounds = index.iter().all(|b| b.offset + BLOCK_HEADER_LEN + b.len as u64 <= data_end);
+    Ok(in_bounds.then_some((index, data_end)))
+}
+
+/// Rebuild the index by walking blocks from the header, stopping at the
+/// first one that is truncated or fails its checksum
+fn scan_blocks(file: &mut File) -> io::Result<(Vec<BlockInfo>, u64)> {
+    let file_len = file.metadata()?.len();
+    let mut index = Vec::new();
+    let mut offset = HEADER.len() as u64;
+
+    while offset + BLOCK_HEADER_LEN <= file_len {
+        let mut block_header = [0u8; BLOCK_HEADER_LEN as usize];
+        file.seek(SeekFrom::Start(offset))?;
+        file.read_exact(&mut block_header)?;
+
+        let len = le_u32(&block_header[0..4]);
+        if offset + BLOCK_HEADER_LEN + len as u64 > file_len {
+            break;
+        }
+        let mut payload = vec![0u8; len as usize];
+        file.read_exact(&mut payload)?;
+        if crc32fast::hash(&payload) != le_u32(&block_header[4..8]) {
+            break;
+        }
+        let Ok((count, first_timestamp, last_timestamp)) = decode_summary(&mut BitReader::new(&payload)) else {
+            break;
+        };
+
+        index.push(BlockInfo { offset, len, count, first_timestamp, last_timestamp });
+        offset += BLOCK_HEADER_LEN + len as u64;
+    }
+
+    Ok((index, offset))
+}
+
+fn write_footer(file: &mut File, index: &[BlockInfo]) -> io::Result<()> {
+    let mut entries = Vec::with_capacity(index.len() * INDEX_ENTRY_LEN);
+    for block in index {
+        entries.extend_from_slice(&block.offset.to_le_bytes());
+        entries.extend_from_slice(&block.len.to_le_bytes());
+        entries.extend_from_slice(&block.count.to_le_bytes());
+        entries.extend_from_slice(&block.first_timestamp.to_le_bytes());
+        entries.extend_from_slice(&block.last_timestamp.to_le_bytes());
+    }
+
+    file.write_all(&entries)?;
+    file.write_all(&(index.len() as u32).to_le_bytes())?;
+    file.write_all(&crc32fast::hash(&entries).to_le_bytes())?;
+    file.write_all(FOOTER_MAGIC)
+}
+
+fn read_block(file: &mut File, block: &BlockInfo) -> io::Result<Vec<CpuHistoryPoint>> {
+    let mut block_header = [0u8; BLOCK_HEADER_LEN as usize];
+    file.seek(SeekFrom::Start(block.offset))?;
+    file.read_exact(&mut block_header)?;
+    if le_u32(&block_header[0..4]) != block.len {
+        return Err(corrupt("block length does not match index"));
+    }
+
+    let mut payload = vec![0u8; block.len as usize];
+    file.read_exact(&mut payload)?;
+    if crc32fast::hash(&payload) != le_u32(&block_header[4..8]) {
+        return Err(corrupt("block checksum mismatch"));
+    }
+
+    decode_block(&payload)
+}
+
+// ---------------------------------------------------------------------------
+// Block encoding
+// ---------------------------------------------------------------------------
+
+const LOAD_COLUMNS: [fn(&CpuHistoryPoint) -> f64; 3] = [
+    |p| p.load_average.0,
+    |p| p.load_average.1,
+    |p| p.load_average.2,
+];
+
+fn encode_block(points: &[&CpuHistoryPoint]) -> io::Result<Vec<u8>> {
+    let mut out = BitWriter::default();
+    let first = points[0].timestamp;
+    out.write_varint(points.len() as u64);
+    out.write_varint(first);
+    out.write_varint(points[points.len() - 1].timestamp - first);
+
+    // Timestamps as delta-of-delta: one zero bit while the interval is steady
+    let mut previous = first;
+    let mut previous_delta = 0i64;
+    for point in &points[1..] {
+        let delta = (point.timestamp - previous) as i64;
+        out.write_change(delta - previous_delta);
+        previous = point.timestamp;
+        previous_delta = delta;
+    }
+
+    let mut usage = XorEncoder::default();
+    for point in points {
+        usage.encode(&mut out, point.total_usage as f64);
+    }
+
+    // Core counts only change when the hardware does
+    let mut cores = 0;
+    for point in points {
+        let count = point.per_core_usage.len() as i64;
+        out.write_change(count - cores);
+        cores = count;
+    }
+    let max_cores = points.iter().map(|p| p.per_core_usage.len()).max().unwrap_or(0);
+    for core in 0..max_cores {
+        let mut column = XorEncoder::default();
+        for usage in points.iter().filter_map(|p| p.per_core_usage.get(core)) {
+            column.encode(&mut out, *usage as f64);
+        }
+    }
+
+    for load in LOAD_COLUMNS {
+        let mut column = XorEncoder::default();
+        for point in points {
+            column.encode(&mut out, load(point));
+        }
+    }
+
+    let mut frequency = 0;
+    for point in points {
+        out.write_change(point.frequency_mhz as i64 - frequency);
+        frequency = point.frequency_mhz as i64;
+    }
+
+    for point in points {
+        out.write_bit(point.temperature.is_some());
+    }
+    let mut temperature = XorEncoder::default();
+    for value in points.iter().filter_map(|p| p.temperature) {
+        temperature.encode(&mut out, value as f64);
+    }
+
+    for point in points {
+        out.write_bit(!point.top_processes.is_empty());
+        if !point.top_processes.is_empty() {
+            out.write_bytes(&serde_json::to_vec(&point.top_processes)?);
+        }
+    }
+
+    Ok(out.finish())
+}
+
+/// Point count and first/last timestamp from the start of a payload
+fn decode_summary(input: &mut BitReader) -> io::Result<(u32, u64, u64)> {
+    let count = u32::try_from(input.read_varint()?).map_err(|_| corrupt("block point count out of range"))?;
+    let first = input.read_varint()?;
+    let span = input.read_varint()?;
+    Ok((count, first, first.saturating_add(span)))
+}
+
+fn decode_block(payload: &[u8]) -> io::Result<Vec<CpuHistoryPoint>> {
+    let mut input = BitReader::new(payload);
+    let (count, first, _) = decode_summary(&mut input)?;
+    if count == 0 {
+        return Err(corrupt("empty block"));
+    }
+
+    let mut points = Vec::with_capacity(count as usize);
+    let mut timestamp = first;
+    let mut delta = 0i64;
+    for i in 0..count {
+        if i > 0 {
+            delta += input.read_change()?;
+            timestamp = timestamp.checked_add_signed(delta).ok_or_else(|| corrupt("timestamp overflow"))?;
+        }
+        points.push(CpuHistoryPoint {
+            timestamp,
+            total_usage: 0.0,
+            per_core_usage: Vec::new(),
+            load_average: (0.0, 0.0, 0.0),
+            frequency_mhz: 0,
+            temperature: None,
+            top_processes: Vec::new(),
+        });
+    }
+
+    let mut usage = XorDecoder::default();
+    for point in points.iter_mut() {
+        point.total_usage = usage.decode(&mut input)? as f32;
+    }
+
+    let mut cores = 0i64;
+    let mut core_counts = Vec::with_capacity(points.len());
+    for _ in 0..count {
+        cores += input.read_change()?;
+        core_counts.push(usize::try_from(cores).map_err(|_| corrupt("negative core count"))?);
+    }
+    let max_cores = core_counts.iter().copied().max().unwrap_or(0);
+    for core in 0..max_cores {
+        let mut column = XorDecoder::default();
+        for (point, &cores) in points.iter_mut().zip(&core_counts) {
+            if core < cores {
+                point.per_core_usage.push(column.decode(&mut input)? as f32);
+            }
+        }
+    }
+
+    let mut loads = [XorDecoder::default(), XorDecoder::default(), XorDecoder::default()];
+    let mut load_values = vec![[0.0; 3]; points.len()];
+    for (column, decoder) in loads.iter_mut().enumerate() {
+        for values in load_values.iter_mut() {
+            values[column] = decoder.decode(&mut input)?;
+        }
+    }
+    for (point, [one, five, fifteen]) in points.iter_mut().zip(load_values) {
+        point.load_average = (one, five, fifteen);
+    }
+
+    let mut frequency = 0i64;
+    for point in points.iter_mut() {
+        frequency += input.read_change()?;
+        point.frequency_mhz = u64::try_from(frequency).map_err(|_| corrupt("negative frequency"))?;
+    }
+
+    let mut has_temperature = Vec::with_capacity(points.len());
+    for _ in 0..count {
+        has_temperature.push(input.read_bit()?);
+    }
+    let mut temperature = XorDecoder::default();
+    for (point, present) in points.iter_mut().zip(has_temperature) {
+        if present {
+            point.temperature = Some(temperature.decode(&mut input)? as f32);
+        }
+    }
+
+    for point in points.iter_mut() {
+        if input.read_bit()? {
+            point.top_processes = serde_json::from_slice::<Vec<ProcessInfo>>(&input.read_bytes()?)
+                .map_err(|e| corrupt(&format!("invalid process list: {}", e)))?;
+        }
+    }
+
+    Ok(points)
+}
+
+#[derive(Default)]
+struct BitWriter {
+    bytes: Vec<u8>,
+    used_bits: u32,
+}
+
+impl BitWriter {
+    fn write_bit(&mut self, bit: bool) {
+        if self.used_bits == 0 {
+            self.bytes.push(0);
+        }
+        if bit {
+            *self.bytes.last_mut().unwrap() |= 0x80 >> self.used_bits;
+        }
+        self.used_bits = (self.used_bits + 1) % 8;
+    }
+
+    fn write_bits(&mut self, value: u64, count: u32) {
+        for shift in (0..count).rev() {
+            self.write_bit((value >> shift) & 1 == 1);
+        }
+    }
+
+    fn write_varint(&mut self, mut value: u64) {
+        loop {
+            let byte = value & 0x7f;
+            value >>= 7;
+            if value == 0 {
+                self.write_bits(byte, 8);
+                return;
+            }
+            self.write_bits(byte | 0x80, 8);
+        }
+    }
+
+    /// A zero bit for no change, otherwise a one bit and the zigzag varint
+    fn write_change(&mut self, change: i64) {
+        self.write_bit(change != 0);
+        if change != 0 {
+            self.write_varint(((change << 1) ^ (change >> 63)) as u64);
+        }
+    }
+
+    fn write_bytes(&mut self, bytes: &[u8]) {
+        self.write_varint(bytes.len() as u64);
+        for &byte in bytes {
+            self.write_bits(byte as u64, 8);
+        }
+    }
+
+    fn finish(self) -> Vec<u8> {
+        self.bytes
+    }
+}
+
+struct BitReader<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl<'a> BitReader<'a> {
+    fn new(bytes: &'a [u8]) -> Self {
+        Self { bytes, position: 0 }
+    }
+
+    fn read_bit(&mut self) -> io::Result<bool> {
+        let byte = self.bytes
+            .get(self.position / 8)
+            .ok_or_else(|| corrupt("block payload truncated"))?;
+        let bit = byte & (0x80 >> (self.position % 8)) != 0;
+        self.position += 1;
+        Ok(bit)
+    }
+
+    fn read_bits(&mut self, count: u32) -> io::Result<u64> {
+        let mut value = 0;
+        for _ in 0..count {
+            value = (value << 1) | self.read_bit()? as u64;
+        }
+        Ok(value)
+    }
+
+    fn read_varint(&mut self) -> io::Result<u64> {
+        let mut value = 0u64;
+        for shift in (0..64).step_by(7) {
+            let byte = self.read_bits(8)?;
+            value |= (byte & 0x7f) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+        Err(corrupt("varint too long"))
+    }
+
+    fn read_change(&mut self) -> io::Result<i64> {
+        if !self.read_bit()? {
+            return Ok(0);
+        }
+        let zigzag = self.read_varint()?;
+        Ok((zigzag >> 1) as i64 ^ -((zigzag & 1) as i64))
+    }
+
+    fn read_bytes(&mut self) -> io::Result<Vec<u8>> {
+        let len = self.read_varint()? as usize;
+        if len > self.bytes.len() {
+            return Err(corrupt("byte string longer than block"));
+        }
+        (0..len).map(|_| self.read_bits(8).map(|b| b as u8)).collect()
+    }
+}
+
+/// Gorilla-style float compression: each value is XORed with the previous
+/// one and only the meaningful bits of the result are stored, reusing the
+/// previous leading/trailing zero window when the new bits fit in it
+#[derive(Default)]
+struct XorEncoder {
+    previous: Option<u64>,
+    window: Option<(u32, u32)>,
+}
+
+impl XorEncoder {
+    fn encode(&mut self, out: &mut BitWriter, value: f64) {
+        let bits = value.to_bits();
+        let Some(previous) = self.previous.replace(bits) else {
+            out.write_bits(bits, 64);
+            return;
+        };
+
+        let xor = bits ^ previous;
+        out.write_bit(xor != 0);
+        if xor == 0 {
+            return;
+        }
+
+        let leading = xor.leading_zeros().min(31);
+        let trailing = xor.trailing_zeros();
+        match self.window {
+            Some((window_leading, window_trailing)) if leading >= window_leading && trailing >= window_trailing => {
+                out.write_bit(false);
+                out.write_bits(xor >> window_trailing, 64 - window_leading - window_trailing);
+            }
+            _ => {
+                let meaningful = 64 - leading - trailing;
+                out.write_bit(true);
+                out.write_bits(leading as u64, 5);
+                out.write_bits((meaningful - 1) as u64, 6);
+                out.write_bits(xor >> trailing, meaningful);
+                self.window = Some((leading, trailing));
+            }
+        }
+    }
+}
+
+#[derive(Default)]
+struct XorDecoder {
+    previous: Option<u64>,
+    window: Option<(u32, u32)>,
+}
+
+impl XorDecoder {
+    fn decode(&mut self, input: &mut BitReader) -> io::Result<f64> {
+        let bits = match self.previous {
+            None => input.read_bits(64)?,
+            Some(previous) if !input.read_bit()? => previous,
+            Some(previous) => {
+                if input.read_bit()? {
+                    let leading = input.read_bits(5)? as u32;
+                    let meaningful = input.read_bits(6)? as u32 + 1;
+                    if leading + meaningful > 64 {
+                        return Err(corrupt("invalid XOR window"));
+                    }
+                    self.window = Some((leading, 64 - leading - meaningful));
+                }
+                let (leading, trailing) = self.window.ok_or_else(|| corrupt("XOR window used before set"))?;
+                previous ^ (input.read_bits(64 - leading - trailing)? << trailing)
+            }
+        };
+
+        self.previous = Some(bits);
+        Ok(f64::from_bits(bits))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn point(timestamp: u64, usage: f32) -> CpuHistoryPoint {
+        CpuHistoryPoint {
+            timestamp,
+            total_usage: usage,
+            per_core_usage: vec![usage, usage / 2.0, 0.0, 100.0],
+            load_average: (1.25, 1.5, 0.75),
+            frequency_mhz: 2400,
+            temperature: Some(55.5),
+            top_processes: Vec::new(),
+        }
+    }
+
+    fn steady_series(start: u64, count: u64) -> Vec<CpuHistoryPoint> {
+        (0..count).map(|i| point(start + i, 10.0 + (i % 7) as f32 * 0.5)).collect()
+    }
+
+    #[test]
+    fn test_round_trip_irregular_points() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("day.seg");
+
+        let mut points = steady_series(1_700_000_000, 5);
+        for point in &mut points[2..] {
+            point.timestamp += 3;
+        }
+        points[3].per_core_usage.truncate(2);
+        points[3].temperature = None;
+        points[4].frequency_mhz = 3100;
+        points[4].load_average = (f64::NAN, 0.0, -1.0);
+        points[4].top_processes = vec![crate::process_monitor::ProcessInfo {
+            pid: 42,
+            name: "reaper".to_string(),
+            cpu_usage: 12.5,
+            memory_mb: 64.0,
+            status: "Run".to_string(),
+            parent_pid: Some(1),
+            thread_count: 4,
+            run_time: 100,
+            user_time: 1.0,
+            system_time: 0.5,
+            io_wait_time_ms: 0,
+            context_switches: 10,
+            minor_faults: 0,
+            major_faults: 0,
+            priority: 0,
+            is_unkillable: false,
+            is_problematic: false,
+        }];
+        append(&path, &points).unwrap();
+
+        let read = read_all(&path).unwrap();
+        assert_eq!(read.corrupt_records, 0);
+        assert_eq!(read.points.len(), 5);
+        for (expected, actual) in points.iter().zip(&read.points) {
+            assert_eq!(expected.timestamp, actual.timestamp);
+            assert_eq!(expected.total_usage, actual.total_usage);
+            assert_eq!(expected.per_core_usage, actual.per_core_usage);
+            assert_eq!(expected.frequency_mhz, actual.frequency_mhz);
+            assert_eq!(expected.temperature, actual.temperature);
+            assert_eq!(expected.top_processes.len(), actual.top_processes.len());
+        }
+        assert!(read.points[4].load_average.0.is_nan());
+        assert_eq!(read.points[4].load_average.2, -1.0);
+        assert_eq!(read.points[4].top_processes[0].name, "reaper");
+    }
+
+    #[test]
+    fn test_range_read_across_blocks() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("day.seg");
+        for block in 0..3 {
+            append(&path, &steady_series(1000 + block * 100, 100)).unwrap();
+        }
+
+        let read = read_range(&path, 1150, 1249).unwrap();
+        let timestamps: Vec<u64> = read.points.iter().map(|p| p.timestamp).collect();
+        assert_eq!(timestamps, (1150..1250).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_steady_series_is_compact() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("day.seg");
+        let points = steady_series(1_700_000_000, 300);
+        append(&path, &points).unwrap();
+
+        let json_len: usize = points.iter().map(|p| serde_json::to_string(p).unwrap().len() + 1).sum();
+        let segment_len = std::fs::metadata(&path).unwrap().len() as usize;
+        assert!(segment_len * 10 < json_len, "segment {} bytes vs JSON {} bytes", segment_len, json_len);
+    }
+
+    #[test]
+    fn test_corrupt_block_is_counted_not_hidden() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("day.seg");
+        append(&path, &steady_series(1000, 10)).unwrap();
+        append(&path, &steady_series(2000, 10)).unwrap();
+
+        // Flip a byte in the first block's payload
+        let mut bytes = std::fs::read(&path).unwrap();
+        bytes[HEADER.len() + BLOCK_HEADER_LEN as usize + 3] ^= 0xff;
+        std::fs::write(&path, bytes).unwrap();
+
+        let read = read_all(&path).unwrap();
+        assert_eq!(read.corrupt_records, 10);
+        assert_eq!(read.points.len(), 10);
+        assert_eq!(read.points[0].timestamp, 2000);
+    }
+
+    #[test]
+    fn test_missing_footer_is_recovered() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("day.seg");
+        append(&path, &steady_series(1000, 10)).unwrap();
+
+        // Simulate a crash after the old footer was cut off
+        let file = OpenOptions::new().write(true).open(&path).unwrap();
+        let len = file.metadata().unwrap().len();
+        file.set_len(len - FOOTER_TAIL_LEN - INDEX_ENTRY_LEN as u64).unwrap();
+        drop(file);
+
+        assert_eq!(read_all(&path).unwrap().points.len(), 10);
+
+        append(&path, &steady_series(2000, 10)).unwrap();
+        let read = read_all(&path).unwrap();
+        assert_eq!(read.points.len(), 20);
+        assert_eq!(read.corrupt_records, 0);
+    }
+}
@@ -0,0 +1,551 @@
+//! Built-in CPU cap by duty cycling
+//!
+//! A throttler thread lets the target run for part of each period and holds
+//! it with SIGSTOP for the rest. After every period it measures the CPU time
+//! the target actually used from `/proc` and a PI controller picks the next
+//! duty cycle, so multi-threaded targets and targets that are partly idle
+//! both converge on the budget.
+//!
+//! A frozen target is always resumed: by `stop`, on drop, when the throttler
+//! thread unwinds, from a panic hook (which also covers `panic = "abort"`)
+//! and from an `atexit` handler. Only a fatal signal to Reaper itself that
+//! lands during a stop phase can leave a target stopped.
+//!
+//! Members that are already stopped when a stop phase begins, for example
+//! suspended by the user, are left out of it and so are never resumed by the
+//! throttler.
+
+use crate::LimitError;
+use once_cell::sync::Lazy;
+use reaper_core::platform::linux::procfs;
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Condvar, Mutex, Once};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+/// Which processes share one throttle budget
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ThrottleTarget {
+    Process(u32),
+    /// A process and all of its descendants, including ones started later
+    Tree(u32),
+    /// Every member of a process group
+    Group(u32),
+}
+
+impl ThrottleTarget {
+    /// Current member pids, never including Reaper itself
+    fn members(&self) -> Vec<u32> {
+        let own = std::process::id();
+        let mut members = match *self {
+            Self::Process(pid) => vec![pid],
+            Self::Tree(root) => {
+                let stats = all_stats();
+                let mut members = vec![root];
+                let mut index = 0;
+                while index < members.len() {
+                    let parent = members[index];
+                    members.extend(stats.iter().filter(|s| s.ppid == parent && s.pid != root).map(|s| s.pid));
+                    index += 1;
+                }
+                members
+            }
+            Self::Group(pgid) => all_stats().into_iter().filter(|s| s.pgrp == pgid as i32).map(|s| s.pid).collect(),
+        };
+        members.retain(|&pid| pid != own && process_exists(pid));
+        members
+    }
+
+    /// `kill` arguments that stop and resume the target; groups are
+    /// signalled as a whole so members that joined since the last scan are
+    /// included
+    fn kill_ids(&self, members: &[u32]) -> Vec<libc::pid_t> {
+        match *self {
+            Self::Group(pgid) => vec![-(pgid as libc::pid_t)],
+            _ => members.iter().map(|&pid| pid as libc::pid_t).collect(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DutyCycleConfig {
+    /// Length of one run-then-stop cycle
+    pub period: Duration,
+    /// Shortest share of a period the target is allowed to run
+    pub min_duty: f64,
+    /// Periods between rescans of a tree's or group's members
+    pub rescan_periods: u32,
+}
+
+impl Default for DutyCycleConfig {
+    fn default() -> Self {
+        Self {
+            period: Duration::from_millis(100),
+            min_duty: 0.02,
+            rescan_periods: 10,
+        }
+    }
+}
+
+/// What the throttler measured in its last period
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct DutyCycleStats {
+    /// Share of the period the target was allowed to run
+    pub duty: f64,
+    /// CPU used, as a share of the whole machine like `max_cpu_percent`
+    pub measured_percent: f32,
+    pub periods: u64,
+    pub members: usize,
+}
+
+#[derive(Debug, Default)]
+struct Shared {
+    stopping: Mutex<bool>,
+    wake: Condvar,
+    stats: Mutex<DutyCycleStats>,
+}
+
+impl Shared {
+    /// Sleep for `duration` unless asked to stop; returns false when stopping
+    fn wait(&self, duration: Duration) -> bool {
+        let stopping = self.stopping.lock().unwrap_or_else(|e| e.into_inner());
+        let (stopping, _) = self.wake
+            .wait_timeout_while(stopping, duration, |stopping| !*stopping)
+            .unwrap_or_else(|e| e.into_inner());
+        !*stopping
+    }
+
+    fn is_stopping(&self) -> bool {
+        *self.stopping.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// Caps the CPU use of a target by alternating SIGSTOP and SIGCONT
+#[derive(Debug)]
+pub struct DutyCycleThrottler {
+    target: ThrottleTarget,
+    max_percent: f32,
+    shared: Arc<Shared>,
+    worker: Option<JoinHandle<()>>,
+}
+
+impl DutyCycleThrottler {
+    /// Start throttling `target` to `max_percent` of the whole machine
+    pub fn start(target: ThrottleTarget, max_percent: f32, config: DutyCycleConfig) -> Result<Self, LimitError> {
+        if !(0.0..=100.0).contains(&max_percent) || max_percent == 0.0 || config.period.is_zero() {
+            return Err(LimitError::InvalidLimit);
+        }
+        let root = match target {
+            ThrottleTarget::Process(pid) | ThrottleTarget::Tree(pid) => pid,
+            ThrottleTarget::Group(pgid) => {
+                if pgid as libc::pid_t == unsafe { libc::getpgrp() } {
+                    return Err(LimitError::InvalidLimit);
+                }
+                pgid
+            }
+        };
+        if root == std::process::id() {
+            return Err(LimitError::InvalidLimit);
+        }
+        if unsafe { libc::kill(root as libc::pid_t, 0) } != 0 && !matches!(target, ThrottleTarget::Group(_)) {
+            return Err(match std::io::Error::last_os_error().raw_os_error() {
+                Some(libc::EPERM) => LimitError::PermissionDenied,
+                _ => LimitError::ProcessNotFound,
+            });
+        }
+        if target.members().is_empty() {
+            return Err(LimitError::ProcessNotFound);
+        }
+
+        install_resume_hooks();
+        let cores = std::thread::available_parallelism().map(|n| n.get()).unwrap_or(1);
+        let controller = DutyController::new(max_percent as f64 / 100.0 * cores as f64, config.min_duty);
+        let shared = Arc::new(Shared::default());
+        let worker = {
+            let shared = Arc::clone(&shared);
+            std::thread::Builder::new()
+                .name(format!("reaper-throttle-{}", root))
+                .spawn(move || run(target, config, controller, cores, &shared))
+                .map_err(|e| LimitError::SystemError(e.to_string()))?
+        };
+
+        Ok(Self { target, max_percent, shared, worker: Some(worker) })
+    }
+
+    pub fn target(&self) -> ThrottleTarget {
+        self.target
+    }
+
+    pub fn max_percent(&self) -> f32 {
+        self.max_percent
+    }
+
+    pub fn stats(&self) -> DutyCycleStats {
+        *self.shared.stats.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Whether the throttler thread is still running; it exits by itself
+    /// once every member of the target has gone
+    pub fn is_running(&self) -> bool {
+        self.worker.as_ref().is_some_and(|worker| !worker.is_finished())
+    }
+
+    /// Stop throttling and leave the target as it was before, resuming only
+    /// what the throttler stopped. Safe to call twice
+    pub fn stop(&mut self) {
+        *self.shared.stopping.lock().unwrap_or_else(|e| e.into_inner()) = true;
+        self.shared.wake.notify_all();
+        // The worker resumes on the way out, also when it panics
+        if let Some(worker) = self.worker.take() {
+            let _ = worker.join();
+        }
+    }
+}
+
+impl Drop for DutyCycleThrottler {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+/// Resumes whatever it froze when dropped, including during unwinding
+struct FrozenGuard {
+    ids: Vec<libc::pid_t>,
+}
+
+impl FrozenGuard {
+    fn freeze(target: ThrottleTarget, members: &[u32]) -> Self {
+        // Members stopped by someone else stay stopped, so a group holding
+        // any of them is frozen member by member
+        let running: Vec<u32> = members.iter().copied().filter(|&pid| !is_stopped(pid)).collect();
+        let ids = if running.len() == members.len() {
+            target.kill_ids(members)
+        } else {
+            running.iter().map(|&pid| pid as libc::pid_t).collect()
+        };
+
+        frozen().extend(ids.iter().copied());
+        for &id in &ids {
+            unsafe { libc::kill(id, libc::SIGSTOP) };
+        }
+        Self { ids }
+    }
+}
+
+impl Drop for FrozenGuard {
+    fn drop(&mut self) {
+        let mut frozen = frozen();
+        for id in &self.ids {
+            unsafe { libc::kill(*id, libc::SIGCONT) };
+            frozen.remove(id);
+        }
+    }
+}
+
+fn run(target: ThrottleTarget, config: DutyCycleConfig, mut controller: DutyController, cores: usize, shared: &Shared) {
+    let mut members = target.members();
+    let mut cpu = CpuMeter::default();
+    cpu.sample(&members);
+    let mut periods = 0u64;
+
+    while !shared.is_stopping() && !members.is_empty() {
+        let started = Instant::now();
+        let duty = controller.duty();
+        let run_for = config.period.mul_f64(duty);
+
+        if !shared.wait(run_for) {
+            break;
+        }
+        if duty < 1.0 {
+            let _frozen = FrozenGuard::freeze(target, &members);
+            if !shared.wait(config.period.saturating_sub(run_for)) {
+                break;
+            }
+        }
+
+        let elapsed = started.elapsed().as_secs_f64();
+        let used = cpu.sample(&members);
+        controller.update(used, run_for.as_secs_f64(), elapsed);
+        periods += 1;
+
+        *shared.stats.lock().unwrap_or_else(|e| e.into_inner()) = DutyCycleStats {
+            duty,
+            measured_percent: (used / elapsed / cores as f64 * 100.0) as f32,
+            periods,
+            members: members.len(),
+        };
+
+        let rescan = periods.is_multiple_of(config.rescan_periods.max(1) as u64);
+        if rescan && !matches!(target, ThrottleTarget::Process(_)) {
+            members = target.members();
+        } else {
+            members.retain(|&pid| process_exists(pid));
+        }
+    }
+}
+
+/// PI controller from measured CPU seconds to the share of the next period
+/// the target may run
+#[derive(Debug, Clone)]
+struct DutyController {
+    /// CPU seconds allowed per wall-clock second
+    target: f64,
+    min_duty: f64,
+    duty: f64,
+    /// Cores the target keeps busy while it is allowed to run
+    load: Option<f64>,
+    /// Budget left over (positive) or overspent (negative), in CPU seconds
+    debt: f64,
+}
+
+impl DutyController {
+    const INTEGRAL_GAIN: f64 = 0.5;
+    const LOAD_SMOOTHING: f64 = 0.3;
+
+    fn new(target: f64, min_duty: f64) -> Self {
+        // Until measured, assume the target keeps one core busy
+        Self { target, min_duty, duty: target.clamp(min_duty, 1.0), load: None, debt: 0.0 }
+    }
+
+    fn duty(&self) -> f64 {
+        self.duty
+    }
+
+    /// Feed one period: CPU seconds `used`, seconds it was allowed to `run`
+    /// and the period's wall-clock `length`
+    fn update(&mut self, used: f64, run: f64, length: f64) -> f64 {
+        if run > 0.0 {
+            let observed = used / run;
+            self.load = Some(match self.load {
+                Some(load) => load + Self::LOAD_SMOOTHING * (observed - load),
+                None => observed,
+            });
+        }
+
+        let budget = self.target * length;
+        self.debt = (self.debt + budget - used).clamp(-2.0 * budget, 2.0 * budget);
+        let wanted = (budget + Self::INTEGRAL_GAIN * self.debt).max(0.0);
+
+        let load = self.load.unwrap_or(1.0);
+        self.duty = if load < 1e-3 { 1.0 } else { (wanted / (load * length)).clamp(self.min_duty, 1.0) };
+        self.duty
+    }
+}
+
+/// CPU seconds used by a changing set of processes
+#[derive(Debug, Default)]
+struct CpuMeter {
+    last: HashMap<u32, u64>,
+}
+
+impl CpuMeter {
+    /// Seconds used since the previous sample; new members start from zero
+    fn sample(&mut self, members: &[u32]) -> f64 {
+        let ticks: HashMap<u32, u64> = members.iter()
+            .filter_map(|&pid| Some((pid, procfs::read_stat(pid).ok()?.total_ticks())))
+            .collect();
+        let used: u64 = ticks.iter()
+            .filter_map(|(pid, &now)| Some(now.saturating_sub(*self.last.get(pid)?)))
+            .sum();
+        self.last = ticks;
+        used as f64 / procfs::clock_ticks() as f64
+    }
+}
+
+fn all_stats() -> Vec<procfs::ProcStat> {
+    procfs::list_pids()
+        .unwrap_or_default()
+        .into_iter()
+        .filter_map(|pid| procfs::read_stat(pid).ok())
+        .collect()
+}
+
+fn process_exists(pid: u32) -> bool {
+    // Zombies can't run and don't need resuming
+    procfs::read_stat(pid).map(|s| s.state != 'Z' && s.state != 'X').unwrap_or(false)
+}
+
+/// Held by a stop signal (`T`), as opposed to a ptrace stop (`t`)
+fn is_stopped(pid: u32) -> bool {
+    procfs::read_stat(pid).is_ok_and(|s| s.state == 'T')
+}
+
+/// `kill` targets currently held stopped by any throttler
+static FROZEN: Lazy<Mutex<HashSet<libc::pid_t>>> = Lazy::new(|| Mutex::new(HashSet::new()));
+
+fn frozen() -> std::sync::MutexGuard<'static, HashSet<libc::pid_t>> {
+    FROZEN.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// Resume every frozen target; for the panic hook and `atexit`
+fn resume_all_frozen() {
+    // A panic while the registry is locked must not deadlock the hook
+    let Ok(frozen) = FROZEN.try_lock() else { return };
+    for &id in frozen.iter() {
+        unsafe { libc::kill(id, libc::SIGCONT) };
+    }
+}
+
+extern "C" fn resume_all_frozen_at_exit() {
+    resume_all_frozen();
+}
+
+fn install_resume_hooks() {
+    static INSTALL: Once = Once::new();
+    INSTALL.call_once(|| {
+        let previous = std::panic::take_hook();
+        std::panic::set_hook(Box::new(move |info| {
+            resume_all_frozen();
+            previous(info);
+        }));
+        unsafe { libc::atexit(resume_all_frozen_at_exit) };
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::os::unix::process::CommandExt;
+    use std::process::{Child, Command};
+
+    fn spin(group: bool) -> Child {
+        let mut command = Command::new("sh");
+        command.args(["-c", "while :; do :; done"]);
+        if group {
+            command.process_group(0);
+        }
+        command.spawn().unwrap()
+    }
+
+    fn state(pid: u32) -> char {
+        procfs::read_stat(pid).unwrap().state
+    }
+
+    #[test]
+    fn test_controller_converges() {
+        // A target that keeps two cores busy whenever it runs, capped at half a core
+        let mut controller = DutyController::new(0.5, 0.02);
+        let mut used_total = 0.0;
+        for period in 0..60 {
+            let run = controller.duty() * 0.1;
+            let used = 2.0 * run;
+            controller.update(used, run, 0.1);
+            if period >= 20 {
+                used_total += used;
+            }
+        }
+        let rate = used_total / (40.0 * 0.1);
+        assert!((rate - 0.5).abs() < 0.02, "settled at {}", rate);
+        assert!((controller.duty() - 0.25).abs() < 0.02);
+
+        // An idle target is left running
+        let mut idle = DutyController::new(0.5, 0.02);
+        idle.update(0.0, 0.05, 0.1);
+        assert_eq!(idle.duty(), 1.0);
+    }
+
+    #[test]
+    fn test_rejects_bad_targets() {
+        let own = std::process::id();
+        let config = DutyCycleConfig::default();
+        assert!(matches!(DutyCycleThrottler::start(ThrottleTarget::Process(own), 50.0, config), Err(LimitError::InvalidLimit)));
+        let own_group = unsafe { libc::getpgrp() } as u32;
+        assert!(matches!(DutyCycleThrottler::start(ThrottleTarget::Group(own_group), 50.0, config), Err(LimitError::InvalidLimit)));
+        assert!(matches!(DutyCycleThrottler::start(ThrottleTarget::Process(1), 0.0, config), Err(LimitError::InvalidLimit)));
+        assert!(matches!(
+            DutyCycleThrottler::start(ThrottleTarget::Process(u32::MAX / 2), 50.0, config),
+            Err(LimitError::ProcessNotFound),
+        ));
+    }
+
+    /// A busy child is held near its budget and left running afterwards
+    #[test]
+    fn test_throttles_busy_child() {
+        let mut child = spin(false);
+        let pid = child.id();
+        let cores = std::thread::available_parallelism().map(|n| n.get()).unwrap_or(1) as f64;
+        // A quarter of one core, as a share of the machine
+        let percent = (25.0 / cores) as f32;
+
+        let mut throttler = DutyCycleThrottler::start(ThrottleTarget::Tree(pid), percent, DutyCycleConfig::default()).unwrap();
+        std::thread::sleep(Duration::from_millis(500));
+        let before = procfs::read_stat(pid).unwrap().total_ticks();
+        std::thread::sleep(Duration::from_millis(1500));
+        let used = (procfs::read_stat(pid).unwrap().total_ticks() - before) as f64 / procfs::clock_ticks() as f64;
+        assert!(used > 0.15 && used < 0.6, "used {:.2}s of CPU in 1.5s", used);
+
+        let stats = throttler.stats();
+        assert!(stats.periods > 10 && stats.duty < 0.9, "{:?}", stats);
+        throttler.stop();
+        throttler.stop();
+        assert_ne!(state(pid), 'T');
+
+        child.kill().unwrap();
+        child.wait().unwrap();
+    }
+
+    /// Dropping a throttler mid-freeze resumes the whole group
+    #[test]
+    fn test_drop_resumes_group() {
+        let mut leader = spin(true);
+        let pgid = leader.id();
+        let mut member = {
+            let mut command = Command::new("sh");
+            command.args(["-c", "while :; do :; done"]).process_group(pgid as i32);
+            command.spawn().unwrap()
+        };
+
+        let throttler = DutyCycleThrottler::start(ThrottleTarget::Group(pgid), 1.0, DutyCycleConfig {
+            period: Duration::from_millis(400),
+            ..DutyCycleConfig::default()
+        }).unwrap();
+        // The first period runs for a few ms and then freezes the group
+        std::thread::sleep(Duration::from_millis(200));
+        assert_eq!((state(pgid), state(member.id())), ('T', 'T'));
+        assert!(frozen().contains(&-(pgid as libc::pid_t)));
+
+        drop(throttler);
+        assert_ne!(state(pgid), 'T');
+        assert_ne!(state(member.id()), 'T');
+        assert!(!frozen().contains(&-(pgid as libc::pid_t)));
+
+        for child in [&mut leader, &mut member] {
+            child.kill().unwrap();
+            child.wait().unwrap();
+        }
+    }
+
+    /// A member suspended by someone else stays suspended through stop
+    /// phases and after the throttler stops, while the rest are resumed
+    #[test]
+    fn test_leaves_suspended_members_stopped() {
+        let mut leader = spin(true);
+        let pgid = leader.id();
+        let mut member = {
+            let mut command = Command::new("sh");
+            command.args(["-c", "while :; do :; done"]).process_group(pgid as i32);
+            command.spawn().unwrap()
+        };
+        unsafe { libc::kill(member.id() as libc::pid_t, libc::SIGSTOP) };
+        std::thread::sleep(Duration::from_millis(50));
+        assert_eq!(state(member.id()), 'T');
+
+        let mut throttler = DutyCycleThrottler::start(ThrottleTarget::Group(pgid), 1.0, DutyCycleConfig {
+            period: Duration::from_millis(50),
+            ..DutyCycleConfig::default()
+        }).unwrap();
+        std::thread::sleep(Duration::from_millis(300));
+        assert!(throttler.stats().periods > 2);
+        assert!(!frozen().contains(&-(pgid as libc::pid_t)));
+        assert_eq!(state(member.id()), 'T');
+
+        throttler.stop();
+        assert_ne!(state(pgid), 'T');
+        assert_eq!(state(member.id()), 'T');
+
+        for child in [&mut leader, &mut member] {
+            child.kill().unwrap();
+            child.wait().unwrap();
+        }
+    }
+}
@@ -1,15 +1,23 @@
+use crate::action_audit;
 use crate::{CpuAnalyzer, ProcessMonitor, KernelInterface, ProcessAction, ActionResult, ProcessDetails, ProcessTreeBuilder, ProcessTreeNode};
 use once_cell::sync::Lazy;
+use reaper_core::sampling::{self, Metric};
 use std::ffi::CString;
 use std::os::raw::c_char;
-use std::sync::Mutex;
-
-static PROCESS_MONITOR: Lazy<Mutex<ProcessMonitor>> = Lazy::new(|| {
-    Mutex::new(ProcessMonitor::new())
+use std::sync::{Arc, Mutex};
+
+// Both monitors read the process-wide shared system and are also driven by
+// the sampling engine once `reaper_sampling_start` has been called
+static PROCESS_MONITOR: Lazy<Arc<Mutex<ProcessMonitor>>> = Lazy::new(|| {
+    let monitor = Arc::new(Mutex::new(ProcessMonitor::with_system(sampling::shared_system())));
+    sampling::register_global(Arc::clone(&monitor), Metric::Processes.default_interval());
+    monitor
 });
 
-static CPU_ANALYZER: Lazy<Mutex<CpuAnalyzer>> = Lazy::new(|| {
-    Mutex::new(CpuAnalyzer::new())
+static CPU_ANALYZER: Lazy<Arc<Mutex<CpuAnalyzer>>> = Lazy::new(|| {
+    let analyzer = Arc::new(Mutex::new(CpuAnalyzer::with_system(sampling::shared_system())));
+    sampling::register_global(Arc::clone(&analyzer), Metric::Cpu.default_interval());
+    analyzer
 });
 
 static KERNEL_INTERFACE: Lazy<Mutex<KernelInterface>> = Lazy::new(|| {
@@ -321,10 +329,12 @@ pub extern "C" fn resume_process(pid: u32) -> *mut CActionResponse {
 }
 
 fn execute_process_action(pid: u32, action: ProcessAction) -> *mut CActionResponse {
+    let audit = action_audit::begin(&format!("{:?}", action).to_lowercase(), pid);
     let result = match KERNEL_INTERFACE.lock() {
         Ok(mut kernel) => kernel.execute_action(pid, action),
         Err(_) => ActionResult::UnknownError("Failed to acquire kernel interface lock".to_string()),
     };
+    audit.finish(result.kind(), result.message());
     
     let (c_result, message) = match result {
         ActionResult::Success(msg) => (CActionResult::Success, msg),
@@ -558,7 +568,7 @@ fn convert_tree_node(node: ProcessTreeNode) -> CProcessTreeNode {
 
 #[no_mangle]
 pub extern "C" fn get_process_tree() -> *mut CProcessTree {
-    let mut builder = ProcessTreeBuilder::new();
+    let mut builder = ProcessTreeBuilder::with_system(sampling::shared_system());
     let tree = builder.build_tree();
     
     // Convert roots to C structures
@@ -852,4 +862,34 @@ pub extern "C" fn disable_high_frequency_sampling() -> u8 {
     } else {
         0 // failure
     }
-}
\ No newline at end of file
+}
+// ============================================================================
+// Remediation Policies FFI Exports
+// ============================================================================
+
+/// Load remediation policies from a `.toml` or `.json` file, replacing the
+/// current ones. Returns the number of policies, or -1 if the file is
+/// invalid.
+///
+/// # Safety
+/// `path` must be a valid NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn reaper_remediation_load_policies(path: *const c_char) -> i32 {
+    if path.is_null() {
+        return -1;
+    }
+    let path = std::ffi::CStr::from_ptr(path).to_string_lossy().into_owned();
+    match crate::remediation::load_global_policies(std::path::Path::new(&path)) {
+        Ok(count) => count as i32,
+        Err(_) => -1,
+    }
+}
+
+// Record what remediation policies would do without acting on processes
+#[no_mangle]
+pub extern "C" fn reaper_remediation_set_dry_run(dry_run: u8) {
+    crate::remediation::global_engine()
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .set_dry_run(dry_run != 0);
+}
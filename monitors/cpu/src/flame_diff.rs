@@ -0,0 +1,276 @@
+use crate::{FlameGraphData, FlameGraphNode, SvgOptions, SvgRenderer};
+use std::collections::HashMap;
+
+/// Two flame graph captures of the same program aligned by frame key
+#[derive(Debug, Clone)]
+pub struct FlameGraphDiff {
+    pub root: FlameGraphDiffNode,
+    pub baseline_samples: u64,
+    pub comparison_samples: u64,
+    pub process_name: String,
+}
+
+/// One call path as seen in both captures; a path missing from one side
+/// has zero samples there
+#[derive(Debug, Clone)]
+pub struct FlameGraphDiffNode {
+    pub function_name: String,
+    pub module_name: Option<String>,
+    pub baseline_self: u64,
+    pub baseline_total: u64,
+    pub comparison_self: u64,
+    pub comparison_total: u64,
+    /// Change in this node's share of all samples, in percentage points
+    pub self_delta_pct: f64,
+    pub total_delta_pct: f64,
+    pub children: HashMap<String, FlameGraphDiffNode>,
+}
+
+impl FlameGraphDiffNode {
+    pub fn self_delta(&self) -> i64 {
+        self.comparison_self as i64 - self.baseline_self as i64
+    }
+
+    pub fn total_delta(&self) -> i64 {
+        self.comparison_total as i64 - self.baseline_total as i64
+    }
+
+    /// Children sorted by name, the order flame graphs draw them in
+    pub fn sorted_children(&self) -> Vec<&FlameGraphDiffNode> {
+        let mut children: Vec<_> = self.children.values().collect();
+        children.sort_by(|a, b| a.function_name.cmp(&b.function_name));
+        children
+    }
+
+    fn merge(
+        baseline: Option<&FlameGraphNode>,
+        comparison: Option<&FlameGraphNode>,
+        totals: (u64, u64),
+    ) -> Self {
+        let named = comparison.or(baseline).expect("at least one side of a diff node");
+        let (baseline_self, baseline_total) = baseline.map_or((0, 0), |n| (n.self_samples, n.total_samples));
+        let (comparison_self, comparison_total) = comparison.map_or((0, 0), |n| (n.self_samples, n.total_samples));
+
+        let mut keys: Vec<&String> = baseline.into_iter()
+            .chain(comparison)
+            .flat_map(|node| node.children.keys())
+            .collect();
+        keys.sort();
+        keys.dedup();
+        let children = keys.into_iter()
+            .map(|key| {
+                let child = Self::merge(
+                    baseline.and_then(|n| n.children.get(key)),
+                    comparison.and_then(|n| n.children.get(key)),
+                    totals,
+                );
+                (key.clone(), child)
+            })
+            .collect();
+
+        Self {
+            function_name: named.function_name.clone(),
+            module_name: named.module_name.clone(),
+            baseline_self,
+            baseline_total,
+            comparison_self,
+            comparison_total,
+            self_delta_pct: share(comparison_self, totals.1) - share(baseline_self, totals.0),
+            total_delta_pct: share(comparison_total, totals.1) - share(baseline_total, totals.0),
+            children,
+        }
+    }
+}
+
+impl FlameGraphDiff {
+    /// Compare `comparison` against `baseline`. Paths below `min_percentage`
+    /// of their capture are pruned from each side first, so a path is kept
+    /// if it is significant in either.
+    pub fn new(baseline: &FlameGraphData, comparison: &FlameGraphData, min_percentage: f64) -> Self {
+        let prepare = |data: &FlameGraphData| {
+            let mut root = data.root.clone();
+            root.update_totals();
+            root.prune_small_nodes(min_percentage, data.total_samples);
+            root
+        };
+        let (baseline_root, comparison_root) = (prepare(baseline), prepare(comparison));
+        let totals = (baseline.total_samples, comparison.total_samples);
+
+        Self {
+            root: FlameGraphDiffNode::merge(Some(&baseline_root), Some(&comparison_root), totals),
+            baseline_samples: baseline.total_samples,
+            comparison_samples: comparison.total_samples,
+            process_name: comparison.process_name.clone(),
+        }
+    }
+
+    /// Paths whose share of self time changed the most, growth or shrinkage,
+    /// with their full stack
+    pub fn biggest_changes(&self, limit: usize) -> Vec<(String, &FlameGraphDiffNode)> {
+        let mut changes = Vec::new();
+        collect_changes(&self.root, String::new(), &mut changes);
+        changes.sort_by(|a, b| b.1.self_delta_pct.abs().total_cmp(&a.1.self_delta_pct.abs()));
+        changes.truncate(limit);
+        changes
+    }
+
+    /// `stack baseline comparison` lines, as produced by `difffolded.pl` and
+    /// read by `flamegraph.pl`
+    pub fn export_to_folded_format(&self) -> String {
+        let mut lines = Vec::new();
+        export_node_folded(&self.root, String::new(), &mut lines);
+        lines.join("\n")
+    }
+
+    pub fn export_to_json(&self) -> serde_json::Value {
+        serde_json::json!({
+            "name": self.process_name,
+            "baseline_samples": self.baseline_samples,
+            "comparison_samples": self.comparison_samples,
+            "root": export_node_json(&self.root),
+        })
+    }
+
+    /// Differential flame graph laid out by the comparison capture, red where
+    /// a frame's share of self time grew and blue where it shrank. Paths that
+    /// disappeared have no width to draw; `biggest_changes` lists them.
+    pub fn export_to_svg(&self, options: &SvgOptions) -> String {
+        SvgRenderer::new(options.clone()).render_diff(self)
+    }
+}
+
+fn share(samples: u64, total: u64) -> f64 {
+    if total == 0 {
+        0.0
+    } else {
+        samples as f64 / total as f64 * 100.0
+    }
+}
+
+fn collect_changes<'a>(
+    node: &'a FlameGraphDiffNode,
+    stack: String,
+    changes: &mut Vec<(String, &'a FlameGraphDiffNode)>,
+) {
+    let stack = match (stack.is_empty(), node.function_name == "ROOT") {
+        (_, true) => stack,
+        (true, false) => node.function_name.clone(),
+        (false, false) => format!("{};{}", stack, node.function_name),
+    };
+    if node.self_delta() != 0 {
+        changes.push((stack.clone(), node));
+    }
+    for child in node.children.values() {
+        collect_changes(child, stack.clone(), changes);
+    }
+}
+
+fn export_node_folded(node: &FlameGraphDiffNode, stack: String, lines: &mut Vec<String>) {
+    let current_stack = if stack.is_empty() {
+        node.function_name.clone()
+    } else {
+        format!("{};{}", stack, node.function_name)
+    };
+
+    if node.baseline_self > 0 || node.comparison_self > 0 {
+        lines.push(format!("{} {} {}", current_stack, node.baseline_self, node.comparison_self));
+    }
+    for child in node.sorted_children() {
+        export_node_folded(child, current_stack.clone(), lines);
+    }
+}
+
+fn export_node_json(node: &FlameGraphDiffNode) -> serde_json::Value {
+    let children: Vec<_> = node.sorted_children().into_iter().map(export_node_json).collect();
+    serde_json::json!({
+        "name": node.function_name,
+        "module": node.module_name,
+        "baseline": { "self_samples": node.baseline_self, "total_samples": node.baseline_total },
+        "comparison": { "self_samples": node.comparison_self, "total_samples": node.comparison_total },
+        "self_delta": node.self_delta(),
+        "total_delta": node.total_delta(),
+        "self_delta_pct": node.self_delta_pct,
+        "total_delta_pct": node.total_delta_pct,
+        "children": children,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{FlameGraphBuilder, StackFrame, StackTrace};
+
+    /// Each stack is listed outermost first, like folded output
+    fn capture(stacks: &[(&[&str], usize)]) -> FlameGraphData {
+        let mut builder = FlameGraphBuilder::new("app".to_string(), 1);
+        for (stack, count) in stacks {
+            let frames: Vec<StackFrame> = stack.iter().rev()
+                .map(|name| StackFrame {
+                    address: 0,
+                    symbol: Some(name.to_string()),
+                    module: None,
+                    file: None,
+                    line: None,
+                    offset: None,
+                })
+                .collect();
+            for _ in 0..*count {
+                builder.add_stack_trace(StackTrace {
+                    pid: 1,
+                    thread_id: Some(1),
+                    timestamp: std::time::SystemTime::now(),
+                    frames: frames.clone(),
+                    sample_duration_ms: 10,
+                    is_complete: true,
+                });
+            }
+        }
+        builder.build()
+    }
+
+    #[test]
+    fn test_diff_aligns_and_normalises() {
+        let baseline = capture(&[(&["main", "parse"], 50), (&["main", "render"], 50)]);
+        let comparison = capture(&[(&["main", "parse"], 150), (&["main", "render"], 40), (&["main", "log"], 10)]);
+        let diff = FlameGraphDiff::new(&baseline, &comparison, 0.0);
+
+        let main = &diff.root.children["main"];
+        assert_eq!((main.baseline_total, main.comparison_total), (100, 200));
+        assert_eq!(main.total_delta(), 100);
+        assert!(main.total_delta_pct.abs() < 1e-9);
+
+        let parse = &main.children["parse"];
+        assert_eq!(parse.self_delta(), 100);
+        assert!((parse.self_delta_pct - 25.0).abs() < 1e-9);
+        let render = &main.children["render"];
+        assert!((render.self_delta_pct + 30.0).abs() < 1e-9);
+        let log = &main.children["log"];
+        assert_eq!((log.baseline_self, log.comparison_self), (0, 10));
+
+        let changes = diff.biggest_changes(2);
+        assert_eq!(changes[0].0, "main;render");
+        assert_eq!(changes[1].0, "main;parse");
+
+        assert_eq!(
+            diff.export_to_folded_format(),
+            "ROOT;main;log 0 10\nROOT;main;parse 50 150\nROOT;main;render 50 40",
+        );
+        let json = diff.export_to_json();
+        assert_eq!(json["root"]["children"][0]["children"][1]["self_delta"], 100);
+    }
+
+    #[test]
+    fn test_diff_prunes_and_renders_svg() {
+        let baseline = capture(&[(&["main", "work"], 980), (&["main", "rare"], 20)]);
+        let comparison = capture(&[(&["main", "work"], 500), (&["main", "new<T>"], 500)]);
+        let diff = FlameGraphDiff::new(&baseline, &comparison, 5.0);
+        assert!(!diff.root.children["main"].children.contains_key("rare"));
+
+        let svg = diff.export_to_svg(&SvgOptions::default());
+        assert!(svg.contains("Differential Flame Graph: app"));
+        assert!(svg.contains("new&lt;T&gt;"));
+        // `new<T>` grew the most, `work` shrank
+        assert!(svg.contains("fill=\"rgb(255,55,55)\""));
+        assert!(svg.contains("rgb(") && svg.contains(",255)\""));
+    }
+}
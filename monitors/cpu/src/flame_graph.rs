@@ -1,4 +1,6 @@
+use crate::{SvgOptions, SvgRenderer};
 use std::collections::HashMap;
+use std::fmt;
 use std::time::Duration;
 
 /// Stack trace information (simplified for flame graphs)
@@ -46,6 +48,21 @@ pub struct FlameGraphNode {
     pub children: HashMap<String, FlameGraphNode>,
 }
 
+/// A line of folded stacks that couldn't be read
+#[derive(Debug, Clone, PartialEq)]
+pub struct FoldedParseError {
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for FoldedParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Invalid folded stack on line {}: {}", self.line, self.message)
+    }
+}
+
+impl std::error::Error for FoldedParseError {}
+
 /// Builder for constructing flame graphs from stack traces
 #[derive(Debug)]
 pub struct FlameGraphBuilder {
@@ -53,7 +70,8 @@ pub struct FlameGraphBuilder {
     total_samples: u64,
     process_name: String,
     pid: u32,
-    samples_by_thread: HashMap<u64, Vec<StackTrace>>,
+    /// Traces with their sample weight, grouped by thread
+    samples_by_thread: HashMap<u64, Vec<(StackTrace, u64)>>,
 }
 
 impl FlameGraphNode {
@@ -87,8 +105,8 @@ impl FlameGraphNode {
     }
     
     pub fn add_sample(&mut self, count: u64) {
-        self.self_samples += count;
-        self.total_samples += count;
+        self.self_samples = self.self_samples.saturating_add(count);
+        self.total_samples = self.total_samples.saturating_add(count);
     }
     
     pub fn get_or_create_child(&mut self, key: String, frame: &StackFrame) -> &mut FlameGraphNode {
@@ -107,9 +125,9 @@ impl FlameGraphNode {
         let mut child_total = 0;
         for child in self.children.values_mut() {
             child.update_totals();
-            child_total += child.total_samples;
+            child_total = child.total_samples.saturating_add(child_total);
         }
-        self.total_samples = self.self_samples + child_total;
+        self.total_samples = self.self_samples.saturating_add(child_total);
     }
     
     pub fn get_percentage(&self, total: u64) -> f64 {
@@ -142,7 +160,13 @@ impl FlameGraphBuilder {
     }
     
     pub fn add_stack_trace(&mut self, stack_trace: StackTrace) {
-        if stack_trace.frames.is_empty() {
+        self.add_weighted_stack_trace(stack_trace, 1);
+    }
+    
+    /// Add a trace standing for `weight` samples, such as an imported
+    /// profile's aggregated count
+    pub fn add_weighted_stack_trace(&mut self, stack_trace: StackTrace, weight: u64) {
+        if stack_trace.frames.is_empty() || weight == 0 {
             return;
         }
         
@@ -151,18 +175,25 @@ impl FlameGraphBuilder {
         self.samples_by_thread
             .entry(thread_id)
             .or_insert_with(Vec::new)
-            .push(stack_trace);
+            .push((stack_trace, weight));
+    }
+    
+    /// Threads with at least one trace
+    pub fn thread_ids(&self) -> Vec<u64> {
+        let mut ids: Vec<u64> = self.samples_by_thread.keys().copied().collect();
+        ids.sort_unstable();
+        ids
     }
     
     pub fn build(mut self) -> FlameGraphData {
         // Process all stack traces - clone the data to avoid borrow issues
-        let traces_to_process: Vec<StackTrace> = self.samples_by_thread
+        let traces_to_process: Vec<(StackTrace, u64)> = self.samples_by_thread
             .values()
             .flat_map(|traces| traces.iter().cloned())
             .collect();
             
-        for trace in traces_to_process {
-            self.process_stack_trace(trace);
+        for (trace, weight) in traces_to_process {
+            self.process_stack_trace(trace, weight);
         }
         
         // Update totals recursively
@@ -181,7 +212,7 @@ impl FlameGraphBuilder {
         }
     }
     
-    fn process_stack_trace(&mut self, stack_trace: StackTrace) {
+    fn process_stack_trace(&mut self, stack_trace: StackTrace, weight: u64) {
         if stack_trace.frames.is_empty() {
             return;
         }
@@ -199,8 +230,8 @@ impl FlameGraphBuilder {
         }
         
         // Add sample to the leaf node
-        current_node.add_sample(1);
-        self.total_samples += 1;
+        current_node.add_sample(weight);
+        self.total_samples = self.total_samples.saturating_add(weight);
     }
     
     fn create_frame_key(&self, frame: &StackFrame) -> String {
@@ -218,6 +249,59 @@ impl FlameGraphBuilder {
 }
 
 impl FlameGraphData {
+    /// Rebuild a flame graph from folded stacks (`a;b;c 42` per line), such as
+    /// `export_to_folded_format` or `stackcollapse-perf.pl` output. Of the
+    /// two counts in differential lines, the second is used.
+    pub fn from_folded(folded: &str, process_name: &str) -> Result<Self, FoldedParseError> {
+        let mut root = FlameGraphNode::new("ROOT".to_string());
+        let mut total_samples = 0;
+
+        for (index, line) in folded.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() {
+                continue;
+            }
+            let error = |message: &str| FoldedParseError { line: index + 1, message: message.to_string() };
+
+            let (mut stack, count) = split_count(line).ok_or_else(|| error("missing or invalid sample count"))?;
+            if let Some((baseline_stack, _)) = split_count(stack) {
+                stack = baseline_stack;
+            }
+            let stack = stack.strip_prefix("ROOT;").unwrap_or(stack);
+            if stack.is_empty() {
+                return Err(error("empty stack"));
+            }
+
+            let mut node = &mut root;
+            for function in stack.split(';') {
+                node = node.children
+                    .entry(function.to_string())
+                    .or_insert_with(|| FlameGraphNode::new(function.to_string()));
+            }
+            node.add_sample(count);
+            total_samples = count.saturating_add(total_samples);
+        }
+
+        root.update_totals();
+        Ok(Self {
+            root,
+            total_samples,
+            total_duration: Duration::from_millis(0),
+            process_name: process_name.to_string(),
+            pid: 0,
+            generated_at: std::time::SystemTime::now(),
+        })
+    }
+
+    pub fn export_to_svg(&self, options: &SvgOptions) -> String {
+        SvgRenderer::new(options.clone()).render(self)
+    }
+    
+    /// Gzipped pprof protobuf, for `go tool pprof` and other pprof viewers
+    pub fn export_to_pprof(&self) -> Vec<u8> {
+        crate::profile_formats::encode_pprof(self)
+    }
+
     pub fn export_to_folded_format(&self) -> String {
         let mut lines = Vec::new();
         self.export_node_folded(&self.root, String::new(), &mut lines);
@@ -303,6 +387,19 @@ impl FlameGraphData {
     }
 }
 
+/// Split `stack 42` at the last whitespace into the stack and its count
+fn split_count(line: &str) -> Option<(&str, u64)> {
+    let (stack, count) = line.rsplit_once(char::is_whitespace)?;
+    // Fractional counts come from some collapsers' weighted samples. NaN,
+    // infinities and anything past u64 are rejected rather than clamped
+    let fractional = || {
+        let count = count.parse::<f64>().ok()?.round();
+        (count.is_finite() && (0.0..u64::MAX as f64).contains(&count)).then_some(count as u64)
+    };
+    let count = count.parse::<u64>().ok().or_else(fractional)?;
+    Some((stack.trim_end(), count))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -348,4 +445,39 @@ mod tests {
         // Check that the stack was built correctly
         assert!(!flame_graph.root.children.is_empty());
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_folded_round_trip() {
+        let folded = "main;parse 30\nmain;parse;lex 10\nmain 5\nmain;render 55";
+        let data = FlameGraphData::from_folded(folded, "app").unwrap();
+        assert_eq!(data.total_samples, 100);
+        assert_eq!(data.root.children["main"].total_samples, 100);
+        assert_eq!(data.root.children["main"].children["parse"].total_samples, 40);
+
+        let mut lines: Vec<String> = data.export_to_folded_format().lines().map(String::from).collect();
+        lines.sort();
+        assert_eq!(lines, ["ROOT;main 5", "ROOT;main;parse 30", "ROOT;main;parse;lex 10", "ROOT;main;render 55"]);
+        let reparsed = FlameGraphData::from_folded(&lines.join("\n"), "app").unwrap();
+        assert_eq!(reparsed.root.children["main"].children["parse"].children["lex"].self_samples, 10);
+
+        // Differential lines use the second count
+        let diff = FlameGraphData::from_folded("main;work 10 25", "app").unwrap();
+        assert_eq!(diff.total_samples, 25);
+
+        assert_eq!(FlameGraphData::from_folded("main;work\nmain 3", "app").unwrap_err().line, 1);
+    }
+
+    #[test]
+    fn test_folded_counts_are_bounded() {
+        assert_eq!(FlameGraphData::from_folded("main;gc 2.6", "app").unwrap().total_samples, 3);
+        for count in ["1e20", "inf", "NaN", "-4.5"] {
+            let error = FlameGraphData::from_folded(&format!("main;work {}", count), "app").unwrap_err();
+            assert_eq!(error.line, 1, "{}", count);
+        }
+
+        // Totals saturate instead of overflowing
+        let data = FlameGraphData::from_folded(&format!("a {}\nb {}", u64::MAX, u64::MAX), "app").unwrap();
+        assert_eq!(data.total_samples, u64::MAX);
+        assert_eq!(data.root.total_samples, u64::MAX);
+    }
+}
@@ -0,0 +1,456 @@
+use crate::{FlameGraphData, FlameGraphDiff, FlameGraphDiffNode, FlameGraphNode, FoldedParseError};
+
+/// Where the root frame goes
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FlameOrientation {
+    /// Root at the bottom, stacks growing upwards
+    #[default]
+    Flame,
+    /// Root at the top, stacks hanging down
+    Icicle,
+}
+
+/// How frames are filled; differential graphs always use red/blue
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FlameColors {
+    /// Warm colours varied by function name, as `flamegraph.pl` draws them
+    #[default]
+    Hot,
+    /// One colour per module, so libraries stand out
+    Module,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SvgOptions {
+    /// Defaults to "Flame Graph: <process name>"
+    pub title: Option<String>,
+    pub width: usize,
+    pub frame_height: usize,
+    pub font_size: usize,
+    pub orientation: FlameOrientation,
+    pub colors: FlameColors,
+    /// Frames whose name contains this are highlighted
+    pub search: Option<String>,
+    /// Frames narrower than this many pixels are left out, with their children
+    pub min_width: f64,
+    /// Embed the script for click-to-zoom
+    pub interactive: bool,
+}
+
+impl Default for SvgOptions {
+    fn default() -> Self {
+        Self {
+            title: None,
+            width: 1200,
+            frame_height: 16,
+            font_size: 12,
+            orientation: FlameOrientation::Flame,
+            colors: FlameColors::Hot,
+            search: None,
+            min_width: 0.1,
+            interactive: true,
+        }
+    }
+}
+
+const PADDING: usize = 10;
+const HEADER_HEIGHT: usize = 48;
+const SEARCH_COLOR: &str = "rgb(230,0,230)";
+
+/// Renders flame graphs as standalone SVG documents
+#[derive(Debug, Clone, Default)]
+pub struct SvgRenderer {
+    options: SvgOptions,
+}
+
+/// A frame placed in sample units, ready to be scaled into pixels
+struct Frame<'a> {
+    name: &'a str,
+    x: u64,
+    width: u64,
+    depth: usize,
+    fill: String,
+    tooltip: String,
+}
+
+impl SvgRenderer {
+    pub fn new(options: SvgOptions) -> Self {
+        Self { options }
+    }
+
+    pub fn options(&self) -> &SvgOptions {
+        &self.options
+    }
+
+    pub fn render(&self, data: &FlameGraphData) -> String {
+        let total = data.root.total_samples.max(data.total_samples);
+        let mut frames = Vec::new();
+        let mut matched = 0;
+        self.place(&data.root, total, 0, 0, false, &mut frames, &mut matched, &|node: &FlameGraphNode| NodeView {
+            name: &node.function_name,
+            samples: node.total_samples,
+            children: sorted(node.children.values(), |n| &n.function_name),
+            fill: match self.options.colors {
+                FlameColors::Hot => hot_color(&node.function_name),
+                FlameColors::Module => module_color(node.module_name.as_deref()),
+            },
+            tooltip: format!(
+                "{} ({} samples, {:.2}%)",
+                display_name(&node.function_name),
+                node.total_samples,
+                node.get_percentage(total),
+            ),
+        });
+
+        let title = format!("Flame Graph: {}", data.process_name);
+        self.document(&title, total, matched, frames)
+    }
+
+    /// Parse folded stacks and render them
+    pub fn render_folded(&self, folded: &str, process_name: &str) -> Result<String, FoldedParseError> {
+        Ok(self.render(&FlameGraphData::from_folded(folded, process_name)?))
+    }
+
+    /// Lay out by the comparison capture, red where a frame's share of self
+    /// time grew and blue where it shrank
+    pub fn render_diff(&self, diff: &FlameGraphDiff) -> String {
+        let total = diff.root.comparison_total;
+        let max_delta = max_self_delta(&diff.root).max(f64::EPSILON);
+        let mut frames = Vec::new();
+        let mut matched = 0;
+        self.place(&diff.root, total, 0, 0, false, &mut frames, &mut matched, &|node: &FlameGraphDiffNode| NodeView {
+            name: &node.function_name,
+            samples: node.comparison_total,
+            children: node.sorted_children(),
+            fill: diff_color(node.self_delta_pct, max_delta),
+            tooltip: format!(
+                "{} ({} → {} samples, {:+.2}%)",
+                display_name(&node.function_name),
+                node.baseline_total,
+                node.comparison_total,
+                node.self_delta_pct,
+            ),
+        });
+
+        let title = format!("Differential Flame Graph: {}", diff.process_name);
+        self.document(&title, total, matched, frames)
+    }
+
+    /// Place `node` and its children at `x` samples from the left edge,
+    /// counting matched samples once per outermost match. Returns the
+    /// node's width in samples.
+    #[allow(clippy::too_many_arguments)]
+    fn place<'a, N>(
+        &self,
+        node: &'a N,
+        total: u64,
+        x: u64,
+        depth: usize,
+        inside_match: bool,
+        frames: &mut Vec<Frame<'a>>,
+        matched: &mut u64,
+        view: &dyn Fn(&'a N) -> NodeView<'a, N>,
+    ) -> u64 {
+        let NodeView { name, samples, children, fill, tooltip } = view(node);
+        if total == 0 || (samples as f64 / total as f64) * self.drawable_width() < self.options.min_width {
+            return samples;
+        }
+
+        let is_match = self.options.search.as_deref()
+            .is_some_and(|search| !search.is_empty() && name.contains(search));
+        if is_match && !inside_match {
+            *matched = matched.saturating_add(samples);
+        }
+
+        let mut child_x = x;
+        for child in children {
+            child_x = child_x.saturating_add(self.place(child, total, child_x, depth + 1, inside_match || is_match, frames, matched, view));
+        }
+
+        frames.push(Frame {
+            name: display_name(name),
+            x,
+            width: samples,
+            depth,
+            fill: if is_match { SEARCH_COLOR.to_string() } else { fill },
+            tooltip,
+        });
+        samples
+    }
+
+    fn drawable_width(&self) -> f64 {
+        self.options.width.saturating_sub(2 * PADDING) as f64
+    }
+
+    fn document(&self, default_title: &str, total: u64, matched: u64, mut frames: Vec<Frame>) -> String {
+        let options = &self.options;
+        let depth = frames.iter().map(|frame| frame.depth + 1).max().unwrap_or(0);
+        let height = HEADER_HEIGHT + depth * options.frame_height + 2 * PADDING;
+        let scale = if total == 0 { 0.0 } else { self.drawable_width() / total as f64 };
+        let char_width = options.font_size as f64 * 0.59;
+        frames.sort_by_key(|frame| (frame.depth, frame.x));
+
+        let mut svg = format!(
+            "<?xml version=\"1.0\" standalone=\"no\"?>\n\
+             <svg version=\"1.1\" xmlns=\"http://www.w3.org/2000/svg\" width=\"{w}\" height=\"{h}\" \
+             viewBox=\"0 0 {w} {h}\" font-family=\"Verdana, sans-serif\" font-size=\"{f}\">\n\
+             <rect width=\"100%\" height=\"100%\" fill=\"#f8f8f8\"/>\n\
+             <text x=\"{cx}\" y=\"{ty}\" text-anchor=\"middle\" font-size=\"{tf}\">{title}</text>\n",
+            w = options.width,
+            h = height,
+            f = options.font_size,
+            cx = options.width / 2,
+            ty = PADDING + options.font_size + 4,
+            tf = options.font_size + 5,
+            title = escape_xml(options.title.as_deref().unwrap_or(default_title)),
+        );
+        if let Some(search) = options.search.as_deref().filter(|s| !s.is_empty()) {
+            let percentage = if total == 0 { 0.0 } else { matched as f64 / total as f64 * 100.0 };
+            svg.push_str(&format!(
+                "<text x=\"{}\" y=\"{}\" text-anchor=\"end\">Matched \"{}\": {:.1}%</text>\n",
+                options.width.saturating_sub(PADDING),
+                HEADER_HEIGHT - 8,
+                escape_xml(search),
+                percentage,
+            ));
+        }
+        if options.interactive {
+            svg.push_str(&format!(
+                "<text id=\"reset\" x=\"{}\" y=\"{}\" style=\"cursor:pointer\">Reset Zoom</text>\n",
+                PADDING,
+                HEADER_HEIGHT - 8,
+            ));
+        }
+
+        svg.push_str("<g id=\"frames\">\n");
+        for frame in &frames {
+            let x = PADDING as f64 + frame.x as f64 * scale;
+            let width = frame.width as f64 * scale;
+            let y = match options.orientation {
+                FlameOrientation::Flame => height - PADDING - (frame.depth + 1) * options.frame_height,
+                FlameOrientation::Icicle => HEADER_HEIGHT + PADDING + frame.depth * options.frame_height,
+            };
+            svg.push_str(&format!(
+                "<g class=\"f\" data-n=\"{n}\" data-x=\"{dx}\" data-w=\"{dw}\" data-d=\"{dd}\">\
+                 <title>{tip}</title>\
+                 <rect x=\"{x:.1}\" y=\"{y}\" width=\"{width:.1}\" height=\"{rh}\" fill=\"{fill}\" rx=\"2\"/>\
+                 <text x=\"{tx:.1}\" y=\"{ty}\">{label}</text></g>\n",
+                n = escape_xml(frame.name),
+                dx = frame.x,
+                dw = frame.width,
+                dd = frame.depth,
+                tip = escape_xml(&frame.tooltip),
+                rh = options.frame_height.saturating_sub(1),
+                fill = frame.fill,
+                tx = x + 3.0,
+                ty = (y + options.frame_height).saturating_sub(4),
+                label = escape_xml(&fit_label(frame.name, width, char_width)),
+            ));
+        }
+        svg.push_str("</g>\n");
+
+        if options.interactive {
+            svg.push_str(&format!(
+                "<script type=\"text/ecmascript\"><![CDATA[\n\
+                 var pad = {}, width = {}, charWidth = {};\n{}]]></script>\n",
+                PADDING,
+                options.width,
+                char_width,
+                ZOOM_SCRIPT,
+            ));
+        }
+        svg.push_str("</svg>\n");
+        svg
+    }
+}
+
+/// Click a frame to widen it to the full graph, keeping its ancestors and
+/// hiding everything outside it; click the root or "Reset Zoom" to undo
+const ZOOM_SCRIPT: &str = r#"function fit(name, w) {
+  var chars = Math.floor((w - 6) / charWidth);
+  if (chars < 3) return "";
+  return name.length > chars ? name.slice(0, chars - 2) + ".." : name;
+}
+function zoom(target) {
+  var x = +target.dataset.x, w = +target.dataset.w, d = +target.dataset.d;
+  var scale = (width - 2 * pad) / w;
+  document.querySelectorAll("g.f").forEach(function (f) {
+    var fx = +f.dataset.x, fw = +f.dataset.w, fd = +f.dataset.d;
+    var inside = fd >= d && fx >= x && fx + fw <= x + w;
+    var ancestor = fd < d && fx <= x && fx + fw >= x + w;
+    f.style.display = inside || ancestor ? "" : "none";
+    if (!inside && !ancestor) return;
+    var nx = ancestor ? pad : pad + (fx - x) * scale, nw = ancestor ? width - 2 * pad : fw * scale;
+    var rect = f.querySelector("rect"), text = f.querySelector("text");
+    rect.setAttribute("x", nx.toFixed(1));
+    rect.setAttribute("width", nw.toFixed(1));
+    text.setAttribute("x", (nx + 3).toFixed(1));
+    text.textContent = fit(f.dataset.n, nw);
+  });
+}
+document.addEventListener("click", function (e) {
+  if (e.target.id === "reset") {
+    zoom(document.querySelector("g.f[data-d='0']"));
+    return;
+  }
+  var frame = e.target.closest("g.f");
+  if (frame) zoom(frame);
+});
+"#;
+
+/// What the layout needs from a node of either tree
+struct NodeView<'a, N> {
+    name: &'a str,
+    samples: u64,
+    children: Vec<&'a N>,
+    fill: String,
+    tooltip: String,
+}
+
+fn sorted<'a, N>(nodes: impl Iterator<Item = &'a N>, name: impl Fn(&N) -> &String) -> Vec<&'a N> {
+    let mut nodes: Vec<&N> = nodes.collect();
+    nodes.sort_by(|a, b| name(a).cmp(name(b)));
+    nodes
+}
+
+/// The root is labelled "all", as in `flamegraph.pl`
+fn display_name(name: &str) -> &str {
+    if name == "ROOT" { "all" } else { name }
+}
+
+/// Truncate `name` to what fits in `width` pixels, or nothing if too narrow
+fn fit_label(name: &str, width: f64, char_width: f64) -> String {
+    let chars = ((width - 6.0) / char_width).floor();
+    if chars < 3.0 {
+        return String::new();
+    }
+    let chars = chars as usize;
+    if name.chars().count() > chars {
+        format!("{}..", name.chars().take(chars - 2).collect::<String>())
+    } else {
+        name.to_string()
+    }
+}
+
+/// FNV-1a, so a function keeps its colour across renders
+fn name_hash(name: &str) -> u64 {
+    name.bytes().fold(0xcbf29ce484222325, |hash, byte| (hash ^ byte as u64).wrapping_mul(0x100000001b3))
+}
+
+fn hot_color(name: &str) -> String {
+    let hash = name_hash(name);
+    let unit = |shift: u32| ((hash >> shift) & 0xff) as f64 / 255.0;
+    format!(
+        "rgb({},{},{})",
+        205 + (50.0 * unit(0)) as u8,
+        (230.0 * unit(8)) as u8,
+        (55.0 * unit(16)) as u8,
+    )
+}
+
+const MODULE_PALETTE: &[&str] = &[
+    "rgb(240,140,100)", "rgb(120,180,230)", "rgb(140,210,130)", "rgb(230,190,90)",
+    "rgb(190,150,220)", "rgb(100,200,200)", "rgb(230,130,170)", "rgb(170,190,110)",
+    "rgb(210,160,120)", "rgb(150,170,240)", "rgb(220,220,120)", "rgb(160,220,180)",
+];
+
+fn module_color(module: Option<&str>) -> String {
+    match module {
+        Some(module) => MODULE_PALETTE[(name_hash(module) % MODULE_PALETTE.len() as u64) as usize].to_string(),
+        None => "rgb(200,200,200)".to_string(),
+    }
+}
+
+/// White for no change, saturating to red (growth) or blue (shrinkage) at
+/// the largest change in the graph
+fn diff_color(delta: f64, max_delta: f64) -> String {
+    let fade = (255.0 - 200.0 * (delta.abs() / max_delta).min(1.0)).round() as u8;
+    if delta > 0.0 {
+        format!("rgb(255,{},{})", fade, fade)
+    } else {
+        format!("rgb({},{},255)", fade, fade)
+    }
+}
+
+fn max_self_delta(node: &FlameGraphDiffNode) -> f64 {
+    node.children.values()
+        .map(max_self_delta)
+        .fold(node.self_delta_pct.abs(), f64::max)
+}
+
+fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FOLDED: &str = "main;parse;tokenize 30\nmain;parse 10\nmain;render<T> 60\nmain;tiny 1\n";
+
+    fn frames(svg: &str) -> Vec<&str> {
+        svg.lines().filter(|line| line.starts_with("<g class=\"f\"")).collect()
+    }
+
+    #[test]
+    fn test_renders_folded_input() {
+        let svg = SvgRenderer::default().render_folded(FOLDED, "app").unwrap();
+        assert!(svg.contains("Flame Graph: app"));
+        assert!(svg.contains("<script"));
+
+        let frames = frames(&svg);
+        assert_eq!(frames.len(), 6);
+        assert!(frames[0].contains("data-n=\"all\"") && frames[0].contains("data-w=\"101\""));
+        assert!(frames.iter().any(|f| f.contains("data-n=\"render&lt;T&gt;\"") && f.contains("data-x=\"40\"")));
+        assert!(frames.iter().any(|f| f.contains("tokenize (30 samples, 29.70%)")));
+    }
+
+    #[test]
+    fn test_orientation_culling_and_search() {
+        let data = FlameGraphData::from_folded(FOLDED, "app").unwrap();
+        let y_of = |svg: &str, name: &str| -> usize {
+            let frame = frames(svg).into_iter().find(|f| f.contains(&format!("data-n=\"{}\"", name))).unwrap();
+            let y = frame.split(" y=\"").nth(1).unwrap();
+            y[..y.find('"').unwrap()].parse().unwrap()
+        };
+
+        let flame = SvgRenderer::default().render(&data);
+        assert!(y_of(&flame, "all") > y_of(&flame, "main"));
+        let icicle = SvgRenderer::new(SvgOptions { orientation: FlameOrientation::Icicle, ..SvgOptions::default() })
+            .render(&data);
+        assert!(y_of(&icicle, "all") < y_of(&icicle, "main"));
+
+        let options = SvgOptions {
+            search: Some("parse".to_string()),
+            min_width: 20.0,
+            interactive: false,
+            ..SvgOptions::default()
+        };
+        let svg = SvgRenderer::new(options).render(&data);
+        assert!(!svg.contains("data-n=\"tiny\""));
+        assert!(!svg.contains("<script"));
+        assert!(svg.contains("Matched \"parse\": 39.6%"));
+        let parse = frames(&svg).into_iter().find(|f| f.contains("data-n=\"parse\"")).unwrap();
+        assert!(parse.contains(SEARCH_COLOR));
+    }
+
+    #[test]
+    fn test_module_colors() {
+        assert_eq!(module_color(Some("libc.so.6")), module_color(Some("libc.so.6")));
+        assert_eq!(module_color(None), "rgb(200,200,200)");
+        assert_eq!(fit_label("a_long_function_name", 60.0, 7.0), "a_lon..");
+        assert_eq!(fit_label("main", 20.0, 7.0), "");
+    }
+
+    #[test]
+    fn test_degenerate_sizes() {
+        for (width, frame_height) in [(0, 0), (5, 1), (1200, 3)] {
+            let options = SvgOptions { width, frame_height, search: Some("main".to_string()), ..SvgOptions::default() };
+            let svg = SvgRenderer::new(options).render_folded(FOLDED, "app").unwrap();
+            assert!(svg.ends_with("</svg>\n"));
+        }
+    }
+}
@@ -0,0 +1,450 @@
+//! I/O limits for the process limiter
+//!
+//! Two independent controls, either or both per process:
+//! - `ioprio_set` puts every thread in an I/O scheduling class (realtime,
+//!   best-effort or idle). Only schedulers that honour priorities (BFQ,
+//!   and CFQ on old kernels) act on it; the idle class is the useful one
+//!   for indexers and backups.
+//! - A cgroup v2 `io.max` line per device caps read/write bytes and IOPS,
+//!   whatever the scheduler. The process shares its Reaper cgroup with the
+//!   CPU and memory limiters.
+//!
+//! The limits live next to CPU limits in `ProcessCpuLimiter`, and
+//! `/proc/<pid>/io` counters taken when a limit is applied show its effect.
+
+use crate::process_limiter::LimitError;
+use std::collections::HashMap;
+use std::time::Instant;
+#[cfg(target_os = "linux")]
+use reaper_core::platform::linux::{cgroup::{self, CgroupFs}, procfs};
+#[cfg(target_os = "linux")]
+use std::path::Path;
+
+/// Kernel I/O scheduling classes (`IOPRIO_CLASS_*`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IoPriorityClass {
+    /// Served before anything else; needs CAP_SYS_ADMIN
+    Realtime,
+    /// The default class, ordered by level
+    BestEffort,
+    /// Only gets the disk when nobody else wants it
+    Idle,
+}
+
+impl IoPriorityClass {
+    pub fn raw(self) -> u16 {
+        match self {
+            Self::Realtime => 1,
+            Self::BestEffort => 2,
+            Self::Idle => 3,
+        }
+    }
+
+    pub fn from_raw(class: u16) -> Option<Self> {
+        match class {
+            1 => Some(Self::Realtime),
+            2 => Some(Self::BestEffort),
+            3 => Some(Self::Idle),
+            _ => None,
+        }
+    }
+}
+
+/// An I/O class and its level, 0 (highest) to 7
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IoPriority {
+    pub class: IoPriorityClass,
+    pub level: u8,
+}
+
+impl IoPriority {
+    pub fn idle() -> Self {
+        Self { class: IoPriorityClass::Idle, level: 0 }
+    }
+
+    pub fn best_effort(level: u8) -> Self {
+        Self { class: IoPriorityClass::BestEffort, level }
+    }
+
+    pub fn realtime(level: u8) -> Self {
+        Self { class: IoPriorityClass::Realtime, level }
+    }
+
+    /// The `ioprio` value: class in the top 3 bits, level below
+    pub fn to_raw(self) -> u16 {
+        // The idle class has no levels
+        let level = if self.class == IoPriorityClass::Idle { 0 } else { self.level as u16 };
+        self.class.raw() << 13 | level
+    }
+
+    /// `None` for `IOPRIO_CLASS_NONE`, where the priority follows the nice value
+    pub fn from_raw(raw: u16) -> Option<Self> {
+        let class = IoPriorityClass::from_raw(raw >> 13)?;
+        Some(Self { class, level: (raw & 0x7) as u8 })
+    }
+}
+
+/// `io.max` caps for one block device; `None` leaves that cap off
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IoBandwidthLimit {
+    pub major: u32,
+    pub minor: u32,
+    pub read_bps: Option<u64>,
+    pub write_bps: Option<u64>,
+    pub read_iops: Option<u64>,
+    pub write_iops: Option<u64>,
+}
+
+impl IoBandwidthLimit {
+    pub fn new(major: u32, minor: u32) -> Self {
+        Self { major, minor, read_bps: None, write_bps: None, read_iops: None, write_iops: None }
+    }
+
+    /// Caps for the disk holding `path`. `io.max` only accepts whole disks,
+    /// so a partition resolves to its parent
+    #[cfg(target_os = "linux")]
+    pub fn for_path(path: &Path) -> Result<Self, LimitError> {
+        use std::os::unix::fs::MetadataExt;
+
+        let dev = std::fs::metadata(path).map_err(|e| LimitError::SystemError(format!("{}: {}", path.display(), e)))?.dev();
+        let (major, minor) = (libc::major(dev), libc::minor(dev));
+        let sysfs = std::fs::canonicalize(format!("/sys/dev/block/{}:{}", major, minor))
+            .map_err(|_| LimitError::SystemError(format!("{} is not on a block device", path.display())))?;
+        if !sysfs.join("partition").exists() {
+            return Ok(Self::new(major, minor));
+        }
+
+        let disk = sysfs.parent().map(|p| p.join("dev")).unwrap_or_default();
+        let (major, minor) = std::fs::read_to_string(&disk).ok()
+            .and_then(|dev| parse_dev(&dev))
+            .ok_or_else(|| LimitError::SystemError(format!("No parent disk for {}:{}", major, minor)))?;
+        Ok(Self::new(major, minor))
+    }
+
+    fn caps(&self) -> [(&'static str, Option<u64>); 4] {
+        [("rbps", self.read_bps), ("wbps", self.write_bps), ("riops", self.read_iops), ("wiops", self.write_iops)]
+    }
+
+    /// The `io.max` line setting these caps
+    pub fn io_max_value(&self) -> String {
+        let caps: Vec<String> = self.caps().iter()
+            .filter_map(|(key, cap)| cap.map(|cap| format!("{}={}", key, cap)))
+            .collect();
+        format!("{}:{} {}", self.major, self.minor, caps.join(" "))
+    }
+
+    /// The `io.max` line lifting every cap on the device
+    pub fn io_max_reset(&self) -> String {
+        format!("{}:{} rbps=max wbps=max riops=max wiops=max", self.major, self.minor)
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct IoLimitSettings {
+    pub priority: Option<IoPriority>,
+    pub bandwidth: Vec<IoBandwidthLimit>,
+}
+
+impl IoLimitSettings {
+    /// Rejects empty settings, levels past 7 and zero caps
+    pub fn validate(&self) -> Result<(), LimitError> {
+        let priority_ok = self.priority.is_none_or(|p| p.level <= 7);
+        let bandwidth_ok = self.bandwidth.iter().all(|limit| {
+            let caps = limit.caps();
+            caps.iter().any(|(_, cap)| cap.is_some()) && caps.iter().all(|(_, cap)| *cap != Some(0))
+        });
+        if !priority_ok || !bandwidth_ok || (self.priority.is_none() && self.bandwidth.is_empty()) {
+            return Err(LimitError::InvalidLimit);
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct IoLimit {
+    pub pid: u32,
+    pub settings: IoLimitSettings,
+    /// Raw `ioprio` of each thread before the class was set, by thread id
+    pub original_priority: Option<HashMap<u32, u16>>,
+    /// Reaper-managed cgroup holding the process, with `io.max` set
+    pub cgroup: Option<String>,
+    /// `/proc/<pid>/io` when the limit was applied
+    baseline: IoCounters,
+    started: Instant,
+}
+
+/// Storage I/O counters from `/proc/<pid>/io`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct IoCounters {
+    /// Bytes fetched from storage
+    pub read_bytes: u64,
+    /// Bytes sent to storage, including writeback of dirtied pages
+    pub write_bytes: u64,
+    pub read_syscalls: u64,
+    pub write_syscalls: u64,
+}
+
+/// What a limited process did since its I/O limit was applied
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct IoLimitUsage {
+    pub pid: u32,
+    pub since_limit: IoCounters,
+    pub elapsed_secs: f64,
+    pub read_bytes_per_sec: f64,
+    pub write_bytes_per_sec: f64,
+}
+
+impl IoLimit {
+    pub fn usage(&self, now: IoCounters) -> IoLimitUsage {
+        let since_limit = IoCounters {
+            read_bytes: now.read_bytes.saturating_sub(self.baseline.read_bytes),
+            write_bytes: now.write_bytes.saturating_sub(self.baseline.write_bytes),
+            read_syscalls: now.read_syscalls.saturating_sub(self.baseline.read_syscalls),
+            write_syscalls: now.write_syscalls.saturating_sub(self.baseline.write_syscalls),
+        };
+        let elapsed_secs = self.started.elapsed().as_secs_f64();
+        let rate = |bytes: u64| if elapsed_secs > 0.0 { bytes as f64 / elapsed_secs } else { 0.0 };
+
+        IoLimitUsage {
+            pid: self.pid,
+            since_limit,
+            elapsed_secs,
+            read_bytes_per_sec: rate(since_limit.read_bytes),
+            write_bytes_per_sec: rate(since_limit.write_bytes),
+        }
+    }
+}
+
+/// Apply `settings` to `pid`, undoing whatever was applied if a step fails
+#[cfg(target_os = "linux")]
+pub(crate) fn apply_io_limit(pid: u32, settings: IoLimitSettings) -> Result<IoLimit, LimitError> {
+    settings.validate()?;
+    let baseline = read_io_counters(pid)?;
+
+    let original_priority = match settings.priority {
+        Some(priority) => Some(set_process_priority(pid, priority)?),
+        None => None,
+    };
+
+    let mut cgroup = None;
+    if !settings.bandwidth.is_empty() {
+        match apply_bandwidth(pid, &settings.bandwidth) {
+            Ok(dir) => cgroup = Some(dir),
+            Err(e) => {
+                if let Some(original) = &original_priority {
+                    let _ = restore_process_priority(pid, original);
+                }
+                return Err(e);
+            }
+        }
+    }
+
+    Ok(IoLimit { pid, settings, original_priority, cgroup, baseline, started: Instant::now() })
+}
+
+#[cfg(not(target_os = "linux"))]
+pub(crate) fn apply_io_limit(_pid: u32, settings: IoLimitSettings) -> Result<IoLimit, LimitError> {
+    settings.validate()?;
+    Err(LimitError::SystemError("I/O limits need ioprio and cgroup v2".to_string()))
+}
+
+/// Lift `io.max` caps and put back each thread's priority. Every step is
+/// attempted; the first error is returned
+#[cfg(target_os = "linux")]
+pub(crate) fn restore_io_limit(limit: &IoLimit) -> Result<(), LimitError> {
+    let mut result = Ok(());
+    if let Some(dir) = &limit.cgroup {
+        result = result.and(restore_bandwidth(limit.pid, Path::new(dir), &limit.settings.bandwidth));
+    }
+    if let Some(original) = &limit.original_priority {
+        result = result.and(restore_process_priority(limit.pid, original));
+    }
+    result
+}
+
+#[cfg(not(target_os = "linux"))]
+pub(crate) fn restore_io_limit(_limit: &IoLimit) -> Result<(), LimitError> {
+    Ok(())
+}
+
+#[cfg(target_os = "linux")]
+pub(crate) fn read_io_counters(pid: u32) -> Result<IoCounters, LimitError> {
+    let io = procfs::read_io(pid)?;
+    Ok(IoCounters {
+        read_bytes: io.read_bytes,
+        write_bytes: io.write_bytes,
+        read_syscalls: io.syscr,
+        write_syscalls: io.syscw,
+    })
+}
+
+#[cfg(not(target_os = "linux"))]
+pub(crate) fn read_io_counters(_pid: u32) -> Result<IoCounters, LimitError> {
+    Err(LimitError::SystemError("/proc/<pid>/io is Linux only".to_string()))
+}
+
+/// Set `priority` on every thread; returns each thread's old raw value
+#[cfg(target_os = "linux")]
+fn set_process_priority(pid: u32, priority: IoPriority) -> Result<HashMap<u32, u16>, LimitError> {
+    let mut original = HashMap::new();
+    for tid in procfs::list_tids(pid).map_err(|_| LimitError::ProcessNotFound)? {
+        match ioprio_get(tid) {
+            Ok(raw) => { original.insert(tid, raw); }
+            // Threads may exit while we walk the list
+            Err(LimitError::ProcessNotFound) if tid != pid => {}
+            Err(e) => return Err(e),
+        }
+    }
+
+    for &tid in original.keys() {
+        match ioprio_set(tid, priority.to_raw()) {
+            Ok(()) | Err(LimitError::ProcessNotFound) => {}
+            Err(e) => {
+                let _ = restore_process_priority(pid, &original);
+                return Err(e);
+            }
+        }
+    }
+    Ok(original)
+}
+
+/// Threads started under the limit inherited its class and get the main
+/// thread's original priority back
+#[cfg(target_os = "linux")]
+fn restore_process_priority(pid: u32, original: &HashMap<u32, u16>) -> Result<(), LimitError> {
+    let main = *original.get(&pid).ok_or(LimitError::ProcessNotFound)?;
+    let mut result = Ok(());
+    for tid in procfs::list_tids(pid).map_err(|_| LimitError::ProcessNotFound)? {
+        match ioprio_set(tid, original.get(&tid).copied().unwrap_or(main)) {
+            Ok(()) => {}
+            Err(LimitError::ProcessNotFound) if tid != pid => {}
+            Err(e) => result = result.and(Err(e)),
+        }
+    }
+    result
+}
+
+/// Move `pid` into its Reaper cgroup and write an `io.max` line per device
+#[cfg(target_os = "linux")]
+fn apply_bandwidth(pid: u32, limits: &[IoBandwidthLimit]) -> Result<String, LimitError> {
+    let fs = CgroupFs::detect()
+        .filter(|fs| fs.has_controller("io"))
+        .ok_or_else(|| LimitError::SystemError("cgroup v2 io controller not available".to_string()))?;
+
+    let attachment = fs.attach(pid, "io")?;
+    let io_max = attachment.dir.join("io.max");
+    for (written, limit) in limits.iter().enumerate() {
+        if let Err(e) = cgroup::write_file(&io_max, &limit.io_max_value()) {
+            let _ = restore_bandwidth(pid, &attachment.dir, &limits[..written]);
+            return Err(e.into());
+        }
+    }
+    Ok(attachment.dir.to_string_lossy().into_owned())
+}
+
+#[cfg(target_os = "linux")]
+fn restore_bandwidth(pid: u32, dir: &Path, limits: &[IoBandwidthLimit]) -> Result<(), LimitError> {
+    let fs = CgroupFs::detect()
+        .ok_or_else(|| LimitError::SystemError("cgroup v2 hierarchy disappeared".to_string()))?;
+    let mut reset = Ok(());
+    for limit in limits {
+        reset = reset.and(cgroup::write_file(&dir.join("io.max"), &limit.io_max_reset()));
+    }
+    // Detach regardless: once the process has gone the cgroup must still go
+    reset.and(fs.detach(pid, "io")).map_err(LimitError::from)
+}
+
+#[cfg(target_os = "linux")]
+const IOPRIO_WHO_PROCESS: libc::c_long = 1;
+
+/// `ioprio_get` of one thread (`IOPRIO_WHO_PROCESS` takes a thread id)
+#[cfg(target_os = "linux")]
+fn ioprio_get(tid: u32) -> Result<u16, LimitError> {
+    let result = unsafe { libc::syscall(libc::SYS_ioprio_get, IOPRIO_WHO_PROCESS, tid as libc::c_long) };
+    if result < 0 {
+        return Err(io_limit_error(std::io::Error::last_os_error()));
+    }
+    Ok(result as u16)
+}
+
+#[cfg(target_os = "linux")]
+fn ioprio_set(tid: u32, raw: u16) -> Result<(), LimitError> {
+    let result = unsafe {
+        libc::syscall(libc::SYS_ioprio_set, IOPRIO_WHO_PROCESS, tid as libc::c_long, raw as libc::c_long)
+    };
+    if result < 0 {
+        return Err(io_limit_error(std::io::Error::last_os_error()));
+    }
+    Ok(())
+}
+
+#[cfg(target_os = "linux")]
+fn io_limit_error(err: std::io::Error) -> LimitError {
+    match err.raw_os_error() {
+        Some(libc::EPERM) | Some(libc::EACCES) => LimitError::PermissionDenied,
+        Some(libc::ESRCH) => LimitError::ProcessNotFound,
+        Some(libc::EINVAL) => LimitError::InvalidLimit,
+        _ => LimitError::SystemError(err.to_string()),
+    }
+}
+
+/// `MAJ:MIN` as found in sysfs `dev` files
+#[cfg(target_os = "linux")]
+fn parse_dev(content: &str) -> Option<(u32, u32)> {
+    let (major, minor) = content.trim().split_once(':')?;
+    Some((major.parse().ok()?, minor.parse().ok()?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_priority_encoding() {
+        assert_eq!(IoPriority::idle().to_raw(), 3 << 13);
+        assert_eq!(IoPriority::best_effort(4).to_raw(), 2 << 13 | 4);
+        assert_eq!(IoPriority::from_raw(1 << 13 | 2), Some(IoPriority::realtime(2)));
+        assert_eq!(IoPriority::from_raw(0), None);
+        // Levels mean nothing in the idle class
+        assert_eq!(IoPriority { class: IoPriorityClass::Idle, level: 5 }.to_raw(), 3 << 13);
+    }
+
+    #[test]
+    fn test_io_max_lines_and_validation() {
+        let mut limit = IoBandwidthLimit::new(8, 0);
+        limit.write_bps = Some(10 << 20);
+        limit.read_iops = Some(200);
+        assert_eq!(limit.io_max_value(), "8:0 wbps=10485760 riops=200");
+        assert_eq!(limit.io_max_reset(), "8:0 rbps=max wbps=max riops=max wiops=max");
+
+        let mut settings = IoLimitSettings { priority: None, bandwidth: vec![limit] };
+        assert!(settings.validate().is_ok());
+        settings.bandwidth.push(IoBandwidthLimit::new(8, 16));
+        assert!(matches!(settings.validate(), Err(LimitError::InvalidLimit)));
+        settings.bandwidth[1].read_bps = Some(0);
+        assert!(matches!(settings.validate(), Err(LimitError::InvalidLimit)));
+
+        assert!(matches!(IoLimitSettings::default().validate(), Err(LimitError::InvalidLimit)));
+        let settings = IoLimitSettings { priority: Some(IoPriority::best_effort(8)), bandwidth: Vec::new() };
+        assert!(matches!(settings.validate(), Err(LimitError::InvalidLimit)));
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_priority_round_trip() {
+        let mut child = std::process::Command::new("sleep").arg("5").spawn().unwrap();
+        let pid = child.id();
+        let before = ioprio_get(pid).unwrap();
+
+        let settings = IoLimitSettings { priority: Some(IoPriority::idle()), bandwidth: Vec::new() };
+        let limit = apply_io_limit(pid, settings).unwrap();
+        assert_eq!(IoPriority::from_raw(ioprio_get(pid).unwrap()), Some(IoPriority::idle()));
+        assert_eq!(limit.usage(IoCounters::default()).since_limit, IoCounters::default());
+
+        restore_io_limit(&limit).unwrap();
+        assert_eq!(ioprio_get(pid).unwrap(), before);
+
+        child.kill().unwrap();
+        child.wait().unwrap();
+    }
+}
@@ -34,6 +34,30 @@ pub enum ActionResult {
     UnknownError(String),
 }
 
+impl ActionResult {
+    /// Variant name, as recorded in the audit log
+    pub fn kind(&self) -> &'static str {
+        match self {
+            ActionResult::Success(_) => "Success",
+            ActionResult::ProcessNotFound => "ProcessNotFound",
+            ActionResult::PermissionDenied(_) => "PermissionDenied",
+            ActionResult::ProcessUnkillable(_) => "ProcessUnkillable",
+            ActionResult::AlreadyInState(_) => "AlreadyInState",
+            ActionResult::UnknownError(_) => "UnknownError",
+        }
+    }
+
+    pub fn message(&self) -> String {
+        match self {
+            ActionResult::ProcessNotFound => "Process not found".to_string(),
+            ActionResult::Success(msg)
+            | ActionResult::PermissionDenied(msg)
+            | ActionResult::ProcessUnkillable(msg)
+            | ActionResult::AlreadyInState(msg)
+            | ActionResult::UnknownError(msg) => msg.clone(),
+        }
+    }
+}
 
 impl KernelInterface {
     pub fn new() -> Self {
@@ -121,7 +145,7 @@ impl KernelInterface {
     }
     
     fn handle_kill_error(&self, pid: u32, action: &str) -> ActionResult {
-        let errno = unsafe { *libc::__error() };
+        let errno = std::io::Error::last_os_error().raw_os_error().unwrap_or(0);
         match errno {
             libc::ESRCH => ActionResult::ProcessNotFound,
             libc::EPERM => ActionResult::PermissionDenied(
@@ -1,23 +1,43 @@
 mod process_monitor;
 mod cpu_analyzer;
 mod cpu_throttler;
+#[cfg(target_os = "linux")]
+mod duty_cycle;
 mod kernel_interface;
 mod process_details;
 mod process_limiter;
+mod io_limiter;
 mod process_tree;
 mod flame_graph;
+mod flame_diff;
+mod flame_svg;
+mod profile_formats;
 mod cpu_history;
+#[cfg(target_os = "linux")]
+mod stack_sampler;
+#[cfg(target_os = "linux")]
+pub mod symbolizer;
 mod thermal_monitor;
+mod action_audit;
+pub mod remediation;
 mod ffi;
 
 pub use process_monitor::*;
 pub use cpu_analyzer::*;
 pub use cpu_throttler::*;
+#[cfg(target_os = "linux")]
+pub use duty_cycle::*;
 pub use kernel_interface::*;
 pub use process_details::*;
 pub use process_limiter::*;
+pub use io_limiter::*;
 pub use process_tree::*;
 pub use flame_graph::*;
+pub use flame_diff::*;
+pub use flame_svg::*;
+pub use profile_formats::*;
 pub use cpu_history::*;
+#[cfg(target_os = "linux")]
+pub use stack_sampler::*;
 pub use thermal_monitor::*;
 pub use ffi::*;
\ No newline at end of file
@@ -1,6 +1,5 @@
 use std::collections::HashMap;
 use std::process::Command;
-use libc::{proc_pidpath, PROC_PIDPATHINFO_MAXSIZE};
 
 #[derive(Debug, Clone)]
 pub struct ProcessDetails {
@@ -30,7 +29,10 @@ impl ProcessDetails {
 }
 
 /// Get the executable path for a process
+#[cfg(target_os = "macos")]
 fn get_process_path(pid: u32) -> Option<String> {
+    use libc::{proc_pidpath, PROC_PIDPATHINFO_MAXSIZE};
+
     let mut path_buf = vec![0u8; PROC_PIDPATHINFO_MAXSIZE as usize];
     
     unsafe {
@@ -50,6 +52,14 @@ fn get_process_path(pid: u32) -> Option<String> {
     }
 }
 
+/// Get the executable path for a process
+#[cfg(target_os = "linux")]
+fn get_process_path(pid: u32) -> Option<String> {
+    std::fs::read_link(format!("/proc/{}/exe", pid))
+        .ok()
+        .map(|p| p.to_string_lossy().into_owned())
+}
+
 /// Get command line arguments for a process
 fn get_process_arguments(pid: u32) -> Vec<String> {
     // Use ps command to get arguments
@@ -1,14 +1,35 @@
 use std::collections::HashMap;
+#[cfg(not(target_os = "linux"))]
 use std::process::Command;
+use crate::action_audit;
+use crate::io_limiter::{self, IoLimit, IoLimitSettings, IoLimitUsage, IoPriority, IoPriorityClass, IoBandwidthLimit};
 use libc::c_int;
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::sync::Mutex;
+#[cfg(target_os = "linux")]
+use reaper_core::platform::linux::{cgroup::{self, CgroupFs}, procfs};
+#[cfg(target_os = "linux")]
+use reaper_core::platform::PlatformError;
+#[cfg(target_os = "linux")]
+use crate::{DutyCycleConfig, DutyCycleStats, DutyCycleThrottler, ThrottleTarget};
 
 /// Process CPU Limiter - Controls CPU usage of external processes
-/// Uses nice values, CPU affinity, and optional cpulimit tool
+/// Uses nice values, CPU affinity, and on Linux a cgroup v2 `cpu.max` quota
+/// or, where cgroups aren't available, the built-in duty-cycle throttler.
+/// Other platforms fall back to the optional cpulimit tool.
+/// I/O limits are kept alongside, so one `remove_limit` lifts both
 #[derive(Debug)]
 pub struct ProcessCpuLimiter {
     /// Active CPU limits by PID
     limits: HashMap<u32, CpuLimit>,
+    /// Active I/O priority and bandwidth limits by PID
+    io_limits: HashMap<u32, IoLimit>,
+    /// Duty-cycle throttlers of the limits that use one, by PID
+    #[cfg(target_os = "linux")]
+    throttlers: HashMap<u32, DutyCycleThrottler>,
     /// Check if cpulimit tool is available
+    #[cfg(not(target_os = "linux"))]
     cpulimit_available: Option<bool>,
 }
 
@@ -18,7 +39,14 @@ pub struct CpuLimit {
     pub max_cpu_percent: f32,
     pub nice_value: i32,
     pub original_nice: Option<i32>,
+    /// CPUs the process is pinned to, one bit per CPU below 64
     pub affinity_mask: Option<u64>,
+    /// Allowed CPUs of each thread before pinning, by thread id
+    pub original_affinity: Option<HashMap<u32, Vec<usize>>>,
+    /// Reaper-managed cgroup holding the process, with `cpu.max` set
+    pub cgroup: Option<String>,
+    /// cgroup the process was moved out of, relative to the hierarchy root
+    pub original_cgroup: Option<String>,
     pub limit_type: LimitType,
 }
 
@@ -27,6 +55,8 @@ pub enum LimitType {
     Nice,           // Only nice value changed
     Affinity,       // CPU affinity set
     CpuLimit,       // Using cpulimit tool
+    Cgroup,         // cgroup v2 cpu.max quota
+    DutyCycle,      // Built-in SIGSTOP/SIGCONT throttler
     Combined,       // Multiple methods
 }
 
@@ -42,82 +72,192 @@ impl ProcessCpuLimiter {
     pub fn new() -> Self {
         Self {
             limits: HashMap::new(),
+            io_limits: HashMap::new(),
+            #[cfg(target_os = "linux")]
+            throttlers: HashMap::new(),
+            #[cfg(not(target_os = "linux"))]
             cpulimit_available: None,
         }
     }
 
     /// Limit CPU usage of a process
+    ///
+    /// `max_percent` is a share of the whole machine, so 50% on an 8-core
+    /// system allows four cores' worth of CPU time.
     pub fn limit_process(&mut self, pid: u32, max_percent: f32) -> Result<(), LimitError> {
         // Validate limit
         if max_percent < 1.0 || max_percent > 100.0 {
             return Err(LimitError::InvalidLimit);
         }
 
+        // Undo an earlier limit's cgroup, throttler and pinning so the new
+        // ones start from the real originals. The nice value stays: going
+        // back down needs privilege, so the new value is applied on top
+        let previous_nice = match self.limits.remove(&pid) {
+            Some(previous) => {
+                self.release_cpu_limit(&previous)?;
+                previous.original_nice
+            }
+            None => None,
+        };
+
         // Get current nice value
-        let original_nice = self.get_nice_value(pid)?;
+        let current_nice = self.get_nice_value(pid)?;
+        let original_nice = previous_nice.unwrap_or(current_nice);
         
         // Calculate appropriate nice value based on limit
-        let nice_value = self.calculate_nice_from_limit(max_percent);
-        
-        // Try multiple methods in order of preference
-        let mut limit_type = LimitType::Nice;
+        let mut nice_value = self.calculate_nice_from_limit(max_percent);
         
-        // 1. Try cpulimit if available (most precise)
-        if self.check_cpulimit_available() {
-            if self.apply_cpulimit(pid, max_percent).is_ok() {
-                limit_type = LimitType::CpuLimit;
-            }
+        // 1. Always apply nice value (works on all systems). Without
+        //    privilege an earlier, stricter limit's value is kept
+        match self.set_nice_value(pid, nice_value) {
+            Err(LimitError::PermissionDenied) if nice_value < current_nice => nice_value = current_nice,
+            result => result?,
         }
-        
-        // 2. Always apply nice value (works on all systems)
-        self.set_nice_value(pid, nice_value)?;
-        
-        // 3. Try CPU affinity on multi-core systems
-        if let Ok(cores) = self.get_cpu_count() {
-            if cores > 1 {
-                let allowed_cores = self.calculate_allowed_cores(max_percent, cores);
-                if let Ok(_mask) = self.set_cpu_affinity(pid, allowed_cores) {
-                    if limit_type == LimitType::CpuLimit {
-                        limit_type = LimitType::Combined;
-                    } else {
-                        limit_type = LimitType::Affinity;
-                    }
-                }
-            }
-        }
-        
-        // Store limit info
-        let limit = CpuLimit {
+
+        let mut limit = CpuLimit {
             pid,
             max_cpu_percent: max_percent,
             nice_value,
             original_nice: Some(original_nice),
             affinity_mask: None,
-            limit_type,
+            original_affinity: None,
+            cgroup: None,
+            original_cgroup: None,
+            limit_type: LimitType::Nice,
         };
+        let mut methods = Vec::new();
+        let cores = self.get_cpu_count().unwrap_or(1);
+
+        // 2. A cgroup quota is a hard cap enforced by the scheduler
+        #[cfg(target_os = "linux")]
+        if let Ok((original, cgroup)) = self.apply_cgroup_quota(pid, max_percent, cores) {
+            limit.original_cgroup = Some(original);
+            limit.cgroup = Some(cgroup);
+            methods.push(LimitType::Cgroup);
+        }
+
+        // 3. Otherwise hold the process and its children to the budget
+        //    with SIGSTOP/SIGCONT
+        #[cfg(target_os = "linux")]
+        {
+            let throttler = methods.is_empty()
+                .then(|| DutyCycleThrottler::start(ThrottleTarget::Tree(pid), max_percent, DutyCycleConfig::default()).ok())
+                .flatten();
+            if let Some(throttler) = throttler {
+                self.throttlers.insert(pid, throttler);
+                methods.push(LimitType::DutyCycle);
+            }
+        }
+
+        // 3. Otherwise try cpulimit if available
+        #[cfg(not(target_os = "linux"))]
+        if methods.is_empty() && self.check_cpulimit_available() && self.apply_cpulimit(pid, max_percent).is_ok() {
+            methods.push(LimitType::CpuLimit);
+        }
         
+        // 4. Try CPU affinity on multi-core systems
+        if cores > 1 {
+            let allowed_cores = self.calculate_allowed_cores(max_percent, cores);
+            if let Ok((mask, original)) = self.set_cpu_affinity(pid, allowed_cores) {
+                limit.affinity_mask = Some(mask);
+                limit.original_affinity = Some(original);
+                methods.push(LimitType::Affinity);
+            }
+        }
+
+        limit.limit_type = match methods.len() {
+            0 => LimitType::Nice,
+            1 => methods.remove(0),
+            _ => LimitType::Combined,
+        };
         self.limits.insert(pid, limit);
         Ok(())
     }
 
-    /// Remove CPU limit from a process
+    /// Remove the CPU and I/O limits of a process
+    ///
+    /// Puts back the original cgroup, per-thread affinity, nice value and
+    /// I/O priority. Every step is attempted even if an earlier one fails;
+    /// the first error is returned.
     pub fn remove_limit(&mut self, pid: u32) -> Result<(), LimitError> {
-        if let Some(limit) = self.limits.remove(&pid) {
-            // Restore original nice value
-            if let Some(original) = limit.original_nice {
-                self.set_nice_value(pid, original)?;
-            }
-            
-            // Kill cpulimit if it was used
-            if limit.limit_type == LimitType::CpuLimit || limit.limit_type == LimitType::Combined {
-                self.kill_cpulimit(pid);
-            }
-            
-            // Note: CPU affinity is not restored as we don't track original
-            Ok(())
-        } else {
-            Err(LimitError::ProcessNotFound)
+        if !self.has_limit(pid) {
+            return Err(LimitError::ProcessNotFound);
         }
+        let cpu = match self.limits.remove(&pid) {
+            Some(limit) => self.restore_cpu_limit(limit),
+            None => Ok(()),
+        };
+        let io = match self.io_limits.remove(&pid) {
+            Some(limit) => io_limiter::restore_io_limit(&limit),
+            None => Ok(()),
+        };
+        cpu.and(io)
+    }
+
+    fn restore_cpu_limit(&mut self, limit: CpuLimit) -> Result<(), LimitError> {
+        let mut result = self.release_cpu_limit(&limit);
+
+        // Restore original nice value
+        if let Some(original) = limit.original_nice {
+            result = result.and(self.set_nice_value(limit.pid, original));
+        }
+        result
+    }
+
+    /// Undo everything of a CPU limit but its nice value
+    fn release_cpu_limit(&mut self, limit: &CpuLimit) -> Result<(), LimitError> {
+        let pid = limit.pid;
+        let mut result = Ok(());
+
+        // Stopping the throttler always leaves the process running
+        #[cfg(target_os = "linux")]
+        if let Some(mut throttler) = self.throttlers.remove(&pid) {
+            throttler.stop();
+        }
+
+        #[cfg(target_os = "linux")]
+        if let Some(cgroup) = &limit.cgroup {
+            result = result.and(self.restore_cgroup(pid, cgroup));
+        }
+
+        if let Some(original) = &limit.original_affinity {
+            result = result.and(self.restore_cpu_affinity(pid, original));
+        }
+        
+        // Kill cpulimit if it was used
+        #[cfg(not(target_os = "linux"))]
+        if limit.limit_type == LimitType::CpuLimit || limit.limit_type == LimitType::Combined {
+            self.kill_cpulimit(pid);
+        }
+        
+        result
+    }
+
+    /// Set the I/O priority and bandwidth caps of a process, replacing any
+    /// earlier I/O limit. Its CPU limit, if any, is left alone
+    pub fn limit_io(&mut self, pid: u32, settings: IoLimitSettings) -> Result<(), LimitError> {
+        settings.validate()?;
+        if let Some(limit) = self.io_limits.remove(&pid) {
+            io_limiter::restore_io_limit(&limit)?;
+        }
+
+        let limit = io_limiter::apply_io_limit(pid, settings)?;
+        self.io_limits.insert(pid, limit);
+        Ok(())
+    }
+
+    /// Lift only the I/O limit of a process
+    pub fn remove_io_limit(&mut self, pid: u32) -> Result<(), LimitError> {
+        let limit = self.io_limits.remove(&pid).ok_or(LimitError::ProcessNotFound)?;
+        io_limiter::restore_io_limit(&limit)
+    }
+
+    /// Storage I/O of a limited process since its I/O limit was applied,
+    /// from `/proc/<pid>/io`
+    pub fn io_usage(&self, pid: u32) -> Result<IoLimitUsage, LimitError> {
+        let limit = self.io_limits.get(&pid).ok_or(LimitError::ProcessNotFound)?;
+        Ok(limit.usage(io_limiter::read_io_counters(pid)?))
     }
 
     /// Set nice value for a process
@@ -132,9 +272,9 @@ impl ProcessCpuLimiter {
             if result == 0 {
                 Ok(())
             } else {
-                let errno = *libc::__error();
+                let errno = errno();
                 match errno {
-                    libc::EPERM => Err(LimitError::PermissionDenied),
+                    libc::EPERM | libc::EACCES => Err(LimitError::PermissionDenied),
                     libc::ESRCH => Err(LimitError::ProcessNotFound),
                     _ => Err(LimitError::SystemError(format!("errno: {}", errno))),
                 }
@@ -143,17 +283,17 @@ impl ProcessCpuLimiter {
     }
 
     /// Get current nice value of a process
-    fn get_nice_value(&self, pid: u32) -> Result<i32, LimitError> {
+    pub(crate) fn get_nice_value(&self, pid: u32) -> Result<i32, LimitError> {
         unsafe {
             // Reset errno before call
-            *libc::__error() = 0;
+            reset_errno();
             
             let nice = libc::getpriority(
                 libc::PRIO_PROCESS,
                 pid as libc::id_t
             );
             
-            let errno = *libc::__error();
+            let errno = errno();
             if errno != 0 {
                 match errno {
                     libc::EPERM => Err(LimitError::PermissionDenied),
@@ -183,15 +323,107 @@ impl ProcessCpuLimiter {
         allowed.max(1).min(total_cores)
     }
 
+    /// Pin every thread of `pid` to the first `allowed_cores` of the CPUs it
+    /// may currently use. Returns the new mask and each thread's old CPUs
+    #[cfg(target_os = "linux")]
+    fn set_cpu_affinity(&self, pid: u32, allowed_cores: usize) -> Result<(u64, HashMap<u32, Vec<usize>>), LimitError> {
+        let mut original = HashMap::new();
+        for tid in thread_ids(pid)? {
+            match get_affinity(tid) {
+                Ok(cpus) => { original.insert(tid, cpus); }
+                // Threads may exit while we walk the list
+                Err(LimitError::ProcessNotFound) if tid != pid => {}
+                Err(e) => return Err(e),
+            }
+        }
+
+        let base = original.get(&pid).cloned().unwrap_or_default();
+        let allowed: Vec<usize> = base.iter().copied().take(allowed_cores.max(1)).collect();
+        if allowed.is_empty() {
+            return Err(LimitError::ProcessNotFound);
+        }
+
+        for &tid in original.keys() {
+            match set_affinity(tid, &allowed) {
+                Ok(()) | Err(LimitError::ProcessNotFound) => {}
+                Err(e) => {
+                    let _ = self.restore_cpu_affinity(pid, &original);
+                    return Err(e);
+                }
+            }
+        }
+
+        Ok((affinity_mask(&allowed), original))
+    }
+
     /// Set CPU affinity for a process (macOS specific implementation)
-    fn set_cpu_affinity(&self, _pid: u32, _allowed_cores: usize) -> Result<u64, LimitError> {
+    #[cfg(not(target_os = "linux"))]
+    fn set_cpu_affinity(&self, _pid: u32, _allowed_cores: usize) -> Result<(u64, HashMap<u32, Vec<usize>>), LimitError> {
         // Note: macOS doesn't have standard CPU affinity APIs like Linux
         // This would require using thread_policy_set with THREAD_AFFINITY_POLICY
         // For now, return error indicating not supported
         Err(LimitError::SystemError("CPU affinity not fully supported on macOS".to_string()))
     }
 
+    /// Give each thread back the CPUs it had. Threads started while pinned
+    /// get the main thread's original CPUs
+    #[cfg(target_os = "linux")]
+    fn restore_cpu_affinity(&self, pid: u32, original: &HashMap<u32, Vec<usize>>) -> Result<(), LimitError> {
+        let main = original.get(&pid).ok_or(LimitError::ProcessNotFound)?;
+        let mut result = Ok(());
+        for tid in thread_ids(pid)? {
+            match set_affinity(tid, original.get(&tid).unwrap_or(main)) {
+                Ok(()) => {}
+                Err(LimitError::ProcessNotFound) if tid != pid => {}
+                Err(e) => result = result.and(Err(e)),
+            }
+        }
+        result
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    fn restore_cpu_affinity(&self, _pid: u32, _original: &HashMap<u32, Vec<usize>>) -> Result<(), LimitError> {
+        Ok(())
+    }
+
+    /// Move `pid` into its Reaper cgroup with a `cpu.max` quota.
+    /// Returns the cgroup it came from and the one it is in now
+    #[cfg(target_os = "linux")]
+    fn apply_cgroup_quota(&self, pid: u32, max_percent: f32, cores: usize) -> Result<(String, String), LimitError> {
+        let fs = CgroupFs::detect()
+            .filter(|fs| fs.has_controller("cpu"))
+            .ok_or_else(|| LimitError::SystemError("cgroup v2 cpu controller not available".to_string()))?;
+
+        let attachment = fs.attach(pid, "cpu")?;
+        if let Err(e) = cgroup::write_file(&attachment.dir.join("cpu.max"), &cpu_max_value(max_percent, cores)) {
+            let _ = fs.detach(pid, "cpu");
+            return Err(e.into());
+        }
+
+        Ok((attachment.original, attachment.dir.to_string_lossy().into_owned()))
+    }
+
+    /// Lift the quota and leave Reaper's cgroup; the process goes back to
+    /// where it came from unless another limiter still holds it there
+    #[cfg(target_os = "linux")]
+    fn restore_cgroup(&self, pid: u32, cgroup: &str) -> Result<(), LimitError> {
+        let fs = CgroupFs::detect()
+            .ok_or_else(|| LimitError::SystemError("cgroup v2 hierarchy disappeared".to_string()))?;
+        let reset = cgroup::write_file(&std::path::Path::new(cgroup).join("cpu.max"), "max");
+        // Detach regardless: once the process has gone the cgroup must still go
+        reset.and(fs.detach(pid, "cpu")).map_err(LimitError::from)
+    }
+
     /// Get number of CPU cores
+    #[cfg(not(target_os = "macos"))]
+    fn get_cpu_count(&self) -> Result<usize, LimitError> {
+        std::thread::available_parallelism()
+            .map(|n| n.get())
+            .map_err(|e| LimitError::SystemError(format!("Failed to get CPU count: {}", e)))
+    }
+
+    /// Get number of CPU cores
+    #[cfg(target_os = "macos")]
     fn get_cpu_count(&self) -> Result<usize, LimitError> {
         unsafe {
             let mut count: c_int = 0;
@@ -216,6 +448,7 @@ impl ProcessCpuLimiter {
     }
 
     /// Check if cpulimit tool is available
+    #[cfg(not(target_os = "linux"))]
     fn check_cpulimit_available(&mut self) -> bool {
         if let Some(available) = self.cpulimit_available {
             return available;
@@ -232,6 +465,7 @@ impl ProcessCpuLimiter {
     }
 
     /// Apply CPU limit using cpulimit tool
+    #[cfg(not(target_os = "linux"))]
     fn apply_cpulimit(&self, pid: u32, limit: f32) -> Result<(), LimitError> {
         let output = Command::new("cpulimit")
             .args(&[
@@ -252,6 +486,7 @@ impl ProcessCpuLimiter {
     }
 
     /// Kill cpulimit process for a PID
+    #[cfg(not(target_os = "linux"))]
     fn kill_cpulimit(&self, target_pid: u32) {
         // Find and kill cpulimit process targeting this PID
         let _ = Command::new("pkill")
@@ -264,9 +499,20 @@ impl ProcessCpuLimiter {
         self.limits.values().collect()
     }
 
-    /// Check if a process has a limit
+    /// Get all active I/O limits
+    pub fn get_io_limits(&self) -> Vec<&IoLimit> {
+        self.io_limits.values().collect()
+    }
+
+    /// What the duty-cycle throttler of a limited process last measured
+    #[cfg(target_os = "linux")]
+    pub fn throttle_stats(&self, pid: u32) -> Option<DutyCycleStats> {
+        self.throttlers.get(&pid).map(|throttler| throttler.stats())
+    }
+
+    /// Check if a process has a CPU or I/O limit
     pub fn has_limit(&self, pid: u32) -> bool {
-        self.limits.contains_key(&pid)
+        self.limits.contains_key(&pid) || self.io_limits.contains_key(&pid)
     }
 
     /// Quick preset limits
@@ -281,7 +527,8 @@ impl ProcessCpuLimiter {
     }
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
 pub enum LimitPreset {
     High,    // 75% CPU
     Medium,  // 50% CPU
@@ -289,6 +536,101 @@ pub enum LimitPreset {
     Minimal, // 10% CPU
 }
 
+/// `cpu.max` for a share of the whole machine: quota and period in µs
+#[cfg(target_os = "linux")]
+fn cpu_max_value(max_percent: f32, cores: usize) -> String {
+    const PERIOD_US: u64 = 100_000;
+    let quota = (max_percent as f64 / 100.0 * cores as f64 * PERIOD_US as f64).round() as u64;
+    // The kernel rejects quotas under 1ms
+    format!("{} {}", quota.max(1_000), PERIOD_US)
+}
+
+#[cfg(target_os = "linux")]
+fn thread_ids(pid: u32) -> Result<Vec<u32>, LimitError> {
+    procfs::list_tids(pid).map_err(|_| LimitError::ProcessNotFound)
+}
+
+/// CPUs a thread may run on
+#[cfg(target_os = "linux")]
+fn get_affinity(tid: u32) -> Result<Vec<usize>, LimitError> {
+    let mut set: libc::cpu_set_t = unsafe { std::mem::zeroed() };
+    let result = unsafe {
+        libc::sched_getaffinity(tid as libc::pid_t, std::mem::size_of::<libc::cpu_set_t>(), &mut set)
+    };
+    if result != 0 {
+        return Err(errno_to_limit_error(errno()));
+    }
+    Ok((0..libc::CPU_SETSIZE as usize).filter(|&cpu| unsafe { libc::CPU_ISSET(cpu, &set) }).collect())
+}
+
+#[cfg(target_os = "linux")]
+fn set_affinity(tid: u32, cpus: &[usize]) -> Result<(), LimitError> {
+    let mut set: libc::cpu_set_t = unsafe { std::mem::zeroed() };
+    for &cpu in cpus {
+        unsafe { libc::CPU_SET(cpu, &mut set) };
+    }
+    let result = unsafe {
+        libc::sched_setaffinity(tid as libc::pid_t, std::mem::size_of::<libc::cpu_set_t>(), &set)
+    };
+    if result == 0 {
+        Ok(())
+    } else {
+        Err(errno_to_limit_error(errno()))
+    }
+}
+
+/// One bit per CPU; CPUs from 64 up don't fit and are left out
+#[cfg(target_os = "linux")]
+fn affinity_mask(cpus: &[usize]) -> u64 {
+    cpus.iter().filter(|&&cpu| cpu < 64).fold(0, |mask, &cpu| mask | 1 << cpu)
+}
+
+#[cfg(target_os = "linux")]
+fn errno_to_limit_error(errno: c_int) -> LimitError {
+    match errno {
+        libc::EPERM | libc::EACCES => LimitError::PermissionDenied,
+        libc::ESRCH => LimitError::ProcessNotFound,
+        _ => LimitError::SystemError(format!("errno: {}", errno)),
+    }
+}
+
+#[cfg(target_os = "linux")]
+impl From<PlatformError> for LimitError {
+    fn from(err: PlatformError) -> Self {
+        match err {
+            PlatformError::ProcessNotFound(_) => LimitError::ProcessNotFound,
+            PlatformError::PermissionDenied(_) => LimitError::PermissionDenied,
+            other => LimitError::SystemError(other.to_string()),
+        }
+    }
+}
+
+/// Current errno value for the calling thread
+fn errno() -> c_int {
+    std::io::Error::last_os_error().raw_os_error().unwrap_or(0)
+}
+
+/// Clear errno before calls where -1 is a valid return value
+unsafe fn reset_errno() {
+    #[cfg(target_os = "macos")]
+    {
+        *libc::__error() = 0;
+    }
+    #[cfg(target_os = "linux")]
+    {
+        *libc::__errno_location() = 0;
+    }
+}
+
+/// The limiter shared by the FFI exports and remediation policies
+static LIMITER: Lazy<Mutex<ProcessCpuLimiter>> = Lazy::new(|| {
+    Mutex::new(ProcessCpuLimiter::new())
+});
+
+pub fn global_limiter() -> &'static Mutex<ProcessCpuLimiter> {
+    &LIMITER
+}
+
 /// C FFI exports for Swift integration
 #[repr(C)]
 pub struct CCpuLimit {
@@ -306,51 +648,51 @@ pub struct CCpuLimitList {
 
 #[no_mangle]
 pub extern "C" fn limit_process_cpu(pid: u32, max_percent: f32) -> i32 {
-    use once_cell::sync::Lazy;
-    use std::sync::Mutex;
-    
-    static LIMITER: Lazy<Mutex<ProcessCpuLimiter>> = Lazy::new(|| {
-        Mutex::new(ProcessCpuLimiter::new())
-    });
-    
-    match LIMITER.lock() {
-        Ok(mut limiter) => {
-            match limiter.limit_process(pid, max_percent) {
-                Ok(_) => 0,
-                Err(LimitError::PermissionDenied) => -1,
-                Err(LimitError::ProcessNotFound) => -2,
-                Err(LimitError::InvalidLimit) => -3,
-                Err(_) => -4,
-            }
+    let audit = action_audit::begin("limit_cpu", pid);
+    let result = match LIMITER.lock() {
+        Ok(mut limiter) => limiter.limit_process(pid, max_percent),
+        Err(_) => {
+            audit.finish("UnknownError", "Failed to acquire limiter lock");
+            return -5;
         }
-        Err(_) => -5,
+    };
+    audit.finish_result(&result, format!("Process {} limited to {}% CPU", pid, max_percent));
+
+    match result {
+        Ok(_) => 0,
+        Err(LimitError::PermissionDenied) => -1,
+        Err(LimitError::ProcessNotFound) => -2,
+        Err(LimitError::InvalidLimit) => -3,
+        Err(_) => -4,
     }
 }
 
 #[no_mangle]
 pub extern "C" fn remove_process_limit(pid: u32) -> i32 {
-    use once_cell::sync::Lazy;
-    use std::sync::Mutex;
-    
-    static LIMITER: Lazy<Mutex<ProcessCpuLimiter>> = Lazy::new(|| {
-        Mutex::new(ProcessCpuLimiter::new())
-    });
-    
-    match LIMITER.lock() {
-        Ok(mut limiter) => {
-            match limiter.remove_limit(pid) {
-                Ok(_) => 0,
-                Err(_) => -1,
-            }
+    let audit = action_audit::begin("remove_limit", pid);
+    let result = match LIMITER.lock() {
+        Ok(mut limiter) => limiter.remove_limit(pid),
+        Err(_) => {
+            audit.finish("UnknownError", "Failed to acquire limiter lock");
+            return -2;
         }
-        Err(_) => -2,
+    };
+    audit.finish_result(&result, format!("Limit removed from process {}", pid));
+
+    match result {
+        Ok(_) => 0,
+        Err(_) => -1,
     }
 }
 
 #[no_mangle]
 pub extern "C" fn set_process_nice(pid: u32, nice_value: i32) -> i32 {
+    let audit = action_audit::begin("set_nice", pid);
     let limiter = ProcessCpuLimiter::new();
-    match limiter.set_nice_value(pid, nice_value) {
+    let result = limiter.set_nice_value(pid, nice_value);
+    audit.finish_result(&result, format!("Process {} nice value set to {}", pid, nice_value));
+
+    match result {
         Ok(_) => 0,
         Err(LimitError::PermissionDenied) => -1,
         Err(LimitError::ProcessNotFound) => -2,
@@ -360,13 +702,6 @@ pub extern "C" fn set_process_nice(pid: u32, nice_value: i32) -> i32 {
 
 #[no_mangle]
 pub extern "C" fn get_all_cpu_limits() -> *mut CCpuLimitList {
-    use once_cell::sync::Lazy;
-    use std::sync::Mutex;
-    
-    static LIMITER: Lazy<Mutex<ProcessCpuLimiter>> = Lazy::new(|| {
-        Mutex::new(ProcessCpuLimiter::new())
-    });
-    
     match LIMITER.lock() {
         Ok(limiter) => {
             let limits = limiter.get_limits();
@@ -387,6 +722,8 @@ pub extern "C" fn get_all_cpu_limits() -> *mut CCpuLimitList {
                     LimitType::Affinity => 1,
                     LimitType::CpuLimit => 2,
                     LimitType::Combined => 3,
+                    LimitType::Cgroup => 4,
+                    LimitType::DutyCycle => 5,
                 };
                 
                 c_limits.push(CCpuLimit {
@@ -426,13 +763,6 @@ pub extern "C" fn free_cpu_limits(list: *mut CCpuLimitList) {
 
 #[no_mangle]
 pub extern "C" fn has_process_limit(pid: u32) -> u8 {
-    use once_cell::sync::Lazy;
-    use std::sync::Mutex;
-    
-    static LIMITER: Lazy<Mutex<ProcessCpuLimiter>> = Lazy::new(|| {
-        Mutex::new(ProcessCpuLimiter::new())
-    });
-    
     match LIMITER.lock() {
         Ok(limiter) => {
             if limiter.has_limit(pid) { 1 } else { 0 }
@@ -441,6 +771,192 @@ pub extern "C" fn has_process_limit(pid: u32) -> u8 {
     }
 }
 
+#[repr(C)]
+pub struct CIoLimitUsage {
+    pub pid: u32,
+    pub read_bytes: u64,
+    pub write_bytes: u64,
+    pub read_bytes_per_sec: f64,
+    pub write_bytes_per_sec: f64,
+    pub elapsed_secs: f64,
+}
+
+/// One device's caps of an I/O limit, or just its priority with a device of
+/// 0:0. `priority_class` and caps of 0 mean unset, as for `limit_process_io`
+#[repr(C)]
+pub struct CIoLimit {
+    pub pid: u32,
+    pub priority_class: u8,
+    pub priority_level: u8,
+    pub major: u32,
+    pub minor: u32,
+    pub read_bps: u64,
+    pub write_bps: u64,
+    pub read_iops: u64,
+    pub write_iops: u64,
+}
+
+#[repr(C)]
+pub struct CIoLimitList {
+    pub limits: *mut CIoLimit,
+    pub count: usize,
+}
+
+fn io_limit_code(result: &Result<(), LimitError>) -> i32 {
+    match result {
+        Ok(_) => 0,
+        Err(LimitError::PermissionDenied) => -1,
+        Err(LimitError::ProcessNotFound) => -2,
+        Err(LimitError::InvalidLimit) => -3,
+        Err(_) => -4,
+    }
+}
+
+/// `priority_class` follows the kernel: 1 realtime, 2 best-effort, 3 idle,
+/// 0 to leave the priority alone. A device of 0:0 sets no bandwidth caps,
+/// and caps of 0 are left off
+#[no_mangle]
+pub extern "C" fn limit_process_io(
+    pid: u32,
+    priority_class: u8,
+    priority_level: u8,
+    major: u32,
+    minor: u32,
+    read_bps: u64,
+    write_bps: u64,
+    read_iops: u64,
+    write_iops: u64,
+) -> i32 {
+    let cap = |value: u64| (value > 0).then_some(value);
+    let priority = match priority_class {
+        0 => None,
+        class => match IoPriorityClass::from_raw(class as u16) {
+            Some(class) => Some(IoPriority { class, level: priority_level }),
+            None => return -3,
+        },
+    };
+    let mut bandwidth = Vec::new();
+    if (major, minor) != (0, 0) {
+        bandwidth.push(IoBandwidthLimit {
+            major,
+            minor,
+            read_bps: cap(read_bps),
+            write_bps: cap(write_bps),
+            read_iops: cap(read_iops),
+            write_iops: cap(write_iops),
+        });
+    }
+
+    let audit = action_audit::begin("limit_io", pid);
+    let result = match LIMITER.lock() {
+        Ok(mut limiter) => limiter.limit_io(pid, IoLimitSettings { priority, bandwidth }),
+        Err(_) => {
+            audit.finish("UnknownError", "Failed to acquire limiter lock");
+            return -5;
+        }
+    };
+    audit.finish_result(&result, format!("Process {} I/O limited", pid));
+    io_limit_code(&result)
+}
+
+#[no_mangle]
+pub extern "C" fn remove_process_io_limit(pid: u32) -> i32 {
+    let audit = action_audit::begin("remove_io_limit", pid);
+    let result = match LIMITER.lock() {
+        Ok(mut limiter) => limiter.remove_io_limit(pid),
+        Err(_) => {
+            audit.finish("UnknownError", "Failed to acquire limiter lock");
+            return -5;
+        }
+    };
+    audit.finish_result(&result, format!("I/O limit removed from process {}", pid));
+    io_limit_code(&result)
+}
+
+/// Every active I/O limit, one entry per capped device; a process with
+/// several devices appears once for each
+#[no_mangle]
+pub extern "C" fn get_all_io_limits() -> *mut CIoLimitList {
+    let limiter = match LIMITER.lock() {
+        Ok(limiter) => limiter,
+        Err(_) => return std::ptr::null_mut(),
+    };
+
+    let mut c_limits = Vec::new();
+    for limit in limiter.get_io_limits() {
+        let (priority_class, priority_level) = match limit.settings.priority {
+            Some(priority) => (priority.class.raw() as u8, priority.level),
+            None => (0, 0),
+        };
+        let entry = |bandwidth: Option<&IoBandwidthLimit>| CIoLimit {
+            pid: limit.pid,
+            priority_class,
+            priority_level,
+            major: bandwidth.map_or(0, |b| b.major),
+            minor: bandwidth.map_or(0, |b| b.minor),
+            read_bps: bandwidth.and_then(|b| b.read_bps).unwrap_or(0),
+            write_bps: bandwidth.and_then(|b| b.write_bps).unwrap_or(0),
+            read_iops: bandwidth.and_then(|b| b.read_iops).unwrap_or(0),
+            write_iops: bandwidth.and_then(|b| b.write_iops).unwrap_or(0),
+        };
+
+        if limit.settings.bandwidth.is_empty() {
+            c_limits.push(entry(None));
+        }
+        c_limits.extend(limit.settings.bandwidth.iter().map(|bandwidth| entry(Some(bandwidth))));
+    }
+
+    let count = c_limits.len();
+    if count == 0 {
+        return Box::into_raw(Box::new(CIoLimitList { limits: std::ptr::null_mut(), count: 0 }));
+    }
+    let mut c_limits = c_limits.into_boxed_slice();
+    let limits = c_limits.as_mut_ptr();
+    std::mem::forget(c_limits);
+    Box::into_raw(Box::new(CIoLimitList { limits, count }))
+}
+
+#[no_mangle]
+pub extern "C" fn free_io_limits(list: *mut CIoLimitList) {
+    if !list.is_null() {
+        unsafe {
+            let list = Box::from_raw(list);
+            if !list.limits.is_null() && list.count > 0 {
+                let _ = Vec::from_raw_parts(list.limits, list.count, list.count);
+            }
+        }
+    }
+}
+
+/// Null when `pid` has no I/O limit
+#[no_mangle]
+pub extern "C" fn get_process_io_usage(pid: u32) -> *mut CIoLimitUsage {
+    let usage = match LIMITER.lock() {
+        Ok(limiter) => limiter.io_usage(pid),
+        Err(_) => return std::ptr::null_mut(),
+    };
+    match usage {
+        Ok(usage) => Box::into_raw(Box::new(CIoLimitUsage {
+            pid,
+            read_bytes: usage.since_limit.read_bytes,
+            write_bytes: usage.since_limit.write_bytes,
+            read_bytes_per_sec: usage.read_bytes_per_sec,
+            write_bytes_per_sec: usage.write_bytes_per_sec,
+            elapsed_secs: usage.elapsed_secs,
+        })),
+        Err(_) => std::ptr::null_mut(),
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn free_io_usage(usage: *mut CIoLimitUsage) {
+    if !usage.is_null() {
+        unsafe {
+            let _ = Box::from_raw(usage);
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -466,6 +982,142 @@ mod tests {
         assert_eq!(limiter.calculate_allowed_cores(10.0, 8), 1);
     }
 
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_cpu_max_and_masks() {
+        assert_eq!(cpu_max_value(50.0, 4), "200000 100000");
+        assert_eq!(cpu_max_value(25.0, 1), "25000 100000");
+        assert_eq!(cpu_max_value(1.0, 0), "1000 100000");
+        assert_eq!(affinity_mask(&[0, 2, 63, 64]), 0b101 | 1 << 63);
+    }
+
+    /// Limiting and unlimiting a child leaves it exactly as it was, whichever
+    /// backends this machine allows
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_remove_limit_restores_original_state() {
+        let mut child = std::process::Command::new("sleep").arg("5").spawn().unwrap();
+        let pid = child.id();
+        let state = |pid: u32| {
+            let limiter = ProcessCpuLimiter::new();
+            (
+                limiter.get_nice_value(pid).unwrap(),
+                get_affinity(pid).unwrap(),
+                procfs::read_pid_file(pid, "cgroup").unwrap(),
+            )
+        };
+        let before = state(pid);
+
+        let mut limiter = ProcessCpuLimiter::new();
+        limiter.limit_process(pid, 10.0).unwrap();
+        let limit = limiter.get_limits()[0].clone();
+        assert_eq!(limiter.get_nice_value(pid).unwrap(), 15);
+        // A hard cap always applies: the cgroup quota, or the built-in throttler
+        assert!(limit.cgroup.is_some() || limiter.throttle_stats(pid).is_some());
+        if let Some(mask) = limit.affinity_mask {
+            assert_eq!(mask, affinity_mask(&get_affinity(pid).unwrap()));
+        }
+        if let Some(cgroup) = &limit.cgroup {
+            assert!(procfs::read_pid_file(pid, "cgroup").unwrap().contains(&format!("/{}/pid-{}", cgroup::REAPER_CGROUP, pid)));
+            assert!(std::path::Path::new(cgroup).join("cpu.max").exists());
+        }
+
+        // Limiting again must not record the limited state as the original,
+        // and without privilege keeps the stricter nice value
+        limiter.limit_process(pid, 50.0).unwrap();
+        assert!(limiter.get_nice_value(pid).unwrap() >= 5);
+        match limiter.remove_limit(pid) {
+            Ok(()) => assert_eq!(state(pid), before),
+            // Only CAP_SYS_NICE can lower the nice value again; the rest is
+            // restored regardless
+            Err(LimitError::PermissionDenied) => {
+                let after = state(pid);
+                assert_eq!((after.1, after.2), (before.1, before.2));
+            }
+            Err(e) => panic!("{:?}", e),
+        }
+        assert!(!limiter.has_limit(pid));
+        if let Some(cgroup) = &limit.cgroup {
+            assert!(!std::path::Path::new(cgroup).exists());
+        }
+
+        child.kill().unwrap();
+        child.wait().unwrap();
+        assert!(matches!(limiter.remove_limit(pid), Err(LimitError::ProcessNotFound)));
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_affinity_round_trip() {
+        let mut child = std::process::Command::new("sleep").arg("5").spawn().unwrap();
+        let pid = child.id();
+        let limiter = ProcessCpuLimiter::new();
+        let before = get_affinity(pid).unwrap();
+
+        let (mask, original) = limiter.set_cpu_affinity(pid, 1).unwrap();
+        assert_eq!(mask, affinity_mask(&before[..1]));
+        assert_eq!(get_affinity(pid).unwrap(), before[..1]);
+        assert_eq!(original[&pid], before);
+
+        limiter.restore_cpu_affinity(pid, &original).unwrap();
+        assert_eq!(get_affinity(pid).unwrap(), before);
+
+        child.kill().unwrap();
+        child.wait().unwrap();
+    }
+
+    /// CPU and I/O limits are listed side by side and removed together
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_io_limit_bookkeeping() {
+        let mut child = std::process::Command::new("sleep").arg("5").spawn().unwrap();
+        let pid = child.id();
+        let mut limiter = ProcessCpuLimiter::new();
+
+        let settings = IoLimitSettings { priority: Some(IoPriority::idle()), bandwidth: Vec::new() };
+        limiter.limit_io(pid, settings).unwrap();
+        assert!(limiter.has_limit(pid));
+        assert!(limiter.get_limits().is_empty());
+        assert_eq!(limiter.get_io_limits()[0].settings.priority, Some(IoPriority::idle()));
+        assert_eq!(limiter.io_usage(pid).unwrap().since_limit.write_bytes, 0);
+
+        // A CPU limit on top keeps the I/O limit
+        limiter.limit_process(pid, 50.0).unwrap();
+        limiter.limit_process(pid, 25.0).unwrap();
+        assert_eq!(limiter.get_io_limits().len(), 1);
+
+        // Unprivileged, only the nice value stays raised
+        assert!(matches!(limiter.remove_limit(pid), Ok(()) | Err(LimitError::PermissionDenied)));
+        assert!(!limiter.has_limit(pid));
+        assert!(limiter.get_io_limits().is_empty());
+        assert!(matches!(limiter.io_usage(pid), Err(LimitError::ProcessNotFound)));
+
+        child.kill().unwrap();
+        child.wait().unwrap();
+    }
+
+    /// I/O-only limits are visible over FFI, one entry per device
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_io_limits_over_ffi() {
+        let mut child = std::process::Command::new("sleep").arg("5").spawn().unwrap();
+        let pid = child.id();
+        let settings = IoLimitSettings { priority: Some(IoPriority::idle()), bandwidth: Vec::new() };
+        global_limiter().lock().unwrap().limit_io(pid, settings).unwrap();
+        assert_eq!(has_process_limit(pid), 1);
+
+        let list = get_all_io_limits();
+        let limits = unsafe { std::slice::from_raw_parts((*list).limits, (*list).count) };
+        let limit = limits.iter().find(|limit| limit.pid == pid).unwrap();
+        assert_eq!((limit.priority_class, limit.priority_level), (3, 0));
+        assert_eq!((limit.major, limit.minor, limit.read_bps, limit.write_iops), (0, 0, 0, 0));
+        free_io_limits(list);
+
+        global_limiter().lock().unwrap().remove_io_limit(pid).unwrap();
+        child.kill().unwrap();
+        child.wait().unwrap();
+    }
+
     #[test]
     fn test_cpu_count() {
         let limiter = ProcessCpuLimiter::new();
@@ -1,5 +1,8 @@
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use reaper_core::common::{Monitor, MonitorType};
+use reaper_core::metrics::{MetricSample, SeriesKey};
+use reaper_core::sampling::{Metric, SharedSystem, SystemHandle};
 use sysinfo::{Pid, System, ProcessStatus};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -36,7 +39,7 @@ pub struct ProcessState {
 }
 
 pub struct ProcessMonitor {
-    system: System,
+    system: SystemHandle,
     process_cache: HashMap<u32, ProcessInfo>,
     last_full_refresh: std::time::Instant,
     refresh_counter: u32,
@@ -50,6 +53,15 @@ impl ProcessMonitor {
         system.refresh_memory();
         system.refresh_processes();
         
+        Self::with_handle(SystemHandle::Owned(Box::new(system)))
+    }
+    
+    /// Read processes from a system shared with other monitors
+    pub fn with_system(system: SharedSystem) -> Self {
+        Self::with_handle(SystemHandle::Shared(system))
+    }
+    
+    fn with_handle(system: SystemHandle) -> Self {
         ProcessMonitor {
             system,
             process_cache: HashMap::with_capacity(200), // Pre-allocate for typical process count
@@ -66,18 +78,18 @@ impl ProcessMonitor {
             || self.last_full_refresh.elapsed().as_secs() > 30;
         
         if needs_full_refresh {
-            self.system.refresh_processes();
+            self.system.refresh(Metric::Processes, |system| system.refresh_processes());
             self.last_full_refresh = std::time::Instant::now();
         } else {
             // Ultra-lightweight refresh - only CPU for existing processes
-            self.system.refresh_processes_specifics(
+            self.system.refresh(Metric::Processes, |system| system.refresh_processes_specifics(
                 sysinfo::ProcessRefreshKind::new()
                     .with_cpu()
                     // Skip memory updates unless necessary
-            );
+            ));
         }
         
-        self.system.refresh_cpu();
+        self.system.refresh(Metric::Cpu, |system| system.refresh_cpu());
         self.update_process_cache_optimized();
     }
     
@@ -85,7 +97,8 @@ impl ProcessMonitor {
         // Only update processes with significant changes
         let mut seen_pids = std::collections::HashSet::with_capacity(self.process_cache.len());
         
-        for (pid, process) in self.system.processes() {
+        let system = self.system.read();
+        for (pid, process) in system.processes() {
             let pid_u32 = pid.as_u32();
             seen_pids.insert(pid_u32);
             
@@ -159,7 +172,8 @@ impl ProcessMonitor {
     }
     
     pub fn analyze_process_state(&self, pid: u32) -> Option<ProcessState> {
-        let process = self.system.process(Pid::from(pid as usize))?;
+        let system = self.system.read();
+        let process = system.process(Pid::from(pid as usize))?;
         
         let status = process.status();
         let state = ProcessState {
@@ -212,4 +226,32 @@ impl ProcessMonitor {
             _ => 0
         }
     }
-}
\ No newline at end of file
+}
+
+impl Monitor for ProcessMonitor {
+    fn name(&self) -> &str {
+        "processes"
+    }
+    
+    fn monitor_type(&self) -> MonitorType {
+        MonitorType::CPU
+    }
+    
+    fn refresh(&mut self) {
+        ProcessMonitor::refresh(self);
+    }
+    
+    /// CPU usage of the busiest processes
+    fn metric_samples(&self) -> Vec<MetricSample> {
+        let mut processes: Vec<&ProcessInfo> = self.process_cache.values().collect();
+        processes.sort_by(|a, b| b.cpu_usage.total_cmp(&a.cpu_usage));
+        
+        processes.into_iter()
+            .take(10)
+            .map(|p| MetricSample::new(
+                SeriesKey::new("process.cpu_percent").with_label("pid", p.pid).with_label("process", &p.name),
+                p.cpu_usage as f64,
+            ))
+            .collect()
+    }
+}
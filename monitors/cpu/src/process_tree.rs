@@ -1,5 +1,7 @@
 use serde::{Deserialize, Serialize};
 use std::collections::{HashMap, HashSet};
+use reaper_core::common::{Monitor, MonitorType};
+use reaper_core::sampling::{Metric, SharedSystem, SystemHandle};
 use sysinfo::System;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -27,18 +29,30 @@ pub struct ProcessTree {
 }
 
 pub struct ProcessTreeBuilder {
-    system: System,
+    system: SystemHandle,
+    last_tree: Option<ProcessTree>,
 }
 
 impl ProcessTreeBuilder {
     pub fn new() -> Self {
         let mut system = System::new();
         system.refresh_all();
-        ProcessTreeBuilder { system }
+        ProcessTreeBuilder { system: SystemHandle::Owned(Box::new(system)), last_tree: None }
+    }
+    
+    /// Build trees from a system shared with other monitors
+    pub fn with_system(system: SharedSystem) -> Self {
+        ProcessTreeBuilder { system: SystemHandle::Shared(system), last_tree: None }
+    }
+    
+    /// The tree from the last `Monitor::refresh`, if any
+    pub fn last_tree(&self) -> Option<&ProcessTree> {
+        self.last_tree.as_ref()
     }
     
     pub fn build_tree(&mut self) -> ProcessTree {
-        self.system.refresh_processes();
+        self.system.refresh(Metric::Processes, |system| system.refresh_processes());
+        let system = self.system.read();
         
         // Collect all processes first
         let mut all_processes: HashMap<u32, ProcessTreeNode> = HashMap::new();
@@ -46,7 +60,7 @@ impl ProcessTreeBuilder {
         let mut root_pids: HashSet<u32> = HashSet::new();
         
         // First pass: create all nodes
-        for (pid, process) in self.system.processes() {
+        for (pid, process) in system.processes() {
             let pid_u32 = pid.as_u32();
             
             // Get command with arguments
@@ -103,7 +117,7 @@ impl ProcessTreeBuilder {
             if !root_pids.contains(pid) {
                 // Check if this process has a parent in our list
                 let mut has_parent = false;
-                for (_, process) in self.system.processes() {
+                for process in system.processes().values() {
                     if process.pid().as_u32() == *pid {
                         if let Some(parent) = process.parent() {
                             if all_processes.contains_key(&parent.as_u32()) {
@@ -193,4 +207,18 @@ impl ProcessTreeBuilder {
         }
         None
     }
-}
\ No newline at end of file
+}
+
+impl Monitor for ProcessTreeBuilder {
+    fn name(&self) -> &str {
+        "process_tree"
+    }
+    
+    fn monitor_type(&self) -> MonitorType {
+        MonitorType::CPU
+    }
+    
+    fn refresh(&mut self) {
+        self.last_tree = Some(self.build_tree());
+    }
+}
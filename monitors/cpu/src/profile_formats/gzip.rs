@@ -0,0 +1,51 @@
+//! gzip for pprof files, with a cap on how much a profile may inflate to
+
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use std::io::{Read, Write};
+
+/// Refuse to inflate beyond this, in case of a corrupt or hostile stream
+const MAX_OUTPUT: usize = 1 << 30;
+
+pub fn is_gzip(data: &[u8]) -> bool {
+    data.starts_with(&[0x1f, 0x8b])
+}
+
+pub fn decompress(data: &[u8]) -> Result<Vec<u8>, String> {
+    decompress_limited(data, MAX_OUTPUT)
+}
+
+fn decompress_limited(data: &[u8], limit: usize) -> Result<Vec<u8>, String> {
+    // Read one byte past the limit to tell "exactly at" from "beyond"
+    let mut out = Vec::new();
+    GzDecoder::new(data)
+        .take(limit as u64 + 1)
+        .read_to_end(&mut out)
+        .map_err(|e| format!("invalid gzip stream: {}", e))?;
+    if out.len() > limit {
+        return Err("decompressed profile too large".to_string());
+    }
+    Ok(out)
+}
+
+pub fn compress(data: &[u8]) -> Vec<u8> {
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    // Writing to a Vec can't fail
+    let _ = encoder.write_all(data);
+    encoder.finish().unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_output_is_capped() {
+        // 64 KiB of zeros compresses to a few hundred bytes
+        let bomb = compress(&[0; 64 * 1024]);
+        assert!(bomb.len() < 1024);
+        assert_eq!(decompress_limited(&bomb, 64 * 1024).unwrap().len(), 64 * 1024);
+        assert!(decompress_limited(&bomb, 4096).unwrap_err().contains("too large"));
+    }
+}
@@ -0,0 +1,154 @@
+//! Importers for profiles captured by other tools, and a pprof exporter
+//!
+//! `perf script` text, pprof protobufs (gzipped or not) and speedscope JSON
+//! become weighted `StackTrace`s that feed `FlameGraphBuilder` like
+//! Reaper's own samples, one thread per `samples_by_thread` group.
+
+mod gzip;
+mod perf_script;
+mod pprof;
+mod speedscope;
+
+pub(crate) use pprof::encode as encode_pprof;
+
+use crate::{FlameGraphBuilder, FlameGraphData, StackTrace};
+use std::fmt;
+use std::time::Duration;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProfileFormat {
+    PerfScript,
+    Pprof,
+    Speedscope,
+}
+
+impl ProfileFormat {
+    /// Guess the format from the first bytes of a profile
+    pub fn detect(data: &[u8]) -> Option<Self> {
+        if gzip::is_gzip(data) {
+            return Some(Self::Pprof);
+        }
+        if std::str::from_utf8(data).is_err() {
+            // Uncompressed protobuf opening with field 1 (sample_type)
+            return (data.first() == Some(&0x0a)).then_some(Self::Pprof);
+        }
+        match data.iter().find(|b| !b.is_ascii_whitespace())? {
+            b'{' => Some(Self::Speedscope),
+            _ => Some(Self::PerfScript),
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::PerfScript => "perf script",
+            Self::Pprof => "pprof",
+            Self::Speedscope => "speedscope",
+        }
+    }
+
+    pub fn parse(&self, data: &[u8]) -> Result<ImportedProfile, ImportError> {
+        let profile = match self {
+            Self::Pprof => pprof::parse(data),
+            Self::PerfScript | Self::Speedscope => {
+                let text = std::str::from_utf8(data).map_err(|_| ImportError::malformed(*self, "not UTF-8 text"))?;
+                match self {
+                    Self::PerfScript => perf_script::parse(text),
+                    _ => speedscope::parse(text),
+                }
+            }
+        }?;
+
+        if profile.samples.is_empty() {
+            return Err(ImportError::malformed(*self, "no samples"));
+        }
+        Ok(profile)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ImportError {
+    UnknownFormat,
+    Malformed { format: ProfileFormat, message: String },
+}
+
+impl ImportError {
+    fn malformed(format: ProfileFormat, message: impl Into<String>) -> Self {
+        Self::Malformed { format, message: message.into() }
+    }
+}
+
+impl fmt::Display for ImportError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnknownFormat => write!(f, "Unrecognised profile format"),
+            Self::Malformed { format, message } => write!(f, "Invalid {} profile: {}", format.name(), message),
+        }
+    }
+}
+
+impl std::error::Error for ImportError {}
+
+/// Stacks read from another tool's profile
+#[derive(Debug, Clone)]
+pub struct ImportedProfile {
+    pub name: String,
+    pub pid: u32,
+    pub duration: Duration,
+    /// Each trace with the number of samples, or the sample weight, it stands for
+    pub samples: Vec<(StackTrace, u64)>,
+}
+
+impl ImportedProfile {
+    pub fn total_weight(&self) -> u64 {
+        self.samples.iter().map(|(_, weight)| weight).sum()
+    }
+
+    pub fn into_builder(self) -> FlameGraphBuilder {
+        let mut builder = FlameGraphBuilder::new(self.name, self.pid);
+        for (trace, weight) in self.samples {
+            builder.add_weighted_stack_trace(trace, weight);
+        }
+        builder
+    }
+
+    pub fn into_flame_graph(self) -> FlameGraphData {
+        let duration = self.duration;
+        let mut data = self.into_builder().build();
+        data.total_duration = duration;
+        data
+    }
+}
+
+/// Detect the format of `data` and import it
+pub fn import_profile(data: &[u8]) -> Result<ImportedProfile, ImportError> {
+    ProfileFormat::detect(data).ok_or(ImportError::UnknownFormat)?.parse(data)
+}
+
+/// `name+0x1f` → `name`
+fn strip_offset(symbol: &str) -> &str {
+    match symbol.rfind("+0x") {
+        Some(index) if index > 0 => &symbol[..index],
+        _ => symbol,
+    }
+}
+
+/// The last path component, as frames name their module
+fn basename(path: &str) -> String {
+    path.rsplit('/').next().unwrap_or(path).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detects_formats() {
+        assert_eq!(ProfileFormat::detect(&[0x1f, 0x8b, 8]), Some(ProfileFormat::Pprof));
+        assert_eq!(ProfileFormat::detect(&[0x0a, 0x04, 0x08, 0xff]), Some(ProfileFormat::Pprof));
+        assert_eq!(ProfileFormat::detect(b"  {\"shared\": {}}"), Some(ProfileFormat::Speedscope));
+        assert_eq!(ProfileFormat::detect(b"node 1/1 [000] 1.0: cycles:\n"), Some(ProfileFormat::PerfScript));
+        assert_eq!(ProfileFormat::detect(&[0xff, 0xfe]), None);
+        assert_eq!(import_profile(b"").unwrap_err(), ImportError::UnknownFormat);
+        assert!(matches!(import_profile(b"# only comments\n"), Err(ImportError::Malformed { .. })));
+    }
+}
@@ -0,0 +1,185 @@
+//! `perf script` output: a header line per sample, then one indented line
+//! per frame (innermost first), then a blank line
+//!
+//! ```text
+//! node 12345/12350 [003] 8412.250134:   10101010 cpu-clock:
+//!             55d0c1234567 v8::internal::Heap::Scavenge+0x47 (/usr/bin/node)
+//!             7f3a12029d90 __libc_start_call_main+0x80 (/usr/lib/x86_64-linux-gnu/libc.so.6)
+//! ```
+
+use super::{basename, strip_offset, ImportError, ImportedProfile, ProfileFormat};
+use crate::{StackFrame, StackTrace};
+use std::time::{Duration, SystemTime};
+
+/// The parts of a sample header used here
+#[derive(Debug, Clone, PartialEq)]
+struct Header {
+    command: String,
+    pid: u32,
+    tid: u32,
+    /// Seconds, on perf's clock
+    time: Option<f64>,
+}
+
+pub fn parse(text: &str) -> Result<ImportedProfile, ImportError> {
+    let mut samples = Vec::new();
+    let mut header: Option<Header> = None;
+    let mut frames = Vec::new();
+    let mut first: Option<Header> = None;
+    let (mut start_time, mut end_time) = (f64::MAX, f64::MIN);
+
+    let mut flush = |header: &mut Option<Header>, frames: &mut Vec<StackFrame>| {
+        if let Some(header) = header.take() {
+            if let Some(time) = header.time {
+                start_time = start_time.min(time);
+                end_time = end_time.max(time);
+            }
+            if !frames.is_empty() {
+                let trace = StackTrace {
+                    pid: header.pid,
+                    thread_id: Some(header.tid as u64),
+                    timestamp: SystemTime::now(),
+                    frames: std::mem::take(frames),
+                    sample_duration_ms: 0,
+                    is_complete: true,
+                };
+                samples.push((trace, 1));
+            }
+        }
+    };
+
+    for (index, line) in text.lines().enumerate() {
+        if line.trim().is_empty() {
+            flush(&mut header, &mut frames);
+        } else if line.starts_with(char::is_whitespace) {
+            if header.is_none() {
+                return Err(malformed(index, "frame outside a sample"));
+            }
+            frames.push(parse_frame(line.trim()));
+        } else if !line.starts_with('#') {
+            flush(&mut header, &mut frames);
+            let parsed = parse_header(line).ok_or_else(|| malformed(index, "unrecognised sample header"))?;
+            first.get_or_insert_with(|| parsed.clone());
+            header = Some(parsed);
+        }
+    }
+    flush(&mut header, &mut frames);
+
+    let first = first.unwrap_or(Header { command: "perf".to_string(), pid: 0, tid: 0, time: None });
+    Ok(ImportedProfile {
+        name: first.command,
+        pid: first.pid,
+        duration: if end_time > start_time { Duration::from_secs_f64(end_time - start_time) } else { Duration::ZERO },
+        samples,
+    })
+}
+
+fn malformed(index: usize, message: &str) -> ImportError {
+    ImportError::malformed(ProfileFormat::PerfScript, format!("line {}: {}", index + 1, message))
+}
+
+/// `comm pid/tid [cpu] time: ...`; the command may contain spaces, and the
+/// pid is left out unless perf was asked for it
+fn parse_header(line: &str) -> Option<Header> {
+    let fields: Vec<&str> = line.split_whitespace().collect();
+    let id_index = (1..fields.len()).find(|&i| {
+        fields[i].split('/').all(|part| !part.is_empty() && part.bytes().all(|b| b.is_ascii_digit()))
+    })?;
+
+    let (pid, tid) = match fields[id_index].split_once('/') {
+        Some((pid, tid)) => (pid.parse().ok()?, tid.parse().ok()?),
+        None => {
+            let tid = fields[id_index].parse().ok()?;
+            (tid, tid)
+        }
+    };
+    let time = fields[id_index + 1..].iter()
+        .find_map(|field| field.strip_suffix(':')?.parse::<f64>().ok());
+
+    Some(Header { command: fields[..id_index].join(" "), pid, tid, time })
+}
+
+/// `address symbol+offset (module)`, where the symbol may be `[unknown]`
+fn parse_frame(line: &str) -> StackFrame {
+    let (address, rest) = match line.split_once(char::is_whitespace) {
+        Some((address, rest)) if u64::from_str_radix(address, 16).is_ok() => {
+            (u64::from_str_radix(address, 16).unwrap_or(0), rest.trim())
+        }
+        _ => (0, line),
+    };
+
+    let (symbol, module) = match rest.rfind(" (") {
+        Some(index) if rest.ends_with(')') => (rest[..index].trim(), Some(&rest[index + 2..rest.len() - 1])),
+        _ => (rest, None),
+    };
+    let symbol = strip_offset(symbol);
+
+    StackFrame {
+        address,
+        symbol: (!symbol.is_empty() && symbol != "[unknown]").then(|| symbol.to_string()),
+        module: module.filter(|module| *module != "[unknown]").map(basename),
+        file: None,
+        line: None,
+        offset: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SCRIPT: &str = "\
+# ========
+# captured on: Tue Oct 14 09:12:44 2025
+node 12345/12350 [003] 8412.250134:   10101010 cpu-clock:
+\t    55d0c1234567 v8::internal::Heap::Scavenge+0x47 (/usr/bin/node)
+\t    7f3a12029d90 __libc_start_call_main+0x80 (/usr/lib/x86_64-linux-gnu/libc.so.6)
+
+Web Content 12345/12351 [001] 8412.260134:   10101010 cpu-clock:
+\t    7f3a12100000 [unknown] ([unknown])
+\t    7f3a12029d90 __libc_start_call_main+0x80 (/usr/lib/x86_64-linux-gnu/libc.so.6)
+
+node 12345/12350 [003] 8412.270134:   10101010 cpu-clock:
+\t    55d0c1234567 v8::internal::Heap::Scavenge+0x47 (/usr/bin/node)
+\t    7f3a12029d90 __libc_start_call_main+0x80 (/usr/lib/x86_64-linux-gnu/libc.so.6)
+";
+
+    #[test]
+    fn test_parse_perf_script() {
+        let profile = parse(SCRIPT).unwrap();
+        assert_eq!(profile.name, "node");
+        assert_eq!(profile.pid, 12345);
+        assert_eq!(profile.samples.len(), 3);
+        assert_eq!(profile.duration, Duration::from_secs_f64(8412.270134 - 8412.250134));
+
+        let (trace, weight) = &profile.samples[0];
+        assert_eq!(*weight, 1);
+        assert_eq!(trace.thread_id, Some(12350));
+        assert_eq!(trace.frames[0].symbol.as_deref(), Some("v8::internal::Heap::Scavenge"));
+        assert_eq!(trace.frames[0].module.as_deref(), Some("node"));
+        assert_eq!(trace.frames[0].address, 0x55d0c1234567);
+
+        let unknown = &profile.samples[1].0.frames[0];
+        assert_eq!((unknown.symbol.as_deref(), unknown.module.as_deref()), (None, None));
+
+        let builder = profile.into_builder();
+        assert_eq!(builder.thread_ids(), vec![12350, 12351]);
+        assert_eq!(builder.build().total_samples, 3);
+    }
+
+    #[test]
+    fn test_parse_header_variants() {
+        assert_eq!(
+            parse_header("kworker/0:1 42 [000] 1.5: cycles:"),
+            Some(Header { command: "kworker/0:1".to_string(), pid: 42, tid: 42, time: Some(1.5) }),
+        );
+        assert_eq!(parse_header("swapper     0/0"), Some(Header {
+            command: "swapper".to_string(),
+            pid: 0,
+            tid: 0,
+            time: None,
+        }));
+        assert!(parse_header("garbage").is_none());
+        assert!(matches!(parse("\tdeadbeef foo (bar)\n"), Err(ImportError::Malformed { .. })));
+    }
+}
@@ -0,0 +1,396 @@
+//! The pprof `profile.proto` format, as written by Go's runtime/pprof,
+//! pprof-rs and `perf_to_profile`
+//!
+//! Only the fields that matter for flame graphs are declared: sample types,
+//! samples with their labels, locations (with inlined lines), functions,
+//! mappings and the string table. prost skips the rest.
+
+use super::{basename, gzip, ImportError, ImportedProfile, ProfileFormat};
+use crate::{FlameGraphData, FlameGraphNode, StackFrame, StackTrace};
+use prost::Message;
+use std::collections::HashMap;
+use std::time::{Duration, UNIX_EPOCH};
+
+#[derive(Clone, PartialEq, Message)]
+struct Profile {
+    #[prost(message, repeated, tag = "1")]
+    sample_type: Vec<ValueType>,
+    #[prost(message, repeated, tag = "2")]
+    sample: Vec<Sample>,
+    #[prost(message, repeated, tag = "3")]
+    mapping: Vec<Mapping>,
+    #[prost(message, repeated, tag = "4")]
+    location: Vec<Location>,
+    #[prost(message, repeated, tag = "5")]
+    function: Vec<Function>,
+    /// Bytes rather than strings, so a bad name doesn't reject the profile
+    #[prost(bytes = "vec", repeated, tag = "6")]
+    string_table: Vec<Vec<u8>>,
+    #[prost(int64, tag = "9")]
+    time_nanos: i64,
+    #[prost(int64, tag = "10")]
+    duration_nanos: i64,
+    #[prost(message, optional, tag = "11")]
+    period_type: Option<ValueType>,
+    #[prost(int64, tag = "12")]
+    period: i64,
+    #[prost(int64, tag = "14")]
+    default_sample_type: i64,
+}
+
+#[derive(Clone, Copy, PartialEq, Message)]
+struct ValueType {
+    #[prost(int64, tag = "1")]
+    r#type: i64,
+    #[prost(int64, tag = "2")]
+    unit: i64,
+}
+
+#[derive(Clone, PartialEq, Message)]
+struct Sample {
+    /// Leaf first
+    #[prost(uint64, repeated, tag = "1")]
+    location_id: Vec<u64>,
+    #[prost(int64, repeated, tag = "2")]
+    value: Vec<i64>,
+    #[prost(message, repeated, tag = "3")]
+    label: Vec<Label>,
+}
+
+#[derive(Clone, Copy, PartialEq, Message)]
+struct Label {
+    #[prost(int64, tag = "1")]
+    key: i64,
+    #[prost(int64, tag = "2")]
+    str: i64,
+    #[prost(int64, tag = "3")]
+    num: i64,
+}
+
+#[derive(Clone, Copy, PartialEq, Message)]
+struct Mapping {
+    #[prost(uint64, tag = "1")]
+    id: u64,
+    #[prost(int64, tag = "5")]
+    filename: i64,
+}
+
+#[derive(Clone, PartialEq, Message)]
+struct Location {
+    #[prost(uint64, tag = "1")]
+    id: u64,
+    #[prost(uint64, tag = "2")]
+    mapping_id: u64,
+    #[prost(uint64, tag = "3")]
+    address: u64,
+    /// Innermost inlined call first
+    #[prost(message, repeated, tag = "4")]
+    line: Vec<Line>,
+}
+
+#[derive(Clone, Copy, PartialEq, Message)]
+struct Line {
+    #[prost(uint64, tag = "1")]
+    function_id: u64,
+    #[prost(int64, tag = "2")]
+    line: i64,
+}
+
+#[derive(Clone, Copy, PartialEq, Message)]
+struct Function {
+    #[prost(uint64, tag = "1")]
+    id: u64,
+    #[prost(int64, tag = "2")]
+    name: i64,
+    #[prost(int64, tag = "3")]
+    system_name: i64,
+    #[prost(int64, tag = "4")]
+    filename: i64,
+}
+
+pub fn parse(data: &[u8]) -> Result<ImportedProfile, ImportError> {
+    let malformed = |message: String| ImportError::malformed(ProfileFormat::Pprof, message);
+    let inflated;
+    let data = if gzip::is_gzip(data) {
+        inflated = gzip::decompress(data).map_err(malformed)?;
+        &inflated[..]
+    } else {
+        data
+    };
+
+    let profile = Profile::decode(data).map_err(|e| malformed(e.to_string()))?;
+    let strings: Vec<String> = profile.string_table.iter().map(|s| String::from_utf8_lossy(s).into_owned()).collect();
+    let locations: HashMap<u64, &Location> = profile.location.iter().map(|l| (l.id, l)).collect();
+    let functions: HashMap<u64, &Function> = profile.function.iter().map(|f| (f.id, f)).collect();
+    let mappings: HashMap<u64, i64> = profile.mapping.iter().map(|m| (m.id, m.filename)).collect();
+
+    let string = |index: i64| usize::try_from(index).ok().and_then(|i| strings.get(i)).map(String::as_str).unwrap_or("");
+    let value_index = sample_value_index(&profile.sample_type, profile.default_sample_type, &string);
+    let timestamp = UNIX_EPOCH + Duration::from_nanos(profile.time_nanos.max(0) as u64);
+
+    let mut traces = Vec::with_capacity(profile.sample.len());
+    for sample in &profile.sample {
+        let weight = sample.value.get(value_index).map_or(0, |&v| v.max(0) as u64);
+        let mut frames = Vec::new();
+        for id in &sample.location_id {
+            let location = locations.get(id).ok_or_else(|| malformed(format!("unknown location {}", id)))?;
+            let module = mappings.get(&location.mapping_id).map(|&m| string(m)).filter(|m| !m.is_empty());
+            if location.line.is_empty() {
+                frames.push(frame(location.address, None, module, None, 0));
+            }
+            for &Line { function_id, line } in &location.line {
+                let function = functions.get(&function_id);
+                let name = function.map(|f| string(f.name)).filter(|name| !name.is_empty());
+                let file = function.map(|f| string(f.filename)).filter(|file| !file.is_empty());
+                frames.push(frame(location.address, name, module, file, line));
+            }
+        }
+
+        let label = |names: &[&str]| {
+            sample.label.iter().find(|label| names.contains(&string(label.key))).map(|label| label.num as u64)
+        };
+        let trace = StackTrace {
+            pid: label(&["pid"]).unwrap_or(0) as u32,
+            thread_id: label(&["thread_id", "tid", "thread id"]),
+            timestamp,
+            frames,
+            sample_duration_ms: profile.period.max(0) as u64 / 1_000_000,
+            is_complete: true,
+        };
+        traces.push((trace, weight));
+    }
+
+    let name = mappings.get(&1).map(|&m| basename(string(m))).filter(|name| !name.is_empty());
+    Ok(ImportedProfile {
+        name: name.unwrap_or_else(|| "pprof".to_string()),
+        pid: traces.first().map_or(0, |(trace, _)| trace.pid),
+        duration: Duration::from_nanos(profile.duration_nanos.max(0) as u64),
+        samples: traces,
+    })
+}
+
+/// The profile's default sample type if it names one, else "samples", else
+/// the last type, as `go tool pprof` picks
+fn sample_value_index<'s>(types: &[ValueType], default_type: i64, string: &impl Fn(i64) -> &'s str) -> usize {
+    let named = |name: &str| types.iter().position(|t| string(t.r#type) == name);
+    let default = (default_type != 0).then(|| types.iter().position(|t| t.r#type == default_type)).flatten();
+    default.or_else(|| named("samples")).unwrap_or(types.len().saturating_sub(1))
+}
+
+fn frame(address: u64, symbol: Option<&str>, module: Option<&str>, file: Option<&str>, line: i64) -> StackFrame {
+    StackFrame {
+        address,
+        symbol: symbol.map(str::to_string),
+        module: module.map(basename),
+        file: file.map(str::to_string),
+        line: (line > 0).then_some(line as u32),
+        offset: None,
+    }
+}
+
+/// Interned strings; index 0 is always ""
+struct StringTable {
+    strings: Vec<String>,
+    indices: HashMap<String, i64>,
+}
+
+impl StringTable {
+    fn new() -> Self {
+        Self { strings: vec![String::new()], indices: HashMap::from([(String::new(), 0)]) }
+    }
+
+    fn intern(&mut self, text: &str) -> i64 {
+        if let Some(&index) = self.indices.get(text) {
+            return index;
+        }
+        let index = self.strings.len() as i64;
+        self.strings.push(text.to_string());
+        self.indices.insert(text.to_string(), index);
+        index
+    }
+
+    fn into_table(self) -> Vec<Vec<u8>> {
+        self.strings.into_iter().map(String::into_bytes).collect()
+    }
+}
+
+/// Collects functions and locations while walking the flame graph
+struct Encoder {
+    strings: StringTable,
+    functions: HashMap<(i64, i64), u64>,
+    locations: HashMap<(u64, i64), u64>,
+    /// (location ids leaf first, self samples)
+    samples: Vec<(Vec<u64>, u64)>,
+}
+
+impl Encoder {
+    fn location(&mut self, node: &FlameGraphNode) -> u64 {
+        let name = self.strings.intern(&node.function_name);
+        let file = self.strings.intern(node.file_path.as_deref().unwrap_or(""));
+        let next_function = self.functions.len() as u64 + 1;
+        let function = *self.functions.entry((name, file)).or_insert(next_function);
+
+        let line = node.line_number.unwrap_or(0) as i64;
+        let next_location = self.locations.len() as u64 + 1;
+        *self.locations.entry((function, line)).or_insert(next_location)
+    }
+
+    fn walk(&mut self, node: &FlameGraphNode, path: &mut Vec<u64>) {
+        for child in node.children.values() {
+            path.push(self.location(child));
+            if child.self_samples > 0 {
+                self.samples.push((path.iter().rev().copied().collect(), child.self_samples));
+            }
+            self.walk(child, path);
+            path.pop();
+        }
+    }
+}
+
+/// Encode `data` as a gzipped pprof profile with a "samples" count and,
+/// when the capture's duration is known, a "cpu" time estimate per sample
+pub fn encode(data: &FlameGraphData) -> Vec<u8> {
+    let mut encoder = Encoder {
+        strings: StringTable::new(),
+        functions: HashMap::new(),
+        locations: HashMap::new(),
+        samples: Vec::new(),
+    };
+    encoder.walk(&data.root, &mut Vec::new());
+
+    let period_ns = match data.total_samples {
+        0 => 0,
+        samples => (data.total_duration.as_nanos() / samples as u128) as i64,
+    };
+    let mut types = vec![("samples", "count")];
+    if period_ns > 0 {
+        types.push(("cpu", "nanoseconds"));
+    }
+    let sample_type: Vec<ValueType> = types.into_iter()
+        .map(|(kind, unit)| ValueType { r#type: encoder.strings.intern(kind), unit: encoder.strings.intern(unit) })
+        .collect();
+
+    let sample = encoder.samples.iter()
+        .map(|(locations, count)| {
+            let count = *count as i64;
+            let mut value = vec![count];
+            if period_ns > 0 {
+                value.push(count.saturating_mul(period_ns));
+            }
+            Sample { location_id: locations.clone(), value, label: Vec::new() }
+        })
+        .collect();
+
+    let mut location: Vec<Location> = encoder.locations.iter()
+        .map(|(&(function_id, line), &id)| Location {
+            id,
+            line: vec![Line { function_id, line }],
+            ..Default::default()
+        })
+        .collect();
+    location.sort_by_key(|l| l.id);
+    let mut function: Vec<Function> = encoder.functions.iter()
+        .map(|(&(name, filename), &id)| Function { id, name, system_name: name, filename })
+        .collect();
+    function.sort_by_key(|f| f.id);
+
+    let profile = Profile {
+        period_type: (period_ns > 0).then(|| sample_type[1]),
+        period: period_ns,
+        sample_type,
+        sample,
+        mapping: Vec::new(),
+        location,
+        function,
+        string_table: encoder.strings.into_table(),
+        time_nanos: data.generated_at.duration_since(UNIX_EPOCH).unwrap_or_default().as_nanos() as i64,
+        duration_nanos: data.total_duration.as_nanos() as i64,
+        default_sample_type: 0,
+    };
+    gzip::compress(&profile.encode_to_vec())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pprof_round_trip() {
+        let mut data = FlameGraphData::from_folded("main;parse;lex 30\nmain;parse 10\nmain;render 60", "app").unwrap();
+        data.total_duration = Duration::from_secs(1);
+
+        let encoded = encode(&data);
+        assert!(gzip::is_gzip(&encoded));
+        let profile = parse(&encoded).unwrap();
+        assert_eq!(profile.total_weight(), 100);
+        assert_eq!(profile.duration, Duration::from_secs(1));
+
+        let (lex, weight) = profile.samples.iter()
+            .find(|(trace, _)| trace.frames[0].symbol.as_deref() == Some("lex"))
+            .unwrap();
+        assert_eq!(*weight, 30);
+        let names: Vec<_> = lex.frames.iter().map(|f| f.symbol.as_deref().unwrap()).collect();
+        assert_eq!(names, ["lex", "parse", "main"]);
+
+        let rebuilt = profile.into_flame_graph();
+        assert_eq!(rebuilt.root.children["main"].children["parse"].total_samples, 40);
+    }
+
+    #[test]
+    fn test_reads_inlined_lines_and_labels() {
+        // What Go writes: cpu values, an inlined call, a thread label
+        let mut strings = StringTable::new();
+        let [samples, count, cpu, ns, tid, inner, outer, file] =
+            ["samples", "count", "cpu", "nanoseconds", "thread_id", "inner", "outer", "a.go"].map(|s| strings.intern(s));
+
+        let profile = Profile {
+            sample_type: vec![ValueType { r#type: samples, unit: count }, ValueType { r#type: cpu, unit: ns }],
+            location: vec![Location {
+                id: 1,
+                address: 0x4000,
+                line: vec![Line { function_id: 1, line: 12 }, Line { function_id: 2, line: 40 }],
+                ..Default::default()
+            }],
+            function: [(1, inner), (2, outer)].into_iter()
+                .map(|(id, name)| Function { id, name, filename: file, ..Default::default() })
+                .collect(),
+            string_table: strings.into_table(),
+            ..Default::default()
+        };
+        let mut encoded = profile.encode_to_vec();
+
+        // Unpacked repeated fields are valid too
+        let mut sample = Vec::new();
+        prost::encoding::uint64::encode(1, &1, &mut sample);
+        prost::encoding::int64::encode(2, &3, &mut sample);
+        prost::encoding::int64::encode(2, &30_000_000, &mut sample);
+        prost::encoding::message::encode(3, &Label { key: tid, num: 77, ..Default::default() }, &mut sample);
+        prost::encoding::bytes::encode(2, &sample, &mut encoded);
+
+        let profile = parse(&encoded).unwrap();
+        let (trace, weight) = &profile.samples[0];
+        assert_eq!(*weight, 3);
+        assert_eq!(trace.thread_id, Some(77));
+        assert_eq!(trace.frames.len(), 2);
+        assert_eq!(trace.frames[0].symbol.as_deref(), Some("inner"));
+        assert_eq!(trace.frames[0].line, Some(12));
+        assert_eq!(trace.frames[1].symbol.as_deref(), Some("outer"));
+        assert_eq!(trace.frames[1].file.as_deref(), Some("a.go"));
+    }
+
+    #[test]
+    fn test_reads_compressed_profiles() {
+        // Our own export recompressed with `gzip -9`, so it has dynamic
+        // Huffman blocks like Go's profiles
+        let compressed = include_bytes!("../../tests/fixtures/profile.pb.gz");
+        let profile = parse(compressed).unwrap();
+        assert_eq!(profile.total_weight(), 2_010);
+        assert_eq!(profile.duration, Duration::from_secs(2));
+        assert_eq!(profile.samples.len(), 67);
+
+        let mut corrupt = compressed.to_vec();
+        let middle = corrupt.len() / 2;
+        corrupt[middle] ^= 0xff;
+        assert!(parse(&corrupt).is_err());
+    }
+}
@@ -0,0 +1,220 @@
+//! speedscope's file format: shared frames plus one profile per thread,
+//! either sampled (stacks with weights) or evented (open/close events)
+
+use super::{ImportError, ImportedProfile, ProfileFormat};
+use crate::{StackFrame, StackTrace};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::time::{Duration, SystemTime};
+
+#[derive(Debug, Deserialize)]
+struct File {
+    #[serde(default)]
+    name: Option<String>,
+    shared: Shared,
+    profiles: Vec<Profile>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Shared {
+    frames: Vec<Frame>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Frame {
+    name: String,
+    #[serde(default)]
+    file: Option<String>,
+    #[serde(default)]
+    line: Option<u32>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+enum Profile {
+    Sampled {
+        #[serde(default)]
+        name: Option<String>,
+        unit: String,
+        #[serde(rename = "startValue", default)]
+        start_value: f64,
+        #[serde(rename = "endValue", default)]
+        end_value: f64,
+        /// Frame indices, outermost first
+        samples: Vec<Vec<usize>>,
+        weights: Vec<f64>,
+    },
+    Evented {
+        #[serde(default)]
+        name: Option<String>,
+        unit: String,
+        #[serde(rename = "startValue", default)]
+        start_value: f64,
+        #[serde(rename = "endValue", default)]
+        end_value: f64,
+        events: Vec<Event>,
+    },
+}
+
+#[derive(Debug, Deserialize)]
+struct Event {
+    #[serde(rename = "type")]
+    kind: String,
+    frame: usize,
+    at: f64,
+}
+
+/// Weights in time units become microseconds so threads sampled at
+/// different resolutions add up; other units are kept as they are
+fn unit_scale(unit: &str) -> f64 {
+    match unit {
+        "nanoseconds" => 0.001,
+        "microseconds" => 1.0,
+        "milliseconds" => 1_000.0,
+        "seconds" => 1_000_000.0,
+        _ => 1.0,
+    }
+}
+
+pub fn parse(text: &str) -> Result<ImportedProfile, ImportError> {
+    let malformed = |message: String| ImportError::malformed(ProfileFormat::Speedscope, message);
+    let file: File = serde_json::from_str(text).map_err(|e| malformed(e.to_string()))?;
+
+    let mut samples = Vec::new();
+    let mut duration = Duration::ZERO;
+    let mut first_name = None;
+
+    for (thread, profile) in file.profiles.iter().enumerate() {
+        // Weight per distinct stack, summed before rounding
+        let mut stacks: HashMap<Vec<usize>, f64> = HashMap::new();
+        let (name, unit, span) = match profile {
+            Profile::Sampled { name, unit, start_value, end_value, samples, weights } => {
+                if samples.len() != weights.len() {
+                    return Err(malformed(format!("profile {} has {} samples but {} weights", thread, samples.len(), weights.len())));
+                }
+                for (stack, weight) in samples.iter().zip(weights) {
+                    *stacks.entry(stack.clone()).or_default() += weight;
+                }
+                (name, unit, end_value - start_value)
+            }
+            Profile::Evented { name, unit, start_value, end_value, events } => {
+                let mut stack: Vec<usize> = Vec::new();
+                let mut last = *start_value;
+                for event in events {
+                    if !stack.is_empty() && event.at > last {
+                        *stacks.entry(stack.clone()).or_default() += event.at - last;
+                    }
+                    last = event.at;
+                    match event.kind.as_str() {
+                        "O" => stack.push(event.frame),
+                        "C" => {
+                            if stack.pop() != Some(event.frame) {
+                                return Err(malformed(format!("profile {} closes frame {} out of order", thread, event.frame)));
+                            }
+                        }
+                        other => return Err(malformed(format!("unknown event type {:?}", other))),
+                    }
+                }
+                (name, unit, end_value - start_value)
+            }
+        };
+
+        let scale = unit_scale(unit);
+        if scale != 1.0 || unit == "microseconds" {
+            duration = duration.max(Duration::from_secs_f64((span * scale / 1_000_000.0).max(0.0)));
+        }
+        first_name = first_name.or(name.clone());
+
+        for (stack, weight) in stacks {
+            let weight = (weight * scale).round().max(if weight > 0.0 { 1.0 } else { 0.0 }) as u64;
+            let frames = stack.iter().rev()
+                .map(|&index| {
+                    let frame = file.shared.frames.get(index)
+                        .ok_or_else(|| malformed(format!("frame index {} out of range", index)))?;
+                    Ok(StackFrame {
+                        address: 0,
+                        symbol: Some(frame.name.clone()),
+                        module: None,
+                        file: frame.file.clone(),
+                        line: frame.line,
+                        offset: None,
+                    })
+                })
+                .collect::<Result<Vec<_>, ImportError>>()?;
+
+            let trace = StackTrace {
+                pid: 0,
+                thread_id: Some(thread as u64),
+                timestamp: SystemTime::now(),
+                frames,
+                sample_duration_ms: 0,
+                is_complete: true,
+            };
+            samples.push((trace, weight));
+        }
+    }
+
+    Ok(ImportedProfile {
+        name: file.name.or(first_name).unwrap_or_else(|| "speedscope".to_string()),
+        pid: 0,
+        duration,
+        samples,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FILE: &str = r#"{
+      "$schema": "https://www.speedscope.app/file-format-schema.json",
+      "name": "ci-run",
+      "shared": { "frames": [
+        { "name": "main", "file": "main.rs", "line": 3 },
+        { "name": "parse" },
+        { "name": "render" }
+      ] },
+      "profiles": [
+        { "type": "sampled", "name": "main thread", "unit": "milliseconds", "startValue": 0, "endValue": 40,
+          "samples": [[0, 1], [0, 2], [0, 1]], "weights": [10, 5, 2.5] },
+        { "type": "evented", "name": "worker", "unit": "microseconds", "startValue": 0, "endValue": 900,
+          "events": [
+            { "type": "O", "frame": 0, "at": 0 },
+            { "type": "O", "frame": 2, "at": 100 },
+            { "type": "C", "frame": 2, "at": 700 },
+            { "type": "C", "frame": 0, "at": 900 }
+          ] }
+      ]
+    }"#;
+
+    #[test]
+    fn test_parse_speedscope() {
+        let profile = parse(FILE).unwrap();
+        assert_eq!(profile.name, "ci-run");
+        assert_eq!(profile.duration, Duration::from_millis(40));
+
+        let weight_of = |thread: u64, leaf: &str| -> u64 {
+            profile.samples.iter()
+                .filter(|(t, _)| t.thread_id == Some(thread) && t.frames[0].symbol.as_deref() == Some(leaf))
+                .map(|(_, w)| w)
+                .sum()
+        };
+        assert_eq!(weight_of(0, "parse"), 12_500);
+        assert_eq!(weight_of(0, "render"), 5_000);
+        assert_eq!(weight_of(1, "render"), 600);
+        assert_eq!(weight_of(1, "main"), 300);
+
+        let (trace, _) = profile.samples.iter().find(|(t, _)| t.thread_id == Some(0)).unwrap();
+        assert_eq!(trace.frames.last().unwrap().file.as_deref(), Some("main.rs"));
+
+        let graph = profile.into_flame_graph();
+        assert_eq!(graph.total_samples, 18_400);
+    }
+
+    #[test]
+    fn test_rejects_bad_indices() {
+        let bad = FILE.replace("[0, 2], [0, 1]", "[0, 9], [0, 1]");
+        assert!(parse(&bad).unwrap_err().to_string().contains("frame index 9"));
+        assert!(parse("{}").is_err());
+    }
+}
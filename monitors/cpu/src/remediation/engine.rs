@@ -0,0 +1,437 @@
+use super::policy::{RemediationAction, RemediationPolicy};
+use crate::{global_limiter, ActionResult, KernelInterface, LimitPreset, ProcessAction};
+use reaper_core::alerts::{Alert, AlertState};
+use reaper_core::audit::{AuditLog, AuditRecord};
+use std::collections::{HashMap, VecDeque};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+use sysinfo::{Pid, System};
+
+/// The process an alert points at, as seen just before acting on it
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProcessIdentity {
+    pub pid: u32,
+    pub name: String,
+    pub exe: Option<PathBuf>,
+    pub cmdline: Vec<String>,
+}
+
+impl ProcessIdentity {
+    /// Refresh `pid` in `system` and describe it, if it's still running
+    pub fn lookup(system: &mut System, pid: u32) -> Option<Self> {
+        let pid = Pid::from_u32(pid);
+        if !system.refresh_process(pid) {
+            return None;
+        }
+        system.process(pid).map(|process| ProcessIdentity {
+            pid: pid.as_u32(),
+            name: process.name().to_string(),
+            exe: process.exe().map(PathBuf::from),
+            cmdline: process.cmd().to_vec(),
+        })
+    }
+}
+
+/// Looks up and acts on processes; swapped out in tests
+pub trait ProcessController: Send {
+    fn identify(&mut self, pid: u32) -> Option<ProcessIdentity>;
+    fn execute(&mut self, pid: u32, action: ProcessAction) -> ActionResult;
+    fn limit(&mut self, pid: u32, preset: LimitPreset) -> Result<String, String>;
+}
+
+/// Acts on real processes through `KernelInterface` and the shared limiter
+pub struct SystemController {
+    system: System,
+    kernel: KernelInterface,
+}
+
+impl SystemController {
+    pub fn new() -> Self {
+        Self {
+            system: System::new(),
+            kernel: KernelInterface::new(),
+        }
+    }
+}
+
+impl Default for SystemController {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ProcessController for SystemController {
+    fn identify(&mut self, pid: u32) -> Option<ProcessIdentity> {
+        ProcessIdentity::lookup(&mut self.system, pid)
+    }
+
+    fn execute(&mut self, pid: u32, action: ProcessAction) -> ActionResult {
+        self.kernel.execute_action(pid, action)
+    }
+
+    fn limit(&mut self, pid: u32, preset: LimitPreset) -> Result<String, String> {
+        global_limiter()
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .limit_to_preset(pid, preset)
+            .map(|_| format!("Process {} limited to {:?}", pid, preset))
+            .map_err(|e| format!("{:?}", e))
+    }
+}
+
+/// What happened when a policy matched an alert
+#[derive(Debug, Clone, PartialEq)]
+pub enum OutcomeStatus {
+    Executed(String),
+    Failed(String),
+    /// The policy or engine is in dry-run mode; nothing was sent
+    DryRun,
+    /// The policy matched but didn't act, e.g. the target was filtered out
+    Skipped(String),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct RemediationOutcome {
+    pub policy: String,
+    pub pid: Option<u32>,
+    pub action: &'static str,
+    pub status: OutcomeStatus,
+}
+
+/// A SIGKILL due once a terminated process's grace period runs out
+struct Escalation {
+    policy: String,
+    target: ProcessIdentity,
+    due: Instant,
+    dry_run: bool,
+}
+
+/// Applies remediation policies to firing alerts
+///
+/// Every action, and every action skipped only because of dry-run mode, is
+/// written to the audit log with the policy as its actor.
+pub struct RemediationEngine {
+    policies: Vec<RemediationPolicy>,
+    controller: Box<dyn ProcessController>,
+    audit: Option<Arc<Mutex<AuditLog>>>,
+    dry_run: bool,
+    recent_actions: HashMap<String, VecDeque<Instant>>,
+    escalations: Vec<Escalation>,
+}
+
+impl RemediationEngine {
+    pub fn new(controller: Box<dyn ProcessController>) -> Self {
+        Self {
+            policies: Vec::new(),
+            controller,
+            audit: None,
+            dry_run: false,
+            recent_actions: HashMap::new(),
+            escalations: Vec::new(),
+        }
+    }
+
+    pub fn with_audit_log(mut self, audit: Arc<Mutex<AuditLog>>) -> Self {
+        self.audit = Some(audit);
+        self
+    }
+
+    pub fn policies(&self) -> &[RemediationPolicy] {
+        &self.policies
+    }
+
+    /// Replace the policies; rate-limit history is kept for policies that
+    /// survive by name
+    pub fn set_policies(&mut self, policies: Vec<RemediationPolicy>) {
+        self.recent_actions.retain(|name, _| policies.iter().any(|p| &p.name == name));
+        self.policies = policies;
+    }
+
+    /// Treat every policy as dry-run, whatever its own setting
+    pub fn set_dry_run(&mut self, dry_run: bool) {
+        self.dry_run = dry_run;
+    }
+
+    /// When the next pending escalation is due
+    pub fn next_due(&self) -> Option<Instant> {
+        self.escalations.iter().map(|e| e.due).min()
+    }
+
+    pub fn handle_alert(&mut self, alert: &Alert, now: Instant) -> Vec<RemediationOutcome> {
+        if alert.state != AlertState::Firing {
+            return Vec::new();
+        }
+
+        let policies: Vec<RemediationPolicy> = self.policies.iter()
+            .filter(|policy| policy.alert == alert.rule)
+            .cloned()
+            .collect();
+
+        policies.iter().map(|policy| self.apply(policy, alert, now)).collect()
+    }
+
+    fn apply(&mut self, policy: &RemediationPolicy, alert: &Alert, now: Instant) -> RemediationOutcome {
+        let outcome = |pid: Option<u32>, status: OutcomeStatus| RemediationOutcome {
+            policy: policy.name.clone(),
+            pid,
+            action: policy.action.name(),
+            status,
+        };
+
+        let Some(pid) = alert.series.labels.get("pid").and_then(|pid| pid.parse::<u32>().ok()) else {
+            return outcome(None, OutcomeStatus::Skipped("alert has no pid label".to_string()));
+        };
+        let Some(target) = self.controller.identify(pid) else {
+            return outcome(Some(pid), OutcomeStatus::Skipped("process not found".to_string()));
+        };
+        if !policy.targets.permits(&target.name, target.exe.as_deref()) {
+            return outcome(Some(pid), OutcomeStatus::Skipped(format!("{} is not a permitted target", target.name)));
+        }
+        if !self.take_rate_limit_slot(policy, now) {
+            return outcome(Some(pid), OutcomeStatus::Skipped("rate limit reached".to_string()));
+        }
+
+        let dry_run = self.dry_run || policy.dry_run;
+        let status = if dry_run {
+            OutcomeStatus::DryRun
+        } else {
+            self.perform(policy.action, pid)
+        };
+
+        if let (RemediationAction::Terminate { kill_after: Some(grace) }, OutcomeStatus::Executed(_) | OutcomeStatus::DryRun) =
+            (policy.action, &status)
+        {
+            self.escalations.push(Escalation {
+                policy: policy.name.clone(),
+                target: target.clone(),
+                due: now + grace,
+                dry_run,
+            });
+        }
+
+        self.record(&policy.name, policy.action.name(), &target, &status, dry_run);
+        outcome(Some(pid), status)
+    }
+
+    /// Kill terminated processes whose grace period is over and that are
+    /// still running
+    pub fn run_due(&mut self, now: Instant) -> Vec<RemediationOutcome> {
+        let (due, pending): (Vec<Escalation>, Vec<Escalation>) = std::mem::take(&mut self.escalations)
+            .into_iter()
+            .partition(|escalation| escalation.due <= now);
+        self.escalations = pending;
+
+        due.into_iter()
+            .filter_map(|escalation| {
+                // The pid may have been reused once the original exited
+                let current = self.controller.identify(escalation.target.pid)?;
+                if current.name != escalation.target.name {
+                    return None;
+                }
+
+                let status = if escalation.dry_run {
+                    OutcomeStatus::DryRun
+                } else {
+                    self.perform(RemediationAction::Kill, current.pid)
+                };
+                self.record(&escalation.policy, "kill", &current, &status, escalation.dry_run);
+
+                Some(RemediationOutcome {
+                    policy: escalation.policy,
+                    pid: Some(current.pid),
+                    action: "kill",
+                    status,
+                })
+            })
+            .collect()
+    }
+
+    fn take_rate_limit_slot(&mut self, policy: &RemediationPolicy, now: Instant) -> bool {
+        let Some(limit) = policy.rate_limit else {
+            return true;
+        };
+
+        let recent = self.recent_actions.entry(policy.name.clone()).or_default();
+        while recent.front().is_some_and(|&at| now.duration_since(at) >= limit.window) {
+            recent.pop_front();
+        }
+        if recent.len() >= limit.max_actions as usize {
+            return false;
+        }
+        recent.push_back(now);
+        true
+    }
+
+    fn perform(&mut self, action: RemediationAction, pid: u32) -> OutcomeStatus {
+        let result = match action {
+            RemediationAction::Limit(preset) => {
+                return match self.controller.limit(pid, preset) {
+                    Ok(message) => OutcomeStatus::Executed(message),
+                    Err(message) => OutcomeStatus::Failed(message),
+                };
+            }
+            RemediationAction::Terminate { .. } => self.controller.execute(pid, ProcessAction::Terminate),
+            RemediationAction::Kill => self.controller.execute(pid, ProcessAction::Kill),
+            RemediationAction::Suspend => self.controller.execute(pid, ProcessAction::Suspend),
+        };
+
+        match result {
+            ActionResult::Success(message) => OutcomeStatus::Executed(message),
+            other => OutcomeStatus::Failed(format!("{}: {}", other.kind(), other.message())),
+        }
+    }
+
+    fn record(&self, policy: &str, action: &str, target: &ProcessIdentity, status: &OutcomeStatus, dry_run: bool) {
+        let Some(audit) = &self.audit else {
+            return;
+        };
+
+        let (result, outcome) = match status {
+            OutcomeStatus::Executed(message) => ("Success", message.clone()),
+            OutcomeStatus::Failed(message) => ("Failed", message.clone()),
+            OutcomeStatus::DryRun => ("DryRun", "dry run".to_string()),
+            OutcomeStatus::Skipped(reason) => ("Skipped", reason.clone()),
+        };
+        let record = AuditRecord::new(format!("policy:{}", policy), action, target.pid)
+            .with_process(&target.name)
+            .with_command(target.exe.as_ref().map(|exe| exe.display().to_string()), target.cmdline.clone())
+            .with_result(result, outcome)
+            .with_dry_run(dry_run);
+
+        let appended = audit.lock().unwrap_or_else(|e| e.into_inner()).append(record);
+        if let Err(e) = appended {
+            eprintln!("Failed to write remediation audit record: {}", e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::remediation::TargetFilter;
+    use reaper_core::alerts::Severity;
+    use reaper_core::metrics::SeriesKey;
+    use std::time::Duration;
+
+    #[derive(Default)]
+    struct FakeProcesses {
+        running: HashMap<u32, ProcessIdentity>,
+        calls: Arc<Mutex<Vec<String>>>,
+    }
+
+    impl FakeProcesses {
+        fn with(processes: &[(u32, &str, &str)]) -> Self {
+            let running = processes.iter()
+                .map(|&(pid, name, exe)| {
+                    let identity = ProcessIdentity { pid, name: name.to_string(), exe: Some(exe.into()), cmdline: vec![] };
+                    (pid, identity)
+                })
+                .collect();
+            Self { running, calls: Arc::default() }
+        }
+    }
+
+    impl ProcessController for FakeProcesses {
+        fn identify(&mut self, pid: u32) -> Option<ProcessIdentity> {
+            self.running.get(&pid).cloned()
+        }
+
+        fn execute(&mut self, pid: u32, action: ProcessAction) -> ActionResult {
+            self.calls.lock().unwrap().push(format!("{:?} {}", action, pid));
+            ActionResult::Success(format!("{:?} sent", action))
+        }
+
+        fn limit(&mut self, pid: u32, preset: LimitPreset) -> Result<String, String> {
+            self.calls.lock().unwrap().push(format!("Limit {:?} {}", preset, pid));
+            Ok("limited".to_string())
+        }
+    }
+
+    fn firing(rule: &str, pid: u32, name: &str) -> Alert {
+        Alert {
+            rule: rule.to_string(),
+            severity: Severity::Warning,
+            series: SeriesKey::new("process.cpu_percent").with_label("pid", pid).with_label("process", name),
+            state: AlertState::Firing,
+            value: 350.0,
+            since: 0,
+        }
+    }
+
+    fn engine(processes: FakeProcesses, policies: Vec<RemediationPolicy>) -> (RemediationEngine, Arc<Mutex<Vec<String>>>) {
+        let calls = Arc::clone(&processes.calls);
+        let mut engine = RemediationEngine::new(Box::new(processes));
+        engine.set_policies(policies);
+        (engine, calls)
+    }
+
+    #[test]
+    fn test_limits_firing_process() {
+        let policy = RemediationPolicy::new("tame-node", "node-hot", RemediationAction::Limit(LimitPreset::Low));
+        let (mut engine, calls) = engine(FakeProcesses::with(&[(42, "node", "/usr/bin/node")]), vec![policy]);
+        let now = Instant::now();
+
+        let outcomes = engine.handle_alert(&firing("node-hot", 42, "node"), now);
+        assert_eq!(outcomes[0].status, OutcomeStatus::Executed("limited".to_string()));
+        assert_eq!(*calls.lock().unwrap(), vec!["Limit Low 42"]);
+
+        // Other rules and non-firing transitions are ignored
+        assert!(engine.handle_alert(&firing("disk-full", 42, "node"), now).is_empty());
+        let mut resolved = firing("node-hot", 42, "node");
+        resolved.state = AlertState::Resolved;
+        assert!(engine.handle_alert(&resolved, now).is_empty());
+    }
+
+    #[test]
+    fn test_terminate_escalates_to_kill() {
+        let action = RemediationAction::Terminate { kill_after: Some(Duration::from_secs(10)) };
+        let policy = RemediationPolicy::new("reap", "leak", action);
+        let (mut engine, calls) = engine(FakeProcesses::with(&[(7, "app", "/opt/app")]), vec![policy]);
+        let start = Instant::now();
+
+        engine.handle_alert(&firing("leak", 7, "app"), start);
+        assert_eq!(engine.next_due(), Some(start + Duration::from_secs(10)));
+        assert!(engine.run_due(start + Duration::from_secs(5)).is_empty());
+
+        let outcomes = engine.run_due(start + Duration::from_secs(10));
+        assert_eq!(outcomes[0].action, "kill");
+        assert_eq!(*calls.lock().unwrap(), vec!["Terminate 7", "Kill 7"]);
+        assert_eq!(engine.next_due(), None);
+    }
+
+    #[test]
+    fn test_filters_rate_limit_and_dry_run() {
+        let log_dir = tempfile::tempdir().unwrap();
+        let audit = Arc::new(Mutex::new(AuditLog::open(log_dir.path().join("audit.jsonl")).unwrap()));
+        let policy = RemediationPolicy::new("stop", "hot", RemediationAction::Suspend)
+            .with_targets(TargetFilter { allow: vec![], deny: vec!["/System/*".to_string()] })
+            .with_rate_limit(1, Duration::from_secs(60))
+            .with_dry_run(true);
+        let processes = FakeProcesses::with(&[(1, "launchd", "/System/launchd"), (2, "node", "/usr/bin/node"), (3, "go", "/usr/bin/go")]);
+        let (engine, calls) = engine(processes, vec![policy]);
+        let mut engine = engine.with_audit_log(Arc::clone(&audit));
+        let now = Instant::now();
+
+        let status = |outcomes: Vec<RemediationOutcome>| outcomes[0].status.clone();
+        assert!(matches!(status(engine.handle_alert(&firing("hot", 1, "launchd"), now)), OutcomeStatus::Skipped(_)));
+        assert_eq!(status(engine.handle_alert(&firing("hot", 2, "node"), now)), OutcomeStatus::DryRun);
+        assert_eq!(
+            status(engine.handle_alert(&firing("hot", 3, "go"), now)),
+            OutcomeStatus::Skipped("rate limit reached".to_string()),
+        );
+        assert_eq!(
+            status(engine.handle_alert(&firing("hot", 3, "go"), now + Duration::from_secs(60))),
+            OutcomeStatus::DryRun,
+        );
+        assert!(calls.lock().unwrap().is_empty());
+
+        let records = audit.lock().unwrap().records().unwrap();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].actor, "policy:stop");
+        assert_eq!(records[0].action, "suspend");
+        assert_eq!(records[0].process.as_deref(), Some("node"));
+        assert_eq!(records[1].exe.as_deref(), Some("/usr/bin/go"));
+        assert!(records.iter().all(|r| r.dry_run && r.result == "DryRun"));
+    }
+}
@@ -0,0 +1,82 @@
+//! Automated actions taken when alerts fire
+//!
+//! A policy names an alert rule and what to do to the process it fired for:
+//! limit it to a preset, suspend it, or terminate it with an optional
+//! escalation to SIGKILL. Policies can be limited to processes by name or
+//! executable path, rate limited, and run in dry-run mode, and everything
+//! they do is written to the audit log.
+
+mod engine;
+mod policy;
+
+pub use engine::{
+    OutcomeStatus, ProcessController, ProcessIdentity, RemediationEngine, RemediationOutcome, SystemController,
+};
+pub use policy::{PolicySet, RateLimit, RemediationAction, RemediationError, RemediationPolicy, TargetFilter};
+
+use once_cell::sync::Lazy;
+use reaper_core::alerts::Alert;
+use std::path::Path;
+use std::sync::mpsc::{Receiver, RecvTimeoutError};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Longest the worker waits for an alert before checking escalations
+const IDLE_WAIT: Duration = Duration::from_secs(1);
+
+static GLOBAL_ENGINE: Lazy<Arc<Mutex<RemediationEngine>>> = Lazy::new(|| {
+    let mut engine = RemediationEngine::new(Box::new(SystemController::new()));
+    if let Some(audit) = reaper_core::audit::global_log() {
+        engine = engine.with_audit_log(audit);
+    }
+    let engine = Arc::new(Mutex::new(engine));
+
+    let receiver = reaper_core::alerts::global_engine()
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .subscribe();
+    let worker = Arc::clone(&engine);
+    let _ = std::thread::Builder::new()
+        .name("reaper-remediation".to_string())
+        .spawn(move || run(&worker, receiver));
+
+    engine
+});
+
+/// The process-wide remediation engine, fed by the global alert engine
+pub fn global_engine() -> Arc<Mutex<RemediationEngine>> {
+    Arc::clone(&GLOBAL_ENGINE)
+}
+
+/// Replace the global engine's policies with those in a `.toml` or `.json`
+/// file; returns how many were loaded
+pub fn load_global_policies(path: &Path) -> Result<usize, RemediationError> {
+    let policy_set = PolicySet::load(path)?;
+    let count = policy_set.policies.len();
+
+    global_engine()
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .set_policies(policy_set.policies);
+
+    Ok(count)
+}
+
+fn run(engine: &Mutex<RemediationEngine>, receiver: Receiver<Alert>) {
+    loop {
+        let wait = engine.lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .next_due()
+            .map_or(IDLE_WAIT, |due| due.saturating_duration_since(Instant::now()).min(IDLE_WAIT));
+
+        match receiver.recv_timeout(wait) {
+            Ok(alert) => {
+                engine.lock().unwrap_or_else(|e| e.into_inner()).handle_alert(&alert, Instant::now());
+            }
+            Err(RecvTimeoutError::Timeout) => {}
+            Err(RecvTimeoutError::Disconnected) => return,
+        }
+
+        engine.lock().unwrap_or_else(|e| e.into_inner()).run_due(Instant::now());
+    }
+}
@@ -0,0 +1,308 @@
+use crate::LimitPreset;
+use reaper_core::alerts::parse_duration;
+use serde::Deserialize;
+use std::fmt;
+use std::path::Path;
+use std::time::Duration;
+
+/// Error loading remediation policies
+#[derive(Debug, Clone, PartialEq)]
+pub enum RemediationError {
+    /// A policy's action or one of its settings is invalid
+    InvalidPolicy { policy: String, message: String },
+    /// The policies file could not be read or decoded
+    InvalidFile(String),
+}
+
+impl fmt::Display for RemediationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidPolicy { policy, message } => {
+                write!(f, "Invalid remediation policy '{}': {}", policy, message)
+            }
+            Self::InvalidFile(msg) => write!(f, "Invalid remediation policies file: {}", msg),
+        }
+    }
+}
+
+impl std::error::Error for RemediationError {}
+
+/// What a policy does to the process an alert fired for
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RemediationAction {
+    /// Cap the process at one of the limiter presets
+    Limit(LimitPreset),
+    /// Send SIGTERM, then SIGKILL if the process is still alive after
+    /// `kill_after`
+    Terminate { kill_after: Option<Duration> },
+    Kill,
+    Suspend,
+}
+
+impl RemediationAction {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Limit(_) => "limit",
+            Self::Terminate { .. } => "terminate",
+            Self::Kill => "kill",
+            Self::Suspend => "suspend",
+        }
+    }
+}
+
+/// Which processes a policy may act on
+///
+/// Patterns containing a `/` match the executable path, others the process
+/// name; `*` matches any run of characters. Deny wins over allow, and an
+/// empty allow list allows everything not denied.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TargetFilter {
+    pub allow: Vec<String>,
+    pub deny: Vec<String>,
+}
+
+impl TargetFilter {
+    pub fn permits(&self, name: &str, exe: Option<&Path>) -> bool {
+        let exe = exe.and_then(|path| path.to_str());
+        let matches = |pattern: &String| {
+            if pattern.contains('/') {
+                exe.is_some_and(|exe| glob_matches(pattern, exe))
+            } else {
+                glob_matches(pattern, name)
+            }
+        };
+
+        !self.deny.iter().any(matches) && (self.allow.is_empty() || self.allow.iter().any(matches))
+    }
+}
+
+/// At most `max_actions` per policy within any `window`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RateLimit {
+    pub max_actions: u32,
+    pub window: Duration,
+}
+
+/// Runs `action` against the process behind each firing of `alert`
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(try_from = "PolicyDefinition")]
+pub struct RemediationPolicy {
+    pub name: String,
+    /// Name of the alert rule that triggers the policy
+    pub alert: String,
+    pub action: RemediationAction,
+    pub targets: TargetFilter,
+    pub rate_limit: Option<RateLimit>,
+    /// Record what would have been done without touching the process
+    pub dry_run: bool,
+}
+
+impl RemediationPolicy {
+    pub fn new(name: impl Into<String>, alert: impl Into<String>, action: RemediationAction) -> Self {
+        Self {
+            name: name.into(),
+            alert: alert.into(),
+            action,
+            targets: TargetFilter::default(),
+            rate_limit: None,
+            dry_run: false,
+        }
+    }
+
+    pub fn with_targets(mut self, targets: TargetFilter) -> Self {
+        self.targets = targets;
+        self
+    }
+
+    pub fn with_rate_limit(mut self, max_actions: u32, window: Duration) -> Self {
+        self.rate_limit = Some(RateLimit { max_actions, window });
+        self
+    }
+
+    pub fn with_dry_run(mut self, dry_run: bool) -> Self {
+        self.dry_run = dry_run;
+        self
+    }
+}
+
+/// A policy as written in a policies file
+#[derive(Deserialize)]
+struct PolicyDefinition {
+    name: String,
+    alert: String,
+    action: String,
+    preset: Option<LimitPreset>,
+    kill_after: Option<String>,
+    #[serde(default)]
+    allow: Vec<String>,
+    #[serde(default)]
+    deny: Vec<String>,
+    max_actions: Option<u32>,
+    per: Option<String>,
+    #[serde(default)]
+    dry_run: bool,
+}
+
+impl TryFrom<PolicyDefinition> for RemediationPolicy {
+    type Error = RemediationError;
+
+    fn try_from(definition: PolicyDefinition) -> Result<Self, RemediationError> {
+        let invalid = |message: String| RemediationError::InvalidPolicy { policy: definition.name.clone(), message };
+
+        let kill_after = match &definition.kill_after {
+            Some(duration) => Some(parse_duration(duration).map_err(invalid)?),
+            None => None,
+        };
+        let action = match definition.action.as_str() {
+            "limit" => RemediationAction::Limit(
+                definition.preset.ok_or_else(|| invalid("limit needs a preset".to_string()))?,
+            ),
+            "terminate" => RemediationAction::Terminate { kill_after },
+            "kill" => RemediationAction::Kill,
+            "suspend" => RemediationAction::Suspend,
+            other => return Err(invalid(format!("unknown action '{}'", other))),
+        };
+
+        let rate_limit = match (definition.max_actions, &definition.per) {
+            (Some(0), _) => return Err(invalid("max_actions must be at least 1".to_string())),
+            (Some(max_actions), per) => Some(RateLimit {
+                max_actions,
+                window: match per {
+                    Some(per) => parse_duration(per).map_err(invalid)?,
+                    None => Duration::from_secs(3600),
+                },
+            }),
+            (None, Some(_)) => return Err(invalid("per needs max_actions".to_string())),
+            (None, None) => None,
+        };
+
+        Ok(Self {
+            name: definition.name,
+            alert: definition.alert,
+            action,
+            targets: TargetFilter { allow: definition.allow, deny: definition.deny },
+            rate_limit,
+            dry_run: definition.dry_run,
+        })
+    }
+}
+
+/// The policies in a TOML (`[[policy]]` tables) or JSON
+/// (`{"policies": [...]}`) file
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+pub struct PolicySet {
+    #[serde(alias = "policy", default)]
+    pub policies: Vec<RemediationPolicy>,
+}
+
+impl PolicySet {
+    pub fn from_toml(input: &str) -> Result<Self, RemediationError> {
+        toml::from_str(input).map_err(|e| RemediationError::InvalidFile(e.to_string()))
+    }
+
+    pub fn from_json(input: &str) -> Result<Self, RemediationError> {
+        serde_json::from_str(input).map_err(|e| RemediationError::InvalidFile(e.to_string()))
+    }
+
+    /// Load a `.toml` or `.json` policies file
+    pub fn load(path: &Path) -> Result<Self, RemediationError> {
+        let contents = std::fs::read_to_string(path)
+            .map_err(|e| RemediationError::InvalidFile(format!("{}: {}", path.display(), e)))?;
+
+        match path.extension().and_then(|e| e.to_str()) {
+            Some("toml") => Self::from_toml(&contents),
+            Some("json") => Self::from_json(&contents),
+            _ => Err(RemediationError::InvalidFile(format!("{}: expected a .toml or .json file", path.display()))),
+        }
+    }
+}
+
+/// Match `text` against a pattern where `*` stands for any run of characters
+fn glob_matches(pattern: &str, text: &str) -> bool {
+    let mut parts = pattern.split('*');
+    let first = parts.next().unwrap_or_default();
+    let Some(mut rest) = text.strip_prefix(first) else {
+        return false;
+    };
+
+    let parts: Vec<&str> = parts.collect();
+    let Some((last, middle)) = parts.split_last() else {
+        return rest.is_empty();
+    };
+    for part in middle {
+        match rest.find(part) {
+            Some(index) => rest = &rest[index + part.len()..],
+            None => return false,
+        }
+    }
+    rest.len() >= last.len() && rest.ends_with(last)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_glob_matches() {
+        assert!(glob_matches("node", "node"));
+        assert!(!glob_matches("node", "nodejs"));
+        assert!(glob_matches("node*", "nodejs"));
+        assert!(glob_matches("*.app/*", "/Applications/Slack.app/Contents/MacOS/Slack"));
+        assert!(glob_matches("/usr/*/sshd", "/usr/sbin/sshd"));
+        assert!(!glob_matches("/usr/*/sshd", "/usr/sbin/sshd-keygen"));
+        assert!(glob_matches("a*b*a", "aba"));
+        assert!(!glob_matches("a*a", "a"));
+    }
+
+    #[test]
+    fn test_target_filter() {
+        let filter = TargetFilter {
+            allow: vec!["node".to_string(), "/opt/*".to_string()],
+            deny: vec!["/opt/critical/*".to_string()],
+        };
+        assert!(filter.permits("node", None));
+        assert!(filter.permits("worker", Some(Path::new("/opt/jobs/worker"))));
+        assert!(!filter.permits("db", Some(Path::new("/opt/critical/db"))));
+        assert!(!filter.permits("python", Some(Path::new("/usr/bin/python"))));
+        assert!(TargetFilter::default().permits("anything", None));
+    }
+
+    #[test]
+    fn test_load_policies() {
+        let toml = r#"
+            [[policy]]
+            name = "tame-node"
+            alert = "node-hot"
+            action = "limit"
+            preset = "low"
+            allow = ["node"]
+            max_actions = 3
+
+            [[policy]]
+            name = "reap-leaks"
+            alert = "leak"
+            action = "terminate"
+            kill_after = "10s"
+            deny = ["/System/*"]
+            max_actions = 1
+            per = "10m"
+            dry_run = true
+        "#;
+        let policies = PolicySet::from_toml(toml).unwrap().policies;
+        assert_eq!(policies[0].action, RemediationAction::Limit(LimitPreset::Low));
+        assert_eq!(policies[0].rate_limit, Some(RateLimit { max_actions: 3, window: Duration::from_secs(3600) }));
+        assert_eq!(
+            policies[1].action,
+            RemediationAction::Terminate { kill_after: Some(Duration::from_secs(10)) },
+        );
+        assert_eq!(policies[1].rate_limit.unwrap().window, Duration::from_secs(600));
+        assert!(policies[1].dry_run);
+
+        let json = r#"{"policies": [{"name": "stop", "alert": "hot", "action": "suspend"}]}"#;
+        assert_eq!(PolicySet::from_json(json).unwrap().policies[0].action, RemediationAction::Suspend);
+
+        let invalid = r#"{"policies": [{"name": "broken", "alert": "hot", "action": "limit"}]}"#;
+        let error = PolicySet::from_json(invalid).unwrap_err().to_string();
+        assert!(error.contains("broken") && error.contains("preset"), "{}", error);
+    }
+}
@@ -0,0 +1,96 @@
+use std::io;
+
+/// One file-backed region of a process's address space
+#[derive(Debug, Clone, PartialEq)]
+pub struct MemoryMapping {
+    pub start: u64,
+    pub end: u64,
+    /// Offset in `path` that `start` was mapped from
+    pub file_offset: u64,
+    pub executable: bool,
+    pub path: String,
+}
+
+impl MemoryMapping {
+    /// Where `address` falls within the mapped file
+    pub fn file_offset_of(&self, address: u64) -> u64 {
+        address - self.start + self.file_offset
+    }
+}
+
+/// The file-backed mappings from `/proc/<pid>/maps`, sorted by address
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ProcessMaps {
+    mappings: Vec<MemoryMapping>,
+}
+
+impl ProcessMaps {
+    pub fn read(pid: u32) -> io::Result<Self> {
+        Ok(Self::parse(&std::fs::read_to_string(format!("/proc/{}/maps", pid))?))
+    }
+
+    pub fn parse(contents: &str) -> Self {
+        let mut mappings: Vec<MemoryMapping> = contents.lines().filter_map(parse_line).collect();
+        mappings.sort_by_key(|mapping| mapping.start);
+        Self { mappings }
+    }
+
+    pub fn mappings(&self) -> &[MemoryMapping] {
+        &self.mappings
+    }
+
+    pub fn find(&self, address: u64) -> Option<&MemoryMapping> {
+        let index = self.mappings.partition_point(|mapping| mapping.start <= address);
+        self.mappings[..index].last().filter(|mapping| address < mapping.end)
+    }
+}
+
+/// `start-end perms offset dev inode path`; anonymous regions are skipped
+fn parse_line(line: &str) -> Option<MemoryMapping> {
+    let mut fields = line.splitn(6, char::is_whitespace);
+    let (start, end) = fields.next()?.split_once('-')?;
+    let permissions = fields.next()?;
+    let offset = fields.next()?;
+    let path = fields.nth(2)?.trim();
+    if path.is_empty() {
+        return None;
+    }
+
+    Some(MemoryMapping {
+        start: u64::from_str_radix(start, 16).ok()?,
+        end: u64::from_str_radix(end, 16).ok()?,
+        file_offset: u64::from_str_radix(offset, 16).ok()?,
+        executable: permissions.contains('x'),
+        path: path.to_string(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MAPS: &str = "\
+55d0c0a00000-55d0c0a2c000 r--p 00000000 fd:01 1835019                    /usr/bin/node
+55d0c0a2c000-55d0c1f00000 r-xp 0002c000 fd:01 1835019                    /usr/bin/node
+55d0c2000000-55d0c2100000 rw-p 00000000 00:00 0                          [heap]
+7f3a10000000-7f3a10021000 rw-p 00000000 00:00 0
+7f3a12000000-7f3a12190000 r-xp 00028000 fd:01 1572920                    /usr/lib/x86_64-linux-gnu/libc.so.6
+7ffd5e1f0000-7ffd5e1f2000 r-xp 00000000 00:00 0                          [vdso]
+";
+
+    #[test]
+    fn test_parse_and_find() {
+        let maps = ProcessMaps::parse(MAPS);
+        assert_eq!(maps.mappings().len(), 5);
+
+        let node = maps.find(0x55d0c0a2c010).unwrap();
+        assert_eq!(node.path, "/usr/bin/node");
+        assert!(node.executable);
+        assert_eq!(node.file_offset_of(0x55d0c0a2c010), 0x2c010);
+
+        assert_eq!(maps.find(0x7f3a12000100).unwrap().path, "/usr/lib/x86_64-linux-gnu/libc.so.6");
+        assert_eq!(maps.find(0x7ffd5e1f0001).unwrap().path, "[vdso]");
+        assert!(maps.find(0x7f3a10000010).is_none());
+        assert!(maps.find(0x1000).is_none());
+    }
+}
@@ -0,0 +1,283 @@
+//! Native sampling profiler for Linux feeding `FlameGraphBuilder`
+//!
+//! Each thread of the target gets a `perf_event_open` task-clock event at
+//! the configured rate, and the kernel records a frame-pointer call chain
+//! with every sample. Where perf events aren't allowed (for example with a
+//! high `perf_event_paranoid` or inside a container) the sampler falls back
+//! to stopping each thread with ptrace at the same rate and walking its
+//! frame pointers itself. Code built without frame pointers gives truncated
+//! stacks.
+//!
+//! `OffCpuProfiler` is the complementary mode: it polls `/proc` for threads
+//! that are blocked and weights their kernel stacks by the time spent waiting.
+
+mod maps;
+mod off_cpu;
+mod perf;
+mod ptrace;
+
+pub use maps::{MemoryMapping, ProcessMaps};
+pub use off_cpu::{OffCpuConfig, OffCpuProfiler, OffCpuSample, WaitReason};
+
+use crate::{FlameGraphBuilder, FlameGraphData, StackFrame, StackTrace};
+use std::fmt;
+use std::io;
+use std::time::{Duration, SystemTime};
+
+/// How to collect samples
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SamplerBackend {
+    /// perf events, falling back to ptrace if they can't be opened or set up
+    #[default]
+    Auto,
+    Perf,
+    Ptrace,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SamplerConfig {
+    /// Samples per second per thread
+    pub frequency_hz: u32,
+    pub duration: Duration,
+    /// Deepest stack to record; perf also caps this at
+    /// `kernel.perf_event_max_stack`
+    pub max_frames: usize,
+    pub backend: SamplerBackend,
+}
+
+impl Default for SamplerConfig {
+    fn default() -> Self {
+        Self {
+            frequency_hz: 99,
+            duration: Duration::from_secs(10),
+            max_frames: 127,
+            backend: SamplerBackend::Auto,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SamplerError {
+    ProcessNotFound(u32),
+    PermissionDenied(String),
+    /// The backend isn't available on this kernel or architecture
+    Unsupported(String),
+    InvalidConfig(String),
+    SystemError(String),
+}
+
+impl fmt::Display for SamplerError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::ProcessNotFound(pid) => write!(f, "Process {} not found", pid),
+            Self::PermissionDenied(msg) => write!(f, "Permission denied: {}", msg),
+            Self::Unsupported(msg) => write!(f, "Sampling not supported: {}", msg),
+            Self::InvalidConfig(msg) => write!(f, "Invalid sampler configuration: {}", msg),
+            Self::SystemError(msg) => write!(f, "Sampling failed: {}", msg),
+        }
+    }
+}
+
+impl std::error::Error for SamplerError {}
+
+/// One captured stack, innermost address first
+#[derive(Debug, Clone, PartialEq)]
+struct RawSample {
+    tid: u32,
+    timestamp: SystemTime,
+    addresses: Vec<u64>,
+    complete: bool,
+}
+
+/// Samples the threads of a running process
+#[derive(Debug, Clone)]
+pub struct StackSampler {
+    config: SamplerConfig,
+}
+
+impl StackSampler {
+    pub fn new(config: SamplerConfig) -> Self {
+        Self { config }
+    }
+
+    pub fn config(&self) -> &SamplerConfig {
+        &self.config
+    }
+
+    /// Sample `pid` for the configured duration, blocking until done
+    pub fn sample(&self, pid: u32) -> Result<Vec<StackTrace>, SamplerError> {
+        if self.config.frequency_hz == 0 || self.config.max_frames == 0 {
+            return Err(SamplerError::InvalidConfig("frequency and max frames must be positive".to_string()));
+        }
+        if !std::path::Path::new(&format!("/proc/{}", pid)).exists() {
+            return Err(SamplerError::ProcessNotFound(pid));
+        }
+
+        let maps_before = ProcessMaps::read(pid).unwrap_or_default();
+        let raw = match self.config.backend {
+            SamplerBackend::Perf => perf::sample(pid, &self.config)?,
+            SamplerBackend::Ptrace => ptrace::sample(pid, &self.config)?,
+            SamplerBackend::Auto => match perf::sample(pid, &self.config) {
+                Err(SamplerError::PermissionDenied(_) | SamplerError::Unsupported(_) | SamplerError::SystemError(_)) => {
+                    ptrace::sample(pid, &self.config)?
+                }
+                result => result?,
+            },
+        };
+        // Libraries loaded while sampling only show up in the later maps
+        let maps = ProcessMaps::read(pid).unwrap_or(maps_before);
+
+        let interval_ms = 1000 / self.config.frequency_hz as u64;
+        Ok(raw.into_iter().map(|sample| to_stack_trace(pid, sample, &maps, interval_ms)).collect())
+    }
+
+    /// Sample `pid` and build its flame graph with symbolized frames
+    pub fn profile(&self, pid: u32, process_name: &str) -> Result<FlameGraphData, SamplerError> {
+        let mut traces = self.sample(pid)?;
+        crate::symbolizer::global_symbolizer()
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .symbolize(&mut traces);
+
+        let mut builder = FlameGraphBuilder::new(process_name.to_string(), pid);
+        for trace in traces {
+            builder.add_stack_trace(trace);
+        }
+
+        let mut data = builder.build();
+        data.total_duration = self.config.duration;
+        Ok(data)
+    }
+}
+
+fn to_stack_trace(pid: u32, sample: RawSample, maps: &ProcessMaps, interval_ms: u64) -> StackTrace {
+    let frames = sample.addresses.iter()
+        .map(|&address| {
+            let mapping = maps.find(address);
+            StackFrame {
+                address,
+                symbol: None,
+                module: mapping.map(|m| m.path.clone()),
+                file: None,
+                line: None,
+                offset: mapping.map(|m| m.file_offset_of(address)),
+            }
+        })
+        .collect();
+
+    StackTrace {
+        pid,
+        thread_id: Some(sample.tid as u64),
+        timestamp: sample.timestamp,
+        frames,
+        sample_duration_ms: interval_ms,
+        is_complete: sample.complete,
+    }
+}
+
+/// Thread ids of `pid` from `/proc/<pid>/task`
+fn thread_ids(pid: u32) -> Result<Vec<u32>, SamplerError> {
+    let entries = std::fs::read_dir(format!("/proc/{}/task", pid)).map_err(|e| match e.kind() {
+        io::ErrorKind::NotFound => SamplerError::ProcessNotFound(pid),
+        _ => SamplerError::SystemError(e.to_string()),
+    })?;
+
+    Ok(entries
+        .filter_map(|entry| entry.ok()?.file_name().to_str()?.parse().ok())
+        .collect())
+}
+
+fn last_os_error() -> io::Error {
+    io::Error::last_os_error()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+    use std::process::{Child, Command, Stdio};
+    use std::time::Instant;
+
+    #[test]
+    fn test_annotates_frames_with_modules() {
+        let maps = ProcessMaps::parse("400000-500000 r-xp 00001000 fd:01 42 /usr/bin/app\n");
+        let sample = RawSample {
+            tid: 7,
+            timestamp: SystemTime::UNIX_EPOCH,
+            addresses: vec![0x400100, 0x10],
+            complete: true,
+        };
+
+        let trace = to_stack_trace(1, sample, &maps, 10);
+        assert_eq!(trace.thread_id, Some(7));
+        assert_eq!(trace.frames[0].module.as_deref(), Some("/usr/bin/app"));
+        assert_eq!(trace.frames[0].offset, Some(0x1100));
+        assert_eq!(trace.frames[1].module, None);
+    }
+
+    /// Kills the child even if an assertion fails first
+    struct Busy(Child);
+
+    impl Drop for Busy {
+        fn drop(&mut self) {
+            let _ = self.0.kill();
+            let _ = self.0.wait();
+        }
+    }
+
+    /// Sample `child` with each backend, expecting all of its `threads` in
+    /// the traces; skipped where the sandbox forbids perf events or ptrace
+    fn assert_samples_threads(child: &Child, threads: usize) {
+        for backend in [SamplerBackend::Perf, SamplerBackend::Ptrace] {
+            let sampler = StackSampler::new(SamplerConfig {
+                frequency_hz: 200,
+                duration: Duration::from_millis(300),
+                backend,
+                ..SamplerConfig::default()
+            });
+            match sampler.sample(child.id()) {
+                Ok(traces) => {
+                    assert!(!traces.is_empty(), "{:?} produced no samples", backend);
+                    assert!(traces.iter().all(|t| !t.frames.is_empty() && t.pid == child.id()));
+                    let sampled: HashSet<_> = traces.iter().map(|t| t.thread_id).collect();
+                    assert_eq!(sampled.len(), threads, "{:?} missed threads", backend);
+                }
+                Err(SamplerError::PermissionDenied(_) | SamplerError::Unsupported(_)) => {}
+                Err(e) => panic!("{:?}: {}", backend, e),
+            }
+        }
+    }
+
+    /// Samples busy children with each backend; the multithreaded one needs
+    /// python3 and is skipped without it
+    #[test]
+    fn test_samples_busy_child() {
+        let child = Busy(Command::new("sh")
+            .args(["-c", "while :; do :; done"])
+            .stdout(Stdio::null())
+            .spawn()
+            .unwrap());
+        assert_samples_threads(&child.0, 1);
+
+        let pid = child.0.id();
+        drop(child);
+        assert_eq!(
+            StackSampler::new(SamplerConfig::default()).sample(pid).unwrap_err(),
+            SamplerError::ProcessNotFound(pid),
+        );
+
+        let script = "import threading\n\
+                      def spin():\n    while True: pass\n\
+                      for _ in range(3): threading.Thread(target=spin).start()\n\
+                      spin()\n";
+        let Ok(child) = Command::new("python3").args(["-c", script]).stdout(Stdio::null()).stderr(Stdio::null()).spawn() else {
+            return;
+        };
+        let child = Busy(child);
+        let started = Instant::now();
+        while thread_ids(child.0.id()).unwrap().len() < 4 && started.elapsed() < Duration::from_secs(5) {
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        assert_samples_threads(&child.0, 4);
+    }
+}
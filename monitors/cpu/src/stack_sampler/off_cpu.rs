@@ -0,0 +1,456 @@
+//! Off-CPU profiling: where threads wait instead of where they run
+//!
+//! Every poll reads each thread's state from `/proc/<pid>/task/<tid>/stat`.
+//! Threads that are sleeping (`S`) or in uninterruptible wait (`D`) have
+//! their kernel stack read from `task/<tid>/stack`, which needs root, and
+//! otherwise fall back to the single `wchan` frame. `syscall` names the call
+//! they are blocked in. Each sample is weighted by the time the thread spent
+//! off-CPU since the previous poll, which is the wall time minus the run and
+//! runqueue time from `schedstat`. Consecutive polls that see the same stack
+//! are merged into one episode.
+
+use super::SamplerError;
+use crate::{FlameGraphBuilder, FlameGraphData, StackFrame, StackTrace};
+use reaper_core::platform::linux::procfs;
+use std::collections::HashMap;
+use std::fmt;
+use std::time::{Duration, Instant, SystemTime};
+
+/// What a blocked thread is waiting for
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum WaitReason {
+    DiskIo,
+    Network,
+    Pipe,
+    Lock,
+    Poll,
+    Sleep,
+    ChildWait,
+    Signal,
+    Other,
+}
+
+impl WaitReason {
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::DiskIo => "disk I/O",
+            Self::Network => "network",
+            Self::Pipe => "pipe",
+            Self::Lock => "lock",
+            Self::Poll => "poll",
+            Self::Sleep => "sleep",
+            Self::ChildWait => "child wait",
+            Self::Signal => "signal wait",
+            Self::Other => "other",
+        }
+    }
+
+    /// Name of the outermost frame that groups an off-CPU graph by reason
+    pub fn frame_name(&self) -> String {
+        format!("[wait: {}]", self.label())
+    }
+}
+
+impl fmt::Display for WaitReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.label())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct OffCpuConfig {
+    /// Time between polls of the thread states
+    pub interval: Duration,
+    pub duration: Duration,
+    /// Count interruptible sleep (`S`) as well as uninterruptible (`D`) waits;
+    /// idle worker pools mostly sit in `S`
+    pub include_interruptible: bool,
+    pub max_frames: usize,
+}
+
+impl Default for OffCpuConfig {
+    fn default() -> Self {
+        Self {
+            interval: Duration::from_millis(10),
+            duration: Duration::from_secs(10),
+            include_interruptible: true,
+            max_frames: 127,
+        }
+    }
+}
+
+/// One blocking episode of a thread
+#[derive(Debug, Clone)]
+pub struct OffCpuSample {
+    /// Kernel frames innermost first, then the syscall, then the
+    /// `[wait: reason]` frame. Incomplete when only `wchan` was readable
+    pub trace: StackTrace,
+    pub reason: WaitReason,
+    pub blocked: Duration,
+}
+
+/// Polls the blocked threads of a running process
+#[derive(Debug, Clone)]
+pub struct OffCpuProfiler {
+    config: OffCpuConfig,
+}
+
+impl OffCpuProfiler {
+    pub fn new(config: OffCpuConfig) -> Self {
+        Self { config }
+    }
+
+    pub fn config(&self) -> &OffCpuConfig {
+        &self.config
+    }
+
+    /// Poll `pid` for the configured duration, blocking until done. Stops
+    /// early, keeping what it has, if the process exits
+    pub fn sample(&self, pid: u32) -> Result<Vec<OffCpuSample>, SamplerError> {
+        if self.config.interval.is_zero() || self.config.max_frames == 0 {
+            return Err(SamplerError::InvalidConfig("interval and max frames must be positive".to_string()));
+        }
+        super::thread_ids(pid)?;
+
+        let mut on_cpu_ns: HashMap<u32, u64> = HashMap::new();
+        let mut open: HashMap<u32, Episode> = HashMap::new();
+        let mut samples = Vec::new();
+
+        let start = Instant::now();
+        let mut last_poll = start;
+        while let Ok(tids) = super::thread_ids(pid) {
+            let now = Instant::now();
+            let elapsed = now.duration_since(last_poll);
+            last_poll = now;
+
+            for tid in tids {
+                let task = |name: &str| procfs::read_pid_file(pid, &format!("task/{}/{}", tid, name)).ok();
+
+                // Time on a CPU or waiting for one since the last poll
+                let on_cpu = task("schedstat").and_then(|s| parse_schedstat(&s));
+                let busy = match (on_cpu, on_cpu_ns.get(&tid)) {
+                    (Some(current), Some(&previous)) => Duration::from_nanos(current.saturating_sub(previous)),
+                    _ => Duration::ZERO,
+                };
+                if let Some(current) = on_cpu {
+                    on_cpu_ns.insert(tid, current);
+                }
+
+                let state = task("stat").and_then(|s| procfs::parse_stat(&s)).map(|s| s.state);
+                let blocked = match state {
+                    Some('D') => true,
+                    Some('S') => self.config.include_interruptible,
+                    _ => false,
+                };
+                if !blocked {
+                    if let Some(episode) = open.remove(&tid) {
+                        samples.push(episode.finish(pid, tid));
+                    }
+                    continue;
+                }
+
+                let (frames, reason, complete) = blocked_stack(
+                    task("stack").as_deref(),
+                    task("wchan").as_deref(),
+                    task("syscall").as_deref(),
+                    self.config.max_frames,
+                );
+                let off_cpu = elapsed.saturating_sub(busy);
+
+                match open.get_mut(&tid) {
+                    Some(episode) if episode.same_stack(&frames) => episode.blocked += off_cpu,
+                    _ => {
+                        let episode = Episode { frames, reason, complete, started: SystemTime::now(), blocked: off_cpu };
+                        if let Some(previous) = open.insert(tid, episode) {
+                            samples.push(previous.finish(pid, tid));
+                        }
+                    }
+                }
+            }
+
+            let next = last_poll + self.config.interval;
+            if next.duration_since(start) > self.config.duration {
+                break;
+            }
+            std::thread::sleep(next.saturating_duration_since(Instant::now()));
+        }
+
+        let mut remaining: Vec<_> = open.into_iter().collect();
+        remaining.sort_by_key(|(tid, _)| *tid);
+        samples.extend(remaining.into_iter().map(|(tid, episode)| episode.finish(pid, tid)));
+        samples.retain(|sample| !sample.blocked.is_zero());
+        Ok(samples)
+    }
+
+    /// Poll `pid` and build its off-CPU flame graph. Sample counts in the
+    /// graph are microseconds spent blocked
+    pub fn profile(&self, pid: u32, process_name: &str) -> Result<FlameGraphData, SamplerError> {
+        let mut builder = FlameGraphBuilder::new(process_name.to_string(), pid);
+        for sample in self.sample(pid)? {
+            let weight = (sample.blocked.as_micros() as u64).max(1);
+            builder.add_weighted_stack_trace(sample.trace, weight);
+        }
+
+        let mut data = builder.build();
+        data.total_duration = self.config.duration;
+        Ok(data)
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Episode {
+    frames: Vec<StackFrame>,
+    reason: WaitReason,
+    complete: bool,
+    started: SystemTime,
+    blocked: Duration,
+}
+
+impl Episode {
+    fn same_stack(&self, frames: &[StackFrame]) -> bool {
+        self.frames.len() == frames.len()
+            && self.frames.iter().zip(frames).all(|(a, b)| a.symbol == b.symbol)
+    }
+
+    fn finish(self, pid: u32, tid: u32) -> OffCpuSample {
+        OffCpuSample {
+            trace: StackTrace {
+                pid,
+                thread_id: Some(tid as u64),
+                timestamp: self.started,
+                frames: self.frames,
+                sample_duration_ms: self.blocked.as_millis() as u64,
+                is_complete: self.complete,
+            },
+            reason: self.reason,
+            blocked: self.blocked,
+        }
+    }
+}
+
+/// Build the frames of a blocked thread from the contents of its `stack`,
+/// `wchan` and `syscall` files, whichever could be read. Returns the frames,
+/// the wait reason and whether the kernel stack was available
+fn blocked_stack(
+    stack: Option<&str>,
+    wchan: Option<&str>,
+    syscall: Option<&str>,
+    max_frames: usize,
+) -> (Vec<StackFrame>, WaitReason, bool) {
+    let mut kernel: Vec<String> = stack
+        .map(procfs::parse_kernel_stack)
+        .unwrap_or_default()
+        .into_iter()
+        .map(|(symbol, _)| symbol)
+        .collect();
+    let complete = !kernel.is_empty();
+    if !complete {
+        kernel.extend(wchan.map(str::trim).filter(|w| !w.is_empty() && *w != "0").map(str::to_string));
+    }
+
+    let syscall = syscall
+        .and_then(procfs::parse_syscall)
+        .map(|(nr, _)| syscall_name(nr).map(str::to_string).unwrap_or_else(|| format!("syscall {}", nr)));
+    let reason = classify(&kernel, syscall.as_deref());
+
+    // Leave room for the syscall and reason frames
+    kernel.truncate(max_frames.saturating_sub(2).max(1));
+    let mut frames: Vec<StackFrame> = kernel.into_iter().map(|symbol| frame(symbol, Some("[kernel]"))).collect();
+    if let Some(syscall) = syscall {
+        frames.push(frame(syscall, Some("[syscall]")));
+    }
+    frames.push(frame(reason.frame_name(), None));
+
+    (frames, reason, complete)
+}
+
+fn frame(symbol: String, module: Option<&str>) -> StackFrame {
+    StackFrame {
+        address: 0,
+        symbol: Some(symbol),
+        module: module.map(str::to_string),
+        file: None,
+        line: None,
+        offset: None,
+    }
+}
+
+/// Kernel functions, innermost first, decide where they are specific enough;
+/// the syscall covers the rest
+fn classify(kernel: &[String], syscall: Option<&str>) -> WaitReason {
+    const KERNEL_HINTS: &[(&[&str], WaitReason)] = &[
+        (&["futex", "mutex", "rwsem", "flock", "locks_lock", "rt_spin"], WaitReason::Lock),
+        (&["pipe_"], WaitReason::Pipe),
+        (&["sk_wait", "sock", "tcp_", "udp_", "inet_", "unix_stream", "nfs"], WaitReason::Network),
+        (&["ep_poll", "do_select", "do_sys_poll", "core_sys_select"], WaitReason::Poll),
+        (&["nanosleep"], WaitReason::Sleep),
+        (&["do_wait", "kernel_wait"], WaitReason::ChildWait),
+        (&["sigsuspend", "sigtimedwait", "sys_pause"], WaitReason::Signal),
+        (
+            &["io_schedule", "bio", "blk_", "folio_wait", "wait_on_page", "jbd2", "ext4", "xfs", "btrfs"],
+            WaitReason::DiskIo,
+        ),
+    ];
+
+    for symbol in kernel {
+        if let Some((_, reason)) = KERNEL_HINTS.iter().find(|(hints, _)| hints.iter().any(|h| symbol.contains(h))) {
+            return *reason;
+        }
+    }
+
+    match syscall.unwrap_or_default() {
+        "futex" | "flock" | "semtimedop" => WaitReason::Lock,
+        "epoll_wait" | "epoll_pwait" | "epoll_pwait2" | "poll" | "ppoll" | "select" | "pselect6" => WaitReason::Poll,
+        "nanosleep" | "clock_nanosleep" => WaitReason::Sleep,
+        "wait4" | "waitid" => WaitReason::ChildWait,
+        "pause" | "rt_sigsuspend" | "rt_sigtimedwait" => WaitReason::Signal,
+        "accept" | "accept4" | "connect" | "recvfrom" | "recvmsg" | "sendto" | "sendmsg" => WaitReason::Network,
+        "fsync" | "fdatasync" | "sync" | "syncfs" | "io_getevents" | "io_uring_enter" => WaitReason::DiskIo,
+        _ => WaitReason::Other,
+    }
+}
+
+/// Names of the syscalls threads commonly block in
+fn syscall_name(nr: i64) -> Option<&'static str> {
+    let name = match nr {
+        libc::SYS_read => "read",
+        libc::SYS_write => "write",
+        libc::SYS_readv => "readv",
+        libc::SYS_writev => "writev",
+        libc::SYS_pread64 => "pread64",
+        libc::SYS_pwrite64 => "pwrite64",
+        libc::SYS_openat => "openat",
+        libc::SYS_futex => "futex",
+        libc::SYS_flock => "flock",
+        libc::SYS_semtimedop => "semtimedop",
+        libc::SYS_epoll_pwait => "epoll_pwait",
+        libc::SYS_epoll_pwait2 => "epoll_pwait2",
+        libc::SYS_ppoll => "ppoll",
+        libc::SYS_pselect6 => "pselect6",
+        libc::SYS_nanosleep => "nanosleep",
+        libc::SYS_clock_nanosleep => "clock_nanosleep",
+        libc::SYS_wait4 => "wait4",
+        libc::SYS_waitid => "waitid",
+        libc::SYS_rt_sigsuspend => "rt_sigsuspend",
+        libc::SYS_rt_sigtimedwait => "rt_sigtimedwait",
+        libc::SYS_accept4 => "accept4",
+        libc::SYS_connect => "connect",
+        libc::SYS_recvfrom => "recvfrom",
+        libc::SYS_recvmsg => "recvmsg",
+        libc::SYS_sendto => "sendto",
+        libc::SYS_sendmsg => "sendmsg",
+        libc::SYS_fsync => "fsync",
+        libc::SYS_fdatasync => "fdatasync",
+        libc::SYS_sync => "sync",
+        libc::SYS_syncfs => "syncfs",
+        libc::SYS_io_getevents => "io_getevents",
+        libc::SYS_io_uring_enter => "io_uring_enter",
+        #[cfg(target_arch = "x86_64")]
+        libc::SYS_poll => "poll",
+        #[cfg(target_arch = "x86_64")]
+        libc::SYS_select => "select",
+        #[cfg(target_arch = "x86_64")]
+        libc::SYS_epoll_wait => "epoll_wait",
+        #[cfg(target_arch = "x86_64")]
+        libc::SYS_accept => "accept",
+        #[cfg(target_arch = "x86_64")]
+        libc::SYS_pause => "pause",
+        _ => return None,
+    };
+    Some(name)
+}
+
+/// Nanoseconds on a CPU plus waiting on a runqueue, from
+/// `task/<tid>/schedstat`
+fn parse_schedstat(content: &str) -> Option<u64> {
+    let mut fields = content.split_whitespace().map(|f| f.parse::<u64>().ok());
+    let running = fields.next()??;
+    let waiting = fields.next()??;
+    Some(running + waiting)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::process::{Command, Stdio};
+
+    const FUTEX_STACK: &str = "\
+[<0>] futex_wait_queue+0x60/0x90
+[<0>] futex_wait+0x185/0x270
+[<0>] do_futex+0x106/0x1b0
+[<0>] __x64_sys_futex+0x8e/0x1d0
+[<0>] do_syscall_64+0x82/0x170
+[<0>] entry_SYSCALL_64_after_hwframe+0x76/0x7e
+";
+
+    fn symbols(frames: &[StackFrame]) -> Vec<&str> {
+        frames.iter().map(|f| f.symbol.as_deref().unwrap()).collect()
+    }
+
+    #[test]
+    fn test_builds_tagged_stacks() {
+        let syscall = format!("{} 0x7f00 0x80 0x0 0x0 0x0 0x0 0x7ffd 0x7f01", libc::SYS_futex);
+        let (frames, reason, complete) = blocked_stack(Some(FUTEX_STACK), Some("futex_wait_queue"), Some(&syscall), 127);
+        assert_eq!(reason, WaitReason::Lock);
+        assert!(complete);
+        assert_eq!(frames.len(), 8);
+        assert_eq!(frames[0].module.as_deref(), Some("[kernel]"));
+        assert_eq!(symbols(&frames)[5..], ["entry_SYSCALL_64_after_hwframe", "futex", "[wait: lock]"]);
+
+        // Without root only wchan is readable
+        let (frames, reason, complete) = blocked_stack(None, Some("pipe_read"), Some("0 0x3 0x1 0x1"), 127);
+        assert_eq!(symbols(&frames), ["pipe_read", "read", "[wait: pipe]"]);
+        assert_eq!(reason, WaitReason::Pipe);
+        assert!(!complete);
+
+        let (frames, reason, _) = blocked_stack(None, Some("0"), None, 127);
+        assert_eq!(symbols(&frames), ["[wait: other]"]);
+        assert_eq!(reason, WaitReason::Other);
+
+        let (frames, _, _) = blocked_stack(Some(FUTEX_STACK), None, Some(&syscall), 4);
+        assert_eq!(symbols(&frames), ["futex_wait_queue", "futex_wait", "futex", "[wait: lock]"]);
+    }
+
+    #[test]
+    fn test_classifies_wait_reasons() {
+        let kernel = |symbols: &[&str]| symbols.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+        assert_eq!(classify(&kernel(&["io_schedule", "folio_wait_bit_common"]), Some("read")), WaitReason::DiskIo);
+        assert_eq!(classify(&kernel(&["sk_wait_data", "tcp_recvmsg"]), Some("recvfrom")), WaitReason::Network);
+        assert_eq!(classify(&kernel(&["ep_poll", "do_epoll_wait"]), None), WaitReason::Poll);
+        assert_eq!(classify(&kernel(&["do_wait", "kernel_wait4"]), Some("wait4")), WaitReason::ChildWait);
+        assert_eq!(classify(&[], Some("clock_nanosleep")), WaitReason::Sleep);
+        assert_eq!(classify(&[], Some("rt_sigtimedwait")), WaitReason::Signal);
+        assert_eq!(classify(&[], Some("syscall 999")), WaitReason::Other);
+        assert_eq!(syscall_name(libc::SYS_clock_nanosleep), Some("clock_nanosleep"));
+        assert_eq!(parse_schedstat("1500 250 12\n"), Some(1750));
+        assert_eq!(parse_schedstat("garbage"), None);
+    }
+
+    /// A sleeping child should show up as blocked for most of the run
+    #[test]
+    fn test_profiles_sleeping_child() {
+        let mut child = Command::new("sleep").arg("5").stdout(Stdio::null()).spawn().unwrap();
+        std::thread::sleep(Duration::from_millis(50));
+
+        let profiler = OffCpuProfiler::new(OffCpuConfig {
+            interval: Duration::from_millis(10),
+            duration: Duration::from_millis(300),
+            ..OffCpuConfig::default()
+        });
+        let samples = profiler.sample(child.id()).unwrap();
+        assert!(!samples.is_empty());
+        let blocked: Duration = samples.iter().map(|s| s.blocked).sum();
+        assert!(blocked >= Duration::from_millis(150), "only {:?} blocked", blocked);
+        assert!(samples.iter().all(|s| s.trace.frames.last().unwrap().symbol.as_deref().unwrap().starts_with("[wait: ")));
+        if samples[0].trace.is_complete {
+            assert_eq!(samples[0].reason, WaitReason::Sleep);
+        }
+
+        let data = profiler.profile(child.id(), "sleep").unwrap();
+        assert!(data.total_samples > 0);
+
+        child.kill().unwrap();
+        child.wait().unwrap();
+        assert_eq!(profiler.sample(child.id()).unwrap_err(), SamplerError::ProcessNotFound(child.id()));
+    }
+}
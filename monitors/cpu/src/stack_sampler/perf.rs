@@ -0,0 +1,361 @@
+use super::{last_os_error, thread_ids, RawSample, SamplerConfig, SamplerError};
+use std::collections::HashMap;
+use std::os::unix::io::RawFd;
+use std::sync::atomic::{fence, Ordering};
+use std::time::{Duration, Instant, SystemTime};
+
+const PERF_TYPE_SOFTWARE: u32 = 1;
+const PERF_COUNT_SW_TASK_CLOCK: u64 = 1;
+
+const PERF_SAMPLE_TID: u64 = 1 << 1;
+const PERF_SAMPLE_TIME: u64 = 1 << 2;
+const PERF_SAMPLE_CALLCHAIN: u64 = 1 << 5;
+
+const FLAG_DISABLED: u64 = 1 << 0;
+const FLAG_EXCLUDE_KERNEL: u64 = 1 << 5;
+const FLAG_EXCLUDE_HV: u64 = 1 << 6;
+const FLAG_FREQ: u64 = 1 << 10;
+const FLAG_USE_CLOCKID: u64 = 1 << 25;
+
+const PERF_EVENT_IOC_ENABLE: libc::c_ulong = 0x2400;
+const PERF_EVENT_IOC_DISABLE: libc::c_ulong = 0x2401;
+
+const PERF_FLAG_FD_CLOEXEC: libc::c_ulong = 1 << 3;
+
+const PERF_RECORD_SAMPLE: u32 = 9;
+/// Call chain entries at or above this mark a context switch (user/kernel)
+/// rather than an address
+const PERF_CONTEXT_MAX: u64 = -4095i64 as u64;
+
+/// Data pages in each thread's ring buffer; must be a power of two. Kept
+/// small since unprivileged users share `kernel.perf_event_mlock_kb`
+const DATA_PAGES: usize = 8;
+/// Offsets of `data_head` and `data_tail` in `perf_event_mmap_page`
+const DATA_HEAD_OFFSET: usize = 1024;
+const DATA_TAIL_OFFSET: usize = 1032;
+
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// `struct perf_event_attr` up to `PERF_ATTR_SIZE_VER5`
+#[repr(C)]
+#[derive(Default)]
+struct PerfEventAttr {
+    kind: u32,
+    size: u32,
+    config: u64,
+    sample_freq: u64,
+    sample_type: u64,
+    read_format: u64,
+    flags: u64,
+    wakeup_events: u32,
+    bp_type: u32,
+    config1: u64,
+    config2: u64,
+    branch_sample_type: u64,
+    sample_regs_user: u64,
+    sample_stack_user: u32,
+    clockid: i32,
+    sample_regs_intr: u64,
+    aux_watermark: u32,
+    sample_max_stack: u16,
+    reserved: u16,
+}
+
+struct PerfEvent {
+    fd: RawFd,
+}
+
+impl PerfEvent {
+    fn open(tid: u32, config: &SamplerConfig) -> Result<Self, SamplerError> {
+        let attr = PerfEventAttr {
+            kind: PERF_TYPE_SOFTWARE,
+            size: std::mem::size_of::<PerfEventAttr>() as u32,
+            config: PERF_COUNT_SW_TASK_CLOCK,
+            sample_freq: config.frequency_hz as u64,
+            sample_type: PERF_SAMPLE_TID | PERF_SAMPLE_TIME | PERF_SAMPLE_CALLCHAIN,
+            flags: FLAG_DISABLED | FLAG_EXCLUDE_KERNEL | FLAG_EXCLUDE_HV | FLAG_FREQ | FLAG_USE_CLOCKID,
+            clockid: libc::CLOCK_MONOTONIC,
+            sample_max_stack: max_stack(config.max_frames),
+            ..PerfEventAttr::default()
+        };
+
+        let fd = unsafe {
+            libc::syscall(
+                libc::SYS_perf_event_open,
+                &attr as *const PerfEventAttr,
+                tid as libc::pid_t,
+                -1 as libc::c_int,
+                -1 as libc::c_int,
+                PERF_FLAG_FD_CLOEXEC,
+            )
+        };
+        if fd < 0 {
+            let error = last_os_error();
+            return Err(match error.raw_os_error() {
+                Some(libc::EACCES | libc::EPERM) => SamplerError::PermissionDenied(format!(
+                    "perf events not permitted ({}); check kernel.perf_event_paranoid",
+                    error
+                )),
+                Some(libc::ENOSYS | libc::ENOENT | libc::EOPNOTSUPP) => SamplerError::Unsupported(error.to_string()),
+                Some(libc::ESRCH) => SamplerError::ProcessNotFound(tid),
+                _ => SamplerError::SystemError(format!("perf_event_open: {}", error)),
+            });
+        }
+        Ok(Self { fd: fd as RawFd })
+    }
+
+    fn ioctl(&self, request: libc::c_ulong, argument: libc::c_ulong) -> Result<(), SamplerError> {
+        if unsafe { libc::ioctl(self.fd, request as _, argument) } < 0 {
+            return Err(SamplerError::SystemError(format!("perf ioctl: {}", last_os_error())));
+        }
+        Ok(())
+    }
+}
+
+impl Drop for PerfEvent {
+    fn drop(&mut self) {
+        unsafe {
+            libc::close(self.fd);
+        }
+    }
+}
+
+/// The ring buffer one thread's event writes its samples to
+struct RingBuffer {
+    base: *mut u8,
+    page_size: usize,
+    data_size: usize,
+}
+
+impl RingBuffer {
+    fn map(event: &PerfEvent) -> Result<Self, SamplerError> {
+        let page_size = unsafe { libc::sysconf(libc::_SC_PAGESIZE) } as usize;
+        let data_size = DATA_PAGES * page_size;
+        let base = unsafe {
+            libc::mmap(
+                std::ptr::null_mut(),
+                page_size + data_size,
+                libc::PROT_READ | libc::PROT_WRITE,
+                libc::MAP_SHARED,
+                event.fd,
+                0,
+            )
+        };
+        if base == libc::MAP_FAILED {
+            let error = last_os_error();
+            return Err(match error.raw_os_error() {
+                Some(libc::EPERM) => SamplerError::PermissionDenied(format!(
+                    "perf buffer exceeds the locked memory limit ({}); check kernel.perf_event_mlock_kb",
+                    error
+                )),
+                _ => SamplerError::SystemError(format!("mmap perf buffer: {}", error)),
+            });
+        }
+        Ok(Self { base: base as *mut u8, page_size, data_size })
+    }
+
+    /// Hand every complete record written since the last drain to `handle`
+    fn drain(&mut self, mut handle: impl FnMut(&[u8])) {
+        let head = unsafe { std::ptr::read_volatile(self.base.add(DATA_HEAD_OFFSET) as *const u64) };
+        fence(Ordering::Acquire);
+        let mut tail = unsafe { std::ptr::read_volatile(self.base.add(DATA_TAIL_OFFSET) as *const u64) };
+
+        while tail + 8 <= head {
+            let header = self.copy(tail, 8);
+            let size = u16::from_ne_bytes([header[6], header[7]]) as u64;
+            if size < 8 || tail + size > head {
+                break;
+            }
+            handle(&self.copy(tail, size as usize));
+            tail += size;
+        }
+
+        fence(Ordering::Release);
+        unsafe { std::ptr::write_volatile(self.base.add(DATA_TAIL_OFFSET) as *mut u64, tail) };
+    }
+
+    /// Copy `len` bytes starting at ring position `position`, unwrapping
+    fn copy(&self, position: u64, len: usize) -> Vec<u8> {
+        let data = unsafe { std::slice::from_raw_parts(self.base.add(self.page_size), self.data_size) };
+        let start = (position % self.data_size as u64) as usize;
+        let first = len.min(self.data_size - start);
+
+        let mut bytes = Vec::with_capacity(len);
+        bytes.extend_from_slice(&data[start..start + first]);
+        bytes.extend_from_slice(&data[..len - first]);
+        bytes
+    }
+}
+
+impl Drop for RingBuffer {
+    fn drop(&mut self) {
+        unsafe {
+            libc::munmap(self.base as *mut libc::c_void, self.page_size + self.data_size);
+        }
+    }
+}
+
+/// A thread's event with its own buffer; events of other tasks can't share
+/// one with `PERF_EVENT_IOC_SET_OUTPUT` unless they're bound to a CPU
+struct ThreadEvent {
+    // Unmapped before the event closes
+    ring: RingBuffer,
+    event: PerfEvent,
+}
+
+pub(super) fn sample(pid: u32, config: &SamplerConfig) -> Result<Vec<RawSample>, SamplerError> {
+    let mut threads: HashMap<u32, ThreadEvent> = HashMap::new();
+    let clock = ClockOffset::now();
+    let mut samples = Vec::new();
+    let deadline = Instant::now() + config.duration;
+
+    loop {
+        // Pick up threads started since the last pass
+        for tid in thread_ids(pid)? {
+            if threads.contains_key(&tid) {
+                continue;
+            }
+            let event = match PerfEvent::open(tid, config) {
+                Ok(event) => event,
+                Err(SamplerError::ProcessNotFound(_)) => continue,
+                Err(e) => return Err(e),
+            };
+            let ring = RingBuffer::map(&event)?;
+            event.ioctl(PERF_EVENT_IOC_ENABLE, 0)?;
+            threads.insert(tid, ThreadEvent { ring, event });
+        }
+        if threads.is_empty() {
+            return Err(SamplerError::ProcessNotFound(pid));
+        }
+
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        if remaining.is_zero() {
+            break;
+        }
+        let mut poll_fds: Vec<libc::pollfd> = threads.values()
+            .map(|thread| libc::pollfd { fd: thread.event.fd, events: libc::POLLIN, revents: 0 })
+            .collect();
+        let timeout = remaining.min(POLL_INTERVAL).as_millis() as libc::c_int;
+        unsafe { libc::poll(poll_fds.as_mut_ptr(), poll_fds.len() as libc::nfds_t, timeout) };
+
+        for thread in threads.values_mut() {
+            thread.ring.drain(|record| samples.extend(parse_record(record, &clock)));
+        }
+    }
+
+    for thread in threads.values_mut() {
+        let _ = thread.event.ioctl(PERF_EVENT_IOC_DISABLE, 0);
+        thread.ring.drain(|record| samples.extend(parse_record(record, &clock)));
+    }
+
+    Ok(samples)
+}
+
+/// Decode a `PERF_RECORD_SAMPLE` laid out for `TID | TIME | CALLCHAIN`
+fn parse_record(record: &[u8], clock: &ClockOffset) -> Option<RawSample> {
+    let u32_at = |offset: usize| Some(u32::from_ne_bytes(record.get(offset..offset + 4)?.try_into().ok()?));
+    let u64_at = |offset: usize| Some(u64::from_ne_bytes(record.get(offset..offset + 8)?.try_into().ok()?));
+
+    if u32_at(0)? != PERF_RECORD_SAMPLE {
+        return None;
+    }
+    let tid = u32_at(12)?;
+    let time = u64_at(16)?;
+    let count = u64_at(24)? as usize;
+
+    let addresses: Vec<u64> = (0..count)
+        .map(|i| u64_at(32 + i * 8))
+        .collect::<Option<Vec<u64>>>()?
+        .into_iter()
+        .filter(|&address| address < PERF_CONTEXT_MAX)
+        .collect();
+    if addresses.is_empty() {
+        return None;
+    }
+
+    Some(RawSample {
+        tid,
+        timestamp: clock.to_system_time(time),
+        addresses,
+        // The kernel stops at the first frame without a valid frame pointer,
+        // which is indistinguishable from reaching the outermost frame
+        complete: true,
+    })
+}
+
+fn max_stack(max_frames: usize) -> u16 {
+    let limit = std::fs::read_to_string("/proc/sys/kernel/perf_event_max_stack")
+        .ok()
+        .and_then(|value| value.trim().parse::<usize>().ok())
+        .unwrap_or(127);
+    max_frames.min(limit).min(u16::MAX as usize) as u16
+}
+
+/// Converts `CLOCK_MONOTONIC` sample times to wall-clock time
+struct ClockOffset {
+    wall: SystemTime,
+    monotonic_ns: u64,
+}
+
+impl ClockOffset {
+    fn now() -> Self {
+        let mut now = libc::timespec { tv_sec: 0, tv_nsec: 0 };
+        unsafe { libc::clock_gettime(libc::CLOCK_MONOTONIC, &mut now) };
+        Self {
+            wall: SystemTime::now(),
+            monotonic_ns: now.tv_sec as u64 * 1_000_000_000 + now.tv_nsec as u64,
+        }
+    }
+
+    fn to_system_time(&self, monotonic_ns: u64) -> SystemTime {
+        if monotonic_ns >= self.monotonic_ns {
+            self.wall + Duration::from_nanos(monotonic_ns - self.monotonic_ns)
+        } else {
+            self.wall - Duration::from_nanos(self.monotonic_ns - monotonic_ns)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_record(tid: u32, time: u64, chain: &[u64]) -> Vec<u8> {
+        let size = 32 + chain.len() * 8;
+        let mut record = Vec::with_capacity(size);
+        record.extend_from_slice(&PERF_RECORD_SAMPLE.to_ne_bytes());
+        record.extend_from_slice(&0u16.to_ne_bytes());
+        record.extend_from_slice(&(size as u16).to_ne_bytes());
+        record.extend_from_slice(&100u32.to_ne_bytes());
+        record.extend_from_slice(&tid.to_ne_bytes());
+        record.extend_from_slice(&time.to_ne_bytes());
+        record.extend_from_slice(&(chain.len() as u64).to_ne_bytes());
+        for address in chain {
+            record.extend_from_slice(&address.to_ne_bytes());
+        }
+        record
+    }
+
+    #[test]
+    fn test_attr_matches_kernel_layout() {
+        assert_eq!(std::mem::size_of::<PerfEventAttr>(), 112);
+    }
+
+    #[test]
+    fn test_parse_sample_record() {
+        let clock = ClockOffset { wall: SystemTime::UNIX_EPOCH + Duration::from_secs(100), monotonic_ns: 5_000_000_000 };
+        let perf_context_user = -512i64 as u64;
+        let record = sample_record(101, 6_000_000_000, &[perf_context_user, 0x401000, 0x402000]);
+
+        let sample = parse_record(&record, &clock).unwrap();
+        assert_eq!(sample.tid, 101);
+        assert_eq!(sample.addresses, vec![0x401000, 0x402000]);
+        assert_eq!(sample.timestamp, SystemTime::UNIX_EPOCH + Duration::from_secs(101));
+
+        // Truncated records and other record types are ignored
+        assert!(parse_record(&record[..record.len() - 4], &clock).is_none());
+        let mut lost = record.clone();
+        lost[0] = 2;
+        assert!(parse_record(&lost, &clock).is_none());
+    }
+}
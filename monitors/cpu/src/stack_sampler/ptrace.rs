@@ -0,0 +1,191 @@
+use super::{last_os_error, thread_ids, RawSample, SamplerConfig, SamplerError};
+use std::time::{Duration, Instant, SystemTime};
+
+/// Reported in the high bits of the wait status for a `PTRACE_INTERRUPT` stop
+const PTRACE_EVENT_STOP: libc::c_int = 128;
+
+/// A thread stopped with `PTRACE_SEIZE` + `PTRACE_INTERRUPT`, resumed on drop
+struct StoppedThread {
+    tid: libc::pid_t,
+    /// Signal to deliver on detach
+    signal: libc::c_int,
+}
+
+impl StoppedThread {
+    /// `Ok(None)` if the thread exited before it could be stopped
+    fn stop(tid: u32) -> Result<Option<Self>, SamplerError> {
+        let tid = tid as libc::pid_t;
+        if unsafe { libc::ptrace(libc::PTRACE_SEIZE, tid, 0, 0) } < 0 {
+            let error = last_os_error();
+            return match error.raw_os_error() {
+                Some(libc::ESRCH) => Ok(None),
+                Some(libc::EPERM) => Err(SamplerError::PermissionDenied(format!(
+                    "ptrace not permitted ({}); check kernel.yama.ptrace_scope",
+                    error
+                ))),
+                _ => Err(SamplerError::SystemError(format!("ptrace seize: {}", error))),
+            };
+        }
+
+        let mut thread = Self { tid, signal: 0 };
+        if unsafe { libc::ptrace(libc::PTRACE_INTERRUPT, tid, 0, 0) } < 0 {
+            return Ok(None);
+        }
+        let mut status = 0;
+        if unsafe { libc::waitpid(tid, &mut status, libc::__WALL) } != tid || !libc::WIFSTOPPED(status) {
+            return Ok(None);
+        }
+        if status >> 16 != PTRACE_EVENT_STOP {
+            // A signal reached the thread before the interrupt; pass it on
+            // when detaching instead of swallowing it, and skip this round
+            thread.signal = libc::WSTOPSIG(status);
+            return Ok(None);
+        }
+        Ok(Some(thread))
+    }
+
+    /// Program counter and frame pointer
+    #[cfg(any(target_arch = "x86_64", target_arch = "aarch64"))]
+    fn registers(&self) -> Option<(u64, u64)> {
+        let mut regs: libc::user_regs_struct = unsafe { std::mem::zeroed() };
+        let mut iov = libc::iovec {
+            iov_base: &mut regs as *mut _ as *mut libc::c_void,
+            iov_len: std::mem::size_of::<libc::user_regs_struct>(),
+        };
+        let result = unsafe {
+            libc::ptrace(libc::PTRACE_GETREGSET, self.tid, libc::NT_PRSTATUS, &mut iov as *mut libc::iovec)
+        };
+        if result < 0 {
+            return None;
+        }
+
+        #[cfg(target_arch = "x86_64")]
+        return Some((regs.rip, regs.rbp));
+        #[cfg(target_arch = "aarch64")]
+        return Some((regs.pc, regs.regs[29]));
+    }
+
+    #[cfg(not(any(target_arch = "x86_64", target_arch = "aarch64")))]
+    fn registers(&self) -> Option<(u64, u64)> {
+        None
+    }
+
+    fn read_word(&self, address: u64) -> Option<u64> {
+        let mut word = 0u64;
+        let local = libc::iovec { iov_base: &mut word as *mut u64 as *mut libc::c_void, iov_len: 8 };
+        let remote = libc::iovec { iov_base: address as *mut libc::c_void, iov_len: 8 };
+        let read = unsafe { libc::process_vm_readv(self.tid, &local, 1, &remote, 1, 0) };
+        (read == 8).then_some(word)
+    }
+}
+
+impl Drop for StoppedThread {
+    fn drop(&mut self) {
+        unsafe {
+            libc::ptrace(libc::PTRACE_DETACH, self.tid, 0, self.signal);
+        }
+    }
+}
+
+pub(super) fn sample(pid: u32, config: &SamplerConfig) -> Result<Vec<RawSample>, SamplerError> {
+    if cfg!(not(any(target_arch = "x86_64", target_arch = "aarch64"))) {
+        return Err(SamplerError::Unsupported("ptrace sampling needs x86_64 or aarch64".to_string()));
+    }
+
+    let interval = Duration::from_secs(1) / config.frequency_hz;
+    let deadline = Instant::now() + config.duration;
+    let mut samples = Vec::new();
+
+    while Instant::now() < deadline {
+        let round = Instant::now();
+        let tids = match thread_ids(pid) {
+            Ok(tids) => tids,
+            // The process exited partway through
+            Err(SamplerError::ProcessNotFound(_)) if !samples.is_empty() => break,
+            Err(e) => return Err(e),
+        };
+
+        for tid in tids {
+            let Some(thread) = StoppedThread::stop(tid)? else {
+                continue;
+            };
+            let Some((pc, fp)) = thread.registers() else {
+                continue;
+            };
+            let (addresses, complete) = walk_frame_pointers(pc, fp, config.max_frames, |address| thread.read_word(address));
+            drop(thread);
+
+            samples.push(RawSample { tid, timestamp: SystemTime::now(), addresses, complete });
+        }
+
+        std::thread::sleep(interval.saturating_sub(round.elapsed()));
+    }
+
+    Ok(samples)
+}
+
+/// Follow the saved frame pointer chain from `fp`, returning the addresses
+/// innermost first and whether the walk reached the outermost frame
+fn walk_frame_pointers(
+    pc: u64,
+    mut fp: u64,
+    max_frames: usize,
+    mut read_word: impl FnMut(u64) -> Option<u64>,
+) -> (Vec<u64>, bool) {
+    let mut addresses = vec![pc];
+
+    while addresses.len() < max_frames {
+        if fp == 0 {
+            return (addresses, true);
+        }
+        if !fp.is_multiple_of(8) {
+            break;
+        }
+        let (Some(next_fp), Some(return_address)) = (read_word(fp), read_word(fp + 8)) else {
+            break;
+        };
+        if return_address == 0 {
+            return (addresses, true);
+        }
+        addresses.push(return_address);
+
+        // Callers live higher up the stack; anything else is a broken chain
+        if next_fp <= fp {
+            return (addresses, next_fp == 0);
+        }
+        fp = next_fp;
+    }
+
+    (addresses, false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_walk_frame_pointers() {
+        // main <- run <- work, each frame: [saved fp, return address]
+        let stack: HashMap<u64, u64> = [
+            (0x7000, 0x7100), (0x7008, 0x401200),
+            (0x7100, 0x7200), (0x7108, 0x401100),
+            (0x7200, 0),      (0x7208, 0x401000),
+        ].into_iter().collect();
+        let read = |address: u64| stack.get(&address).copied();
+
+        let (addresses, complete) = walk_frame_pointers(0x401300, 0x7000, 16, read);
+        assert_eq!(addresses, vec![0x401300, 0x401200, 0x401100, 0x401000]);
+        assert!(complete);
+
+        let (addresses, complete) = walk_frame_pointers(0x401300, 0x7000, 2, read);
+        assert_eq!(addresses.len(), 2);
+        assert!(!complete);
+
+        // Unreadable memory, misaligned or looping frame pointers stop the walk
+        assert_eq!(walk_frame_pointers(0x401300, 0x9000, 16, read), (vec![0x401300], false));
+        assert_eq!(walk_frame_pointers(0x401300, 0x7001, 16, read), (vec![0x401300], false));
+        let looping = |address: u64| Some(if address.is_multiple_of(16) { address } else { 0x401000 });
+        assert_eq!(walk_frame_pointers(0x401300, 0x7000, 16, looping), (vec![0x401300, 0x401000], false));
+    }
+}
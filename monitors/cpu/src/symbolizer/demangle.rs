@@ -0,0 +1,97 @@
+//! Rust and C++ symbol demangling
+//!
+//! Rust names go through `rustc-demangle` without their hash, C++ names
+//! through `cpp_demangle`. Parameter lists and return types are dropped, as
+//! flame graphs label frames by function.
+
+use cpp_demangle::{DemangleOptions, Symbol};
+
+/// Demangle `name`, or return it unchanged if it isn't a mangled Rust or C++
+/// name
+pub fn demangle(name: &str) -> String {
+    if is_rust(name) {
+        if let Ok(demangled) = rustc_demangle::try_demangle(name) {
+            return format!("{:#}", demangled);
+        }
+    }
+
+    // Compiler-generated clones such as `.cold` or `.isra.0` follow the name;
+    // `cpp_demangle` drops them along with the parameters
+    let (mangled, clone_suffix) = match name.find('.') {
+        Some(dot) if name.starts_with("_Z") => (&name[..dot], Some(&name[dot..])),
+        _ => (name, None),
+    };
+    match (demangle_cpp(mangled), clone_suffix) {
+        (Some(demangled), Some(suffix)) => format!("{} [clone {}]", demangled, suffix),
+        (Some(demangled), None) => demangled,
+        (None, _) => name.to_string(),
+    }
+}
+
+/// `_R` (v0) names, and legacy `_ZN...17h<hash>E` names
+fn is_rust(name: &str) -> bool {
+    if name.starts_with("_R") {
+        return true;
+    }
+    let Some(body) = name.strip_prefix("_ZN").and_then(|body| body.strip_suffix('E')) else {
+        return false;
+    };
+    body.len() > 19
+        && body[body.len() - 19..].starts_with("17h")
+        && body[body.len() - 16..].bytes().all(|b| b.is_ascii_hexdigit())
+}
+
+pub fn demangle_cpp(name: &str) -> Option<String> {
+    if !name.starts_with("_Z") {
+        return None;
+    }
+    let options = DemangleOptions::new().no_params().no_return_type();
+    Symbol::new(name).ok()?.demangle(&options).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_demangle_rust() {
+        assert_eq!(demangle("_ZN4core3ptr13drop_in_place17h1234567890abcdefE"), "core::ptr::drop_in_place");
+        assert_eq!(demangle("_RNvCskwGfYPst2Cb_3foo16example_function"), "foo::example_function");
+    }
+
+    #[test]
+    fn test_demangle_cpp() {
+        let cases = [
+            ("_ZN3foo3barEv", "foo::bar"),
+            ("_ZNK3foo3barEv", "foo::bar"),
+            ("_Z3maxIiET_S0_S0_", "max<int>"),
+            ("_ZN3FooC2Ev", "Foo::Foo"),
+            ("_ZN3FooD1Ev", "Foo::~Foo"),
+            ("_ZplRK1AS1_", "operator+"),
+            ("_ZN4llvm5APInt4sdivERKS0_", "llvm::APInt::sdiv"),
+            ("_ZNSt8ios_base4InitC1Ev", "std::ios_base::Init::Init"),
+            ("_ZNSt6vectorIiSaIiEE9push_backERKi", "std::vector<int, std::allocator<int> >::push_back"),
+            ("_ZN9__gnu_cxx13new_allocatorIcE8allocateEmPKv", "__gnu_cxx::new_allocator<char>::allocate"),
+            (
+                "_ZNSt7__cxx1112basic_stringIcSt11char_traitsIcESaIcEE6appendEPKc",
+                "std::__cxx11::basic_string<char, std::char_traits<char>, std::allocator<char> >::append",
+            ),
+            ("_ZZ4mainENKUlvE_clEv", "main::{lambda()#1}::operator() const"),
+            ("_ZZN3app3runEvENKUliE0_clEi", "app::run()::{lambda(int)#2}::operator() const"),
+            ("_ZN12_GLOBAL__N_16workerEv", "(anonymous namespace)::worker"),
+            ("_ZTV3Foo", "{vtable(Foo)}"),
+            ("_ZThn8_N3Foo3runEv", "{virtual override thunk({offset(-8)}, Foo::run)}"),
+            ("_ZN3foo3barEv.cold", "foo::bar [clone .cold]"),
+            ("_ZSt4swapIiEvRT_S1_", "std::swap<int>"),
+            ("_ZN5boost6detail17sp_counted_impl_pIN3app6ServerEE7disposeEv", "boost::detail::sp_counted_impl_p<app::Server>::dispose"),
+        ];
+        for (mangled, expected) in cases {
+            assert_eq!(demangle(mangled), expected, "{}", mangled);
+        }
+
+        // Not mangled at all, or not validly
+        assert_eq!(demangle("malloc"), "malloc");
+        assert_eq!(demangle("_Zgarbage"), "_Zgarbage");
+        assert_eq!(demangle("_Z18446744073709551615a"), "_Z18446744073709551615a");
+    }
+}
@@ -0,0 +1,240 @@
+use object::{Object, ObjectSection, ObjectSegment, ObjectSymbol, SymbolKind};
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+/// A function symbol covering `start..end` in the module's virtual addresses
+#[derive(Debug, Clone)]
+struct Symbol {
+    start: u64,
+    end: u64,
+    name: String,
+}
+
+/// Source position for the addresses `start..end`
+#[derive(Debug, Clone, Copy)]
+struct LineRange {
+    start: u64,
+    end: u64,
+    file: usize,
+    line: u32,
+}
+
+/// A loadable segment, mapping file offsets back to virtual addresses
+#[derive(Debug, Clone, Copy)]
+struct Segment {
+    address: u64,
+    file_offset: u64,
+    file_size: u64,
+}
+
+/// What an address resolved to
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Location {
+    /// Raw (mangled) symbol name
+    pub symbol: Option<String>,
+    pub file: Option<Arc<str>>,
+    pub line: Option<u32>,
+}
+
+/// Symbols and line tables of one ELF file, looked up by file offset
+#[derive(Debug, Default)]
+pub struct ModuleSymbols {
+    build_id: Option<String>,
+    segments: Vec<Segment>,
+    symbols: Vec<Symbol>,
+    lines: Vec<LineRange>,
+    files: Vec<Arc<str>>,
+}
+
+impl ModuleSymbols {
+    /// Build id of an ELF image as lowercase hex, without loading its symbols
+    pub fn read_build_id(data: &[u8]) -> Option<String> {
+        let file = object::File::parse(data).ok()?;
+        file.build_id().ok().flatten().map(hex)
+    }
+
+    /// Load an ELF image, pulling DWARF from the separate debug file under
+    /// `debug_root/usr/lib/debug/.build-id` when the binary is stripped
+    pub fn load(data: &[u8], debug_root: &Path) -> io::Result<Self> {
+        let file = object::File::parse(data).map_err(invalid_data)?;
+        let mut module = Self {
+            build_id: file.build_id().ok().flatten().map(hex),
+            segments: file.segments()
+                .map(|segment| {
+                    let (file_offset, file_size) = segment.file_range();
+                    Segment { address: segment.address(), file_offset, file_size }
+                })
+                .collect(),
+            ..Self::default()
+        };
+        module.add_symbols(&file);
+
+        let debug_data = match (has_line_info(&file), &module.build_id) {
+            (false, Some(build_id)) => std::fs::read(debug_file_path(debug_root, build_id)).ok(),
+            _ => None,
+        };
+        match debug_data.as_deref().and_then(|data| object::File::parse(data).ok()) {
+            Some(debug_file) => {
+                if module.symbols.is_empty() {
+                    module.add_symbols(&debug_file);
+                }
+                module.add_lines(&debug_file);
+            }
+            None => module.add_lines(&file),
+        }
+
+        Ok(module)
+    }
+
+    pub fn build_id(&self) -> Option<&str> {
+        self.build_id.as_deref()
+    }
+
+    /// Resolve the byte at `file_offset` in the ELF file
+    pub fn lookup(&self, file_offset: u64) -> Location {
+        let Some(address) = self.virtual_address(file_offset) else {
+            return Location::default();
+        };
+
+        let index = self.symbols.partition_point(|symbol| symbol.start <= address);
+        let symbol = self.symbols[..index].last()
+            .filter(|symbol| address < symbol.end)
+            .map(|symbol| symbol.name.clone());
+
+        let index = self.lines.partition_point(|range| range.start <= address);
+        let line = self.lines[..index].last().filter(|range| address < range.end);
+
+        Location {
+            symbol,
+            file: line.map(|range| self.files[range.file].clone()),
+            line: line.map(|range| range.line).filter(|&line| line != 0),
+        }
+    }
+
+    fn virtual_address(&self, file_offset: u64) -> Option<u64> {
+        self.segments.iter()
+            .find(|s| file_offset >= s.file_offset && file_offset < s.file_offset + s.file_size)
+            .map(|s| file_offset - s.file_offset + s.address)
+    }
+
+    /// Defined functions from `.symtab` and `.dynsym`, sorted and with
+    /// zero-sized symbols extended to the next one
+    fn add_symbols(&mut self, file: &object::File) {
+        let mut symbols: Vec<Symbol> = file.symbols()
+            .chain(file.dynamic_symbols())
+            .filter(|symbol| symbol.kind() == SymbolKind::Text && symbol.is_definition())
+            .filter_map(|symbol| {
+                let name = symbol.name().ok().filter(|name| !name.is_empty())?;
+                Some(Symbol { start: symbol.address(), end: symbol.address() + symbol.size(), name: name.to_string() })
+            })
+            .collect();
+        symbols.sort_by(|a, b| a.start.cmp(&b.start).then(b.end.cmp(&a.end)));
+        symbols.dedup_by_key(|symbol| symbol.start);
+
+        for index in 0..symbols.len() {
+            if symbols[index].end == symbols[index].start {
+                symbols[index].end = symbols.get(index + 1).map_or(u64::MAX, |next| next.start);
+            }
+        }
+        self.symbols = symbols;
+    }
+
+    /// Rows of every DWARF line program, flattened into sorted ranges
+    fn add_lines(&mut self, file: &object::File) {
+        let endian = if file.is_little_endian() { gimli::RunTimeEndian::Little } else { gimli::RunTimeEndian::Big };
+        let load = |id: gimli::SectionId| -> Result<Cow<[u8]>, gimli::Error> {
+            Ok(file.section_by_name(id.name())
+                .and_then(|section| section.uncompressed_data().ok())
+                .unwrap_or(Cow::Borrowed(&[])))
+        };
+        let Ok(sections) = gimli::DwarfSections::load(load) else {
+            return;
+        };
+        let dwarf = sections.borrow(|section| gimli::EndianSlice::new(section, endian));
+
+        let mut file_ids: HashMap<String, usize> = HashMap::new();
+        let mut units = dwarf.units();
+        while let Ok(Some(header)) = units.next() {
+            let Ok(unit) = dwarf.unit(header) else {
+                continue;
+            };
+            let Some(program) = unit.line_program.clone() else {
+                continue;
+            };
+
+            let mut unit_files: HashMap<u64, usize> = HashMap::new();
+            let mut previous: Option<(u64, usize, u32)> = None;
+            let mut rows = program.rows();
+            while let Ok(Some((header, row))) = rows.next_row() {
+                if let Some((start, file, line)) = previous {
+                    if row.address() > start {
+                        self.lines.push(LineRange { start, end: row.address(), file, line });
+                    }
+                }
+                if row.end_sequence() {
+                    previous = None;
+                    continue;
+                }
+
+                let file = *unit_files.entry(row.file_index()).or_insert_with(|| {
+                    let path = row.file(header)
+                        .and_then(|entry| file_path(&dwarf, &unit, header, entry))
+                        .unwrap_or_else(|| "??".to_string());
+                    let next_id = self.files.len();
+                    *file_ids.entry(path.clone()).or_insert_with(|| {
+                        self.files.push(Arc::from(path));
+                        next_id
+                    })
+                });
+                let line = row.line().map_or(0, |line| line.get() as u32);
+                previous = Some((row.address(), file, line));
+            }
+        }
+
+        self.lines.sort_by_key(|range| range.start);
+    }
+}
+
+type Reader<'a> = gimli::EndianSlice<'a, gimli::RunTimeEndian>;
+
+/// `directory/name` for a line program file entry
+fn file_path(
+    dwarf: &gimli::Dwarf<Reader>,
+    unit: &gimli::Unit<Reader>,
+    header: &gimli::LineProgramHeader<Reader>,
+    entry: &gimli::FileEntry<Reader>,
+) -> Option<String> {
+    let name = dwarf.attr_string(unit, entry.path_name()).ok()?.to_string_lossy().into_owned();
+    if name.starts_with('/') {
+        return Some(name);
+    }
+
+    let directory = entry.directory(header)
+        .and_then(|dir| dwarf.attr_string(unit, dir).ok())
+        .map(|dir| dir.to_string_lossy().into_owned());
+    Some(match directory {
+        Some(directory) if !directory.is_empty() => format!("{}/{}", directory.trim_end_matches('/'), name),
+        _ => name,
+    })
+}
+
+fn has_line_info(file: &object::File) -> bool {
+    file.section_by_name(".debug_line").is_some_and(|section| section.size() > 0)
+}
+
+/// `<root>/usr/lib/debug/.build-id/ab/cdef....debug`
+pub fn debug_file_path(root: &Path, build_id: &str) -> PathBuf {
+    let (directory, rest) = build_id.split_at(2.min(build_id.len()));
+    root.join("usr/lib/debug/.build-id").join(directory).join(format!("{}.debug", rest))
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn invalid_data(error: object::Error) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, error.to_string())
+}
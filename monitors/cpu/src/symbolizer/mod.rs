@@ -0,0 +1,234 @@
+//! Resolves sampled addresses to function names and source lines
+//!
+//! Each frame is located in the file mapped at its address, either from the
+//! module and offset the sampler recorded or from `/proc/<pid>/maps`. Files
+//! are read through `/proc/<pid>/root` so binaries inside containers
+//! resolve too. Symbol tables and DWARF line programs are loaded once per
+//! build id and reused across processes; stripped binaries pick up their
+//! line info from `/usr/lib/debug/.build-id` when it is installed.
+
+mod demangle;
+mod elf;
+
+pub use demangle::demangle;
+pub use elf::{Location, ModuleSymbols};
+
+use crate::{ProcessMaps, StackFrame, StackTrace};
+use once_cell::sync::Lazy;
+use std::collections::HashMap;
+use std::fs;
+use std::os::unix::fs::MetadataExt;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+/// Identifies a file's contents without reading it: the same library seen
+/// through different processes' roots, and unchanged since it was loaded
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+struct FileIdentity {
+    device: u64,
+    inode: u64,
+    modified_ns: i64,
+    size: u64,
+}
+
+impl FileIdentity {
+    fn of(path: &Path) -> Option<Self> {
+        let metadata = fs::metadata(path).ok()?;
+        Some(Self {
+            device: metadata.dev(),
+            inode: metadata.ino(),
+            modified_ns: metadata.mtime() * 1_000_000_000 + metadata.mtime_nsec(),
+            size: metadata.size(),
+        })
+    }
+}
+
+/// Caches loaded modules and rewrites stack frames in bulk
+#[derive(Debug, Default)]
+pub struct Symbolizer {
+    /// Loaded modules by build id, or by path for files without one; `None`
+    /// records a file that couldn't be parsed
+    modules: HashMap<String, Option<Arc<ModuleSymbols>>>,
+    /// Module key of each file already read
+    files: HashMap<FileIdentity, String>,
+}
+
+impl Symbolizer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Number of distinct modules loaded so far
+    pub fn cached_modules(&self) -> usize {
+        self.modules.values().filter(|module| module.is_some()).count()
+    }
+
+    pub fn clear(&mut self) {
+        self.modules.clear();
+        self.files.clear();
+    }
+
+    /// Fill in `symbol`, `file` and `line` of every frame that has no symbol
+    /// yet. Resolved frames have `module` shortened to the file name.
+    pub fn symbolize(&mut self, traces: &mut [StackTrace]) {
+        let mut maps: HashMap<u32, Option<ProcessMaps>> = HashMap::new();
+        let mut resolved: HashMap<(u32, String, u64), Location> = HashMap::new();
+
+        for trace in traces.iter_mut() {
+            let pid = trace.pid;
+            for (depth, frame) in trace.frames.iter_mut().enumerate() {
+                if frame.symbol.is_some() {
+                    continue;
+                }
+                let process_maps = maps.entry(pid).or_insert_with(|| ProcessMaps::read(pid).ok());
+                let Some((module, offset)) = locate(frame, process_maps.as_ref()) else {
+                    continue;
+                };
+                // Return addresses point after the call; look up the call itself
+                let offset = if depth > 0 { offset.saturating_sub(1) } else { offset };
+
+                let key = (pid, module, offset);
+                if !resolved.contains_key(&key) {
+                    let location = self.resolve(pid, &key.1, offset);
+                    resolved.insert(key.clone(), location);
+                }
+                apply(frame, &key.1, &resolved[&key]);
+            }
+        }
+    }
+
+    /// Look up `offset` in the file `module` as `pid` sees it
+    pub fn resolve(&mut self, pid: u32, module: &str, offset: u64) -> Location {
+        let root = PathBuf::from(format!("/proc/{}/root", pid));
+        let in_root = root.join(module.trim_start_matches('/'));
+        let (path, debug_root) = if in_root.exists() {
+            (in_root, root)
+        } else {
+            (PathBuf::from(module), PathBuf::from("/"))
+        };
+
+        self.module(&path, &debug_root)
+            .map(|symbols| symbols.lookup(offset))
+            .unwrap_or_default()
+    }
+
+    fn module(&mut self, path: &Path, debug_root: &Path) -> Option<Arc<ModuleSymbols>> {
+        let identity = FileIdentity::of(path)?;
+        if let Some(key) = self.files.get(&identity) {
+            return self.modules.get(key).cloned().flatten();
+        }
+
+        let data = fs::read(path).ok()?;
+        let key = ModuleSymbols::read_build_id(&data).unwrap_or_else(|| path.display().to_string());
+        let module = self.modules.entry(key.clone())
+            .or_insert_with(|| ModuleSymbols::load(&data, debug_root).ok().map(Arc::new))
+            .clone();
+        self.files.insert(identity, key);
+        module
+    }
+}
+
+/// The module path and file offset of `frame`, preferring what the sampler
+/// recorded over the current maps, which may have changed since
+fn locate(frame: &StackFrame, maps: Option<&ProcessMaps>) -> Option<(String, u64)> {
+    let (module, offset) = match (&frame.module, frame.offset) {
+        (Some(module), Some(offset)) if module.starts_with('/') => (module.clone(), offset),
+        _ => {
+            let mapping = maps?.find(frame.address)?;
+            (mapping.path.clone(), mapping.file_offset_of(frame.address))
+        }
+    };
+
+    // Pseudo-files such as `[vdso]` have nothing on disk to read
+    if !module.starts_with('/') {
+        return None;
+    }
+    let module = module.strip_suffix(" (deleted)").map(str::to_string).unwrap_or(module);
+    Some((module, offset))
+}
+
+fn apply(frame: &mut StackFrame, module: &str, location: &Location) {
+    let Some(symbol) = &location.symbol else {
+        return;
+    };
+    frame.symbol = Some(demangle(symbol));
+    frame.module = Some(module.rsplit('/').next().unwrap_or(module).to_string());
+    frame.file = location.file.as_ref().map(|file| file.to_string());
+    frame.line = location.line;
+}
+
+/// The symbolizer shared by every sampler, so each module is loaded once
+static SYMBOLIZER: Lazy<Mutex<Symbolizer>> = Lazy::new(|| Mutex::new(Symbolizer::new()));
+
+pub fn global_symbolizer() -> &'static Mutex<Symbolizer> {
+    &SYMBOLIZER
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::SystemTime;
+
+    #[inline(never)]
+    fn symbolizer_test_target() -> u64 {
+        std::hint::black_box(42)
+    }
+
+    fn trace(addresses: &[u64]) -> StackTrace {
+        StackTrace {
+            pid: std::process::id(),
+            thread_id: None,
+            timestamp: SystemTime::now(),
+            frames: addresses.iter()
+                .map(|&address| StackFrame { address, symbol: None, module: None, file: None, line: None, offset: None })
+                .collect(),
+            sample_duration_ms: 10,
+            is_complete: true,
+        }
+    }
+
+    #[test]
+    fn test_symbolizes_own_functions() {
+        let address = symbolizer_test_target as *const () as u64;
+        let mut traces = vec![trace(&[address, 0x10]), trace(&[address])];
+
+        let mut symbolizer = Symbolizer::new();
+        symbolizer.symbolize(&mut traces);
+
+        let frame = &traces[0].frames[0];
+        let symbol = frame.symbol.as_deref().unwrap();
+        assert!(symbol.ends_with("symbolizer_test_target"), "{}", symbol);
+        assert!(frame.module.as_deref().is_some_and(|module| !module.contains('/')));
+        if let Some(file) = &frame.file {
+            assert!(file.ends_with("symbolizer/mod.rs"), "{}", file);
+            assert!(frame.line.is_some());
+        }
+        assert_eq!(traces[1].frames[0].symbol, frame.symbol);
+        assert_eq!(traces[0].frames[1].symbol, None);
+        assert_eq!(symbolizer.cached_modules(), 1);
+
+        // Already symbolized frames are left alone
+        traces[0].frames[0].symbol = Some("custom".to_string());
+        symbolizer.symbolize(&mut traces);
+        assert_eq!(traces[0].frames[0].symbol.as_deref(), Some("custom"));
+    }
+
+    #[test]
+    fn test_locate_prefers_recorded_module() {
+        let maps = ProcessMaps::parse(
+            "400000-500000 r-xp 00001000 fd:01 42 /usr/bin/app (deleted)\n\
+             7ffd5e1f0000-7ffd5e1f2000 r-xp 00000000 00:00 0 [vdso]\n",
+        );
+        let mut frame = StackFrame { address: 0x400100, symbol: None, module: None, file: None, line: None, offset: None };
+        assert_eq!(locate(&frame, Some(&maps)), Some(("/usr/bin/app".to_string(), 0x1100)));
+
+        frame.module = Some("/usr/lib/libfoo.so".to_string());
+        frame.offset = Some(0x20);
+        assert_eq!(locate(&frame, Some(&maps)), Some(("/usr/lib/libfoo.so".to_string(), 0x20)));
+
+        frame = StackFrame { address: 0x7ffd5e1f0010, ..frame };
+        frame.module = None;
+        assert_eq!(locate(&frame, Some(&maps)), None);
+        assert_eq!(locate(&frame, None), None);
+    }
+}
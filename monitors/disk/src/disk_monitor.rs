@@ -1,3 +1,5 @@
+use reaper_core::common::{Monitor, MonitorType};
+use reaper_core::metrics::{MetricSample, SeriesKey};
 use sysinfo::Disks;
 use std::collections::HashMap;
 
@@ -181,4 +183,38 @@ impl DiskMonitor {
             format!("{:.1} {}", size, UNITS[unit_index])
         }
     }
-}
\ No newline at end of file
+}
+
+impl Monitor for DiskMonitor {
+    fn name(&self) -> &str {
+        "disk"
+    }
+    
+    fn monitor_type(&self) -> MonitorType {
+        MonitorType::Disk
+    }
+    
+    fn refresh(&mut self) {
+        DiskMonitor::refresh(self);
+    }
+    
+    fn metric_samples(&self) -> Vec<MetricSample> {
+        self.get_all_disks()
+            .into_iter()
+            .flat_map(|disk| [
+                MetricSample::new(
+                    SeriesKey::new("disk.used_bytes").with_label("mount_point", &disk.mount_point),
+                    disk.used_bytes as f64,
+                ),
+                MetricSample::new(
+                    SeriesKey::new("disk.available_bytes").with_label("mount_point", &disk.mount_point),
+                    disk.available_bytes as f64,
+                ),
+                MetricSample::new(
+                    SeriesKey::new("disk.usage_percent").with_label("mount_point", &disk.mount_point),
+                    disk.usage_percent as f64,
+                ),
+            ])
+            .collect()
+    }
+}
@@ -1,14 +1,18 @@
 use crate::disk_monitor::DiskMonitor;
 use crate::file_analyzer::{FileAnalyzer, DirectoryAnalysis, DuplicateGroup, FileEntry, FileCategory};
 use once_cell::sync::Lazy;
+use reaper_core::sampling;
 use std::ffi::CString;
 use std::os::raw::c_char;
 use std::sync::Mutex;
 use std::sync::Arc;
 use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
 
-static DISK_MONITOR: Lazy<Mutex<DiskMonitor>> = Lazy::new(|| {
-    Mutex::new(DiskMonitor::new())
+static DISK_MONITOR: Lazy<Arc<Mutex<DiskMonitor>>> = Lazy::new(|| {
+    let monitor = Arc::new(Mutex::new(DiskMonitor::new()));
+    sampling::register_global(Arc::clone(&monitor), Duration::from_secs(10));
+    monitor
 });
 
 #[repr(C)]
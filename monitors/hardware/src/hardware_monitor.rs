@@ -1,3 +1,5 @@
+use reaper_core::common::{Monitor, MonitorType};
+use reaper_core::metrics::{MetricSample, SeriesKey};
 use sysinfo::{System, Components};
 use std::process::Command;
 
@@ -233,6 +235,37 @@ impl HardwareMonitor {
     }
 }
 
+impl Monitor for HardwareMonitor {
+    fn name(&self) -> &str {
+        "hardware"
+    }
+    
+    fn monitor_type(&self) -> MonitorType {
+        MonitorType::Hardware
+    }
+    
+    fn refresh(&mut self) {
+        self.cached_metrics = None;
+        self.get_metrics();
+    }
+    
+    fn metric_samples(&self) -> Vec<MetricSample> {
+        let Some(metrics) = &self.cached_metrics else {
+            return Vec::new();
+        };
+        
+        let mut samples: Vec<MetricSample> = metrics.temperatures
+            .iter()
+            .map(|sensor| MetricSample::new(
+                SeriesKey::new("thermal.temperature_celsius").with_label("sensor", &sensor.name),
+                sensor.value_celsius as f64,
+            ))
+            .collect();
+        samples.push(MetricSample::new(SeriesKey::new("thermal.state"), metrics.thermal_state.clone() as u8 as f64));
+        samples
+    }
+}
+
 impl SensorType {
     pub fn icon(&self) -> &str {
         match self {
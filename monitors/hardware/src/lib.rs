@@ -1,5 +1,7 @@
 use once_cell::sync::Lazy;
-use std::sync::Mutex;
+use reaper_core::sampling;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
 
 pub mod hardware_monitor;
 pub mod ffi;
@@ -8,6 +10,8 @@ pub mod ffi;
 pub use hardware_monitor::{HardwareMonitor, HardwareMetrics, TemperatureSensor, SensorType};
 
 // Global hardware monitor instance
-static HARDWARE_MONITOR: Lazy<Mutex<HardwareMonitor>> = Lazy::new(|| {
-    Mutex::new(HardwareMonitor::new())
+static HARDWARE_MONITOR: Lazy<Arc<Mutex<HardwareMonitor>>> = Lazy::new(|| {
+    let monitor = Arc::new(Mutex::new(HardwareMonitor::new()));
+    sampling::register_global(Arc::clone(&monitor), Duration::from_secs(5));
+    monitor
 });
\ No newline at end of file
@@ -0,0 +1,274 @@
+//! What system memory is used for, from `/proc/meminfo` and `/proc/vmstat`
+//!
+//! meminfo gives the split at one instant: page cache, buffers, slab,
+//! shmem, hugepages, dirty pages waiting for writeback. vmstat counters
+//! only ever grow, so the collector turns them into rates between
+//! refreshes: faults, paging and swapping per second, and OOM kills.
+
+use std::collections::HashMap;
+use std::time::Instant;
+#[cfg(target_os = "linux")]
+use reaper_core::platform::linux::procfs;
+
+/// Memory by use, in bytes
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct MemoryComposition {
+    pub total_bytes: u64,
+    pub free_bytes: u64,
+    pub available_bytes: u64,
+    /// Block device metadata
+    pub buffers_bytes: u64,
+    /// Page cache, shmem included
+    pub cached_bytes: u64,
+    /// Pages in both swap and memory
+    pub swap_cached_bytes: u64,
+    pub active_anon_bytes: u64,
+    pub inactive_anon_bytes: u64,
+    pub active_file_bytes: u64,
+    pub inactive_file_bytes: u64,
+    /// Anonymous pages mapped into processes
+    pub anon_bytes: u64,
+    /// Files mapped into processes
+    pub mapped_bytes: u64,
+    /// tmpfs, shared memory segments and shared anonymous mappings
+    pub shmem_bytes: u64,
+    /// Kernel slab caches the kernel can drop under pressure
+    pub slab_reclaimable_bytes: u64,
+    pub slab_unreclaimable_bytes: u64,
+    pub kernel_stack_bytes: u64,
+    pub page_tables_bytes: u64,
+    /// Waiting to be written back to disk
+    pub dirty_bytes: u64,
+    /// Being written back right now
+    pub writeback_bytes: u64,
+    pub anon_huge_bytes: u64,
+    /// Pool of explicitly reserved hugepages
+    pub hugepages_total_bytes: u64,
+    pub hugepages_free_bytes: u64,
+    pub swap_total_bytes: u64,
+    pub swap_free_bytes: u64,
+    /// Memory promised to processes, which may exceed what exists
+    pub committed_bytes: u64,
+    pub commit_limit_bytes: u64,
+    /// Rates since the previous refresh; `None` on the first
+    pub rates: Option<VmRates>,
+}
+
+impl MemoryComposition {
+    /// Build from `parse_meminfo` output, which is already in bytes
+    pub fn from_meminfo(meminfo: &HashMap<String, u64>) -> Self {
+        let get = |key: &str| meminfo.get(key).copied().unwrap_or(0);
+        let hugepage_size = get("Hugepagesize");
+
+        Self {
+            total_bytes: get("MemTotal"),
+            free_bytes: get("MemFree"),
+            available_bytes: get("MemAvailable"),
+            buffers_bytes: get("Buffers"),
+            cached_bytes: get("Cached"),
+            swap_cached_bytes: get("SwapCached"),
+            active_anon_bytes: get("Active(anon)"),
+            inactive_anon_bytes: get("Inactive(anon)"),
+            active_file_bytes: get("Active(file)"),
+            inactive_file_bytes: get("Inactive(file)"),
+            anon_bytes: get("AnonPages"),
+            mapped_bytes: get("Mapped"),
+            shmem_bytes: get("Shmem"),
+            slab_reclaimable_bytes: get("SReclaimable"),
+            slab_unreclaimable_bytes: get("SUnreclaim"),
+            kernel_stack_bytes: get("KernelStack"),
+            page_tables_bytes: get("PageTables"),
+            dirty_bytes: get("Dirty"),
+            writeback_bytes: get("Writeback"),
+            anon_huge_bytes: get("AnonHugePages"),
+            // Hugepage counts have no unit
+            hugepages_total_bytes: get("HugePages_Total") * hugepage_size,
+            hugepages_free_bytes: get("HugePages_Free") * hugepage_size,
+            swap_total_bytes: get("SwapTotal"),
+            swap_free_bytes: get("SwapFree"),
+            committed_bytes: get("Committed_AS"),
+            commit_limit_bytes: get("CommitLimit"),
+            rates: None,
+        }
+    }
+
+    /// Cache the kernel can drop, counted as cache by `free`: page cache
+    /// that isn't shmem, plus reclaimable slab
+    pub fn reclaimable_cache_bytes(&self) -> u64 {
+        self.cached_bytes.saturating_sub(self.shmem_bytes) + self.slab_reclaimable_bytes
+    }
+}
+
+/// vmstat counters that matter for memory, as read
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct VmCounters {
+    pub page_faults: u64,
+    pub major_faults: u64,
+    /// KiB read from and written to block devices
+    pub paged_in_kb: u64,
+    pub paged_out_kb: u64,
+    /// Pages swapped in and out
+    pub swapped_in: u64,
+    pub swapped_out: u64,
+    pub oom_kills: u64,
+}
+
+impl VmCounters {
+    pub fn from_vmstat(vmstat: &HashMap<String, u64>) -> Self {
+        let get = |key: &str| vmstat.get(key).copied().unwrap_or(0);
+        Self {
+            page_faults: get("pgfault"),
+            major_faults: get("pgmajfault"),
+            paged_in_kb: get("pgpgin"),
+            paged_out_kb: get("pgpgout"),
+            swapped_in: get("pswpin"),
+            swapped_out: get("pswpout"),
+            oom_kills: get("oom_kill"),
+        }
+    }
+}
+
+/// Activity between two refreshes
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct VmRates {
+    pub interval_secs: f64,
+    pub page_faults_per_sec: f64,
+    /// Faults that had to read from disk
+    pub major_faults_per_sec: f64,
+    pub page_in_bytes_per_sec: f64,
+    pub page_out_bytes_per_sec: f64,
+    pub swap_in_bytes_per_sec: f64,
+    pub swap_out_bytes_per_sec: f64,
+    /// OOM kills during the interval
+    pub oom_kills: u64,
+}
+
+impl VmRates {
+    pub fn between(before: &VmCounters, after: &VmCounters, interval_secs: f64, page_size: u64) -> Self {
+        // Counters reset only at boot, but never trust a negative delta
+        let per_sec = |before: u64, after: u64, unit: u64| {
+            if interval_secs > 0.0 { after.saturating_sub(before) as f64 * unit as f64 / interval_secs } else { 0.0 }
+        };
+
+        Self {
+            interval_secs,
+            page_faults_per_sec: per_sec(before.page_faults, after.page_faults, 1),
+            major_faults_per_sec: per_sec(before.major_faults, after.major_faults, 1),
+            page_in_bytes_per_sec: per_sec(before.paged_in_kb, after.paged_in_kb, 1024),
+            page_out_bytes_per_sec: per_sec(before.paged_out_kb, after.paged_out_kb, 1024),
+            swap_in_bytes_per_sec: per_sec(before.swapped_in, after.swapped_in, page_size),
+            swap_out_bytes_per_sec: per_sec(before.swapped_out, after.swapped_out, page_size),
+            oom_kills: after.oom_kills.saturating_sub(before.oom_kills),
+        }
+    }
+}
+
+/// Reads meminfo and vmstat on each refresh, keeping the previous vmstat
+/// counters for rates
+#[derive(Debug, Default)]
+pub struct CompositionCollector {
+    previous: Option<(Instant, VmCounters)>,
+    latest: Option<MemoryComposition>,
+}
+
+impl CompositionCollector {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The composition as of the last refresh; `None` off Linux
+    pub fn latest(&self) -> Option<MemoryComposition> {
+        self.latest
+    }
+
+    #[cfg(target_os = "linux")]
+    pub fn refresh(&mut self) -> Option<MemoryComposition> {
+        let meminfo = procfs::parse_meminfo(&procfs::read_proc_file("meminfo").ok()?);
+        let vmstat = procfs::read_proc_file("vmstat").ok().map(|c| VmCounters::from_vmstat(&procfs::parse_vmstat(&c)));
+        self.update(Instant::now(), &meminfo, vmstat, procfs::page_size())
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    pub fn refresh(&mut self) -> Option<MemoryComposition> {
+        None
+    }
+
+    fn update(
+        &mut self,
+        now: Instant,
+        meminfo: &HashMap<String, u64>,
+        counters: Option<VmCounters>,
+        page_size: u64,
+    ) -> Option<MemoryComposition> {
+        let mut composition = MemoryComposition::from_meminfo(meminfo);
+        if let Some(counters) = counters {
+            if let Some((at, previous)) = &self.previous {
+                let interval = now.saturating_duration_since(*at).as_secs_f64();
+                composition.rates = Some(VmRates::between(previous, &counters, interval, page_size));
+            }
+            self.previous = Some((now, counters));
+        }
+        self.latest = Some(composition);
+        self.latest
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn kb(pairs: &[(&str, u64)]) -> HashMap<String, u64> {
+        pairs.iter().map(|&(k, v)| (k.to_string(), v * 1024)).collect()
+    }
+
+    #[test]
+    fn test_composition_from_meminfo() {
+        let mut meminfo = kb(&[
+            ("MemTotal", 16_000_000), ("MemFree", 1_000_000), ("Buffers", 200_000),
+            ("Cached", 6_000_000), ("Shmem", 500_000), ("SReclaimable", 300_000),
+            ("Dirty", 4_000), ("Hugepagesize", 2048),
+        ]);
+        meminfo.insert("HugePages_Total".to_string(), 10);
+
+        let composition = MemoryComposition::from_meminfo(&meminfo);
+        assert_eq!(composition.total_bytes, 16_000_000 * 1024);
+        assert_eq!(composition.dirty_bytes, 4_000 * 1024);
+        assert_eq!(composition.hugepages_total_bytes, 10 * 2048 * 1024);
+        assert_eq!(composition.reclaimable_cache_bytes(), 5_800_000 * 1024);
+        assert_eq!(composition.rates, None);
+    }
+
+    #[test]
+    fn test_rates_between_refreshes() {
+        let mut collector = CompositionCollector::new();
+        let meminfo = kb(&[("MemTotal", 1024)]);
+        let start = Instant::now();
+        let counters = |faults: u64, swapped_out: u64, ooms: u64| VmCounters {
+            page_faults: faults,
+            paged_in_kb: faults / 10,
+            swapped_out,
+            oom_kills: ooms,
+            ..Default::default()
+        };
+
+        assert_eq!(collector.update(start, &meminfo, Some(counters(1000, 0, 3)), 4096).unwrap().rates, None);
+        let rates = collector.update(start + Duration::from_secs(2), &meminfo, Some(counters(5000, 100, 4)), 4096)
+            .unwrap().rates.unwrap();
+        assert_eq!(rates.page_faults_per_sec, 2000.0);
+        assert_eq!(rates.page_in_bytes_per_sec, 200.0 * 1024.0);
+        assert_eq!(rates.swap_out_bytes_per_sec, 50.0 * 4096.0);
+        assert_eq!(rates.oom_kills, 1);
+        assert_eq!(collector.latest().unwrap().rates, Some(rates));
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_reads_live_composition() {
+        let mut collector = CompositionCollector::new();
+        let first = collector.refresh().unwrap();
+        assert!(first.total_bytes > 0);
+        assert!(first.available_bytes <= first.total_bytes);
+        assert!(collector.refresh().unwrap().rates.is_some());
+    }
+}
@@ -1,11 +1,21 @@
-use crate::memory_monitor::{MemoryMonitor, ProcessMemoryInfo, MemoryPressureLevel};
+use crate::leak_detector::GrowthPattern;
+use crate::memory_limiter::{global_memory_limiter, MemoryLimitError, MemoryLimitSettings};
+use crate::memory_map::{MemoryMap, RegionGrouping};
+use crate::memory_monitor::{rank_memory_processes, MemoryMonitor, ProcessMemoryInfo, MemoryPressureLevel};
+use crate::pressure::ResourcePressure;
+use crate::smaps::{self, MemoryBreakdown, MemorySortKey};
 use once_cell::sync::Lazy;
+use reaper_core::audit::PendingAudit;
+use reaper_core::sampling::{self, Metric};
 use std::ffi::CString;
 use std::os::raw::c_char;
-use std::sync::Mutex;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
 
-static MEMORY_MONITOR: Lazy<Mutex<MemoryMonitor>> = Lazy::new(|| {
-    Mutex::new(MemoryMonitor::new())
+static MEMORY_MONITOR: Lazy<Arc<Mutex<MemoryMonitor>>> = Lazy::new(|| {
+    let monitor = Arc::new(Mutex::new(MemoryMonitor::with_system(sampling::shared_system())));
+    sampling::register_global(Arc::clone(&monitor), Metric::Memory.default_interval());
+    monitor
 });
 
 #[repr(C)]
@@ -158,6 +168,123 @@ fn create_process_memory_list(processes: Vec<ProcessMemoryInfo>) -> *mut CProces
     }))
 }
 
+#[repr(C)]
+#[derive(Default)]
+pub struct CMemoryBreakdown {
+    pub rss_bytes: u64,
+    pub pss_bytes: u64,
+    pub uss_bytes: u64,
+    pub shared_bytes: u64,
+    pub anonymous_bytes: u64,
+    pub file_bytes: u64,
+    pub swap_bytes: u64,
+    pub swap_pss_bytes: u64,
+}
+
+impl From<MemoryBreakdown> for CMemoryBreakdown {
+    fn from(b: MemoryBreakdown) -> Self {
+        Self {
+            rss_bytes: b.rss_bytes,
+            pss_bytes: b.pss_bytes,
+            uss_bytes: b.uss_bytes,
+            shared_bytes: b.shared_bytes,
+            anonymous_bytes: b.anonymous_bytes,
+            file_bytes: b.file_bytes,
+            swap_bytes: b.swap_bytes,
+            swap_pss_bytes: b.swap_pss_bytes,
+        }
+    }
+}
+
+#[repr(C)]
+pub struct CProcessMemoryBreakdown {
+    pub pid: u32,
+    pub name: *mut c_char,
+    pub virtual_memory_bytes: u64,
+    pub has_breakdown: u8,  // 0 when smaps_rollup couldn't be read
+    pub breakdown: CMemoryBreakdown,
+}
+
+#[repr(C)]
+pub struct CProcessMemoryBreakdownList {
+    pub processes: *mut CProcessMemoryBreakdown,
+    pub count: usize,
+}
+
+/// Null when `pid` has gone or its smaps can't be read
+#[no_mangle]
+pub extern "C" fn get_process_memory_breakdown(pid: u32) -> *mut CMemoryBreakdown {
+    match smaps::read_memory_breakdown(pid) {
+        Some(breakdown) => Box::into_raw(Box::new(breakdown.into())),
+        None => std::ptr::null_mut(),
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn free_memory_breakdown(breakdown: *mut CMemoryBreakdown) {
+    if !breakdown.is_null() {
+        unsafe {
+            let _ = Box::from_raw(breakdown);
+        }
+    }
+}
+
+/// `sort_key`: 0 RSS, 1 PSS, 2 USS, 3 shared, 4 anonymous, 5 file,
+/// 6 swap, 7 swap PSS, 8 virtual
+#[no_mangle]
+pub extern "C" fn get_top_memory_processes_by(limit: usize, sort_key: u8) -> *mut CProcessMemoryBreakdownList {
+    let Some(key) = MemorySortKey::from_index(sort_key) else {
+        return std::ptr::null_mut();
+    };
+    // Only the process list is taken under the lock; reading every
+    // process's smaps would block the other memory calls meanwhile
+    let processes = match MEMORY_MONITOR.lock() {
+        Ok(monitor) => monitor.get_process_memory_info(),
+        Err(_) => return std::ptr::null_mut(),
+    };
+    let processes = rank_memory_processes(processes, limit, key);
+
+    let c_processes: Vec<CProcessMemoryBreakdown> = processes.into_iter()
+        .map(|process| CProcessMemoryBreakdown {
+            pid: process.pid,
+            name: CString::new(process.name)
+                .unwrap_or_else(|_| CString::new("Unknown").unwrap())
+                .into_raw(),
+            virtual_memory_bytes: process.virtual_memory_bytes,
+            has_breakdown: process.breakdown.is_some() as u8,
+            breakdown: process.breakdown.map(Into::into).unwrap_or_default(),
+        })
+        .collect();
+
+    let count = c_processes.len();
+    let processes_ptr = if count == 0 {
+        std::ptr::null_mut()
+    } else {
+        let mut boxed = c_processes.into_boxed_slice();
+        let ptr = boxed.as_mut_ptr();
+        std::mem::forget(boxed);
+        ptr
+    };
+    Box::into_raw(Box::new(CProcessMemoryBreakdownList { processes: processes_ptr, count }))
+}
+
+#[no_mangle]
+pub extern "C" fn free_process_memory_breakdown_list(list: *mut CProcessMemoryBreakdownList) {
+    if !list.is_null() {
+        unsafe {
+            let boxed = Box::from_raw(list);
+            if !boxed.processes.is_null() && boxed.count > 0 {
+                let processes = Vec::from_raw_parts(boxed.processes, boxed.count, boxed.count);
+                for process in processes {
+                    if !process.name.is_null() {
+                        let _ = CString::from_raw(process.name);
+                    }
+                }
+            }
+        }
+    }
+}
+
 #[no_mangle]
 pub extern "C" fn free_process_memory_list(list: *mut CProcessMemoryList) {
     if !list.is_null() {
@@ -181,6 +308,172 @@ pub extern "C" fn free_process_memory_list(list: *mut CProcessMemoryList) {
     }
 }
 
+#[repr(C)]
+pub struct CLeakSuspect {
+    pub pid: u32,
+    pub name: *mut c_char,
+    pub growth_bytes_per_min: f64,
+    pub confidence: f32,
+    pub samples: usize,
+    pub window_secs: f64,
+    pub first_bytes: u64,
+    pub last_bytes: u64,
+    pub growth_bytes: f64,
+    pub drops: usize,
+    pub pattern: u8,  // 0 = steady, 1 = rising floor under sawtooth
+}
+
+#[repr(C)]
+pub struct CLeakSuspectList {
+    pub suspects: *mut CLeakSuspect,
+    pub count: usize,
+}
+
+#[no_mangle]
+pub extern "C" fn get_leak_suspects() -> *mut CLeakSuspectList {
+    let suspects = match MEMORY_MONITOR.lock() {
+        Ok(monitor) => monitor.find_leak_suspects(),
+        Err(_) => return std::ptr::null_mut(),
+    };
+
+    let suspects: Vec<CLeakSuspect> = suspects.into_iter()
+        .map(|suspect| CLeakSuspect {
+            pid: suspect.pid,
+            name: c_string(&suspect.name),
+            growth_bytes_per_min: suspect.growth_bytes_per_min,
+            confidence: suspect.confidence,
+            samples: suspect.evidence.samples,
+            window_secs: suspect.evidence.window_secs,
+            first_bytes: suspect.evidence.first_bytes,
+            last_bytes: suspect.evidence.last_bytes,
+            growth_bytes: suspect.evidence.growth_bytes,
+            drops: suspect.evidence.drops,
+            pattern: match suspect.evidence.pattern {
+                GrowthPattern::Steady => 0,
+                GrowthPattern::RisingFloor => 1,
+            },
+        })
+        .collect();
+
+    Box::into_raw(Box::new(CLeakSuspectList {
+        count: suspects.len(),
+        suspects: into_raw_slice(suspects),
+    }))
+}
+
+#[no_mangle]
+pub extern "C" fn free_leak_suspects(list: *mut CLeakSuspectList) {
+    if !list.is_null() {
+        unsafe {
+            let list = Box::from_raw(list);
+            if !list.suspects.is_null() && list.count > 0 {
+                for suspect in Vec::from_raw_parts(list.suspects, list.count, list.count) {
+                    let _ = CString::from_raw(suspect.name);
+                }
+            }
+        }
+    }
+}
+
+#[repr(C)]
+pub struct CMemoryComposition {
+    pub total_bytes: u64,
+    pub free_bytes: u64,
+    pub available_bytes: u64,
+    pub buffers_bytes: u64,
+    pub cached_bytes: u64,
+    pub swap_cached_bytes: u64,
+    pub active_anon_bytes: u64,
+    pub inactive_anon_bytes: u64,
+    pub active_file_bytes: u64,
+    pub inactive_file_bytes: u64,
+    pub anon_bytes: u64,
+    pub mapped_bytes: u64,
+    pub shmem_bytes: u64,
+    pub slab_reclaimable_bytes: u64,
+    pub slab_unreclaimable_bytes: u64,
+    pub kernel_stack_bytes: u64,
+    pub page_tables_bytes: u64,
+    pub dirty_bytes: u64,
+    pub writeback_bytes: u64,
+    pub anon_huge_bytes: u64,
+    pub hugepages_total_bytes: u64,
+    pub hugepages_free_bytes: u64,
+    pub swap_total_bytes: u64,
+    pub swap_free_bytes: u64,
+    pub committed_bytes: u64,
+    pub commit_limit_bytes: u64,
+    pub has_rates: u8,  // 0 until two refreshes have happened
+    pub interval_secs: f64,
+    pub page_faults_per_sec: f64,
+    pub major_faults_per_sec: f64,
+    pub page_in_bytes_per_sec: f64,
+    pub page_out_bytes_per_sec: f64,
+    pub swap_in_bytes_per_sec: f64,
+    pub swap_out_bytes_per_sec: f64,
+    pub oom_kills: u64,
+}
+
+/// Null off Linux or when `/proc/meminfo` can't be read
+#[no_mangle]
+pub extern "C" fn get_memory_composition() -> *mut CMemoryComposition {
+    let composition = match MEMORY_MONITOR.lock() {
+        Ok(monitor) => monitor.get_memory_composition(),
+        Err(_) => return std::ptr::null_mut(),
+    };
+    let Some(c) = composition else {
+        return std::ptr::null_mut();
+    };
+    let rates = c.rates.unwrap_or_default();
+
+    Box::into_raw(Box::new(CMemoryComposition {
+        total_bytes: c.total_bytes,
+        free_bytes: c.free_bytes,
+        available_bytes: c.available_bytes,
+        buffers_bytes: c.buffers_bytes,
+        cached_bytes: c.cached_bytes,
+        swap_cached_bytes: c.swap_cached_bytes,
+        active_anon_bytes: c.active_anon_bytes,
+        inactive_anon_bytes: c.inactive_anon_bytes,
+        active_file_bytes: c.active_file_bytes,
+        inactive_file_bytes: c.inactive_file_bytes,
+        anon_bytes: c.anon_bytes,
+        mapped_bytes: c.mapped_bytes,
+        shmem_bytes: c.shmem_bytes,
+        slab_reclaimable_bytes: c.slab_reclaimable_bytes,
+        slab_unreclaimable_bytes: c.slab_unreclaimable_bytes,
+        kernel_stack_bytes: c.kernel_stack_bytes,
+        page_tables_bytes: c.page_tables_bytes,
+        dirty_bytes: c.dirty_bytes,
+        writeback_bytes: c.writeback_bytes,
+        anon_huge_bytes: c.anon_huge_bytes,
+        hugepages_total_bytes: c.hugepages_total_bytes,
+        hugepages_free_bytes: c.hugepages_free_bytes,
+        swap_total_bytes: c.swap_total_bytes,
+        swap_free_bytes: c.swap_free_bytes,
+        committed_bytes: c.committed_bytes,
+        commit_limit_bytes: c.commit_limit_bytes,
+        has_rates: c.rates.is_some() as u8,
+        interval_secs: rates.interval_secs,
+        page_faults_per_sec: rates.page_faults_per_sec,
+        major_faults_per_sec: rates.major_faults_per_sec,
+        page_in_bytes_per_sec: rates.page_in_bytes_per_sec,
+        page_out_bytes_per_sec: rates.page_out_bytes_per_sec,
+        swap_in_bytes_per_sec: rates.swap_in_bytes_per_sec,
+        swap_out_bytes_per_sec: rates.swap_out_bytes_per_sec,
+        oom_kills: rates.oom_kills,
+    }))
+}
+
+#[no_mangle]
+pub extern "C" fn free_memory_composition(composition: *mut CMemoryComposition) {
+    if !composition.is_null() {
+        unsafe {
+            let _ = Box::from_raw(composition);
+        }
+    }
+}
+
 #[no_mangle]
 pub extern "C" fn get_memory_pressure() -> *mut c_char {
     let pressure = match MEMORY_MONITOR.lock() {
@@ -193,11 +486,374 @@ pub extern "C" fn get_memory_pressure() -> *mut c_char {
         .into_raw()
 }
 
+#[repr(C)]
+#[derive(Default)]
+pub struct CResourcePressure {
+    pub available: u8,  // 0 when the kernel has no PSI for this resource
+    pub some_avg10: f32,
+    pub some_avg60: f32,
+    pub some_avg300: f32,
+    pub some_total_us: u64,
+    pub has_full: u8,
+    pub full_avg10: f32,
+    pub full_avg60: f32,
+    pub full_avg300: f32,
+    pub full_total_us: u64,
+}
+
+impl From<Option<&ResourcePressure>> for CResourcePressure {
+    fn from(pressure: Option<&ResourcePressure>) -> Self {
+        let Some(pressure) = pressure else {
+            return Self::default();
+        };
+        let full = pressure.full.unwrap_or_default();
+        Self {
+            available: 1,
+            some_avg10: pressure.some.avg10,
+            some_avg60: pressure.some.avg60,
+            some_avg300: pressure.some.avg300,
+            some_total_us: pressure.some.total_us,
+            has_full: pressure.full.is_some() as u8,
+            full_avg10: full.avg10,
+            full_avg60: full.avg60,
+            full_avg300: full.avg300,
+            full_total_us: full.total_us,
+        }
+    }
+}
+
+#[repr(C)]
+pub struct CSystemPressure {
+    pub cpu: CResourcePressure,
+    pub memory: CResourcePressure,
+    pub io: CResourcePressure,
+}
+
+/// Stall times as of the last refresh, the same data `get_memory_pressure`
+/// derives its level from
+#[no_mangle]
+pub extern "C" fn get_system_pressure() -> *mut CSystemPressure {
+    let pressure = match MEMORY_MONITOR.lock() {
+        Ok(monitor) => monitor.get_system_pressure(),
+        Err(_) => return std::ptr::null_mut(),
+    };
+
+    Box::into_raw(Box::new(CSystemPressure {
+        cpu: pressure.cpu.as_ref().into(),
+        memory: pressure.memory.as_ref().into(),
+        io: pressure.io.as_ref().into(),
+    }))
+}
+
 #[no_mangle]
-pub extern "C" fn free_string(s: *mut c_char) {
-    if !s.is_null() {
+pub extern "C" fn free_system_pressure(pressure: *mut CSystemPressure) {
+    if !pressure.is_null() {
         unsafe {
-            let _ = CString::from_raw(s);
+            let _ = Box::from_raw(pressure);
         }
     }
-}
\ No newline at end of file
+}
+
+/// Baselines for `diff_process_memory_map`, by PID and process start time
+/// so a reused PID never diffs against another process
+static MAP_SNAPSHOTS: Lazy<Mutex<HashMap<(u32, u64), MemoryMap>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// Drop the snapshots of processes that have exited
+fn prune_map_snapshots(snapshots: &mut HashMap<(u32, u64), MemoryMap>) {
+    snapshots.retain(|&(pid, start), _| MemoryMap::process_start_time(pid) == Some(start));
+}
+
+#[repr(C)]
+pub struct CMemoryRegion {
+    pub label: *mut c_char,  // path, "[heap]", "[stack]", "[anon]", …
+    pub perms: *mut c_char,
+    pub start: u64,
+    pub end: u64,
+    pub regions: usize,
+    pub size_bytes: u64,
+    pub rss_bytes: u64,
+    pub pss_bytes: u64,
+    pub dirty_bytes: u64,
+    pub swap_bytes: u64,
+}
+
+#[repr(C)]
+pub struct CMemoryMap {
+    pub pid: u32,
+    pub total_rss_bytes: u64,
+    pub regions: *mut CMemoryRegion,
+    pub count: usize,
+}
+
+#[repr(C)]
+pub struct CRegionGrowth {
+    pub label: *mut c_char,
+    pub start: u64,
+    pub is_new: u8,
+    pub size_delta: i64,
+    pub rss_delta: i64,
+    pub pss_delta: i64,
+    pub dirty_delta: i64,
+    pub swap_delta: i64,
+    pub rss_bytes: u64,
+}
+
+#[repr(C)]
+pub struct CRegionGrowthList {
+    pub growth: *mut CRegionGrowth,
+    pub count: usize,
+}
+
+fn region_grouping(grouping: u8) -> Option<RegionGrouping> {
+    match grouping {
+        0 => Some(RegionGrouping::Region),
+        1 => Some(RegionGrouping::Mapping),
+        2 => Some(RegionGrouping::File),
+        _ => None,
+    }
+}
+
+fn c_string(s: &str) -> *mut c_char {
+    CString::new(s).unwrap_or_else(|_| CString::new("Unknown").unwrap()).into_raw()
+}
+
+fn into_raw_slice<T>(items: Vec<T>) -> *mut T {
+    if items.is_empty() {
+        return std::ptr::null_mut();
+    }
+    Box::into_raw(items.into_boxed_slice()) as *mut T
+}
+
+/// `grouping`: 0 regions as the kernel lists them, 1 merged into mappings,
+/// 2 summed per backing file. Null when the PID's smaps can't be read
+#[no_mangle]
+pub extern "C" fn get_process_memory_map(pid: u32, grouping: u8) -> *mut CMemoryMap {
+    let (Some(grouping), Some(map)) = (region_grouping(grouping), MemoryMap::read(pid)) else {
+        return std::ptr::null_mut();
+    };
+
+    let regions: Vec<CMemoryRegion> = map.grouped(grouping).iter()
+        .map(|region| CMemoryRegion {
+            label: c_string(region.kind.label()),
+            perms: c_string(&region.perms),
+            start: region.start,
+            end: region.end,
+            regions: region.regions,
+            size_bytes: region.size_bytes,
+            rss_bytes: region.rss_bytes,
+            pss_bytes: region.pss_bytes,
+            dirty_bytes: region.dirty_bytes,
+            swap_bytes: region.swap_bytes,
+        })
+        .collect();
+
+    Box::into_raw(Box::new(CMemoryMap {
+        pid,
+        total_rss_bytes: map.total_rss(),
+        count: regions.len(),
+        regions: into_raw_slice(regions),
+    }))
+}
+
+#[no_mangle]
+pub extern "C" fn free_memory_map(map: *mut CMemoryMap) {
+    if !map.is_null() {
+        unsafe {
+            let map = Box::from_raw(map);
+            if !map.regions.is_null() && map.count > 0 {
+                let regions = Vec::from_raw_parts(map.regions, map.count, map.count);
+                for region in regions {
+                    let _ = CString::from_raw(region.label);
+                    let _ = CString::from_raw(region.perms);
+                }
+            }
+        }
+    }
+}
+
+/// Record the current map of `pid` as the baseline for
+/// `diff_process_memory_map`. Returns 0, or -2 when it can't be read
+#[no_mangle]
+pub extern "C" fn snapshot_process_memory_map(pid: u32) -> i32 {
+    let (Some(start), Some(map)) = (MemoryMap::process_start_time(pid), MemoryMap::read(pid)) else {
+        return -2;
+    };
+    match MAP_SNAPSHOTS.lock() {
+        Ok(mut snapshots) => {
+            prune_map_snapshots(&mut snapshots);
+            snapshots.insert((pid, start), map);
+            0
+        }
+        Err(_) => -5,
+    }
+}
+
+/// What grew since the last snapshot of `pid`; the snapshot is kept, so
+/// repeated calls show growth since the same point. Null without a snapshot
+#[no_mangle]
+pub extern "C" fn diff_process_memory_map(pid: u32, grouping: u8) -> *mut CRegionGrowthList {
+    let Some(start) = MemoryMap::process_start_time(pid) else {
+        return std::ptr::null_mut();
+    };
+    let (Some(grouping), Some(now)) = (region_grouping(grouping), MemoryMap::read(pid)) else {
+        return std::ptr::null_mut();
+    };
+    let growth = match MAP_SNAPSHOTS.lock() {
+        Ok(mut snapshots) => match snapshots.get(&(pid, start)) {
+            Some(before) => before.diff(&now, grouping),
+            None => {
+                prune_map_snapshots(&mut snapshots);
+                return std::ptr::null_mut();
+            }
+        },
+        Err(_) => return std::ptr::null_mut(),
+    };
+
+    let growth: Vec<CRegionGrowth> = growth.iter()
+        .map(|g| CRegionGrowth {
+            label: c_string(g.kind.label()),
+            start: g.start,
+            is_new: g.is_new as u8,
+            size_delta: g.size_delta,
+            rss_delta: g.rss_delta,
+            pss_delta: g.pss_delta,
+            dirty_delta: g.dirty_delta,
+            swap_delta: g.swap_delta,
+            rss_bytes: g.rss_bytes,
+        })
+        .collect();
+
+    Box::into_raw(Box::new(CRegionGrowthList {
+        count: growth.len(),
+        growth: into_raw_slice(growth),
+    }))
+}
+
+#[no_mangle]
+pub extern "C" fn free_region_growth_list(list: *mut CRegionGrowthList) {
+    if !list.is_null() {
+        unsafe {
+            let list = Box::from_raw(list);
+            if !list.growth.is_null() && list.count > 0 {
+                for growth in Vec::from_raw_parts(list.growth, list.count, list.count) {
+                    let _ = CString::from_raw(growth.label);
+                }
+            }
+        }
+    }
+}
+
+/// Drop the snapshot of `pid` taken by `snapshot_process_memory_map`
+#[no_mangle]
+pub extern "C" fn clear_memory_map_snapshot(pid: u32) {
+    if let Ok(mut snapshots) = MAP_SNAPSHOTS.lock() {
+        snapshots.retain(|&(snapshot_pid, _), _| snapshot_pid != pid);
+        prune_map_snapshots(&mut snapshots);
+    }
+}
+
+#[repr(C)]
+pub struct CMemoryLimitUsage {
+    pub pid: u32,
+    pub current_bytes: u64,
+    pub limit_bytes: u64,
+    pub usage_percent: f32,
+    pub resident_bytes: u64,
+    pub swap_bytes: u64,
+    pub oom_kills: i64,    // -1 when unknown
+    pub backend: u8,       // 0 = cgroup, 1 = RLIMIT_AS
+}
+
+fn memory_limit_error_code(error: &MemoryLimitError) -> i32 {
+    match error {
+        MemoryLimitError::PermissionDenied => -1,
+        MemoryLimitError::ProcessNotFound => -2,
+        MemoryLimitError::InvalidLimit => -3,
+        MemoryLimitError::Unsupported(_) | MemoryLimitError::SystemError(_) => -4,
+    }
+}
+
+/// `high_bytes` of 0 leaves `memory.high` unset; a negative `swap_max_bytes`
+/// leaves swap alone
+#[no_mangle]
+pub extern "C" fn limit_process_memory(pid: u32, max_bytes: u64, high_bytes: u64, swap_max_bytes: i64) -> i32 {
+    let settings = MemoryLimitSettings {
+        max_bytes,
+        high_bytes: (high_bytes > 0).then_some(high_bytes),
+        swap_max_bytes: u64::try_from(swap_max_bytes).ok(),
+    };
+    let audit = PendingAudit::begin("limit_memory", pid);
+    let result = match global_memory_limiter().lock() {
+        Ok(mut limiter) => limiter.limit_process(pid, settings),
+        Err(_) => {
+            audit.finish("UnknownError", "Failed to acquire memory limiter lock");
+            return -5;
+        }
+    };
+    audit.finish_result(&result, format!("Process {} limited to {} bytes of memory", pid, max_bytes));
+
+    match result {
+        Ok(()) => 0,
+        Err(e) => memory_limit_error_code(&e),
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn remove_process_memory_limit(pid: u32) -> i32 {
+    let audit = PendingAudit::begin("remove_memory_limit", pid);
+    let result = match global_memory_limiter().lock() {
+        Ok(mut limiter) => limiter.remove_limit(pid),
+        Err(_) => {
+            audit.finish("UnknownError", "Failed to acquire memory limiter lock");
+            return -5;
+        }
+    };
+    audit.finish_result(&result, format!("Memory limit removed from process {}", pid));
+
+    match result {
+        Ok(()) => 0,
+        Err(e) => memory_limit_error_code(&e),
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn has_process_memory_limit(pid: u32) -> u8 {
+    match global_memory_limiter().lock() {
+        Ok(limiter) => limiter.has_limit(pid) as u8,
+        Err(_) => 0,
+    }
+}
+
+/// Null when `pid` has no memory limit
+#[no_mangle]
+pub extern "C" fn get_process_memory_limit_usage(pid: u32) -> *mut CMemoryLimitUsage {
+    let Ok(limiter) = global_memory_limiter().lock() else {
+        return std::ptr::null_mut();
+    };
+    let (Some(limit), Ok(usage)) = (limiter.get_limits().into_iter().find(|l| l.pid == pid), limiter.usage(pid)) else {
+        return std::ptr::null_mut();
+    };
+
+    Box::into_raw(Box::new(CMemoryLimitUsage {
+        pid,
+        current_bytes: usage.current_bytes,
+        limit_bytes: usage.limit_bytes,
+        usage_percent: usage.usage_percent,
+        resident_bytes: usage.resident_bytes,
+        swap_bytes: usage.swap_bytes,
+        oom_kills: usage.oom_kills.map(|n| n as i64).unwrap_or(-1),
+        backend: match limit.backend {
+            crate::MemoryLimitBackend::Cgroup => 0,
+            crate::MemoryLimitBackend::AddressSpace => 1,
+        },
+    }))
+}
+
+#[no_mangle]
+pub extern "C" fn free_memory_limit_usage(usage: *mut CMemoryLimitUsage) {
+    if !usage.is_null() {
+        unsafe {
+            let _ = Box::from_raw(usage);
+        }
+    }
+}
@@ -0,0 +1,367 @@
+//! Memory leak detection over timestamped samples
+//!
+//! The trend is the Theil–Sen estimator: the median of the slopes between
+//! every pair of samples, in bytes per second of wall time, so it neither
+//! depends on how often the monitor is refreshed nor gets dragged around by
+//! a few outliers. Confidence is Kendall's tau, the share of sample pairs
+//! that agree the process is growing.
+//!
+//! Garbage-collected runtimes and caches that get trimmed grow and drop
+//! back in a sawtooth. Those are only reported when the floor the drops
+//! return to rises too.
+//!
+//! Both statistics are quadratic in the number of points, so long
+//! histories are fitted on chunk medians, and each process is fitted once
+//! per sample rather than on every read.
+
+use std::collections::{HashMap, VecDeque};
+use std::time::{Duration, Instant};
+
+/// Most points a trend is fitted to; longer histories are downsampled
+const MAX_FIT_POINTS: usize = 60;
+
+#[derive(Debug, Clone)]
+pub struct LeakDetectorConfig {
+    /// Samples kept per process; the oldest are dropped first
+    pub max_samples: usize,
+    /// Fewest samples a process needs before it can be a suspect
+    pub min_samples: usize,
+    /// Shortest span of samples a process needs before it can be a suspect
+    pub min_window: Duration,
+    pub min_growth_bytes_per_min: f64,
+    /// Kendall's tau a trend needs to count, 0 to 1
+    pub min_confidence: f32,
+    /// A drop larger than this share of the sampled range starts a new
+    /// sawtooth cycle
+    pub drop_fraction: f64,
+}
+
+impl Default for LeakDetectorConfig {
+    fn default() -> Self {
+        Self {
+            max_samples: 240,
+            min_samples: 10,
+            min_window: Duration::from_secs(120),
+            min_growth_bytes_per_min: 1024.0 * 1024.0,
+            min_confidence: 0.6,
+            drop_fraction: 0.2,
+        }
+    }
+}
+
+/// Shape of the growth a suspect was reported for
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GrowthPattern {
+    /// Memory climbs without significant drops
+    Steady,
+    /// Memory is freed in cycles, but each cycle bottoms out higher
+    RisingFloor,
+}
+
+/// The samples and statistics behind a suspect
+#[derive(Debug, Clone, PartialEq)]
+pub struct LeakEvidence {
+    pub samples: usize,
+    pub window_secs: f64,
+    pub first_bytes: u64,
+    pub last_bytes: u64,
+    /// Trend growth over the window
+    pub growth_bytes: f64,
+    /// Kendall's tau of the samples the trend was fitted to
+    pub kendall_tau: f32,
+    /// Significant drops seen in the window
+    pub drops: usize,
+    pub pattern: GrowthPattern,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct LeakSuspect {
+    pub pid: u32,
+    pub name: String,
+    pub growth_bytes_per_min: f64,
+    /// 0 to 1
+    pub confidence: f32,
+    pub evidence: LeakEvidence,
+}
+
+/// A fitted trend, whether or not it amounts to a leak
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MemoryTrend {
+    pub bytes_per_min: f64,
+    pub confidence: f32,
+}
+
+#[derive(Debug, Default)]
+struct History {
+    name: String,
+    samples: VecDeque<(Instant, u64)>,
+    /// Fitted when the last sample was recorded
+    trend: Option<MemoryTrend>,
+    suspect: Option<LeakSuspect>,
+}
+
+/// Per-process memory history and the leak analysis over it
+#[derive(Debug, Default)]
+pub struct LeakDetector {
+    config: LeakDetectorConfig,
+    histories: HashMap<u32, History>,
+}
+
+impl LeakDetector {
+    pub fn new(config: LeakDetectorConfig) -> Self {
+        Self { config, histories: HashMap::new() }
+    }
+
+    pub fn config(&self) -> &LeakDetectorConfig {
+        &self.config
+    }
+
+    /// Add a sample of `pid`'s memory taken at `at` and refit its trend
+    pub fn record(&mut self, pid: u32, name: &str, at: Instant, bytes: u64) {
+        let history = self.histories.entry(pid).or_default();
+        if history.name != name {
+            history.name = name.to_string();
+        }
+        history.samples.push_back((at, bytes));
+        while history.samples.len() > self.config.max_samples.max(2) {
+            history.samples.pop_front();
+        }
+
+        let points = points(&history.samples);
+        history.trend = fit_trend(&points);
+        history.suspect = analyze(&self.config, pid, &history.name, &points);
+    }
+
+    /// The last sample recorded for `pid`, in bytes
+    pub fn latest(&self, pid: u32) -> Option<u64> {
+        self.histories.get(&pid)?.samples.back().map(|&(_, bytes)| bytes)
+    }
+
+    /// Forget processes that aren't in `alive`
+    pub fn retain(&mut self, mut alive: impl FnMut(u32) -> bool) {
+        self.histories.retain(|&pid, _| alive(pid));
+    }
+
+    /// The fitted trend of `pid`, once it has at least two samples
+    pub fn trend(&self, pid: u32) -> Option<MemoryTrend> {
+        self.histories.get(&pid)?.trend
+    }
+
+    /// `pid` as a suspect, if its history shows a leak
+    pub fn analyze(&self, pid: u32) -> Option<LeakSuspect> {
+        self.histories.get(&pid)?.suspect.clone()
+    }
+
+    /// Every process that looks like it leaks, fastest growth first
+    pub fn suspects(&self) -> Vec<LeakSuspect> {
+        let mut suspects: Vec<LeakSuspect> = self.histories.values().filter_map(|h| h.suspect.clone()).collect();
+        suspects.sort_by(|a, b| b.growth_bytes_per_min.total_cmp(&a.growth_bytes_per_min));
+        suspects
+    }
+}
+
+/// Samples as (seconds since the first, bytes)
+fn points(samples: &VecDeque<(Instant, u64)>) -> Vec<(f64, f64)> {
+    let Some(&(start, _)) = samples.front() else { return Vec::new() };
+    samples.iter()
+        .map(|&(at, bytes)| (at.saturating_duration_since(start).as_secs_f64(), bytes as f64))
+        .collect()
+}
+
+fn fit_trend(points: &[(f64, f64)]) -> Option<MemoryTrend> {
+    let fitted = downsample(points);
+    (fitted.len() >= 2).then(|| MemoryTrend {
+        bytes_per_min: theil_sen(&fitted) * 60.0,
+        confidence: kendall_tau(&fitted).max(0.0) as f32,
+    })
+}
+
+fn analyze(config: &LeakDetectorConfig, pid: u32, name: &str, points: &[(f64, f64)]) -> Option<LeakSuspect> {
+    let window_secs = points.last()?.0;
+    if points.len() < config.min_samples || window_secs < config.min_window.as_secs_f64() {
+        return None;
+    }
+
+    let drops = significant_drops(points, config.drop_fraction);
+    let (fitted, pattern) = if drops.len() >= 2 {
+        (downsample(&cycle_floors(points, &drops)), GrowthPattern::RisingFloor)
+    } else {
+        (downsample(points), GrowthPattern::Steady)
+    };
+
+    let slope = theil_sen(&fitted);
+    let tau = kendall_tau(&fitted).max(0.0) as f32;
+    let bytes_per_min = slope * 60.0;
+    if bytes_per_min < config.min_growth_bytes_per_min || tau < config.min_confidence {
+        return None;
+    }
+
+    Some(LeakSuspect {
+        pid,
+        name: name.to_string(),
+        growth_bytes_per_min: bytes_per_min,
+        confidence: tau,
+        evidence: LeakEvidence {
+            samples: points.len(),
+            window_secs,
+            first_bytes: points[0].1 as u64,
+            last_bytes: points[points.len() - 1].1 as u64,
+            growth_bytes: slope * window_secs,
+            kendall_tau: tau,
+            drops: drops.len(),
+            pattern,
+        },
+    })
+}
+
+/// At most `MAX_FIT_POINTS` points: the mean time and median bytes of
+/// each run of consecutive points
+fn downsample(points: &[(f64, f64)]) -> Vec<(f64, f64)> {
+    if points.len() <= MAX_FIT_POINTS {
+        return points.to_vec();
+    }
+    points.chunks(points.len().div_ceil(MAX_FIT_POINTS))
+        .map(|chunk| {
+            let time = chunk.iter().map(|p| p.0).sum::<f64>() / chunk.len() as f64;
+            let mut bytes: Vec<f64> = chunk.iter().map(|p| p.1).collect();
+            (time, median(&mut bytes))
+        })
+        .collect()
+}
+
+/// Median of the slopes between every pair of points with distinct times
+fn theil_sen(points: &[(f64, f64)]) -> f64 {
+    let mut slopes = Vec::with_capacity(points.len() * points.len().saturating_sub(1) / 2);
+    for (i, a) in points.iter().enumerate() {
+        for b in &points[i + 1..] {
+            if b.0 > a.0 {
+                slopes.push((b.1 - a.1) / (b.0 - a.0));
+            }
+        }
+    }
+    median(&mut slopes)
+}
+
+/// (concordant − discordant) / pairs: 1 when every later sample is larger
+fn kendall_tau(points: &[(f64, f64)]) -> f64 {
+    let mut score = 0i64;
+    let mut pairs = 0i64;
+    for (i, a) in points.iter().enumerate() {
+        for b in &points[i + 1..] {
+            pairs += 1;
+            score += match (b.1 - a.1) * (b.0 - a.0) {
+                d if d > 0.0 => 1,
+                d if d < 0.0 => -1,
+                _ => 0,
+            };
+        }
+    }
+    if pairs == 0 { 0.0 } else { score as f64 / pairs as f64 }
+}
+
+fn median(values: &mut [f64]) -> f64 {
+    if values.is_empty() {
+        return 0.0;
+    }
+    values.sort_by(f64::total_cmp);
+    let mid = values.len() / 2;
+    if values.len().is_multiple_of(2) { (values[mid - 1] + values[mid]) / 2.0 } else { values[mid] }
+}
+
+/// Indices of samples that fell by more than `fraction` of the range
+fn significant_drops(points: &[(f64, f64)], fraction: f64) -> Vec<usize> {
+    let (min, max) = points.iter().fold((f64::MAX, f64::MIN), |(lo, hi), p| (lo.min(p.1), hi.max(p.1)));
+    let threshold = (max - min) * fraction;
+    if threshold <= 0.0 {
+        return Vec::new();
+    }
+    (1..points.len()).filter(|&i| points[i - 1].1 - points[i].1 > threshold).collect()
+}
+
+/// The lowest sample of each cycle between drops
+fn cycle_floors(points: &[(f64, f64)], drops: &[usize]) -> Vec<(f64, f64)> {
+    let bounds: Vec<usize> = std::iter::once(0).chain(drops.iter().copied()).chain(std::iter::once(points.len())).collect();
+    bounds.windows(2)
+        .filter_map(|w| points[w[0]..w[1]].iter().copied().min_by(|a, b| a.1.total_cmp(&b.1)))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MB: f64 = 1024.0 * 1024.0;
+
+    /// Samples every `interval` seconds for `count` samples from `bytes_at(t)`
+    fn detector_with(interval: f64, count: usize, bytes_at: impl Fn(f64) -> f64) -> LeakDetector {
+        let mut detector = LeakDetector::default();
+        let start = Instant::now();
+        for i in 0..count {
+            let t = i as f64 * interval;
+            detector.record(7, "app", start + Duration::from_secs_f64(t), bytes_at(t) as u64);
+        }
+        detector
+    }
+
+    #[test]
+    fn test_rate_independent_of_sampling_interval() {
+        // 2 MB/min with noise, sampled every 5s and every 2s
+        let leak = |t: f64| 100.0 * MB + t / 30.0 * MB + ((t * 7.0).sin() * 0.3 * MB);
+        for interval in [5.0, 2.0] {
+            let detector = detector_with(interval, 120, leak);
+            let suspect = detector.analyze(7).unwrap();
+            assert!((suspect.growth_bytes_per_min / MB - 2.0).abs() < 0.1, "{}", suspect.growth_bytes_per_min / MB);
+            assert_eq!(suspect.evidence.pattern, GrowthPattern::Steady);
+            assert!(suspect.confidence > 0.8);
+        }
+
+        // One wild sample barely moves the trend
+        let outlier = detector_with(5.0, 60, |t| if t == 150.0 { 900.0 * MB } else { leak(t) });
+        let trend = outlier.trend(7).unwrap();
+        assert!((trend.bytes_per_min / MB - 2.0).abs() < 0.2);
+    }
+
+    #[test]
+    fn test_requires_window_and_samples() {
+        // Fast growth, but only a minute of samples
+        assert!(detector_with(1.0, 60, |t| t * MB).analyze(7).is_none());
+        // Long enough, too few samples
+        assert!(detector_with(60.0, 5, |t| t * MB).analyze(7).is_none());
+        // Flat with noise
+        assert!(detector_with(5.0, 100, |t| 50.0 * MB + (t.sin() * MB)).analyze(7).is_none());
+    }
+
+    #[test]
+    fn test_sawtooth_rejected_unless_floor_rises() {
+        // GC cycle every 60s back to the same floor
+        let gc = |t: f64| 200.0 * MB + (t % 60.0) * MB;
+        assert!(detector_with(5.0, 120, gc).analyze(7).is_none());
+
+        // Same cycles, but each collection leaves 5 MB more behind
+        let leaky_gc = |t: f64| 200.0 * MB + (t / 60.0).floor() * 5.0 * MB + (t % 60.0) * MB;
+        let suspect = detector_with(5.0, 120, leaky_gc).analyze(7).unwrap();
+        assert_eq!(suspect.evidence.pattern, GrowthPattern::RisingFloor);
+        assert!((suspect.growth_bytes_per_min / MB - 5.0).abs() < 0.5);
+        assert!(suspect.evidence.drops >= 2);
+    }
+
+    #[test]
+    fn test_long_histories_are_downsampled() {
+        let points: Vec<(f64, f64)> = (0..240).map(|i| (i as f64, i as f64 * 10.0)).collect();
+        let fitted = downsample(&points);
+        assert_eq!(fitted.len(), MAX_FIT_POINTS);
+        assert_eq!(theil_sen(&fitted), 10.0);
+
+        // The full default history still gives the sampled rate
+        let detector = detector_with(5.0, 240, |t| 100.0 * MB + t / 60.0 * 3.0 * MB);
+        assert!((detector.analyze(7).unwrap().growth_bytes_per_min / MB - 3.0).abs() < 0.01);
+    }
+
+    #[test]
+    fn test_history_is_bounded_and_pruned() {
+        let mut detector = detector_with(1.0, 500, |t| t * MB);
+        assert_eq!(detector.histories[&7].samples.len(), detector.config().max_samples);
+        detector.retain(|pid| pid != 7);
+        assert!(detector.trend(7).is_none());
+    }
+}
@@ -1,5 +1,20 @@
 pub mod memory_monitor;
+pub mod composition;
+pub mod leak_detector;
+pub mod memory_limiter;
+pub mod memory_map;
+pub mod pressure;
+pub mod smaps;
 pub mod ffi;
 
-pub use memory_monitor::{MemoryMonitor, MemoryInfo, ProcessMemoryInfo};
+pub use memory_monitor::{rank_memory_processes, MemoryMonitor, MemoryInfo, ProcessMemoryInfo};
+pub use memory_limiter::{
+    global_memory_limiter, MemoryLimit, MemoryLimitBackend, MemoryLimitError, MemoryLimitSettings,
+    MemoryLimitUsage, ProcessMemoryLimiter,
+};
+pub use composition::{CompositionCollector, MemoryComposition, VmCounters, VmRates};
+pub use leak_detector::{GrowthPattern, LeakDetector, LeakDetectorConfig, LeakEvidence, LeakSuspect, MemoryTrend};
+pub use memory_map::{MappingKind, MemoryMap, MemoryRegion, RegionGrouping, RegionGrowth};
+pub use pressure::{PressureResource, PressureStat, ResourcePressure, SystemPressure};
+pub use smaps::{MemoryBreakdown, MemorySortKey};
 pub use ffi::*;
\ No newline at end of file
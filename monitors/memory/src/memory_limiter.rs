@@ -0,0 +1,418 @@
+//! Per-process memory limits
+//!
+//! On Linux a limited process joins its Reaper cgroup (shared with the CPU
+//! and I/O limiters) with `memory.max`, `memory.high` and `memory.swap.max`
+//! set. cgroup v2 doesn't move memory a process already holds, so the cgroup
+//! only charges what it allocates from then on. Where the memory controller
+//! isn't available the limiter lowers the soft `RLIMIT_AS` with `prlimit`,
+//! which makes allocations past the limit fail instead of reclaiming.
+
+use once_cell::sync::Lazy;
+use reaper_core::audit::AuditResult;
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Mutex;
+#[cfg(target_os = "linux")]
+use reaper_core::platform::linux::{cgroup::{self, CgroupFs}, procfs};
+#[cfg(target_os = "linux")]
+use reaper_core::platform::PlatformError;
+#[cfg(target_os = "linux")]
+use std::path::Path;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MemoryLimitBackend {
+    /// cgroup v2 memory controller
+    Cgroup,
+    /// Soft `RLIMIT_AS` set with `prlimit`
+    AddressSpace,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MemoryLimitSettings {
+    /// Hard cap: the cgroup OOM-kills past it, RLIMIT_AS fails allocations
+    pub max_bytes: u64,
+    /// Point where the kernel starts throttling and reclaiming (cgroup only)
+    pub high_bytes: Option<u64>,
+    /// Swap allowed on top of `max_bytes`; `Some(0)` keeps the process out
+    /// of swap (cgroup only)
+    pub swap_max_bytes: Option<u64>,
+}
+
+impl MemoryLimitSettings {
+    pub fn new(max_bytes: u64) -> Self {
+        Self { max_bytes, high_bytes: None, swap_max_bytes: None }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct MemoryLimit {
+    pub pid: u32,
+    pub settings: MemoryLimitSettings,
+    pub backend: MemoryLimitBackend,
+    /// Reaper cgroup holding the process
+    pub cgroup: Option<String>,
+    /// Soft and hard `RLIMIT_AS` before limiting
+    pub original_rlimit: Option<(u64, u64)>,
+}
+
+/// A limited process's memory against its limit
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MemoryLimitUsage {
+    pub pid: u32,
+    /// Memory charged to the cgroup, or the address space for RLIMIT_AS
+    pub current_bytes: u64,
+    pub limit_bytes: u64,
+    pub usage_percent: f32,
+    pub resident_bytes: u64,
+    pub swap_bytes: u64,
+    /// Times the process hit `memory.high` and was throttled
+    pub high_events: Option<u64>,
+    /// Processes the cgroup OOM-killed at `memory.max`
+    pub oom_kills: Option<u64>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum MemoryLimitError {
+    PermissionDenied,
+    ProcessNotFound,
+    InvalidLimit,
+    /// Neither backend works on this platform
+    Unsupported(String),
+    SystemError(String),
+}
+
+impl fmt::Display for MemoryLimitError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::PermissionDenied => write!(f, "Permission denied"),
+            Self::ProcessNotFound => write!(f, "Process not found"),
+            Self::InvalidLimit => write!(f, "Invalid memory limit"),
+            Self::Unsupported(msg) => write!(f, "Memory limits not supported: {}", msg),
+            Self::SystemError(msg) => write!(f, "Memory limit failed: {}", msg),
+        }
+    }
+}
+
+impl std::error::Error for MemoryLimitError {}
+
+impl AuditResult for MemoryLimitError {
+    fn audit_result(&self) -> &'static str {
+        match self {
+            Self::PermissionDenied => "PermissionDenied",
+            Self::ProcessNotFound => "ProcessNotFound",
+            Self::InvalidLimit => "InvalidLimit",
+            Self::Unsupported(_) => "Unsupported",
+            Self::SystemError(_) => "SystemError",
+        }
+    }
+
+    fn audit_outcome(&self) -> String {
+        self.to_string()
+    }
+}
+
+#[cfg(target_os = "linux")]
+impl From<PlatformError> for MemoryLimitError {
+    fn from(err: PlatformError) -> Self {
+        match err {
+            PlatformError::ProcessNotFound(_) => Self::ProcessNotFound,
+            PlatformError::PermissionDenied(_) => Self::PermissionDenied,
+            other => Self::SystemError(other.to_string()),
+        }
+    }
+}
+
+/// Caps the memory of external processes
+#[derive(Debug, Default)]
+pub struct ProcessMemoryLimiter {
+    limits: HashMap<u32, MemoryLimit>,
+}
+
+impl ProcessMemoryLimiter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Limit `pid`, replacing any earlier limit. Uses the cgroup backend
+    /// when the memory controller is available, otherwise RLIMIT_AS
+    pub fn limit_process(&mut self, pid: u32, settings: MemoryLimitSettings) -> Result<(), MemoryLimitError> {
+        if settings.max_bytes == 0 || settings.high_bytes.is_some_and(|high| high == 0 || high > settings.max_bytes) {
+            return Err(MemoryLimitError::InvalidLimit);
+        }
+        if self.has_limit(pid) {
+            self.remove_limit(pid)?;
+        }
+
+        let limit = self.apply(pid, settings)?;
+        self.limits.insert(pid, limit);
+        Ok(())
+    }
+
+    #[cfg(target_os = "linux")]
+    fn apply(&self, pid: u32, settings: MemoryLimitSettings) -> Result<MemoryLimit, MemoryLimitError> {
+        if let Some(fs) = CgroupFs::detect().filter(|fs| fs.has_controller("memory")) {
+            let dir = apply_cgroup(&fs, pid, &settings)?;
+            return Ok(MemoryLimit {
+                pid,
+                settings,
+                backend: MemoryLimitBackend::Cgroup,
+                cgroup: Some(dir),
+                original_rlimit: None,
+            });
+        }
+
+        let original = get_address_space_limit(pid)?;
+        set_address_space_limit(pid, (settings.max_bytes.min(original.1), original.1))?;
+        Ok(MemoryLimit {
+            pid,
+            settings,
+            backend: MemoryLimitBackend::AddressSpace,
+            cgroup: None,
+            original_rlimit: Some(original),
+        })
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    fn apply(&self, _pid: u32, _settings: MemoryLimitSettings) -> Result<MemoryLimit, MemoryLimitError> {
+        Err(MemoryLimitError::Unsupported("needs cgroups or prlimit".to_string()))
+    }
+
+    /// Lift the limit, putting back the original cgroup or RLIMIT_AS
+    pub fn remove_limit(&mut self, pid: u32) -> Result<(), MemoryLimitError> {
+        let limit = self.limits.remove(&pid).ok_or(MemoryLimitError::ProcessNotFound)?;
+        Self::restore(&limit)
+    }
+
+    #[cfg(target_os = "linux")]
+    fn restore(limit: &MemoryLimit) -> Result<(), MemoryLimitError> {
+        match limit.backend {
+            MemoryLimitBackend::Cgroup => {
+                let fs = CgroupFs::detect()
+                    .ok_or_else(|| MemoryLimitError::SystemError("cgroup v2 hierarchy disappeared".to_string()))?;
+                let dir = Path::new(limit.cgroup.as_deref().unwrap_or_default());
+                // Another limiter may keep the process in the cgroup
+                let reset = reset_cgroup(dir);
+                reset.and(fs.detach(limit.pid, "memory")).map_err(MemoryLimitError::from)
+            }
+            MemoryLimitBackend::AddressSpace => match limit.original_rlimit {
+                Some(original) => set_address_space_limit(limit.pid, original),
+                None => Ok(()),
+            },
+        }
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    fn restore(_limit: &MemoryLimit) -> Result<(), MemoryLimitError> {
+        Ok(())
+    }
+
+    /// Current memory of a limited process against its limit
+    #[cfg(target_os = "linux")]
+    pub fn usage(&self, pid: u32) -> Result<MemoryLimitUsage, MemoryLimitError> {
+        let limit = self.limits.get(&pid).ok_or(MemoryLimitError::ProcessNotFound)?;
+        let status = procfs::read_pid_file(pid, "status")?;
+        let kb = |key: &str| status_kb(&status, key).unwrap_or(0) * 1024;
+
+        let mut usage = MemoryLimitUsage {
+            pid,
+            current_bytes: kb("VmSize"),
+            limit_bytes: limit.settings.max_bytes,
+            usage_percent: 0.0,
+            resident_bytes: kb("VmRSS"),
+            swap_bytes: kb("VmSwap"),
+            high_events: None,
+            oom_kills: None,
+        };
+
+        match (limit.backend, &limit.cgroup) {
+            (MemoryLimitBackend::Cgroup, Some(dir)) => {
+                let dir = Path::new(dir);
+                let read = |name: &str| cgroup::read_file(&dir.join(name)).ok();
+                usage.current_bytes = read("memory.current").and_then(|v| v.parse().ok()).unwrap_or(0);
+                if let Some(swap) = read("memory.swap.current").and_then(|v| v.parse().ok()) {
+                    usage.swap_bytes = swap;
+                }
+                let events = read("memory.events").unwrap_or_default();
+                usage.high_events = event_count(&events, "high");
+                usage.oom_kills = event_count(&events, "oom_kill");
+            }
+            _ => {
+                // A hard limit below the requested one is what applies
+                if let Ok((soft, _)) = get_address_space_limit(pid) {
+                    usage.limit_bytes = soft;
+                }
+            }
+        }
+        usage.usage_percent = usage.current_bytes as f32 / usage.limit_bytes.max(1) as f32 * 100.0;
+        Ok(usage)
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    pub fn usage(&self, _pid: u32) -> Result<MemoryLimitUsage, MemoryLimitError> {
+        Err(MemoryLimitError::Unsupported("needs cgroups or prlimit".to_string()))
+    }
+
+    pub fn get_limits(&self) -> Vec<&MemoryLimit> {
+        self.limits.values().collect()
+    }
+
+    pub fn has_limit(&self, pid: u32) -> bool {
+        self.limits.contains_key(&pid)
+    }
+}
+
+/// Attach `pid` to its Reaper cgroup and write the memory limits there.
+/// Returns the cgroup directory
+#[cfg(target_os = "linux")]
+fn apply_cgroup(fs: &CgroupFs, pid: u32, settings: &MemoryLimitSettings) -> Result<String, MemoryLimitError> {
+    let attachment = fs.attach(pid, "memory")?;
+    let dir = &attachment.dir;
+
+    let mut written = cgroup::write_file(&dir.join("memory.max"), &settings.max_bytes.to_string());
+    if let Some(high) = settings.high_bytes {
+        written = written.and_then(|_| cgroup::write_file(&dir.join("memory.high"), &high.to_string()));
+    }
+    if let Some(swap) = settings.swap_max_bytes {
+        written = written.and_then(|_| cgroup::write_file(&dir.join("memory.swap.max"), &swap.to_string()));
+    }
+
+    if let Err(e) = written {
+        let _ = reset_cgroup(dir);
+        let _ = fs.detach(pid, "memory");
+        return Err(e.into());
+    }
+    Ok(dir.to_string_lossy().into_owned())
+}
+
+/// Put the memory interface files back to their unlimited defaults
+#[cfg(target_os = "linux")]
+fn reset_cgroup(dir: &Path) -> reaper_core::platform::PlatformResult<()> {
+    let reset = cgroup::write_file(&dir.join("memory.max"), "max")
+        .and(cgroup::write_file(&dir.join("memory.high"), "max"));
+    // Without swap accounting there is no memory.swap.max to reset
+    if dir.join("memory.swap.max").exists() {
+        reset.and(cgroup::write_file(&dir.join("memory.swap.max"), "max"))
+    } else {
+        reset
+    }
+}
+
+/// Soft and hard `RLIMIT_AS` of `pid`
+#[cfg(target_os = "linux")]
+fn get_address_space_limit(pid: u32) -> Result<(u64, u64), MemoryLimitError> {
+    let mut old = libc::rlimit { rlim_cur: 0, rlim_max: 0 };
+    let result = unsafe { libc::prlimit(pid as libc::pid_t, libc::RLIMIT_AS, std::ptr::null(), &mut old) };
+    if result != 0 {
+        return Err(last_error());
+    }
+    Ok((old.rlim_cur, old.rlim_max))
+}
+
+#[cfg(target_os = "linux")]
+fn set_address_space_limit(pid: u32, (soft, hard): (u64, u64)) -> Result<(), MemoryLimitError> {
+    let new = libc::rlimit { rlim_cur: soft, rlim_max: hard };
+    let result = unsafe { libc::prlimit(pid as libc::pid_t, libc::RLIMIT_AS, &new, std::ptr::null_mut()) };
+    if result == 0 {
+        Ok(())
+    } else {
+        Err(last_error())
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn last_error() -> MemoryLimitError {
+    let err = std::io::Error::last_os_error();
+    match err.raw_os_error() {
+        Some(libc::EPERM) => MemoryLimitError::PermissionDenied,
+        Some(libc::ESRCH) => MemoryLimitError::ProcessNotFound,
+        Some(libc::EINVAL) => MemoryLimitError::InvalidLimit,
+        _ => MemoryLimitError::SystemError(err.to_string()),
+    }
+}
+
+/// A `Key:   1234 kB` value from `/proc/<pid>/status`
+#[cfg(target_os = "linux")]
+fn status_kb(status: &str, key: &str) -> Option<u64> {
+    status.lines()
+        .find_map(|line| line.strip_prefix(key)?.strip_prefix(':'))
+        .and_then(|value| value.split_whitespace().next()?.parse().ok())
+}
+
+/// A counter from `memory.events`
+#[cfg(target_os = "linux")]
+fn event_count(events: &str, name: &str) -> Option<u64> {
+    events.lines()
+        .find_map(|line| line.strip_prefix(name)?.strip_prefix(' '))
+        .and_then(|value| value.trim().parse().ok())
+}
+
+/// The limiter shared by the FFI exports
+static LIMITER: Lazy<Mutex<ProcessMemoryLimiter>> = Lazy::new(|| Mutex::new(ProcessMemoryLimiter::new()));
+
+pub fn global_memory_limiter() -> &'static Mutex<ProcessMemoryLimiter> {
+    &LIMITER
+}
+
+#[cfg(all(test, target_os = "linux"))]
+mod tests {
+    use super::*;
+    use std::process::Command;
+
+    #[test]
+    fn test_parses_status_and_events() {
+        let status = "Name:\tsleep\nVmSize:\t    8192 kB\nVmRSS:\t     512 kB\n";
+        assert_eq!(status_kb(status, "VmSize"), Some(8192));
+        assert_eq!(status_kb(status, "VmSwap"), None);
+
+        let events = "low 0\nhigh 12\nmax 3\noom 1\noom_kill 1\noom_group_kill 0\n";
+        assert_eq!(event_count(events, "high"), Some(12));
+        assert_eq!(event_count(events, "oom_kill"), Some(1));
+        assert_eq!(event_count(events, "oom"), Some(1));
+    }
+
+    #[test]
+    fn test_rejects_invalid_settings() {
+        let mut limiter = ProcessMemoryLimiter::new();
+        let pid = std::process::id();
+        assert_eq!(limiter.limit_process(pid, MemoryLimitSettings::new(0)), Err(MemoryLimitError::InvalidLimit));
+        let high_above_max = MemoryLimitSettings { high_bytes: Some(2 << 20), ..MemoryLimitSettings::new(1 << 20) };
+        assert_eq!(limiter.limit_process(pid, high_above_max), Err(MemoryLimitError::InvalidLimit));
+        assert_eq!(limiter.remove_limit(pid), Err(MemoryLimitError::ProcessNotFound));
+    }
+
+    /// Limits a child with whichever backend this machine offers and checks
+    /// that removing the limit restores it
+    #[test]
+    fn test_limit_and_restore_child() {
+        let mut child = Command::new("sleep").arg("5").spawn().unwrap();
+        let pid = child.id();
+        let rlimit_before = get_address_space_limit(pid).unwrap();
+        let cgroup_before = procfs::read_pid_file(pid, "cgroup").unwrap();
+
+        let mut limiter = ProcessMemoryLimiter::new();
+        let settings = MemoryLimitSettings { high_bytes: Some(384 << 20), ..MemoryLimitSettings::new(512 << 20) };
+        limiter.limit_process(pid, settings).unwrap();
+        let limit = limiter.get_limits()[0].clone();
+
+        let usage = limiter.usage(pid).unwrap();
+        assert_eq!(usage.limit_bytes, 512 << 20);
+        match limit.backend {
+            MemoryLimitBackend::AddressSpace => {
+                assert_eq!(get_address_space_limit(pid).unwrap(), ((512 << 20).min(rlimit_before.1), rlimit_before.1));
+                assert!(usage.current_bytes > 0 && usage.usage_percent > 0.0);
+            }
+            MemoryLimitBackend::Cgroup => {
+                let dir = Path::new(limit.cgroup.as_deref().unwrap());
+                assert_eq!(cgroup::read_file(&dir.join("memory.max")).unwrap(), (512u64 << 20).to_string());
+                assert!(usage.oom_kills.is_some());
+            }
+        }
+
+        limiter.remove_limit(pid).unwrap();
+        assert_eq!(get_address_space_limit(pid).unwrap(), rlimit_before);
+        assert_eq!(procfs::read_pid_file(pid, "cgroup").unwrap(), cgroup_before);
+
+        child.kill().unwrap();
+        child.wait().unwrap();
+    }
+}
@@ -0,0 +1,370 @@
+//! Memory map of a single process from `/proc/<pid>/smaps`
+//!
+//! Regions can be viewed as the kernel lists them, merged back into the
+//! mappings they were split from (a library's text/data segments, an
+//! arena and its guard page), or summed per backing file. Diffing two
+//! snapshots answers what grew in a process that looks like it leaks.
+
+use crate::smaps::parse_smaps_field;
+#[cfg(target_os = "linux")]
+use reaper_core::platform::linux::procfs;
+use std::collections::HashMap;
+
+/// What backs a region
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum MappingKind {
+    File(String),
+    Heap,
+    Stack,
+    Anonymous,
+    /// Kernel-provided or named regions: `[vdso]`, `[vvar]`, `[anon:name]`, …
+    Special(String),
+}
+
+impl MappingKind {
+    fn from_pathname(pathname: &str) -> Self {
+        match pathname {
+            "" => Self::Anonymous,
+            "[heap]" => Self::Heap,
+            "[stack]" => Self::Stack,
+            special if special.starts_with('[') => Self::Special(special.to_string()),
+            path => Self::File(path.to_string()),
+        }
+    }
+
+    pub fn label(&self) -> &str {
+        match self {
+            Self::File(path) => path,
+            Self::Heap => "[heap]",
+            Self::Stack => "[stack]",
+            Self::Anonymous => "[anon]",
+            Self::Special(name) => name,
+        }
+    }
+}
+
+/// One region, or several summed by `by_mapping`/`by_file`. Sizes in bytes
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MemoryRegion {
+    pub start: u64,
+    pub end: u64,
+    /// `rwxp`-style; for merged regions, every permission any of them has
+    pub perms: String,
+    pub kind: MappingKind,
+    /// Number of kernel regions summed into this one
+    pub regions: usize,
+    pub size_bytes: u64,
+    pub rss_bytes: u64,
+    pub pss_bytes: u64,
+    pub dirty_bytes: u64,
+    pub swap_bytes: u64,
+}
+
+impl MemoryRegion {
+    fn merge(&mut self, other: &MemoryRegion) {
+        self.start = self.start.min(other.start);
+        self.end = self.end.max(other.end);
+        self.perms = self.perms.chars().zip(other.perms.chars())
+            .map(|(a, b)| if a == '-' { b } else { a })
+            .collect();
+        self.regions += other.regions;
+        self.size_bytes += other.size_bytes;
+        self.rss_bytes += other.rss_bytes;
+        self.pss_bytes += other.pss_bytes;
+        self.dirty_bytes += other.dirty_bytes;
+        self.swap_bytes += other.swap_bytes;
+    }
+}
+
+/// How one region or group changed between two snapshots
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RegionGrowth {
+    pub kind: MappingKind,
+    pub start: u64,
+    /// Whether the region only exists in the later snapshot
+    pub is_new: bool,
+    pub size_delta: i64,
+    pub rss_delta: i64,
+    pub pss_delta: i64,
+    pub dirty_delta: i64,
+    pub swap_delta: i64,
+    /// Resident size in the later snapshot
+    pub rss_bytes: u64,
+}
+
+/// Regions to compare in `MemoryMap::diff`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RegionGrouping {
+    Region,
+    Mapping,
+    File,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MemoryMap {
+    pub pid: u32,
+    /// In address order
+    pub regions: Vec<MemoryRegion>,
+}
+
+impl MemoryMap {
+    #[cfg(target_os = "linux")]
+    pub fn read(pid: u32) -> Option<Self> {
+        Some(Self::parse(pid, &procfs::read_pid_file(pid, "smaps").ok()?))
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    pub fn read(_pid: u32) -> Option<Self> {
+        None
+    }
+
+    /// When `pid` started, in clock ticks since boot. Together with the PID
+    /// it names one process, even after the PID is reused
+    #[cfg(target_os = "linux")]
+    pub fn process_start_time(pid: u32) -> Option<u64> {
+        procfs::parse_stat(&procfs::read_pid_file(pid, "stat").ok()?).map(|stat| stat.starttime)
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    pub fn process_start_time(_pid: u32) -> Option<u64> {
+        None
+    }
+
+    pub fn parse(pid: u32, content: &str) -> Self {
+        let mut regions: Vec<MemoryRegion> = Vec::new();
+        for line in content.lines() {
+            if let Some(region) = parse_header(line) {
+                regions.push(region);
+                continue;
+            }
+            let (Some(region), Some((key, kb))) = (regions.last_mut(), parse_smaps_field(line)) else {
+                continue;
+            };
+            let bytes = kb * 1024;
+            match key {
+                "Size" => region.size_bytes = bytes,
+                "Rss" => region.rss_bytes = bytes,
+                "Pss" => region.pss_bytes = bytes,
+                "Shared_Dirty" | "Private_Dirty" => region.dirty_bytes += bytes,
+                "Swap" => region.swap_bytes = bytes,
+                _ => {}
+            }
+        }
+        Self { pid, regions }
+    }
+
+    pub fn total_rss(&self) -> u64 {
+        self.regions.iter().map(|r| r.rss_bytes).sum()
+    }
+
+    /// Adjacent regions with the same backing merged into the mapping
+    /// they were split from, in address order
+    pub fn by_mapping(&self) -> Vec<MemoryRegion> {
+        let mut mappings: Vec<MemoryRegion> = Vec::new();
+        for region in &self.regions {
+            match mappings.last_mut() {
+                Some(last) if last.end == region.start && last.kind == region.kind => last.merge(region),
+                _ => mappings.push(region.clone()),
+            }
+        }
+        mappings
+    }
+
+    /// Regions summed per backing file, with all anonymous memory as one
+    /// entry, largest resident first
+    pub fn by_file(&self) -> Vec<MemoryRegion> {
+        let mut files: Vec<MemoryRegion> = Vec::new();
+        let mut index: HashMap<&MappingKind, usize> = HashMap::new();
+        for region in &self.regions {
+            match index.get(&region.kind) {
+                Some(&i) => files[i].merge(region),
+                None => {
+                    index.insert(&region.kind, files.len());
+                    files.push(region.clone());
+                }
+            }
+        }
+        files.sort_by_key(|f| std::cmp::Reverse(f.rss_bytes));
+        files
+    }
+
+    pub fn grouped(&self, grouping: RegionGrouping) -> Vec<MemoryRegion> {
+        match grouping {
+            RegionGrouping::Region => self.regions.clone(),
+            RegionGrouping::Mapping => self.by_mapping(),
+            RegionGrouping::File => self.by_file(),
+        }
+    }
+
+    /// What grew from this snapshot to `later`, most resident growth first.
+    /// Regions and mappings are matched by backing and start address, which
+    /// stays put as the heap or an arena grows
+    pub fn diff(&self, later: &MemoryMap, grouping: RegionGrouping) -> Vec<RegionGrowth> {
+        let key = |r: &MemoryRegion| match grouping {
+            RegionGrouping::File => (r.kind.clone(), 0),
+            _ => (r.kind.clone(), r.start),
+        };
+        let before: HashMap<_, MemoryRegion> = self.grouped(grouping).into_iter().map(|r| (key(&r), r)).collect();
+
+        let delta = |after: u64, before: u64| after as i64 - before as i64;
+        let mut growth: Vec<RegionGrowth> = later.grouped(grouping).into_iter()
+            .filter_map(|after| {
+                let old = before.get(&key(&after));
+                let old_of = |field: fn(&MemoryRegion) -> u64| old.map_or(0, field);
+                let change = RegionGrowth {
+                    kind: after.kind.clone(),
+                    start: after.start,
+                    is_new: old.is_none(),
+                    size_delta: delta(after.size_bytes, old_of(|r| r.size_bytes)),
+                    rss_delta: delta(after.rss_bytes, old_of(|r| r.rss_bytes)),
+                    pss_delta: delta(after.pss_bytes, old_of(|r| r.pss_bytes)),
+                    dirty_delta: delta(after.dirty_bytes, old_of(|r| r.dirty_bytes)),
+                    swap_delta: delta(after.swap_bytes, old_of(|r| r.swap_bytes)),
+                    rss_bytes: after.rss_bytes,
+                };
+                // Memory pushed out to swap still grew
+                (change.size_delta > 0 || change.rss_delta + change.swap_delta > 0).then_some(change)
+            })
+            .collect();
+        growth.sort_by_key(|g| std::cmp::Reverse((g.rss_delta + g.swap_delta, g.size_delta)));
+        growth
+    }
+}
+
+/// `start-end perms offset dev inode [pathname]`
+fn parse_header(line: &str) -> Option<MemoryRegion> {
+    let mut fields = line.splitn(6, ' ');
+    let (start, end) = fields.next()?.split_once('-')?;
+    let perms = fields.next()?;
+    if perms.len() != 4 {
+        return None;
+    }
+    let (start, end) = (u64::from_str_radix(start, 16).ok()?, u64::from_str_radix(end, 16).ok()?);
+    let pathname = fields.nth(3).unwrap_or("").trim();
+
+    Some(MemoryRegion {
+        start,
+        end,
+        perms: perms.to_string(),
+        kind: MappingKind::from_pathname(pathname),
+        regions: 1,
+        size_bytes: end - start,
+        rss_bytes: 0,
+        pss_bytes: 0,
+        dirty_bytes: 0,
+        swap_bytes: 0,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn smaps(heap_rss: u64, anon_rss: u64, anon_swap: u64) -> String {
+        format!("\
+55d0c0a00000-55d0c0a02000 r--p 00000000 fe:00 317783                     /usr/bin/app
+Size:                  8 kB
+Rss:                   8 kB
+Pss:                   8 kB
+Private_Clean:         8 kB
+55d0c0a02000-55d0c0a06000 r-xp 00002000 fe:00 317783                     /usr/bin/app
+Size:                 16 kB
+Rss:                  16 kB
+Pss:                  16 kB
+VmFlags: rd ex mr mw me sd
+55d0c1000000-55d0c1400000 rw-p 00000000 00:00 0                          [heap]
+Size:               4096 kB
+Rss:                {heap_rss} kB
+Pss:                {heap_rss} kB
+Private_Dirty:      {heap_rss} kB
+7f0000000000-7f0000100000 rw-p 00000000 00:00 0
+Size:               1024 kB
+Rss:                {anon_rss} kB
+Pss:                {anon_rss} kB
+Shared_Dirty:          4 kB
+Private_Dirty:      {anon_rss} kB
+Swap:               {anon_swap} kB
+7f0000200000-7f0000201000 r--p 00000000 fe:00 1234                       /usr/lib/libc.so.6
+Size:                  4 kB
+Rss:                   4 kB
+Pss:                   1 kB
+7ffc00000000-7ffc00021000 rw-p 00000000 00:00 0                          [stack]
+Size:                132 kB
+Rss:                  12 kB
+Pss:                  12 kB
+")
+    }
+
+    #[test]
+    fn test_parse_regions() {
+        let map = MemoryMap::parse(42, &smaps(100, 40, 0));
+        assert_eq!(map.regions.len(), 6);
+        let anon = &map.regions[3];
+        assert_eq!(anon.kind, MappingKind::Anonymous);
+        assert_eq!((anon.start, anon.end), (0x7f0000000000, 0x7f0000100000));
+        assert_eq!(anon.rss_bytes, 40 * 1024);
+        assert_eq!(anon.dirty_bytes, 44 * 1024);
+        assert_eq!(map.regions[2].kind.label(), "[heap]");
+        assert_eq!(map.regions[5].kind, MappingKind::Stack);
+        assert_eq!(map.total_rss(), (8 + 16 + 100 + 40 + 4 + 12) * 1024);
+    }
+
+    #[test]
+    fn test_group_by_mapping_and_file() {
+        let map = MemoryMap::parse(42, &smaps(100, 40, 0));
+
+        let mappings = map.by_mapping();
+        assert_eq!(mappings.len(), 5);
+        assert_eq!(mappings[0].regions, 2);
+        assert_eq!(mappings[0].perms, "r-xp");
+        assert_eq!(mappings[0].rss_bytes, 24 * 1024);
+        assert_eq!(mappings[0].end, 0x55d0c0a06000);
+
+        let files = map.by_file();
+        assert_eq!(files[0].kind, MappingKind::Heap);
+        assert_eq!(files.iter().find(|f| f.kind.label() == "/usr/bin/app").unwrap().regions, 2);
+    }
+
+    #[test]
+    fn test_diff_shows_growth() {
+        let before = MemoryMap::parse(42, &smaps(100, 40, 0));
+        let mut later_smaps = smaps(900, 40, 200);
+        later_smaps.push_str("7f0000300000-7f0000400000 rw-p 00000000 00:00 0 \nSize: 1024 kB\nRss: 512 kB\n");
+        let after = MemoryMap::parse(42, &later_smaps);
+
+        let growth = before.diff(&after, RegionGrouping::Mapping);
+        assert_eq!(growth.len(), 3);
+        assert_eq!(growth[0].kind, MappingKind::Heap);
+        assert_eq!(growth[0].rss_delta, 800 * 1024);
+        assert!(growth[1].is_new);
+        assert_eq!(growth[2].swap_delta, 200 * 1024);
+
+        // Both anonymous regions land in one entry per file
+        let by_file = before.diff(&after, RegionGrouping::File);
+        let anon = by_file.iter().find(|g| g.kind == MappingKind::Anonymous).unwrap();
+        assert_eq!(anon.rss_delta + anon.swap_delta, (512 + 200) * 1024);
+        assert!(before.diff(&before, RegionGrouping::Region).is_empty());
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_read_own_map() {
+        let map = MemoryMap::read(std::process::id()).unwrap();
+        assert!(map.regions.iter().any(|r| r.kind == MappingKind::Stack));
+        assert!(map.regions.windows(2).all(|w| w[0].end <= w[1].start));
+        assert!(map.by_mapping().len() <= map.regions.len());
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_start_time_identifies_process() {
+        let own = MemoryMap::process_start_time(std::process::id()).unwrap();
+        assert_eq!(MemoryMap::process_start_time(std::process::id()), Some(own));
+
+        let mut child = std::process::Command::new("true").spawn().unwrap();
+        let pid = child.id();
+        assert!(MemoryMap::process_start_time(pid).unwrap() >= own);
+        child.wait().unwrap();
+        assert_eq!(MemoryMap::process_start_time(pid), None);
+    }
+}
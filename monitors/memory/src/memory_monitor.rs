@@ -1,5 +1,11 @@
+use reaper_core::common::{Monitor, MonitorType};
+use reaper_core::metrics::{MetricSample, SeriesKey};
+use reaper_core::sampling::{Metric, SharedSystem, SystemHandle};
+use crate::composition::{CompositionCollector, MemoryComposition};
+use crate::leak_detector::{LeakDetector, LeakDetectorConfig, LeakSuspect};
+use crate::pressure::{PressureResource, PressureStat, ResourcePressure, SystemPressure};
+use crate::smaps::{self, MemoryBreakdown, MemorySortKey};
 use sysinfo::System;
-use std::collections::HashMap;
 
 #[derive(Debug, Clone)]
 pub struct MemoryInfo {
@@ -17,15 +23,34 @@ pub struct MemoryInfo {
     pub memory_pressure: MemoryPressureLevel,
 }
 
-#[derive(Debug, Clone)]
+/// From memory PSI where the kernel has it, otherwise from used/total
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum MemoryPressureLevel {
-    Low,      // < 50% usage
-    Normal,   // 50-75% usage  
-    High,     // 75-90% usage
-    Critical, // > 90% usage
+    Low,      // < 1% stalled, < 50% usage
+    Normal,   // 1-10% stalled, 50-75% usage
+    High,     // 10-40% stalled or any full stall, 75-90% usage
+    Critical, // > 40% stalled or > 10% fully stalled, > 90% usage
 }
 
 impl MemoryPressureLevel {
+    /// Level from memory stall time. The worse of the 10s and 60s averages
+    /// catches bursts and keeps the level from flapping as they fade
+    pub fn from_pressure(pressure: &ResourcePressure) -> Self {
+        let recent = |stat: &PressureStat| stat.avg10.max(stat.avg60);
+        let some = recent(&pressure.some);
+        let full = pressure.full.as_ref().map_or(0.0, recent);
+
+        if full >= 10.0 || some >= 40.0 {
+            Self::Critical
+        } else if full >= 1.0 || some >= 10.0 {
+            Self::High
+        } else if some >= 1.0 {
+            Self::Normal
+        } else {
+            Self::Low
+        }
+    }
+
     pub fn from_usage_percent(percent: f32) -> Self {
         match percent {
             p if p < 50.0 => Self::Low,
@@ -54,11 +79,15 @@ pub struct ProcessMemoryInfo {
     pub memory_percent: f32,
     pub is_growing: bool,  // Track if memory is increasing
     pub growth_rate_mb_per_min: f32,
+    /// From smaps_rollup; only filled by `get_top_memory_processes_by`
+    pub breakdown: Option<MemoryBreakdown>,
 }
 
 pub struct MemoryMonitor {
-    system: System,
-    process_memory_history: HashMap<u32, Vec<u64>>,  // Track memory over time
+    system: SystemHandle,
+    leak_detector: LeakDetector,
+    pressure: SystemPressure,
+    composition: CompositionCollector,
     last_update: std::time::Instant,
 }
 
@@ -67,61 +96,71 @@ impl MemoryMonitor {
         let mut system = System::new_all();
         system.refresh_all();
         
+        Self::with_handle(SystemHandle::Owned(Box::new(system)))
+    }
+    
+    /// Read memory and processes from a system shared with other monitors
+    pub fn with_system(system: SharedSystem) -> Self {
+        Self::with_handle(SystemHandle::Shared(system))
+    }
+    
+    /// Flag leaks by `config` instead of the defaults
+    pub fn with_leak_config(mut self, config: LeakDetectorConfig) -> Self {
+        self.leak_detector = LeakDetector::new(config);
+        self
+    }
+    
+    fn with_handle(system: SystemHandle) -> Self {
         Self {
             system,
-            process_memory_history: HashMap::new(),
+            leak_detector: LeakDetector::default(),
+            pressure: SystemPressure::read(),
+            composition: {
+                let mut collector = CompositionCollector::new();
+                collector.refresh();
+                collector
+            },
             last_update: std::time::Instant::now(),
         }
     }
     
     pub fn refresh(&mut self) {
-        self.system.refresh_memory();
-        self.system.refresh_processes();
+        self.system.refresh(Metric::Memory, |system| system.refresh_memory());
+        self.system.refresh(Metric::Processes, |system| system.refresh_processes());
         self.update_memory_history();
+        self.pressure = SystemPressure::read();
+        self.composition.refresh();
         self.last_update = std::time::Instant::now();
     }
     
     fn update_memory_history(&mut self) {
-        // Track memory usage for each process
-        for (pid, process) in self.system.processes() {
-            let memory = process.memory();
-            let pid_u32 = pid.as_u32();
-            
-            let history = self.process_memory_history
-                .entry(pid_u32)
-                .or_insert_with(Vec::new);
-            
-            history.push(memory);
-            
-            // Keep only last 60 samples (1 minute at 1Hz)
-            if history.len() > 60 {
-                history.remove(0);
-            }
+        let now = std::time::Instant::now();
+        let system = self.system.read();
+        for (pid, process) in system.processes() {
+            self.leak_detector.record(pid.as_u32(), process.name(), now, process.memory());
         }
         
         // Clean up history for dead processes
-        let active_pids: Vec<u32> = self.system.processes()
-            .keys()
-            .map(|pid| pid.as_u32())
-            .collect();
-        
-        self.process_memory_history.retain(|pid, _| active_pids.contains(pid));
+        self.leak_detector.retain(|pid| system.process(sysinfo::Pid::from_u32(pid)).is_some());
     }
     
     pub fn get_memory_info(&self) -> MemoryInfo {
-        let total = self.system.total_memory() * 1024;  // Convert KB to bytes
-        let used = self.system.used_memory() * 1024;
-        let available = self.system.available_memory() * 1024;
-        let free = self.system.free_memory() * 1024;
+        let system = self.system.read();
+        // sysinfo reports bytes, as meminfo does below
+        let total = system.total_memory();
+        let used = system.used_memory();
+        let available = system.available_memory();
+        let free = system.free_memory();
         
-        let swap_total = self.system.total_swap() * 1024;
-        let swap_used = self.system.used_swap() * 1024;
-        let swap_free = self.system.free_swap() * 1024;
+        let swap_total = system.total_swap();
+        let swap_used = system.used_swap();
+        let swap_free = system.free_swap();
         
-        // Note: macOS doesn't provide cached/buffer separately through sysinfo
-        // These would need platform-specific implementations
-        let cached = 0;
-        let buffer = 0;
+        // sysinfo doesn't report cache and buffers; Linux has them in
+        // meminfo, other platforms would need their own sources
+        let composition = self.composition.latest();
+        let cached = composition.map_or(0, |c| c.reclaimable_cache_bytes());
+        let buffer = composition.map_or(0, |c| c.buffers_bytes);
         
         let usage_percent = if total > 0 {
             (used as f32 / total as f32) * 100.0
@@ -147,19 +186,24 @@ impl MemoryMonitor {
             buffer_bytes: buffer,
             usage_percent,
             swap_usage_percent,
-            memory_pressure: MemoryPressureLevel::from_usage_percent(usage_percent),
+            memory_pressure: match &self.pressure.memory {
+                Some(pressure) => MemoryPressureLevel::from_pressure(pressure),
+                None => MemoryPressureLevel::from_usage_percent(usage_percent),
+            },
         }
     }
     
     pub fn get_process_memory_info(&self) -> Vec<ProcessMemoryInfo> {
-        let total_memory = self.system.total_memory() as f32;
+        let system = self.system.read();
+        let total_memory = system.total_memory() as f32;
         
-        self.system.processes()
+        system.processes()
             .iter()
             .map(|(pid, process)| {
                 let pid_u32 = pid.as_u32();
-                let memory_bytes = process.memory() * 1024;  // KB to bytes
-                let virtual_memory_bytes = process.virtual_memory() * 1024;
+                // sysinfo reports both in bytes
+                let memory_bytes = process.memory();
+                let virtual_memory_bytes = process.virtual_memory();
                 
                 let memory_percent = if total_memory > 0.0 {
                     (process.memory() as f32 / total_memory) * 100.0
@@ -178,6 +222,7 @@ impl MemoryMonitor {
                     memory_percent,
                     is_growing,
                     growth_rate_mb_per_min: growth_rate,
+                    breakdown: None,
                 }
             })
             .collect()
@@ -189,46 +234,38 @@ impl MemoryMonitor {
         processes.truncate(limit);
         processes
     }
+
+    /// Largest processes by any part of their memory breakdown, which is
+    /// attached to each. Ranking by USS answers which process frees the
+    /// most memory if killed. See `rank_memory_processes` for doing the
+    /// smaps reads without holding the monitor
+    pub fn get_top_memory_processes_by(&self, limit: usize, key: MemorySortKey) -> Vec<ProcessMemoryInfo> {
+        rank_memory_processes(self.get_process_memory_info(), limit, key)
+    }
     
+    /// Processes the leak detector suspects, as plain process info
     pub fn detect_memory_leaks(&self) -> Vec<ProcessMemoryInfo> {
+        let suspects: Vec<u32> = self.find_leak_suspects().iter().map(|s| s.pid).collect();
         self.get_process_memory_info()
             .into_iter()
-            .filter(|p| p.is_growing && p.growth_rate_mb_per_min > 1.0)  // Growing > 1MB/min
+            .filter(|p| suspects.contains(&p.pid))
             .collect()
     }
     
+    /// Processes whose memory trend looks like a leak, with the evidence,
+    /// fastest growth first
+    pub fn find_leak_suspects(&self) -> Vec<LeakSuspect> {
+        self.leak_detector.suspects()
+    }
+    
     fn calculate_growth_rate(&self, pid: u32) -> (bool, f32) {
-        if let Some(history) = self.process_memory_history.get(&pid) {
-            if history.len() < 10 {
-                return (false, 0.0);
-            }
-            
-            // Calculate linear regression for trend
-            let n = history.len() as f32;
-            let mut sum_x = 0.0;
-            let mut sum_y = 0.0;
-            let mut sum_xy = 0.0;
-            let mut sum_x2 = 0.0;
-            
-            for (i, &memory) in history.iter().enumerate() {
-                let x = i as f32;
-                let y = memory as f32 / 1024.0 / 1024.0;  // Convert to MB
-                
-                sum_x += x;
-                sum_y += y;
-                sum_xy += x * y;
-                sum_x2 += x * x;
+        match self.leak_detector.trend(pid) {
+            Some(trend) => {
+                let growth_rate = (trend.bytes_per_min / 1024.0 / 1024.0) as f32;
+                let is_growing = growth_rate > 0.1 && trend.confidence >= self.leak_detector.config().min_confidence;
+                (is_growing, growth_rate)
             }
-            
-            let slope = (n * sum_xy - sum_x * sum_y) / (n * sum_x2 - sum_x * sum_x);
-            
-            // slope is MB per sample, convert to MB per minute (60 samples)
-            let growth_rate = slope * 60.0;
-            let is_growing = growth_rate > 0.1;  // Growing if > 0.1 MB/min
-            
-            (is_growing, growth_rate)
-        } else {
-            (false, 0.0)
+            None => (false, 0.0),
         }
     }
     
@@ -236,4 +273,128 @@ impl MemoryMonitor {
         let info = self.get_memory_info();
         info.memory_pressure
     }
-}
\ No newline at end of file
+
+    /// What memory is used for, with paging and swap rates since the
+    /// previous refresh. `None` off Linux
+    pub fn get_memory_composition(&self) -> Option<MemoryComposition> {
+        self.composition.latest()
+    }
+
+    /// CPU, memory and I/O stall times as of the last refresh
+    pub fn get_system_pressure(&self) -> SystemPressure {
+        self.pressure
+    }
+}
+
+/// The `limit` largest of `processes` by `key`, each with its memory
+/// breakdown. Every key but RSS and virtual size reads the smaps of every
+/// process, which walks their page tables
+pub fn rank_memory_processes(mut processes: Vec<ProcessMemoryInfo>, limit: usize, key: MemorySortKey) -> Vec<ProcessMemoryInfo> {
+    if !key.needs_breakdown() {
+        match key {
+            MemorySortKey::Virtual => processes.sort_by_key(|p| std::cmp::Reverse(p.virtual_memory_bytes)),
+            _ => processes.sort_by_key(|p| std::cmp::Reverse(p.memory_bytes)),
+        }
+        processes.truncate(limit);
+    }
+
+    for process in &mut processes {
+        process.breakdown = smaps::read_memory_breakdown(process.pid);
+    }
+
+    if key.needs_breakdown() {
+        // Processes we can't read rank last
+        processes.sort_by_key(|p| std::cmp::Reverse(p.breakdown.map_or(0, |b| key.value(&b))));
+        processes.truncate(limit);
+    }
+    processes
+}
+
+impl Monitor for MemoryMonitor {
+    fn name(&self) -> &str {
+        "memory"
+    }
+    
+    fn monitor_type(&self) -> MonitorType {
+        MonitorType::Memory
+    }
+    
+    fn refresh(&mut self) {
+        MemoryMonitor::refresh(self);
+    }
+    
+    fn metric_samples(&self) -> Vec<MetricSample> {
+        let info = self.get_memory_info();
+        let mut samples = vec![
+            MetricSample::new(SeriesKey::new("memory.used_bytes"), info.used_bytes as f64),
+            MetricSample::new(SeriesKey::new("memory.available_bytes"), info.available_bytes as f64),
+            MetricSample::new(SeriesKey::new("memory.swap_used_bytes"), info.swap_used_bytes as f64),
+            MetricSample::new(SeriesKey::new("memory.usage_percent"), info.usage_percent as f64),
+            MetricSample::new(SeriesKey::new("memory.pressure"), info.memory_pressure as u8 as f64),
+        ];
+        
+        if let Some(rates) = self.composition.latest().and_then(|c| c.rates) {
+            samples.push(MetricSample::new(SeriesKey::new("memory.major_faults_per_sec"), rates.major_faults_per_sec));
+            samples.push(MetricSample::new(SeriesKey::new("memory.swap_out_bytes_per_sec"), rates.swap_out_bytes_per_sec));
+        }
+        
+        for resource in [PressureResource::Cpu, PressureResource::Memory, PressureResource::Io] {
+            let Some(pressure) = self.pressure.get(resource) else { continue };
+            let key = |name: &str| SeriesKey::new(name).with_label("resource", resource.as_str());
+            samples.push(MetricSample::new(key("pressure.some_avg10"), pressure.some.avg10 as f64));
+            if let Some(full) = &pressure.full {
+                samples.push(MetricSample::new(key("pressure.full_avg10"), full.avg10 as f64));
+            }
+        }
+        
+        let process_key = |name: &str, p: &ProcessMemoryInfo| {
+            SeriesKey::new(name).with_label("pid", p.pid).with_label("process", &p.name)
+        };
+        
+        let mut processes = self.get_process_memory_info();
+        processes.sort_by_key(|p| std::cmp::Reverse(p.memory_bytes));
+        
+        // Leak flags for every suspect, sizes for the largest processes
+        let suspects: Vec<u32> = self.find_leak_suspects().iter().map(|s| s.pid).collect();
+        samples.extend(processes.iter()
+            .filter(|p| suspects.contains(&p.pid))
+            .map(|p| MetricSample::new(process_key("process.memory_leak", p), 1.0)));
+        samples.extend(processes.iter()
+            .take(10)
+            .map(|p| MetricSample::new(process_key("process.resident_bytes", p), p.memory_bytes as f64)));
+        samples
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_process_memory_in_bytes() {
+        let mut monitor = MemoryMonitor::new();
+        monitor.refresh();
+        let pid = std::process::id();
+        let rss = smaps::read_memory_breakdown(pid).unwrap().rss_bytes as f64;
+
+        // RSS moves a little between reads, but never by a factor of 1024
+        let recorded = monitor.leak_detector.latest(pid).unwrap() as f64;
+        assert!(recorded > rss / 2.0 && recorded < rss * 2.0, "{} vs {}", recorded, rss);
+        let info = monitor.get_process_memory_info().into_iter().find(|p| p.pid == pid).unwrap();
+        assert!((info.memory_bytes as f64) < rss * 2.0);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_memory_info_shares_units_with_meminfo() {
+        let monitor = MemoryMonitor::new();
+        let info = monitor.get_memory_info();
+        let composition = monitor.get_memory_composition().unwrap();
+
+        assert!(info.cached_bytes + info.buffer_bytes <= info.total_bytes);
+        assert!(info.used_bytes <= info.total_bytes);
+        // sysinfo may report a cgroup limit rather than all of MemTotal
+        assert!(info.total_bytes <= composition.total_bytes);
+    }
+}
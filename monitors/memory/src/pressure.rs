@@ -0,0 +1,154 @@
+//! Pressure Stall Information from `/proc/pressure/{cpu,memory,io}`
+//!
+//! PSI reports the share of wall time in which tasks were stalled waiting
+//! for a resource: `some` when at least one task was, `full` when every
+//! runnable task was. Unlike used/total, memory stalls only show up once
+//! reclaim or refaults actually slow things down, so a box full of page
+//! cache reads as unpressured.
+
+#[cfg(target_os = "linux")]
+use reaper_core::platform::linux::procfs;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PressureResource {
+    Cpu,
+    Memory,
+    Io,
+}
+
+impl PressureResource {
+    pub fn as_str(&self) -> &str {
+        match self {
+            Self::Cpu => "cpu",
+            Self::Memory => "memory",
+            Self::Io => "io",
+        }
+    }
+}
+
+/// One line of a PSI file: stalled percentages over 10s, 60s and 300s
+/// windows, and the total stall time since boot
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct PressureStat {
+    pub avg10: f32,
+    pub avg60: f32,
+    pub avg300: f32,
+    pub total_us: u64,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct ResourcePressure {
+    pub some: PressureStat,
+    /// Missing for CPU before Linux 5.13; always zero for CPU system-wide
+    pub full: Option<PressureStat>,
+}
+
+/// PSI for each resource; `None` where the kernel doesn't provide it
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct SystemPressure {
+    pub cpu: Option<ResourcePressure>,
+    pub memory: Option<ResourcePressure>,
+    pub io: Option<ResourcePressure>,
+}
+
+impl SystemPressure {
+    /// Read all three files; empty on kernels without PSI and off Linux
+    pub fn read() -> Self {
+        Self {
+            cpu: read_pressure(PressureResource::Cpu),
+            memory: read_pressure(PressureResource::Memory),
+            io: read_pressure(PressureResource::Io),
+        }
+    }
+
+    pub fn get(&self, resource: PressureResource) -> Option<&ResourcePressure> {
+        match resource {
+            PressureResource::Cpu => self.cpu.as_ref(),
+            PressureResource::Memory => self.memory.as_ref(),
+            PressureResource::Io => self.io.as_ref(),
+        }
+    }
+}
+
+/// Parse a `/proc/pressure/*` file
+pub fn parse_pressure(content: &str) -> Option<ResourcePressure> {
+    let mut pressure = ResourcePressure::default();
+    let mut found_some = false;
+
+    for line in content.lines() {
+        let mut fields = line.split_whitespace();
+        let kind = fields.next();
+        let mut stat = PressureStat::default();
+        for field in fields {
+            let Some((key, value)) = field.split_once('=') else { continue };
+            match key {
+                "avg10" => stat.avg10 = value.parse().ok()?,
+                "avg60" => stat.avg60 = value.parse().ok()?,
+                "avg300" => stat.avg300 = value.parse().ok()?,
+                "total" => stat.total_us = value.parse().ok()?,
+                _ => {}
+            }
+        }
+        match kind {
+            Some("some") => {
+                pressure.some = stat;
+                found_some = true;
+            }
+            Some("full") => pressure.full = Some(stat),
+            _ => {}
+        }
+    }
+
+    found_some.then_some(pressure)
+}
+
+#[cfg(target_os = "linux")]
+pub fn read_pressure(resource: PressureResource) -> Option<ResourcePressure> {
+    // Reads fail with EOPNOTSUPP when the kernel was booted with psi=0
+    let content = procfs::read_proc_file(&format!("pressure/{}", resource.as_str())).ok()?;
+    parse_pressure(&content)
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn read_pressure(_resource: PressureResource) -> Option<ResourcePressure> {
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::memory_monitor::MemoryPressureLevel;
+
+    #[test]
+    fn test_parse_pressure() {
+        let memory = parse_pressure(
+            "some avg10=1.49 avg60=3.43 avg300=4.46 total=224934905\n\
+             full avg10=0.00 avg60=0.50 avg300=0.00 total=1200\n",
+        ).unwrap();
+        assert_eq!(memory.some, PressureStat { avg10: 1.49, avg60: 3.43, avg300: 4.46, total_us: 224934905 });
+        assert_eq!(memory.full.unwrap().avg60, 0.5);
+
+        // CPU before 5.13 has no `full` line
+        let cpu = parse_pressure("some avg10=0.00 avg60=0.00 avg300=0.00 total=0\n").unwrap();
+        assert_eq!(cpu.full, None);
+        assert_eq!(parse_pressure(""), None);
+        assert_eq!(parse_pressure("some avg10=x avg60=0 avg300=0 total=0"), None);
+    }
+
+    #[test]
+    fn test_level_from_stalls() {
+        let pressure = |some: f32, full: f32| ResourcePressure {
+            some: PressureStat { avg10: some, avg60: some, ..Default::default() },
+            full: Some(PressureStat { avg10: full, avg60: full, ..Default::default() }),
+        };
+        assert_eq!(MemoryPressureLevel::from_pressure(&pressure(0.0, 0.0)), MemoryPressureLevel::Low);
+        assert_eq!(MemoryPressureLevel::from_pressure(&pressure(3.0, 0.0)), MemoryPressureLevel::Normal);
+        assert_eq!(MemoryPressureLevel::from_pressure(&pressure(20.0, 1.0)), MemoryPressureLevel::High);
+        assert_eq!(MemoryPressureLevel::from_pressure(&pressure(20.0, 12.0)), MemoryPressureLevel::Critical);
+
+        // A burst in the last 10s counts before the longer window catches up
+        let mut burst = pressure(0.0, 0.0);
+        burst.full = Some(PressureStat { avg10: 30.0, ..Default::default() });
+        assert_eq!(MemoryPressureLevel::from_pressure(&burst), MemoryPressureLevel::Critical);
+    }
+}
@@ -0,0 +1,174 @@
+//! Per-process memory breakdown from `/proc/<pid>/smaps_rollup`
+//!
+//! RSS counts every shared library page in full for every process mapping
+//! it. PSS splits shared pages between their users, and USS (private pages
+//! only) is what the system gets back when the process exits.
+
+#[cfg(target_os = "linux")]
+use reaper_core::platform::linux::procfs;
+use std::collections::HashMap;
+
+/// Resident, proportional, unique and swapped memory of one process, in bytes
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct MemoryBreakdown {
+    pub rss_bytes: u64,
+    /// Each shared page divided by the number of processes mapping it
+    pub pss_bytes: u64,
+    /// Private clean and dirty pages, freed when the process exits
+    pub uss_bytes: u64,
+    /// Resident pages also mapped by other processes
+    pub shared_bytes: u64,
+    pub anonymous_bytes: u64,
+    /// Resident pages backed by files, shmem included
+    pub file_bytes: u64,
+    pub swap_bytes: u64,
+    /// Swapped-out pages divided among the processes sharing them
+    pub swap_pss_bytes: u64,
+}
+
+impl MemoryBreakdown {
+    /// Build from the `Key: N kB` fields of an smaps entry or rollup
+    pub fn from_fields(fields: &HashMap<String, u64>) -> Self {
+        let kb = |key: &str| fields.get(key).copied().unwrap_or(0) * 1024;
+        let rss = kb("Rss");
+        let anonymous = kb("Anonymous");
+
+        Self {
+            rss_bytes: rss,
+            pss_bytes: kb("Pss"),
+            uss_bytes: kb("Private_Clean") + kb("Private_Dirty"),
+            shared_bytes: kb("Shared_Clean") + kb("Shared_Dirty"),
+            anonymous_bytes: anonymous,
+            file_bytes: rss.saturating_sub(anonymous),
+            swap_bytes: kb("Swap"),
+            swap_pss_bytes: kb("SwapPss"),
+        }
+    }
+}
+
+/// What to rank processes by in `get_top_memory_processes_by`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MemorySortKey {
+    Rss,
+    Pss,
+    /// Memory freed by killing the process
+    Uss,
+    Shared,
+    Anonymous,
+    File,
+    Swap,
+    SwapPss,
+    Virtual,
+}
+
+impl MemorySortKey {
+    pub fn from_index(index: u8) -> Option<Self> {
+        Some(match index {
+            0 => Self::Rss,
+            1 => Self::Pss,
+            2 => Self::Uss,
+            3 => Self::Shared,
+            4 => Self::Anonymous,
+            5 => Self::File,
+            6 => Self::Swap,
+            7 => Self::SwapPss,
+            8 => Self::Virtual,
+            _ => return None,
+        })
+    }
+
+    /// Whether ranking needs every process's smaps rather than sysinfo alone
+    pub fn needs_breakdown(&self) -> bool {
+        !matches!(self, Self::Rss | Self::Virtual)
+    }
+
+    pub fn value(&self, breakdown: &MemoryBreakdown) -> u64 {
+        match self {
+            Self::Rss => breakdown.rss_bytes,
+            Self::Pss => breakdown.pss_bytes,
+            Self::Uss => breakdown.uss_bytes,
+            Self::Shared => breakdown.shared_bytes,
+            Self::Anonymous => breakdown.anonymous_bytes,
+            Self::File => breakdown.file_bytes,
+            Self::Swap => breakdown.swap_bytes,
+            Self::SwapPss => breakdown.swap_pss_bytes,
+            Self::Virtual => 0,
+        }
+    }
+}
+
+/// Parse one `Key:   N kB` line of smaps; values without a unit (such as
+/// `THPeligible`) are skipped
+pub fn parse_smaps_field(line: &str) -> Option<(&str, u64)> {
+    let (key, rest) = line.split_once(':')?;
+    let mut value = rest.split_whitespace();
+    let amount = value.next()?.parse().ok()?;
+    (value.next() == Some("kB")).then_some((key, amount))
+}
+
+/// Parse `/proc/<pid>/smaps_rollup`. Kernel threads have no mappings and
+/// an empty file
+pub fn parse_smaps_rollup(content: &str) -> Option<MemoryBreakdown> {
+    let fields: HashMap<String, u64> = content.lines()
+        .filter_map(parse_smaps_field)
+        .map(|(key, value)| (key.to_string(), value))
+        .collect();
+    (!fields.is_empty()).then(|| MemoryBreakdown::from_fields(&fields))
+}
+
+#[cfg(target_os = "linux")]
+pub fn read_memory_breakdown(pid: u32) -> Option<MemoryBreakdown> {
+    parse_smaps_rollup(&procfs::read_pid_file(pid, "smaps_rollup").ok()?)
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn read_memory_breakdown(_pid: u32) -> Option<MemoryBreakdown> {
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ROLLUP: &str = "\
+556f35723000-7ffdc3ab0000 ---p 00000000 00:00 0                          [rollup]
+Rss:                1420 kB
+Pss:                 504 kB
+Pss_Anon:            100 kB
+Shared_Clean:       1244 kB
+Shared_Dirty:          0 kB
+Private_Clean:        76 kB
+Private_Dirty:       100 kB
+Anonymous:           100 kB
+Swap:                 64 kB
+SwapPss:              32 kB
+THPeligible:           0
+";
+
+    #[test]
+    fn test_parse_smaps_rollup() {
+        let breakdown = parse_smaps_rollup(ROLLUP).unwrap();
+        assert_eq!(breakdown, MemoryBreakdown {
+            rss_bytes: 1420 * 1024,
+            pss_bytes: 504 * 1024,
+            uss_bytes: 176 * 1024,
+            shared_bytes: 1244 * 1024,
+            anonymous_bytes: 100 * 1024,
+            file_bytes: 1320 * 1024,
+            swap_bytes: 64 * 1024,
+            swap_pss_bytes: 32 * 1024,
+        });
+        assert_eq!(parse_smaps_rollup(""), None);
+        assert_eq!(parse_smaps_field("THPeligible:    0"), None);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_read_own_breakdown() {
+        let breakdown = read_memory_breakdown(std::process::id()).unwrap();
+        assert!(breakdown.rss_bytes > 0);
+        assert!(breakdown.pss_bytes <= breakdown.rss_bytes);
+        assert!(breakdown.uss_bytes <= breakdown.pss_bytes);
+        assert_eq!(breakdown.rss_bytes, breakdown.uss_bytes + breakdown.shared_bytes);
+    }
+}
@@ -1,4 +1,5 @@
-use std::process::Command;
+use crate::interface_stats::{default_interface_source, InterfaceStatsSource};
+use crate::process_traffic::{default_traffic_source, ProcessTrafficSource, TrafficRateTracker};
 use std::collections::HashMap;
 use std::time::Instant;
 
@@ -24,6 +25,50 @@ pub struct InterfaceStats {
     pub errors_out: u64,
     pub drops_in: u64,
     pub drops_out: u64,
+    /// Negotiated link speed, unknown for virtual links or links that are down
+    pub link_speed_mbps: Option<u64>,
+    pub mtu: u32,
+    pub mac_address: Option<String>,
+    pub kind: InterfaceKind,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InterfaceKind {
+    Wired,
+    Wireless,
+    Virtual,
+    Loopback,
+    Unknown,
+}
+
+impl InterfaceStats {
+    /// An interface with the given name and no counters or link information
+    pub fn empty(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            is_active: false,
+            bytes_sent: 0,
+            bytes_received: 0,
+            packets_sent: 0,
+            packets_received: 0,
+            errors_in: 0,
+            errors_out: 0,
+            drops_in: 0,
+            drops_out: 0,
+            link_speed_mbps: None,
+            mtu: 0,
+            mac_address: None,
+            kind: InterfaceKind::Unknown,
+        }
+    }
+
+    /// Link utilisation in percent for the given rates in bytes per second.
+    /// Links are full duplex, so the busier direction is what saturates.
+    pub fn utilization_percent(&self, upload_bps: u64, download_bps: u64) -> Option<f64> {
+        let capacity_bits = self.link_speed_mbps? as f64 * 1_000_000.0;
+        let busiest_bits = upload_bps.max(download_bps) as f64 * 8.0;
+        Some(busiest_bits / capacity_bits * 100.0)
+    }
 }
 
 #[derive(Debug)]
@@ -42,10 +87,18 @@ pub struct BandwidthMonitor {
     sample_count: u64,
     total_upload: u64,
     total_download: u64,
+    traffic_source: Box<dyn ProcessTrafficSource>,
+    traffic_rates: TrafficRateTracker,
+    interface_source: Box<dyn InterfaceStatsSource>,
+    interface_rates: HashMap<String, (u64, u64)>, // name -> (upload, download)
 }
 
 impl BandwidthMonitor {
     pub fn new() -> Self {
+        Self::with_traffic_source(default_traffic_source())
+    }
+    
+    pub fn with_traffic_source(traffic_source: Box<dyn ProcessTrafficSource>) -> Self {
         Self {
             interface_snapshots: HashMap::new(),
             process_bandwidth: HashMap::new(),
@@ -62,8 +115,17 @@ impl BandwidthMonitor {
             sample_count: 0,
             total_upload: 0,
             total_download: 0,
+            traffic_source,
+            traffic_rates: TrafficRateTracker::new(),
+            interface_source: default_interface_source(),
+            interface_rates: HashMap::new(),
         }
     }
+
+    pub fn with_interface_source(mut self, interface_source: Box<dyn InterfaceStatsSource>) -> Self {
+        self.interface_source = interface_source;
+        self
+    }
     
     pub fn get_current_bandwidth(&mut self) -> BandwidthStats {
         self.refresh();
@@ -71,23 +133,17 @@ impl BandwidthMonitor {
     }
     
     pub fn get_interface_stats(&self) -> Vec<InterfaceStats> {
-        let mut interfaces = Vec::new();
-        
-        // Run ifconfig to get interface statistics
-        if let Ok(output) = Command::new("ifconfig").arg("-a").output() {
-            let stdout = String::from_utf8_lossy(&output.stdout);
-            interfaces = self.parse_ifconfig(&stdout);
-        }
-        
-        // Alternative: Use netstat -i for interface statistics
-        if interfaces.is_empty() {
-            if let Ok(output) = Command::new("netstat").args(&["-i", "-b"]).output() {
-                let stdout = String::from_utf8_lossy(&output.stdout);
-                interfaces = self.parse_netstat_interfaces(&stdout);
-            }
-        }
-        
-        interfaces
+        self.interface_source.collect()
+    }
+    
+    /// Rates of one interface from the last two refreshes, as (upload, download)
+    pub fn get_interface_bandwidth(&self, name: &str) -> Option<(u64, u64)> {
+        self.interface_rates.get(name).copied()
+    }
+    
+    /// Latest (upload, download) bytes per second of every interface
+    pub fn interface_rates(&self) -> &HashMap<String, (u64, u64)> {
+        &self.interface_rates
     }
     
     pub fn get_process_bandwidth(&self, pid: u32) -> Option<(u64, u64)> {
@@ -119,6 +175,7 @@ impl BandwidthMonitor {
                     
                     total_upload_bps += upload_bps;
                     total_download_bps += download_bps;
+                    self.interface_rates.insert(interface.name.clone(), (upload_bps, download_bps));
                 }
             }
             
@@ -158,113 +215,12 @@ impl BandwidthMonitor {
             self.current_stats.average_download_bps = self.total_download / self.sample_count;
         }
         
-        // Try to get per-process bandwidth using nettop (macOS specific)
         self.update_process_bandwidth();
     }
     
-    fn parse_ifconfig(&self, output: &str) -> Vec<InterfaceStats> {
-        let mut interfaces = Vec::new();
-        let mut current_interface: Option<InterfaceStats> = None;
-        
-        for line in output.lines() {
-            // Check if this is a new interface line (starts at column 0)
-            if !line.starts_with('\t') && !line.starts_with(' ') && line.contains(':') {
-                // Save previous interface if exists
-                if let Some(interface) = current_interface.take() {
-                    interfaces.push(interface);
-                }
-                
-                // Parse interface name
-                if let Some(colon_pos) = line.find(':') {
-                    let name = line[..colon_pos].to_string();
-                    let is_active = line.contains("UP") && line.contains("RUNNING");
-                    
-                    current_interface = Some(InterfaceStats {
-                        name,
-                        is_active,
-                        bytes_sent: 0,
-                        bytes_received: 0,
-                        packets_sent: 0,
-                        packets_received: 0,
-                        errors_in: 0,
-                        errors_out: 0,
-                        drops_in: 0,
-                        drops_out: 0,
-                    });
-                }
-            } else if current_interface.is_some() {
-                // Parse interface statistics
-                // Look for lines like: "RX packets:12345 errors:0 dropped:0"
-                if line.contains("packets") || line.contains("bytes") {
-                    // This is highly platform-specific, simplified for macOS
-                    // Real implementation would need more robust parsing
-                }
-            }
-        }
-        
-        // Don't forget the last interface
-        if let Some(interface) = current_interface {
-            interfaces.push(interface);
-        }
-        
-        interfaces
-    }
-    
-    fn parse_netstat_interfaces(&self, output: &str) -> Vec<InterfaceStats> {
-        let mut interfaces = Vec::new();
-        let lines: Vec<&str> = output.lines().collect();
-        
-        // Skip header lines
-        for line in lines.iter().skip(1) {
-            let parts: Vec<&str> = line.split_whitespace().collect();
-            
-            if parts.len() >= 11 {
-                let name = parts[0].to_string();
-                
-                // Parse statistics (positions may vary)
-                let packets_in = parts[4].parse::<u64>().unwrap_or(0);
-                let errs_in = parts[5].parse::<u64>().unwrap_or(0);
-                let bytes_in = parts[6].parse::<u64>().unwrap_or(0);
-                let packets_out = parts[7].parse::<u64>().unwrap_or(0);
-                let errs_out = parts[8].parse::<u64>().unwrap_or(0);
-                let bytes_out = parts[9].parse::<u64>().unwrap_or(0);
-                
-                interfaces.push(InterfaceStats {
-                    name,
-                    is_active: bytes_in > 0 || bytes_out > 0,
-                    bytes_sent: bytes_out,
-                    bytes_received: bytes_in,
-                    packets_sent: packets_out,
-                    packets_received: packets_in,
-                    errors_in: errs_in,
-                    errors_out: errs_out,
-                    drops_in: 0,
-                    drops_out: 0,
-                });
-            }
-        }
-        
-        interfaces
-    }
-    
     fn update_process_bandwidth(&mut self) {
-        // Try to use nettop to get per-process bandwidth (macOS specific)
-        // Note: nettop requires special entitlements or root
-        // This is a simplified implementation
-        
-        if let Ok(output) = Command::new("nettop")
-            .args(&["-P", "-l", "1", "-J", "bytes_in,bytes_out"])
-            .output()
-        {
-            let stdout = String::from_utf8_lossy(&output.stdout);
-            self.parse_nettop(&stdout);
-        }
-    }
-    
-    fn parse_nettop(&mut self, _output: &str) {
-        // Parse nettop output to get per-process bandwidth
-        // This is platform-specific and requires proper parsing
-        // For now, we'll leave this as a stub
+        let samples = self.traffic_source.sample();
+        self.process_bandwidth = self.traffic_rates.update(samples, Instant::now());
     }
 }
 
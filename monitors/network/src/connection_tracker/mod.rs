@@ -0,0 +1,183 @@
+use std::collections::HashMap;
+
+mod netstat;
+mod procfs;
+
+pub use netstat::NetstatConnectionSource;
+pub use procfs::{
+    ProcfsConnectionSource, SocketEntry, parse_hex_endpoint, parse_inet_table, parse_socket_link,
+    parse_unix_table,
+};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Protocol {
+    TCP,
+    UDP,
+    TCP6,
+    UDP6,
+    Unix,
+    Other(String),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ConnectionState {
+    Established,
+    Listen,
+    SynSent,
+    SynReceived,
+    FinWait1,
+    FinWait2,
+    TimeWait,
+    CloseWait,
+    LastAck,
+    Closing,
+    Closed,
+    Unknown,
+}
+
+#[derive(Debug, Clone)]
+pub struct NetworkConnection {
+    pub pid: Option<u32>,
+    pub process_name: String,
+    pub local_address: String,
+    pub local_port: u16,
+    pub remote_address: String,
+    pub remote_port: u16,
+    pub protocol: Protocol,
+    pub state: ConnectionState,
+    pub bytes_sent: u64,
+    pub bytes_received: u64,
+}
+
+/// A backend that produces the current socket list with owning processes
+pub trait ConnectionSource: Send {
+    fn collect(&self) -> Vec<NetworkConnection>;
+}
+
+/// Connection source used when none is given explicitly
+pub fn default_source() -> Box<dyn ConnectionSource> {
+    #[cfg(target_os = "linux")]
+    {
+        Box::new(ProcfsConnectionSource::new())
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    {
+        Box::new(NetstatConnectionSource::new())
+    }
+}
+
+pub struct ConnectionTracker {
+    connections: Vec<NetworkConnection>,
+    process_map: HashMap<u32, String>, // pid -> process name
+    source: Box<dyn ConnectionSource>,
+}
+
+impl ConnectionTracker {
+    pub fn new() -> Self {
+        Self::with_source(default_source())
+    }
+    
+    pub fn with_source(source: Box<dyn ConnectionSource>) -> Self {
+        Self {
+            connections: Vec::new(),
+            process_map: HashMap::new(),
+            source,
+        }
+    }
+    
+    pub fn get_connections(&mut self) -> Vec<NetworkConnection> {
+        self.refresh();
+        self.connections.clone()
+    }
+    
+    pub fn get_connections_for_pid(&mut self, pid: u32) -> Vec<NetworkConnection> {
+        self.refresh();
+        self.connections
+            .iter()
+            .filter(|c| c.pid == Some(pid))
+            .cloned()
+            .collect()
+    }
+    
+    /// Name of a process seen owning a socket during the last refresh
+    pub fn process_name(&self, pid: u32) -> Option<&str> {
+        self.process_map.get(&pid).map(|s| s.as_str())
+    }
+    
+    pub fn refresh(&mut self) {
+        self.connections = self.source.collect();
+        self.process_map = self.connections
+            .iter()
+            .filter_map(|c| Some((c.pid?, c.process_name.clone())))
+            .collect();
+    }
+}
+
+impl Default for ConnectionTracker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Protocol {
+    pub fn display_name(&self) -> &str {
+        match self {
+            Protocol::TCP => "TCP",
+            Protocol::UDP => "UDP",
+            Protocol::TCP6 => "TCP6",
+            Protocol::UDP6 => "UDP6",
+            Protocol::Unix => "Unix",
+            Protocol::Other(name) => name,
+        }
+    }
+}
+
+impl ConnectionState {
+    /// Parse a state name as printed by netstat or lsof
+    pub fn from_name(state_str: &str) -> Self {
+        match state_str.to_uppercase().as_str() {
+            "ESTABLISHED" => ConnectionState::Established,
+            "LISTEN" => ConnectionState::Listen,
+            "SYN_SENT" => ConnectionState::SynSent,
+            "SYN_RECEIVED" | "SYN_RCVD" => ConnectionState::SynReceived,
+            "FIN_WAIT_1" | "FIN_WAIT1" => ConnectionState::FinWait1,
+            "FIN_WAIT_2" | "FIN_WAIT2" => ConnectionState::FinWait2,
+            "TIME_WAIT" => ConnectionState::TimeWait,
+            "CLOSE_WAIT" => ConnectionState::CloseWait,
+            "LAST_ACK" => ConnectionState::LastAck,
+            "CLOSING" => ConnectionState::Closing,
+            "CLOSED" => ConnectionState::Closed,
+            _ => ConnectionState::Unknown,
+        }
+    }
+    
+    pub fn display_name(&self) -> &str {
+        match self {
+            ConnectionState::Established => "Established",
+            ConnectionState::Listen => "Listen",
+            ConnectionState::SynSent => "SYN Sent",
+            ConnectionState::SynReceived => "SYN Received",
+            ConnectionState::FinWait1 => "FIN Wait 1",
+            ConnectionState::FinWait2 => "FIN Wait 2",
+            ConnectionState::TimeWait => "Time Wait",
+            ConnectionState::CloseWait => "Close Wait",
+            ConnectionState::LastAck => "Last ACK",
+            ConnectionState::Closing => "Closing",
+            ConnectionState::Closed => "Closed",
+            ConnectionState::Unknown => "Unknown",
+        }
+    }
+    
+    pub fn color(&self) -> &str {
+        match self {
+            ConnectionState::Established => "green",
+            ConnectionState::Listen => "blue",
+            ConnectionState::SynSent | ConnectionState::SynReceived => "yellow",
+            ConnectionState::TimeWait | ConnectionState::CloseWait => "orange",
+            ConnectionState::Closed | ConnectionState::LastAck | 
+            ConnectionState::FinWait1 | ConnectionState::FinWait2 => "gray",
+            _ => "secondary",
+        }
+    }
+}
\ No newline at end of file
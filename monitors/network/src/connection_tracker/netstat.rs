@@ -0,0 +1,204 @@
+//! macOS connection source built on `netstat -anv` and `lsof -i`
+
+use super::{ConnectionSource, ConnectionState, NetworkConnection, Protocol};
+use regex::Regex;
+use std::process::Command;
+
+/// Connection source that shells out to `netstat` and maps sockets to
+/// processes with `lsof`
+pub struct NetstatConnectionSource;
+
+impl NetstatConnectionSource {
+    pub fn new() -> Self {
+        Self
+    }
+
+    /// Build connections from captured `netstat -anv` and `lsof -i -n -P` output
+    pub fn parse(netstat_output: &str, lsof_output: &str) -> Vec<NetworkConnection> {
+        let mut connections = Vec::new();
+        parse_tcp_connections(netstat_output, &mut connections);
+        parse_udp_connections(netstat_output, &mut connections);
+        map_processes_with_lsof(lsof_output, &mut connections);
+        connections
+    }
+}
+
+impl Default for NetstatConnectionSource {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ConnectionSource for NetstatConnectionSource {
+    fn collect(&self) -> Vec<NetworkConnection> {
+        let netstat = Command::new("netstat")
+            .args(["-anv"])
+            .output()
+            .map(|o| String::from_utf8_lossy(&o.stdout).into_owned())
+            .unwrap_or_default();
+
+        let lsof = Command::new("lsof")
+            .args(["-i", "-n", "-P"])
+            .output()
+            .map(|o| String::from_utf8_lossy(&o.stdout).into_owned())
+            .unwrap_or_default();
+
+        Self::parse(&netstat, &lsof)
+    }
+}
+
+fn parse_tcp_connections(netstat_output: &str, connections: &mut Vec<NetworkConnection>) {
+    // Regex for TCP connections
+    // Example: tcp4       0      0  127.0.0.1.6942         127.0.0.1.52389        ESTABLISHED
+    let tcp_regex = Regex::new(
+        r"(tcp[46]?)\s+\d+\s+\d+\s+([\d\.\:]+)\.(\d+)\s+([\d\.\:]+|\*)\.(\d+|\*)\s+(\w+)"
+    ).unwrap();
+
+    for line in netstat_output.lines() {
+        if let Some(captures) = tcp_regex.captures(line) {
+            let protocol = match &captures[1] {
+                "tcp" | "tcp4" => Protocol::TCP,
+                "tcp6" => Protocol::TCP6,
+                _ => continue,
+            };
+
+            let local_addr = captures[2].to_string();
+            let local_port = captures[3].parse::<u16>().unwrap_or(0);
+            let remote_addr = captures[4].to_string();
+            let remote_port = if &captures[5] == "*" {
+                0
+            } else {
+                captures[5].parse::<u16>().unwrap_or(0)
+            };
+
+            let state = ConnectionState::from_name(&captures[6]);
+
+            connections.push(NetworkConnection {
+                pid: None,
+                process_name: String::new(),
+                local_address: local_addr,
+                local_port,
+                remote_address: remote_addr,
+                remote_port,
+                protocol,
+                state,
+                bytes_sent: 0,
+                bytes_received: 0,
+            });
+        }
+    }
+}
+
+fn parse_udp_connections(netstat_output: &str, connections: &mut Vec<NetworkConnection>) {
+    // Regex for UDP connections
+    let udp_regex = Regex::new(
+        r"(udp[46]?)\s+\d+\s+\d+\s+([\d\.\:]+)\.(\d+)\s+([\d\.\:]+|\*)\.(\d+|\*)"
+    ).unwrap();
+
+    for line in netstat_output.lines() {
+        if let Some(captures) = udp_regex.captures(line) {
+            let protocol = match &captures[1] {
+                "udp" | "udp4" => Protocol::UDP,
+                "udp6" => Protocol::UDP6,
+                _ => continue,
+            };
+
+            let local_addr = captures[2].to_string();
+            let local_port = captures[3].parse::<u16>().unwrap_or(0);
+            let remote_addr = if &captures[4] == "*" {
+                "*".to_string()
+            } else {
+                captures[4].to_string()
+            };
+            let remote_port = if &captures[5] == "*" {
+                0
+            } else {
+                captures[5].parse::<u16>().unwrap_or(0)
+            };
+
+            connections.push(NetworkConnection {
+                pid: None,
+                process_name: String::new(),
+                local_address: local_addr,
+                local_port,
+                remote_address: remote_addr,
+                remote_port,
+                protocol,
+                state: ConnectionState::Established, // UDP doesn't have states
+                bytes_sent: 0,
+                bytes_received: 0,
+            });
+        }
+    }
+}
+
+fn map_processes_with_lsof(lsof_output: &str, connections: &mut [NetworkConnection]) {
+    // Parse lsof output
+    // Example: COMMAND     PID   USER   FD   TYPE             DEVICE SIZE/OFF NODE NAME
+    //          firefox   12345   user   45u  IPv4 0x1234567890abcdef      0t0  TCP 192.168.1.2:54321->93.184.216.34:443 (ESTABLISHED)
+    for line in lsof_output.lines().skip(1) { // Skip header
+        let parts: Vec<&str> = line.split_whitespace().collect();
+        if parts.len() >= 9 {
+            let process_name = parts[0];
+            if let Ok(pid) = parts[1].parse::<u32>() {
+                // The NAME column is followed by an optional "(STATE)" column
+                let connection_info = parts.iter()
+                    .rev()
+                    .find(|p| !p.starts_with('('))
+                    .copied()
+                    .unwrap_or_default();
+                match_connection_with_process(connections, pid, process_name, connection_info);
+            }
+        }
+    }
+}
+
+fn match_connection_with_process(
+    connections: &mut [NetworkConnection],
+    pid: u32,
+    process_name: &str,
+    connection_str: &str,
+) {
+    // Parse connection string like "192.168.1.2:54321->93.184.216.34:443"
+    if let Some(arrow_pos) = connection_str.find("->") {
+        let local_part = &connection_str[..arrow_pos];
+        let remote_part = &connection_str[arrow_pos + 2..];
+
+        // Parse local address and port
+        if let Some(colon_pos) = local_part.rfind(':') {
+            let local_port = local_part[colon_pos + 1..]
+                .parse::<u16>()
+                .unwrap_or(0);
+
+            // Parse remote address and port
+            if let Some(remote_colon) = remote_part.rfind(':') {
+                let remote_port = remote_part[remote_colon + 1..]
+                    .split('(') // Remove state info like "(ESTABLISHED)"
+                    .next()
+                    .and_then(|s| s.parse::<u16>().ok())
+                    .unwrap_or(0);
+
+                // Find matching connection and update it
+                if let Some(conn) = connections.iter_mut()
+                    .find(|c| c.local_port == local_port && c.remote_port == remote_port)
+                {
+                    conn.pid = Some(pid);
+                    conn.process_name = process_name.to_string();
+                }
+            }
+        }
+    } else if let Some(colon_pos) = connection_str.rfind(':') {
+        // Handle LISTEN connections (no remote address)
+        let port = connection_str[colon_pos + 1..]
+            .parse::<u16>()
+            .unwrap_or(0);
+
+        // Find matching LISTEN connection
+        if let Some(conn) = connections.iter_mut()
+            .find(|c| c.local_port == port && c.state == ConnectionState::Listen)
+        {
+            conn.pid = Some(pid);
+            conn.process_name = process_name.to_string();
+        }
+    }
+}
@@ -0,0 +1,260 @@
+//! Linux connection source built on `/proc/net/*` socket tables
+//!
+//! Sockets are listed from `/proc/net/{tcp,tcp6,udp,udp6,unix}` and mapped
+//! to their owning processes by matching the socket inode against the
+//! `socket:[inode]` links in `/proc/<pid>/fd`. No external process is spawned.
+
+use super::{ConnectionSource, ConnectionState, NetworkConnection, Protocol};
+use std::collections::HashMap;
+use std::net::{Ipv4Addr, Ipv6Addr};
+use std::path::{Path, PathBuf};
+
+/// Socket flag marking a listening unix socket (`__SO_ACCEPTCON`)
+const UNIX_ACCEPTCON: u32 = 0x0001_0000;
+
+/// One row of a `/proc/net` socket table
+#[derive(Debug, Clone, PartialEq)]
+pub struct SocketEntry {
+    pub protocol: Protocol,
+    pub local_address: String,
+    pub local_port: u16,
+    pub remote_address: String,
+    pub remote_port: u16,
+    pub state: ConnectionState,
+    pub inode: u64,
+}
+
+/// Connection source that reads the kernel socket tables directly
+pub struct ProcfsConnectionSource {
+    proc_root: PathBuf,
+}
+
+impl ProcfsConnectionSource {
+    pub fn new() -> Self {
+        Self::with_root("/proc")
+    }
+
+    /// Read from an alternative proc root, e.g. a fixture directory
+    pub fn with_root(proc_root: impl Into<PathBuf>) -> Self {
+        Self {
+            proc_root: proc_root.into(),
+        }
+    }
+
+    /// Read all socket tables under `<root>/net`
+    pub fn read_sockets(&self) -> Vec<SocketEntry> {
+        let tables = [
+            ("tcp", Protocol::TCP),
+            ("tcp6", Protocol::TCP6),
+            ("udp", Protocol::UDP),
+            ("udp6", Protocol::UDP6),
+        ];
+
+        let mut sockets = Vec::new();
+        for (file, protocol) in tables {
+            if let Ok(content) = std::fs::read_to_string(self.proc_root.join("net").join(file)) {
+                sockets.extend(parse_inet_table(&content, protocol));
+            }
+        }
+        if let Ok(content) = std::fs::read_to_string(self.proc_root.join("net/unix")) {
+            sockets.extend(parse_unix_table(&content));
+        }
+
+        sockets
+    }
+
+    /// Map socket inodes to `(pid, process name)` by scanning `<root>/<pid>/fd`
+    pub fn map_socket_owners(&self) -> HashMap<u64, (u32, String)> {
+        let mut owners = HashMap::new();
+        let Ok(entries) = std::fs::read_dir(&self.proc_root) else {
+            return owners;
+        };
+
+        for entry in entries.filter_map(|e| e.ok()) {
+            let Some(pid) = entry.file_name().to_str().and_then(|n| n.parse::<u32>().ok()) else {
+                continue;
+            };
+            let pid_dir = entry.path();
+            // Processes we may not inspect simply stay unattributed
+            let Ok(fds) = std::fs::read_dir(pid_dir.join("fd")) else {
+                continue;
+            };
+
+            let mut name = None;
+            for fd in fds.filter_map(|e| e.ok()) {
+                let Some(inode) = std::fs::read_link(fd.path())
+                    .ok()
+                    .and_then(|target| parse_socket_link(&target.to_string_lossy()))
+                else {
+                    continue;
+                };
+
+                let name = name.get_or_insert_with(|| read_comm(&pid_dir));
+                owners.entry(inode).or_insert_with(|| (pid, name.clone()));
+            }
+        }
+
+        owners
+    }
+}
+
+impl Default for ProcfsConnectionSource {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ConnectionSource for ProcfsConnectionSource {
+    fn collect(&self) -> Vec<NetworkConnection> {
+        let owners = self.map_socket_owners();
+
+        self.read_sockets()
+            .into_iter()
+            .map(|socket| {
+                let owner = owners.get(&socket.inode);
+                NetworkConnection {
+                    pid: owner.map(|(pid, _)| *pid),
+                    process_name: owner.map(|(_, name)| name.clone()).unwrap_or_default(),
+                    local_address: socket.local_address,
+                    local_port: socket.local_port,
+                    remote_address: socket.remote_address,
+                    remote_port: socket.remote_port,
+                    protocol: socket.protocol,
+                    state: socket.state,
+                    bytes_sent: 0,
+                    bytes_received: 0,
+                }
+            })
+            .collect()
+    }
+}
+
+fn read_comm(pid_dir: &Path) -> String {
+    std::fs::read_to_string(pid_dir.join("comm"))
+        .map(|c| c.trim().to_string())
+        .unwrap_or_default()
+}
+
+/// Extract the inode from an fd link target such as `socket:[12345]`
+pub fn parse_socket_link(target: &str) -> Option<u64> {
+    target.strip_prefix("socket:[")?.strip_suffix(']')?.parse().ok()
+}
+
+/// Parse `/proc/net/{tcp,tcp6,udp,udp6}`
+pub fn parse_inet_table(content: &str, protocol: Protocol) -> Vec<SocketEntry> {
+    content.lines()
+        .skip(1) // Header
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            if fields.len() < 10 {
+                return None;
+            }
+
+            let (local_address, local_port) = parse_hex_endpoint(fields[1])?;
+            let (remote_address, remote_port) = parse_hex_endpoint(fields[2])?;
+            let state_code = u8::from_str_radix(fields[3], 16).ok()?;
+
+            let state = match protocol {
+                Protocol::TCP | Protocol::TCP6 => tcp_state(state_code),
+                // UDP doesn't have states
+                _ => ConnectionState::Established,
+            };
+
+            Some(SocketEntry {
+                protocol: protocol.clone(),
+                local_address,
+                local_port,
+                remote_address,
+                remote_port,
+                state,
+                inode: fields[9].parse().ok()?,
+            })
+        })
+        .collect()
+}
+
+/// Parse `/proc/net/unix`
+pub fn parse_unix_table(content: &str) -> Vec<SocketEntry> {
+    content.lines()
+        .skip(1) // Header
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            if fields.len() < 7 {
+                return None;
+            }
+
+            let flags = u32::from_str_radix(fields[3], 16).ok()?;
+            let socket_state = u8::from_str_radix(fields[5], 16).ok()?;
+            let state = if flags & UNIX_ACCEPTCON != 0 {
+                ConnectionState::Listen
+            } else {
+                // SS_UNCONNECTED, SS_CONNECTING, SS_CONNECTED, SS_DISCONNECTING
+                match socket_state {
+                    2 => ConnectionState::SynSent,
+                    3 => ConnectionState::Established,
+                    4 => ConnectionState::Closing,
+                    _ => ConnectionState::Closed,
+                }
+            };
+
+            Some(SocketEntry {
+                protocol: Protocol::Unix,
+                // Unnamed sockets have no path column
+                local_address: fields.get(7).map(|p| p.to_string()).unwrap_or_default(),
+                local_port: 0,
+                remote_address: String::new(),
+                remote_port: 0,
+                state,
+                inode: fields[6].parse().ok()?,
+            })
+        })
+        .collect()
+}
+
+/// Decode an `ADDRESS:PORT` pair as printed by the kernel. The address is
+/// the raw network-order bytes printed as native-endian 32-bit words.
+pub fn parse_hex_endpoint(endpoint: &str) -> Option<(String, u16)> {
+    let (addr_hex, port_hex) = endpoint.split_once(':')?;
+    let port = u16::from_str_radix(port_hex, 16).ok()?;
+
+    let address = match addr_hex.len() {
+        8 => {
+            let word = u32::from_str_radix(addr_hex, 16).ok()?;
+            Ipv4Addr::from(word.to_ne_bytes()).to_string()
+        }
+        32 => {
+            let mut bytes = [0u8; 16];
+            for (i, chunk) in bytes.chunks_mut(4).enumerate() {
+                let word = u32::from_str_radix(&addr_hex[i * 8..i * 8 + 8], 16).ok()?;
+                chunk.copy_from_slice(&word.to_ne_bytes());
+            }
+            let addr = Ipv6Addr::from(bytes);
+            // Show IPv4-mapped addresses in their familiar dotted form
+            match addr.to_ipv4_mapped() {
+                Some(v4) => v4.to_string(),
+                None => addr.to_string(),
+            }
+        }
+        _ => return None,
+    };
+
+    Some((address, port))
+}
+
+/// Map the `st` column of the TCP tables (see `include/net/tcp_states.h`)
+fn tcp_state(code: u8) -> ConnectionState {
+    match code {
+        0x01 => ConnectionState::Established,
+        0x02 => ConnectionState::SynSent,
+        0x03 | 0x0C => ConnectionState::SynReceived,
+        0x04 => ConnectionState::FinWait1,
+        0x05 => ConnectionState::FinWait2,
+        0x06 => ConnectionState::TimeWait,
+        0x07 => ConnectionState::Closed,
+        0x08 => ConnectionState::CloseWait,
+        0x09 => ConnectionState::LastAck,
+        0x0A => ConnectionState::Listen,
+        0x0B => ConnectionState::Closing,
+        _ => ConnectionState::Unknown,
+    }
+}
@@ -0,0 +1,245 @@
+//! macOS interface collector built on `ifconfig -a` and `netstat -i -b`
+//!
+//! `ifconfig` provides flags, MTU, MAC address and media; the byte, packet
+//! and error counters come from the `<Link#N>` rows of `netstat -i -b`.
+
+use super::InterfaceStatsSource;
+use crate::bandwidth_monitor::{InterfaceKind, InterfaceStats};
+use std::collections::HashMap;
+use std::process::Command;
+
+/// Name prefixes of macOS pseudo-interfaces (tunnels, bridges, AWDL, …)
+const VIRTUAL_PREFIXES: &[&str] = &[
+    "utun", "bridge", "awdl", "llw", "gif", "stf", "anpi", "ap", "vmnet", "feth", "ipsec",
+];
+
+pub struct IfconfigInterfaceSource;
+
+impl IfconfigInterfaceSource {
+    pub fn new() -> Self {
+        Self
+    }
+
+    /// Build interface stats from captured `ifconfig -a` and `netstat -i -b` output
+    pub fn parse(ifconfig_output: &str, netstat_output: &str) -> Vec<InterfaceStats> {
+        let counters = parse_netstat_counters(netstat_output);
+        let mut interfaces = parse_ifconfig(ifconfig_output);
+
+        for interface in &mut interfaces {
+            if let Some(c) = counters.get(&interface.name) {
+                interface.packets_received = c.packets_in;
+                interface.errors_in = c.errors_in;
+                interface.bytes_received = c.bytes_in;
+                interface.packets_sent = c.packets_out;
+                interface.errors_out = c.errors_out;
+                interface.bytes_sent = c.bytes_out;
+            }
+        }
+
+        interfaces
+    }
+}
+
+impl Default for IfconfigInterfaceSource {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl InterfaceStatsSource for IfconfigInterfaceSource {
+    fn collect(&self) -> Vec<InterfaceStats> {
+        let ifconfig = Command::new("ifconfig")
+            .arg("-a")
+            .output()
+            .map(|o| String::from_utf8_lossy(&o.stdout).into_owned())
+            .unwrap_or_default();
+
+        let netstat = Command::new("netstat")
+            .args(["-i", "-b"])
+            .output()
+            .map(|o| String::from_utf8_lossy(&o.stdout).into_owned())
+            .unwrap_or_default();
+
+        Self::parse(&ifconfig, &netstat)
+    }
+}
+
+/// Parse interface blocks such as
+/// `en0: flags=8863<UP,BROADCAST,SMART,RUNNING,SIMPLEX,MULTICAST> mtu 1500`
+/// followed by indented `ether` and `media` lines
+fn parse_ifconfig(output: &str) -> Vec<InterfaceStats> {
+    let mut interfaces = Vec::new();
+    let mut current: Option<(InterfaceStats, bool)> = None; // (stats, has LOOPBACK flag)
+
+    for line in output.lines() {
+        if !line.starts_with('\t') && !line.starts_with(' ') && line.contains(':') {
+            if let Some((interface, loopback)) = current.take() {
+                interfaces.push(finish(interface, loopback));
+            }
+
+            let Some((name, rest)) = line.split_once(':') else {
+                continue;
+            };
+            let flags = rest.split_once('<')
+                .and_then(|(_, f)| f.split_once('>'))
+                .map(|(f, _)| f.split(',').collect::<Vec<_>>())
+                .unwrap_or_default();
+            let mtu = rest.split_whitespace()
+                .skip_while(|&word| word != "mtu")
+                .nth(1)
+                .and_then(|m| m.parse().ok())
+                .unwrap_or(0);
+
+            current = Some((InterfaceStats {
+                is_active: flags.contains(&"UP") && flags.contains(&"RUNNING"),
+                mtu,
+                ..InterfaceStats::empty(name)
+            }, flags.contains(&"LOOPBACK")));
+        } else if let Some((interface, _)) = current.as_mut() {
+            let line = line.trim();
+            if let Some(mac) = line.strip_prefix("ether ") {
+                interface.mac_address = Some(mac.trim().to_string());
+            } else if let Some(media) = line.strip_prefix("media: ") {
+                interface.link_speed_mbps = parse_media_speed(media);
+            }
+        }
+    }
+
+    if let Some((interface, loopback)) = current {
+        interfaces.push(finish(interface, loopback));
+    }
+
+    interfaces
+}
+
+fn finish(mut interface: InterfaceStats, loopback: bool) -> InterfaceStats {
+    interface.kind = if loopback {
+        InterfaceKind::Loopback
+    } else if VIRTUAL_PREFIXES.iter().any(|p| interface.name.starts_with(p)) {
+        InterfaceKind::Virtual
+    } else if interface.link_speed_mbps.is_some() {
+        // Only Ethernet media lines carry a baseT/baseSR speed
+        InterfaceKind::Wired
+    } else {
+        InterfaceKind::Unknown
+    };
+    interface
+}
+
+/// Link speed from a media line such as `autoselect (1000baseT <full-duplex>)`
+/// or `autoselect (10GbaseT <full-duplex>)`
+fn parse_media_speed(media: &str) -> Option<u64> {
+    let (_, active) = media.split_once('(')?;
+    let (speed, _) = active.split_once("base")?;
+
+    match speed.strip_suffix('G') {
+        Some(gbits) => gbits.parse::<u64>().ok().map(|g| g * 1000),
+        None => speed.parse().ok(),
+    }
+}
+
+#[derive(Debug, Default)]
+struct NetstatCounters {
+    packets_in: u64,
+    errors_in: u64,
+    bytes_in: u64,
+    packets_out: u64,
+    errors_out: u64,
+    bytes_out: u64,
+}
+
+/// Parse the `<Link#N>` rows of `netstat -i -b`. The Address column is
+/// empty for interfaces without a hardware address, so counters are read
+/// from the right: `Ipkts Ierrs Ibytes Opkts Oerrs Obytes Coll`.
+fn parse_netstat_counters(output: &str) -> HashMap<String, NetstatCounters> {
+    let mut counters = HashMap::new();
+
+    for line in output.lines().skip(1) {
+        let parts: Vec<&str> = line.split_whitespace().collect();
+        if parts.len() < 10 || !parts[2].starts_with("<Link") {
+            continue;
+        }
+
+        let values: Vec<u64> = parts[parts.len() - 7..]
+            .iter()
+            .map(|v| v.parse().unwrap_or(0))
+            .collect();
+
+        counters.entry(parts[0].trim_end_matches('*').to_string())
+            .or_insert(NetstatCounters {
+                packets_in: values[0],
+                errors_in: values[1],
+                bytes_in: values[2],
+                packets_out: values[3],
+                errors_out: values[4],
+                bytes_out: values[5],
+            });
+    }
+
+    counters
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const IFCONFIG: &str = "\
+lo0: flags=8049<UP,LOOPBACK,RUNNING,MULTICAST> mtu 16384
+\toptions=1203<RXCSUM,TXCSUM,TXSTATUS,SW_TIMESTAMP>
+\tinet 127.0.0.1 netmask 0xff000000
+en0: flags=8863<UP,BROADCAST,SMART,RUNNING,SIMPLEX,MULTICAST> mtu 1500
+\tether a4:83:e7:12:34:56
+\tmedia: autoselect (1000baseT <full-duplex>)
+\tstatus: active
+en1: flags=8863<UP,BROADCAST,SMART,RUNNING,SIMPLEX,MULTICAST> mtu 1500
+\tether a4:83:e7:65:43:21
+\tmedia: autoselect
+\tstatus: inactive
+utun0: flags=8051<UP,POINTOPOINT,RUNNING,MULTICAST> mtu 1380
+";
+
+    const NETSTAT: &str = "\
+Name       Mtu   Network       Address            Ipkts Ierrs     Ibytes    Opkts Oerrs     Obytes  Coll
+lo0        16384 <Link#1>                        91234     0   12345678    91234     0   12345678     0
+lo0        16384 127           127.0.0.1         91234     -   12345678    91234     -   12345678     -
+en0        1500  <Link#6>    a4:83:e7:12:34:56 5000000    3 6000000000  2500000    1  400000000     0
+en0        1500  192.168.1     192.168.1.20     4900000     -  5900000000  2400000     -  390000000     -
+utun0      1380  <Link#12>                          10     0       1500       12     0       1800     0
+";
+
+    #[test]
+    fn test_parse_interfaces() {
+        let interfaces = IfconfigInterfaceSource::parse(IFCONFIG, NETSTAT);
+        assert_eq!(interfaces.len(), 4);
+
+        let lo0 = &interfaces[0];
+        assert_eq!(lo0.kind, InterfaceKind::Loopback);
+        assert_eq!(lo0.mtu, 16384);
+        assert_eq!(lo0.bytes_sent, 12345678);
+
+        let en0 = &interfaces[1];
+        assert!(en0.is_active);
+        assert_eq!(en0.kind, InterfaceKind::Wired);
+        assert_eq!(en0.link_speed_mbps, Some(1000));
+        assert_eq!(en0.mac_address.as_deref(), Some("a4:83:e7:12:34:56"));
+        assert_eq!(en0.packets_received, 5000000);
+        assert_eq!(en0.errors_in, 3);
+        assert_eq!(en0.bytes_received, 6000000000);
+        assert_eq!(en0.bytes_sent, 400000000);
+
+        let en1 = &interfaces[2];
+        assert_eq!(en1.kind, InterfaceKind::Unknown);
+        assert_eq!(en1.link_speed_mbps, None);
+        assert_eq!(en1.bytes_received, 0);
+
+        assert_eq!(interfaces[3].kind, InterfaceKind::Virtual);
+        assert_eq!(interfaces[3].packets_sent, 12);
+    }
+
+    #[test]
+    fn test_parse_media_speed() {
+        assert_eq!(parse_media_speed("autoselect (100baseTX <full-duplex>)"), Some(100));
+        assert_eq!(parse_media_speed("autoselect (10GbaseT <full-duplex>)"), Some(10000));
+        assert_eq!(parse_media_speed("autoselect"), None);
+    }
+}
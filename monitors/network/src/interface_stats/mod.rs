@@ -0,0 +1,29 @@
+//! Network interface statistics collectors
+
+use crate::bandwidth_monitor::InterfaceStats;
+
+mod ifconfig;
+#[cfg(target_os = "linux")]
+mod sysfs;
+
+pub use ifconfig::IfconfigInterfaceSource;
+#[cfg(target_os = "linux")]
+pub use sysfs::SysfsInterfaceSource;
+
+/// A backend that lists interfaces with their counters and link properties
+pub trait InterfaceStatsSource: Send {
+    fn collect(&self) -> Vec<InterfaceStats>;
+}
+
+/// Interface source used when none is given explicitly
+pub fn default_interface_source() -> Box<dyn InterfaceStatsSource> {
+    #[cfg(target_os = "linux")]
+    {
+        Box::new(SysfsInterfaceSource::new())
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    {
+        Box::new(IfconfigInterfaceSource::new())
+    }
+}
@@ -0,0 +1,139 @@
+//! Linux interface collector built on `/proc/net/dev` and `/sys/class/net`
+
+use super::InterfaceStatsSource;
+use crate::bandwidth_monitor::{InterfaceKind, InterfaceStats};
+use reaper_core::platform::linux::procfs::{parse_net_dev, NetDevCounters};
+use std::collections::{BTreeSet, HashMap};
+use std::path::{Path, PathBuf};
+
+/// `IFF_UP` from `linux/if.h`
+const IFF_UP: u32 = 0x1;
+/// `ARPHRD_LOOPBACK` from `linux/if_arp.h`
+const ARPHRD_LOOPBACK: u32 = 772;
+/// `ARPHRD_ETHER`
+const ARPHRD_ETHER: u32 = 1;
+
+pub struct SysfsInterfaceSource {
+    proc_root: PathBuf,
+    sys_root: PathBuf,
+}
+
+impl SysfsInterfaceSource {
+    pub fn new() -> Self {
+        Self::with_roots("/proc", "/sys")
+    }
+
+    /// Read from alternative proc and sys roots, e.g. fixture directories
+    pub fn with_roots(proc_root: impl Into<PathBuf>, sys_root: impl Into<PathBuf>) -> Self {
+        Self {
+            proc_root: proc_root.into(),
+            sys_root: sys_root.into(),
+        }
+    }
+
+    fn class_net(&self) -> PathBuf {
+        self.sys_root.join("class/net")
+    }
+
+    fn read_interface(&self, name: &str, counters: Option<&NetDevCounters>) -> InterfaceStats {
+        let dir = self.class_net().join(name);
+
+        // Counters from /proc/net/dev when listed there, statistics/* otherwise
+        let counters = counters.cloned().unwrap_or_else(|| {
+            let stat = |file: &str| read_u64(&dir.join("statistics").join(file)).unwrap_or(0);
+            NetDevCounters {
+                rx_bytes: stat("rx_bytes"),
+                rx_packets: stat("rx_packets"),
+                rx_errors: stat("rx_errors"),
+                rx_dropped: stat("rx_dropped"),
+                tx_bytes: stat("tx_bytes"),
+                tx_packets: stat("tx_packets"),
+                tx_errors: stat("tx_errors"),
+                tx_dropped: stat("tx_dropped"),
+            }
+        });
+
+        let operstate = read_trimmed(&dir.join("operstate")).unwrap_or_default();
+        let flags = read_trimmed(&dir.join("flags"))
+            .and_then(|f| u32::from_str_radix(f.trim_start_matches("0x"), 16).ok())
+            .unwrap_or(0);
+        // Drivers that do not report carrier (loopback, tun) stay "unknown"
+        let is_active = operstate == "up" || (operstate == "unknown" && flags & IFF_UP != 0);
+
+        InterfaceStats {
+            name: name.to_string(),
+            is_active,
+            bytes_sent: counters.tx_bytes,
+            bytes_received: counters.rx_bytes,
+            packets_sent: counters.tx_packets,
+            packets_received: counters.rx_packets,
+            errors_in: counters.rx_errors,
+            errors_out: counters.tx_errors,
+            drops_in: counters.rx_dropped,
+            drops_out: counters.tx_dropped,
+            // speed reads as -1 or fails with EINVAL when the link is down
+            link_speed_mbps: read_trimmed(&dir.join("speed"))
+                .and_then(|s| s.parse::<i64>().ok())
+                .filter(|&s| s > 0)
+                .map(|s| s as u64),
+            mtu: read_u64(&dir.join("mtu")).unwrap_or(0) as u32,
+            mac_address: read_trimmed(&dir.join("address"))
+                .filter(|a| !a.is_empty() && a.chars().any(|c| c != '0' && c != ':')),
+            kind: self.classify(name, &dir),
+        }
+    }
+
+    fn classify(&self, name: &str, dir: &Path) -> InterfaceKind {
+        let arp_type = read_u64(&dir.join("type")).unwrap_or(0) as u32;
+
+        if arp_type == ARPHRD_LOOPBACK {
+            InterfaceKind::Loopback
+        } else if dir.join("wireless").exists() || dir.join("phy80211").exists() {
+            InterfaceKind::Wireless
+        } else if is_virtual_device(&self.class_net(), name) {
+            InterfaceKind::Virtual
+        } else if arp_type == ARPHRD_ETHER {
+            InterfaceKind::Wired
+        } else {
+            InterfaceKind::Unknown
+        }
+    }
+}
+
+impl Default for SysfsInterfaceSource {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl InterfaceStatsSource for SysfsInterfaceSource {
+    fn collect(&self) -> Vec<InterfaceStats> {
+        let net_dev: HashMap<String, NetDevCounters> = std::fs::read_to_string(self.proc_root.join("net/dev"))
+            .map(|c| parse_net_dev(&c))
+            .unwrap_or_default();
+
+        let mut names: BTreeSet<String> = net_dev.keys().cloned().collect();
+        if let Ok(entries) = std::fs::read_dir(self.class_net()) {
+            names.extend(entries.filter_map(|e| e.ok()?.file_name().into_string().ok()));
+        }
+
+        names.iter()
+            .map(|name| self.read_interface(name, net_dev.get(name)))
+            .collect()
+    }
+}
+
+/// Devices without backing hardware are linked under `/sys/devices/virtual`
+fn is_virtual_device(class_net: &Path, name: &str) -> bool {
+    std::fs::read_link(class_net.join(name))
+        .map(|target| target.components().any(|c| c.as_os_str() == "virtual"))
+        .unwrap_or(false)
+}
+
+fn read_trimmed(path: &Path) -> Option<String> {
+    std::fs::read_to_string(path).ok().map(|s| s.trim().to_string())
+}
+
+fn read_u64(path: &Path) -> Option<u64> {
+    read_trimmed(path)?.parse().ok()
+}
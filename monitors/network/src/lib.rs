@@ -1,17 +1,28 @@
 use once_cell::sync::Lazy;
-use std::sync::Mutex;
+use reaper_core::sampling;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
 
 pub mod network_monitor;
 pub mod connection_tracker;
 pub mod bandwidth_monitor;
+pub mod interface_stats;
+pub mod process_traffic;
 pub mod ffi;
 
 // Re-export main types
 pub use network_monitor::{NetworkMonitor, NetworkMetrics};
-pub use connection_tracker::{NetworkConnection, ConnectionState, Protocol};
-pub use bandwidth_monitor::{BandwidthStats, InterfaceStats};
+pub use connection_tracker::{
+    NetworkConnection, ConnectionState, Protocol, ConnectionSource, NetstatConnectionSource,
+    ProcfsConnectionSource,
+};
+pub use bandwidth_monitor::{BandwidthStats, InterfaceKind, InterfaceStats};
+pub use interface_stats::InterfaceStatsSource;
+pub use process_traffic::{ProcessTrafficSource, TrafficSample};
 
 // Global network monitor instance
-static NETWORK_MONITOR: Lazy<Mutex<NetworkMonitor>> = Lazy::new(|| {
-    Mutex::new(NetworkMonitor::new())
+static NETWORK_MONITOR: Lazy<Arc<Mutex<NetworkMonitor>>> = Lazy::new(|| {
+    let monitor = Arc::new(Mutex::new(NetworkMonitor::new()));
+    sampling::register_global(Arc::clone(&monitor), Duration::from_secs(2));
+    monitor
 });
\ No newline at end of file
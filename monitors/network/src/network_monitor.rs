@@ -1,5 +1,7 @@
 use crate::connection_tracker::{ConnectionTracker, NetworkConnection};
 use crate::bandwidth_monitor::{BandwidthMonitor, BandwidthStats};
+use reaper_core::common::{Monitor, MonitorType};
+use reaper_core::metrics::{MetricSample, SeriesKey};
 use std::time::{Duration, Instant};
 
 #[derive(Debug, Clone)]
@@ -105,4 +107,35 @@ impl NetworkMonitor {
         self.connection_tracker.refresh();
         self.bandwidth_monitor.refresh();
     }
-}
\ No newline at end of file
+}
+
+impl Monitor for NetworkMonitor {
+    fn name(&self) -> &str {
+        "network"
+    }
+    
+    fn monitor_type(&self) -> MonitorType {
+        MonitorType::Network
+    }
+    
+    fn refresh(&mut self) {
+        NetworkMonitor::refresh(self);
+    }
+    
+    fn metric_samples(&self) -> Vec<MetricSample> {
+        self.bandwidth_monitor
+            .interface_rates()
+            .iter()
+            .flat_map(|(interface, &(upload_bps, download_bps))| [
+                MetricSample::new(
+                    SeriesKey::new("net.upload_bps").with_label("interface", interface),
+                    upload_bps as f64,
+                ),
+                MetricSample::new(
+                    SeriesKey::new("net.download_bps").with_label("interface", interface),
+                    download_bps as f64,
+                ),
+            ])
+            .collect()
+    }
+}
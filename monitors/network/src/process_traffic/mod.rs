@@ -0,0 +1,145 @@
+//! Per-process network traffic accounting
+//!
+//! A `ProcessTrafficSource` reports cumulative byte counters attributed to
+//! processes. Each counter carries a key (socket inode, namespace inode, …)
+//! so that `TrafficRateTracker` can compute rates from per-counter deltas:
+//! a socket closing between samples removes its counter instead of making
+//! the process total go backwards.
+
+use std::collections::HashMap;
+use std::time::Instant;
+
+mod nettop;
+#[cfg(target_os = "linux")]
+mod procfs;
+#[cfg(target_os = "linux")]
+mod sock_diag;
+
+pub use nettop::NettopTrafficSource;
+#[cfg(target_os = "linux")]
+pub use procfs::LinuxTrafficSource;
+#[cfg(target_os = "linux")]
+pub use sock_diag::{SocketTraffic, SockDiag};
+
+/// Cumulative counter attributed to a process
+#[derive(Debug, Clone, PartialEq)]
+pub struct TrafficSample {
+    pub pid: u32,
+    /// Identifies the underlying counter within the process
+    pub key: u64,
+    pub bytes_sent: u64,
+    pub bytes_received: u64,
+}
+
+/// A backend that reports cumulative per-process traffic counters
+pub trait ProcessTrafficSource: Send {
+    fn sample(&mut self) -> Vec<TrafficSample>;
+}
+
+/// Traffic source used when none is given explicitly
+pub fn default_traffic_source() -> Box<dyn ProcessTrafficSource> {
+    #[cfg(target_os = "linux")]
+    {
+        Box::new(LinuxTrafficSource::new())
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    {
+        Box::new(NettopTrafficSource::new())
+    }
+}
+
+/// Turns successive cumulative samples into per-process rates
+#[derive(Debug, Default)]
+pub struct TrafficRateTracker {
+    previous: HashMap<(u32, u64), (u64, u64)>,
+    last_sample: Option<Instant>,
+}
+
+impl TrafficRateTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Feed a new set of samples taken at `now` and return
+    /// `pid -> (upload_bps, download_bps)`. The first call only records a
+    /// baseline and returns an empty map.
+    pub fn update(&mut self, samples: Vec<TrafficSample>, now: Instant) -> HashMap<u32, (u64, u64)> {
+        let elapsed = self.last_sample
+            .map(|last| now.duration_since(last).as_secs_f64())
+            .unwrap_or(0.0);
+
+        let mut deltas: HashMap<u32, (u64, u64)> = HashMap::new();
+        let mut current = HashMap::with_capacity(samples.len());
+
+        for sample in samples {
+            let key = (sample.pid, sample.key);
+            // Counters seen for the first time only establish a baseline
+            if let Some(&(sent, received)) = self.previous.get(&key) {
+                let entry = deltas.entry(sample.pid).or_default();
+                entry.0 += sample.bytes_sent.saturating_sub(sent);
+                entry.1 += sample.bytes_received.saturating_sub(received);
+            }
+            current.insert(key, (sample.bytes_sent, sample.bytes_received));
+        }
+
+        self.previous = current;
+        self.last_sample = Some(now);
+
+        if elapsed <= 0.0 {
+            return HashMap::new();
+        }
+
+        deltas.into_iter()
+            .map(|(pid, (sent, received))| {
+                (pid, ((sent as f64 / elapsed) as u64, (received as f64 / elapsed) as u64))
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn sample(pid: u32, key: u64, sent: u64, received: u64) -> TrafficSample {
+        TrafficSample { pid, key, bytes_sent: sent, bytes_received: received }
+    }
+
+    #[test]
+    fn test_first_update_is_baseline() {
+        let mut tracker = TrafficRateTracker::new();
+        let rates = tracker.update(vec![sample(1, 10, 1000, 2000)], Instant::now());
+        assert!(rates.is_empty());
+    }
+
+    #[test]
+    fn test_rates_sum_socket_deltas() {
+        let mut tracker = TrafficRateTracker::new();
+        let start = Instant::now();
+
+        tracker.update(vec![sample(1, 10, 1000, 2000), sample(1, 11, 0, 0)], start);
+        let rates = tracker.update(
+            vec![sample(1, 10, 3000, 6000), sample(1, 11, 1000, 0)],
+            start + Duration::from_secs(2),
+        );
+
+        assert_eq!(rates[&1], (1500, 2000));
+    }
+
+    #[test]
+    fn test_closed_socket_does_not_go_negative() {
+        let mut tracker = TrafficRateTracker::new();
+        let start = Instant::now();
+
+        tracker.update(vec![sample(1, 10, 1_000_000, 0), sample(1, 11, 500, 0)], start);
+        // Socket 10 closed; socket 12 is new and only sets a baseline
+        let rates = tracker.update(
+            vec![sample(1, 11, 1500, 0), sample(1, 12, 9999, 9999)],
+            start + Duration::from_secs(1),
+        );
+
+        assert_eq!(rates[&1], (1000, 0));
+    }
+}
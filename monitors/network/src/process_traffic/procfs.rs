@@ -0,0 +1,135 @@
+//! Linux traffic source combining sock_diag, network namespaces and `/proc/<pid>/io`
+//!
+//! - TCP sockets in Reaper's own network namespace are measured exactly via
+//!   sock_diag and attributed through their socket inode.
+//! - Processes in other namespaces (containers) are invisible to sock_diag;
+//!   their namespace's interface counters from `/proc/<pid>/net/dev` are
+//!   attributed to the lowest PID in that namespace.
+//! - When netlink is unavailable, `rchar`/`wchar` minus storage I/O from
+//!   `/proc/<pid>/io` approximates socket traffic for processes that own
+//!   inet sockets.
+
+use super::sock_diag::SockDiag;
+use super::{ProcessTrafficSource, TrafficSample};
+use crate::connection_tracker::{ProcfsConnectionSource, Protocol};
+use reaper_core::platform::linux::procfs::{parse_net_dev, read_io};
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+/// Key used for the `/proc/<pid>/io` estimate, distinct from any inode
+const PROC_IO_KEY: u64 = u64::MAX;
+
+pub struct LinuxTrafficSource {
+    sock_diag: Option<SockDiag>,
+    sockets: ProcfsConnectionSource,
+}
+
+impl LinuxTrafficSource {
+    pub fn new() -> Self {
+        Self {
+            sock_diag: SockDiag::open().ok(),
+            sockets: ProcfsConnectionSource::new(),
+        }
+    }
+
+    /// Exact TCP counters for sockets owned by known processes
+    fn sock_diag_samples(&self, owners: &HashMap<u64, (u32, String)>) -> Option<Vec<TrafficSample>> {
+        let traffic = self.sock_diag.as_ref()?.tcp_traffic().ok()?;
+
+        Some(traffic.into_iter()
+            .filter_map(|socket| {
+                let (pid, _) = owners.get(&socket.inode)?;
+                Some(TrafficSample {
+                    pid: *pid,
+                    key: socket.inode,
+                    bytes_sent: socket.bytes_acked,
+                    bytes_received: socket.bytes_received,
+                })
+            })
+            .collect())
+    }
+
+    /// Non-storage I/O of processes owning inet sockets
+    fn proc_io_samples(&self, owners: &HashMap<u64, (u32, String)>) -> Vec<TrafficSample> {
+        let inet_inodes: HashSet<u64> = self.sockets.read_sockets()
+            .into_iter()
+            .filter(|s| s.protocol != Protocol::Unix)
+            .map(|s| s.inode)
+            .collect();
+
+        let pids: HashSet<u32> = owners.iter()
+            .filter(|(inode, _)| inet_inodes.contains(inode))
+            .map(|(_, (pid, _))| *pid)
+            .collect();
+
+        pids.into_iter()
+            .filter_map(|pid| {
+                let io = read_io(pid).ok()?;
+                Some(TrafficSample {
+                    pid,
+                    key: PROC_IO_KEY,
+                    bytes_sent: io.wchar.saturating_sub(io.write_bytes),
+                    bytes_received: io.rchar.saturating_sub(io.read_bytes),
+                })
+            })
+            .collect()
+    }
+
+    /// Interface totals of network namespaces other than our own
+    fn namespace_samples(&self) -> Vec<TrafficSample> {
+        let Some(own_ns) = netns_inode("self") else {
+            return Vec::new();
+        };
+
+        // namespace inode -> lowest PID seen in it
+        let mut namespaces: BTreeMap<u64, u32> = BTreeMap::new();
+        let Ok(entries) = std::fs::read_dir("/proc") else {
+            return Vec::new();
+        };
+        for pid in entries.filter_map(|e| e.ok()?.file_name().to_str()?.parse::<u32>().ok()) {
+            if let Some(ns) = netns_inode(&pid.to_string()).filter(|&ns| ns != own_ns) {
+                let owner = namespaces.entry(ns).or_insert(pid);
+                *owner = (*owner).min(pid);
+            }
+        }
+
+        namespaces.into_iter()
+            .filter_map(|(ns, pid)| {
+                let content = std::fs::read_to_string(format!("/proc/{}/net/dev", pid)).ok()?;
+                let (sent, received) = parse_net_dev(&content)
+                    .into_iter()
+                    .filter(|(name, _)| name != "lo")
+                    .fold((0, 0), |(tx, rx), (_, c)| (tx + c.tx_bytes, rx + c.rx_bytes));
+                Some(TrafficSample { pid, key: ns, bytes_sent: sent, bytes_received: received })
+            })
+            .collect()
+    }
+}
+
+impl Default for LinuxTrafficSource {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ProcessTrafficSource for LinuxTrafficSource {
+    fn sample(&mut self) -> Vec<TrafficSample> {
+        let owners = self.sockets.map_socket_owners();
+
+        let mut samples = match self.sock_diag_samples(&owners) {
+            Some(samples) => samples,
+            None => self.proc_io_samples(&owners),
+        };
+        samples.extend(self.namespace_samples());
+        samples
+    }
+}
+
+/// Inode of the network namespace of `/proc/<pid>`, from the `net:[inode]` link
+fn netns_inode(pid: &str) -> Option<u64> {
+    let target = std::fs::read_link(format!("/proc/{}/ns/net", pid)).ok()?;
+    target.to_str()?
+        .strip_prefix("net:[")?
+        .strip_suffix(']')?
+        .parse()
+        .ok()
+}
@@ -0,0 +1,288 @@
+//! Netlink `sock_diag` client reading per-socket TCP byte counters
+//!
+//! Dumps all TCP sockets of the caller's network namespace with the
+//! `INET_DIAG_INFO` extension and extracts `tcpi_bytes_acked` (sent) and
+//! `tcpi_bytes_received` from the returned `struct tcp_info`.
+
+use std::io;
+use std::mem;
+use std::os::fd::{AsRawFd, FromRawFd, OwnedFd};
+
+/// `SOCK_DIAG_BY_FAMILY` from `linux/sock_diag.h`
+const SOCK_DIAG_BY_FAMILY: u16 = 20;
+/// `INET_DIAG_INFO` attribute carrying `struct tcp_info`
+const INET_DIAG_INFO: u16 = 2;
+
+const NLMSG_HDR_LEN: usize = 16;
+/// `struct inet_diag_req_v2`
+const INET_DIAG_REQ_LEN: usize = 56;
+/// `struct inet_diag_msg`
+const INET_DIAG_MSG_LEN: usize = 72;
+/// Offset of `idiag_inode` within `struct inet_diag_msg`
+const IDIAG_INODE_OFFSET: usize = 68;
+
+/// Offsets within `struct tcp_info` (stable since Linux 4.2)
+const TCPI_BYTES_ACKED_OFFSET: usize = 120;
+const TCPI_BYTES_RECEIVED_OFFSET: usize = 128;
+
+/// Byte counters of one TCP socket
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SocketTraffic {
+    pub inode: u64,
+    pub bytes_acked: u64,
+    pub bytes_received: u64,
+}
+
+/// An open `NETLINK_SOCK_DIAG` socket
+pub struct SockDiag {
+    fd: OwnedFd,
+}
+
+impl SockDiag {
+    pub fn open() -> io::Result<Self> {
+        let fd = unsafe {
+            libc::socket(libc::AF_NETLINK, libc::SOCK_DGRAM | libc::SOCK_CLOEXEC, libc::NETLINK_SOCK_DIAG)
+        };
+        if fd < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        let fd = unsafe { OwnedFd::from_raw_fd(fd) };
+
+        // Never block the refresh path for long if the kernel stops answering
+        let timeout = libc::timeval { tv_sec: 1, tv_usec: 0 };
+        unsafe {
+            libc::setsockopt(
+                fd.as_raw_fd(),
+                libc::SOL_SOCKET,
+                libc::SO_RCVTIMEO,
+                &timeout as *const _ as *const libc::c_void,
+                mem::size_of::<libc::timeval>() as libc::socklen_t,
+            );
+        }
+
+        Ok(Self { fd })
+    }
+
+    /// Dump byte counters for all IPv4 and IPv6 TCP sockets
+    pub fn tcp_traffic(&self) -> io::Result<Vec<SocketTraffic>> {
+        let mut sockets = self.dump(libc::AF_INET as u8)?;
+        sockets.extend(self.dump(libc::AF_INET6 as u8)?);
+        Ok(sockets)
+    }
+
+    fn dump(&self, family: u8) -> io::Result<Vec<SocketTraffic>> {
+        let request = build_request(family, 1);
+        let mut addr: libc::sockaddr_nl = unsafe { mem::zeroed() };
+        addr.nl_family = libc::AF_NETLINK as libc::sa_family_t;
+
+        let sent = unsafe {
+            libc::sendto(
+                self.fd.as_raw_fd(),
+                request.as_ptr() as *const libc::c_void,
+                request.len(),
+                0,
+                &addr as *const _ as *const libc::sockaddr,
+                mem::size_of::<libc::sockaddr_nl>() as libc::socklen_t,
+            )
+        };
+        if sent < 0 {
+            return Err(io::Error::last_os_error());
+        }
+
+        let mut sockets = Vec::new();
+        let mut buf = vec![0u8; 32 * 1024];
+        loop {
+            let len = unsafe {
+                libc::recv(self.fd.as_raw_fd(), buf.as_mut_ptr() as *mut libc::c_void, buf.len(), 0)
+            };
+            if len < 0 {
+                return Err(io::Error::last_os_error());
+            }
+
+            match parse_messages(&buf[..len as usize], &mut sockets)? {
+                DumpStatus::Done => return Ok(sockets),
+                DumpStatus::More if len == 0 => return Ok(sockets),
+                DumpStatus::More => {}
+            }
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+enum DumpStatus {
+    More,
+    Done,
+}
+
+/// Build an `nlmsghdr` + `inet_diag_req_v2` dump request for TCP sockets
+/// in any state, asking for the `INET_DIAG_INFO` extension
+fn build_request(family: u8, seq: u32) -> Vec<u8> {
+    let total_len = (NLMSG_HDR_LEN + INET_DIAG_REQ_LEN) as u32;
+    let flags = (libc::NLM_F_REQUEST | libc::NLM_F_DUMP) as u16;
+
+    let mut msg = Vec::with_capacity(total_len as usize);
+    msg.extend_from_slice(&total_len.to_ne_bytes());
+    msg.extend_from_slice(&SOCK_DIAG_BY_FAMILY.to_ne_bytes());
+    msg.extend_from_slice(&flags.to_ne_bytes());
+    msg.extend_from_slice(&seq.to_ne_bytes());
+    msg.extend_from_slice(&0u32.to_ne_bytes()); // nlmsg_pid
+
+    msg.push(family);
+    msg.push(libc::IPPROTO_TCP as u8);
+    msg.push(1 << (INET_DIAG_INFO - 1)); // idiag_ext
+    msg.push(0); // pad
+    msg.extend_from_slice(&u32::MAX.to_ne_bytes()); // idiag_states: all
+    msg.resize(total_len as usize, 0); // zeroed inet_diag_sockid
+
+    msg
+}
+
+fn read_u16(buf: &[u8], offset: usize) -> Option<u16> {
+    Some(u16::from_ne_bytes(buf.get(offset..offset + 2)?.try_into().ok()?))
+}
+
+fn read_u32(buf: &[u8], offset: usize) -> Option<u32> {
+    Some(u32::from_ne_bytes(buf.get(offset..offset + 4)?.try_into().ok()?))
+}
+
+fn read_u64(buf: &[u8], offset: usize) -> Option<u64> {
+    Some(u64::from_ne_bytes(buf.get(offset..offset + 8)?.try_into().ok()?))
+}
+
+const fn align4(len: usize) -> usize {
+    (len + 3) & !3
+}
+
+/// Parse one `recv` buffer of netlink messages, appending socket counters
+fn parse_messages(buf: &[u8], sockets: &mut Vec<SocketTraffic>) -> io::Result<DumpStatus> {
+    let mut offset = 0;
+
+    while offset + NLMSG_HDR_LEN <= buf.len() {
+        let msg_len = read_u32(buf, offset).unwrap_or(0) as usize;
+        let msg_type = read_u16(buf, offset + 4).unwrap_or(0);
+        if msg_len < NLMSG_HDR_LEN || offset + msg_len > buf.len() {
+            break;
+        }
+        let payload = &buf[offset + NLMSG_HDR_LEN..offset + msg_len];
+
+        match msg_type as i32 {
+            libc::NLMSG_DONE => return Ok(DumpStatus::Done),
+            libc::NLMSG_ERROR => {
+                let errno = read_u32(payload, 0).map(|e| -(e as i32)).unwrap_or(0);
+                if errno != 0 {
+                    return Err(io::Error::from_raw_os_error(errno));
+                }
+            }
+            _ if msg_type == SOCK_DIAG_BY_FAMILY => {
+                if let Some(socket) = parse_diag_msg(payload) {
+                    sockets.push(socket);
+                }
+            }
+            _ => {}
+        }
+
+        offset += align4(msg_len);
+    }
+
+    Ok(DumpStatus::More)
+}
+
+/// Parse an `inet_diag_msg` followed by its route attributes
+fn parse_diag_msg(payload: &[u8]) -> Option<SocketTraffic> {
+    let inode = read_u32(payload, IDIAG_INODE_OFFSET)? as u64;
+    let mut offset = INET_DIAG_MSG_LEN;
+
+    while offset + 4 <= payload.len() {
+        let attr_len = read_u16(payload, offset)? as usize;
+        let attr_type = read_u16(payload, offset + 2)?;
+        if attr_len < 4 || offset + attr_len > payload.len() {
+            break;
+        }
+
+        if attr_type == INET_DIAG_INFO {
+            let info = &payload[offset + 4..offset + attr_len];
+            // Older kernels return a shorter tcp_info without byte counters
+            return Some(SocketTraffic {
+                inode,
+                bytes_acked: read_u64(info, TCPI_BYTES_ACKED_OFFSET)?,
+                bytes_received: read_u64(info, TCPI_BYTES_RECEIVED_OFFSET)?,
+            });
+        }
+
+        offset += align4(attr_len);
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn diag_message(inode: u32, acked: u64, received: u64) -> Vec<u8> {
+        let mut info = vec![0u8; 232];
+        info[TCPI_BYTES_ACKED_OFFSET..TCPI_BYTES_ACKED_OFFSET + 8].copy_from_slice(&acked.to_ne_bytes());
+        info[TCPI_BYTES_RECEIVED_OFFSET..TCPI_BYTES_RECEIVED_OFFSET + 8].copy_from_slice(&received.to_ne_bytes());
+
+        let mut payload = vec![0u8; INET_DIAG_MSG_LEN];
+        payload[IDIAG_INODE_OFFSET..IDIAG_INODE_OFFSET + 4].copy_from_slice(&inode.to_ne_bytes());
+        payload.extend_from_slice(&((info.len() + 4) as u16).to_ne_bytes());
+        payload.extend_from_slice(&INET_DIAG_INFO.to_ne_bytes());
+        payload.extend_from_slice(&info);
+
+        let mut msg = Vec::new();
+        msg.extend_from_slice(&((NLMSG_HDR_LEN + payload.len()) as u32).to_ne_bytes());
+        msg.extend_from_slice(&SOCK_DIAG_BY_FAMILY.to_ne_bytes());
+        msg.extend_from_slice(&[0u8; 10]);
+        msg.extend_from_slice(&payload);
+        msg
+    }
+
+    fn done_message() -> Vec<u8> {
+        let mut msg = Vec::new();
+        msg.extend_from_slice(&(NLMSG_HDR_LEN as u32 + 4).to_ne_bytes());
+        msg.extend_from_slice(&(libc::NLMSG_DONE as u16).to_ne_bytes());
+        msg.extend_from_slice(&[0u8; 14]);
+        msg
+    }
+
+    #[test]
+    fn test_request_layout() {
+        let request = build_request(libc::AF_INET as u8, 7);
+        assert_eq!(request.len(), NLMSG_HDR_LEN + INET_DIAG_REQ_LEN);
+        assert_eq!(read_u16(&request, 4), Some(SOCK_DIAG_BY_FAMILY));
+        assert_eq!(read_u32(&request, 8), Some(7));
+        assert_eq!(request[NLMSG_HDR_LEN + 1], libc::IPPROTO_TCP as u8);
+    }
+
+    #[test]
+    fn test_parse_dump_messages() {
+        let mut buf = diag_message(40001, 1500, 9000);
+        buf.extend(diag_message(40002, 10, 20));
+        buf.extend(done_message());
+
+        let mut sockets = Vec::new();
+        let status = parse_messages(&buf, &mut sockets).unwrap();
+
+        assert_eq!(status, DumpStatus::Done);
+        assert_eq!(sockets, vec![
+            SocketTraffic { inode: 40001, bytes_acked: 1500, bytes_received: 9000 },
+            SocketTraffic { inode: 40002, bytes_acked: 10, bytes_received: 20 },
+        ]);
+    }
+
+    #[test]
+    fn test_short_tcp_info_is_skipped() {
+        let mut buf = diag_message(40001, 1, 1);
+        // Truncate tcp_info below the byte counters
+        let short_len = (NLMSG_HDR_LEN + INET_DIAG_MSG_LEN + 4 + 100) as u32;
+        buf.truncate(short_len as usize);
+        buf[0..4].copy_from_slice(&short_len.to_ne_bytes());
+        buf[NLMSG_HDR_LEN + INET_DIAG_MSG_LEN..NLMSG_HDR_LEN + INET_DIAG_MSG_LEN + 2]
+            .copy_from_slice(&104u16.to_ne_bytes());
+
+        let mut sockets = Vec::new();
+        assert_eq!(parse_messages(&buf, &mut sockets).unwrap(), DumpStatus::More);
+        assert!(sockets.is_empty());
+    }
+}
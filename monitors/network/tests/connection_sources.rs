@@ -0,0 +1,156 @@
+// Connection source tests
+// Both backends are exercised against captured fixture files so they can run on any host
+
+use reaper_network_monitor::connection_tracker::{
+    parse_hex_endpoint, parse_inet_table, parse_socket_link, parse_unix_table,
+};
+use reaper_network_monitor::{
+    ConnectionSource, ConnectionState, NetstatConnectionSource, ProcfsConnectionSource, Protocol,
+};
+use std::path::Path;
+use tempfile::TempDir;
+
+const FIXTURES: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures");
+
+fn fixture(name: &str) -> String {
+    std::fs::read_to_string(Path::new(FIXTURES).join(name)).unwrap()
+}
+
+/// Build a fake proc root: the fixture socket tables plus two processes
+/// whose fd directories link to socket inodes
+fn fake_proc_root() -> TempDir {
+    let root = TempDir::new().unwrap();
+    let net = root.path().join("net");
+    std::fs::create_dir(&net).unwrap();
+    for table in ["tcp", "tcp6", "udp", "udp6", "unix"] {
+        std::fs::write(net.join(table), fixture(&format!("proc/net/{}", table))).unwrap();
+    }
+
+    let processes: [(u32, &str, &[&str]); 2] = [
+        (4242, "server", &["socket:[40001]", "socket:[40006]", "/dev/null"]),
+        (5151, "curl", &["socket:[40002]"]),
+    ];
+    for (pid, comm, fds) in processes {
+        let pid_dir = root.path().join(pid.to_string());
+        std::fs::create_dir_all(pid_dir.join("fd")).unwrap();
+        std::fs::write(pid_dir.join("comm"), format!("{}\n", comm)).unwrap();
+        for (fd, target) in fds.iter().enumerate() {
+            std::os::unix::fs::symlink(target, pid_dir.join("fd").join((fd + 3).to_string())).unwrap();
+        }
+    }
+
+    root
+}
+
+#[test]
+fn test_parse_hex_endpoints() {
+    assert_eq!(parse_hex_endpoint("0100007F:1F90"), Some(("127.0.0.1".to_string(), 8080)));
+    assert_eq!(parse_hex_endpoint("22D8B85D:01BB"), Some(("93.184.216.34".to_string(), 443)));
+    assert_eq!(
+        parse_hex_endpoint("00000000000000000000000001000000:0016"),
+        Some(("::1".to_string(), 22))
+    );
+    assert_eq!(
+        parse_hex_endpoint("0000000000000000FFFF00000100007F:1F90"),
+        Some(("127.0.0.1".to_string(), 8080))
+    );
+    assert_eq!(parse_hex_endpoint("garbage"), None);
+}
+
+#[test]
+fn test_parse_tcp_table() {
+    let sockets = parse_inet_table(&fixture("proc/net/tcp"), Protocol::TCP);
+
+    assert_eq!(sockets.len(), 3);
+    assert_eq!(sockets[0].state, ConnectionState::Listen);
+    assert_eq!(sockets[0].inode, 40001);
+    assert_eq!(sockets[1].local_address, "10.0.2.15");
+    assert_eq!(sockets[1].local_port, 54321);
+    assert_eq!(sockets[1].remote_port, 443);
+    assert_eq!(sockets[1].state, ConnectionState::Established);
+    assert_eq!(sockets[2].state, ConnectionState::TimeWait);
+}
+
+#[test]
+fn test_parse_unix_table() {
+    let sockets = parse_unix_table(&fixture("proc/net/unix"));
+
+    assert_eq!(sockets.len(), 3);
+    assert_eq!(sockets[0].local_address, "/run/app.sock");
+    assert_eq!(sockets[0].state, ConnectionState::Listen);
+    assert_eq!(sockets[1].local_address, "");
+    assert_eq!(sockets[1].state, ConnectionState::Established);
+    assert_eq!(sockets[2].local_address, "@abstract");
+    assert!(sockets.iter().all(|s| s.protocol == Protocol::Unix));
+}
+
+#[test]
+fn test_parse_socket_link() {
+    assert_eq!(parse_socket_link("socket:[40001]"), Some(40001));
+    assert_eq!(parse_socket_link("pipe:[40001]"), None);
+    assert_eq!(parse_socket_link("/dev/null"), None);
+}
+
+#[test]
+fn test_procfs_source_maps_sockets_to_processes() {
+    let root = fake_proc_root();
+    let source = ProcfsConnectionSource::with_root(root.path());
+    let connections = source.collect();
+
+    // 3 tcp + 2 tcp6 + 1 udp + 0 udp6 + 3 unix
+    assert_eq!(connections.len(), 9);
+
+    let listener = connections.iter()
+        .find(|c| c.protocol == Protocol::TCP && c.state == ConnectionState::Listen)
+        .unwrap();
+    assert_eq!(listener.pid, Some(4242));
+    assert_eq!(listener.process_name, "server");
+    assert_eq!(listener.local_address, "127.0.0.1");
+    assert_eq!(listener.local_port, 8080);
+
+    let outbound = connections.iter()
+        .find(|c| c.remote_port == 443)
+        .unwrap();
+    assert_eq!(outbound.pid, Some(5151));
+    assert_eq!(outbound.process_name, "curl");
+    assert_eq!(outbound.remote_address, "93.184.216.34");
+
+    let unix_socket = connections.iter()
+        .find(|c| c.local_address == "/run/app.sock")
+        .unwrap();
+    assert_eq!(unix_socket.pid, Some(4242));
+
+    let dns = connections.iter()
+        .find(|c| c.protocol == Protocol::UDP)
+        .unwrap();
+    assert_eq!(dns.pid, None);
+    assert_eq!(dns.local_address, "127.0.0.53");
+    assert_eq!(dns.local_port, 53);
+}
+
+#[test]
+fn test_netstat_source_parses_fixture_output() {
+    let connections = NetstatConnectionSource::parse(&fixture("netstat_anv.txt"), &fixture("lsof_i.txt"));
+
+    assert_eq!(connections.len(), 3);
+
+    let established = connections.iter()
+        .find(|c| c.state == ConnectionState::Established && c.protocol == Protocol::TCP)
+        .unwrap();
+    assert_eq!(established.local_address, "192.168.1.2");
+    assert_eq!(established.remote_port, 443);
+    assert_eq!(established.pid, Some(12345));
+    assert_eq!(established.process_name, "firefox");
+
+    let listener = connections.iter()
+        .find(|c| c.state == ConnectionState::Listen)
+        .unwrap();
+    assert_eq!(listener.local_port, 8080);
+    assert_eq!(listener.pid, Some(4242));
+
+    let udp = connections.iter()
+        .find(|c| c.protocol == Protocol::UDP)
+        .unwrap();
+    assert_eq!(udp.local_port, 5353);
+    assert_eq!(udp.pid, None);
+}
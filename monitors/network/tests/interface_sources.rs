@@ -0,0 +1,131 @@
+// Interface source tests against a fake /proc and /sys layout
+#![cfg(target_os = "linux")]
+
+use reaper_network_monitor::interface_stats::SysfsInterfaceSource;
+use reaper_network_monitor::{InterfaceKind, InterfaceStats, InterfaceStatsSource};
+use std::path::Path;
+use tempfile::TempDir;
+
+const FIXTURES: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures");
+
+struct FakeInterface<'a> {
+    name: &'a str,
+    device: &'a str,
+    arp_type: u32,
+    operstate: &'a str,
+    flags: &'a str,
+    speed: &'a str,
+    mtu: u32,
+    address: &'a str,
+}
+
+/// Lay out `sys/devices/<device>/net/<name>` with a `sys/class/net/<name>`
+/// symlink, as the kernel does
+fn add_interface(sys: &Path, iface: &FakeInterface) {
+    let dir = sys.join("devices").join(iface.device).join("net").join(iface.name);
+    std::fs::create_dir_all(dir.join("statistics")).unwrap();
+    std::fs::write(dir.join("type"), format!("{}\n", iface.arp_type)).unwrap();
+    std::fs::write(dir.join("operstate"), format!("{}\n", iface.operstate)).unwrap();
+    std::fs::write(dir.join("flags"), format!("{}\n", iface.flags)).unwrap();
+    std::fs::write(dir.join("speed"), format!("{}\n", iface.speed)).unwrap();
+    std::fs::write(dir.join("mtu"), format!("{}\n", iface.mtu)).unwrap();
+    std::fs::write(dir.join("address"), format!("{}\n", iface.address)).unwrap();
+
+    let target = Path::new("../../devices").join(iface.device).join("net").join(iface.name);
+    std::os::unix::fs::symlink(target, sys.join("class/net").join(iface.name)).unwrap();
+}
+
+fn fake_roots() -> (TempDir, TempDir) {
+    let proc_root = TempDir::new().unwrap();
+    std::fs::create_dir(proc_root.path().join("net")).unwrap();
+    std::fs::copy(Path::new(FIXTURES).join("proc/net/dev"), proc_root.path().join("net/dev")).unwrap();
+
+    let sys_root = TempDir::new().unwrap();
+    let sys = sys_root.path();
+    std::fs::create_dir_all(sys.join("class/net")).unwrap();
+
+    let interfaces = [
+        FakeInterface { name: "lo", device: "virtual", arp_type: 772, operstate: "unknown",
+            flags: "0x9", speed: "", mtu: 65536, address: "00:00:00:00:00:00" },
+        FakeInterface { name: "eth0", device: "pci0000:00/0000:00:1f.6", arp_type: 1, operstate: "up",
+            flags: "0x1003", speed: "1000", mtu: 1500, address: "3c:7c:3f:aa:bb:cc" },
+        FakeInterface { name: "wlan0", device: "pci0000:00/0000:00:14.3", arp_type: 1, operstate: "dormant",
+            flags: "0x1003", speed: "-1", mtu: 1500, address: "f4:26:79:11:22:33" },
+        FakeInterface { name: "veth1", device: "virtual", arp_type: 1, operstate: "up",
+            flags: "0x1003", speed: "10000", mtu: 1500, address: "ce:5a:1b:00:00:01" },
+        // Not in the proc fixture: counters must come from statistics/*
+        FakeInterface { name: "tun0", device: "virtual", arp_type: 65534, operstate: "unknown",
+            flags: "0x1091", speed: "10", mtu: 1420, address: "" },
+    ];
+    for iface in &interfaces {
+        add_interface(sys, iface);
+    }
+
+    std::fs::create_dir(sys.join("devices/pci0000:00/0000:00:14.3/net/wlan0/wireless")).unwrap();
+    let tun_stats = sys.join("devices/virtual/net/tun0/statistics");
+    std::fs::write(tun_stats.join("rx_bytes"), "4096\n").unwrap();
+    std::fs::write(tun_stats.join("tx_packets"), "17\n").unwrap();
+
+    (proc_root, sys_root)
+}
+
+fn find<'a>(interfaces: &'a [InterfaceStats], name: &str) -> &'a InterfaceStats {
+    interfaces.iter().find(|i| i.name == name).unwrap()
+}
+
+#[test]
+fn test_sysfs_interface_source() {
+    let (proc_root, sys_root) = fake_roots();
+    let interfaces = SysfsInterfaceSource::with_roots(proc_root.path(), sys_root.path()).collect();
+    assert_eq!(interfaces.len(), 5);
+
+    let lo = find(&interfaces, "lo");
+    assert_eq!(lo.kind, InterfaceKind::Loopback);
+    assert!(lo.is_active);
+    assert_eq!(lo.mac_address, None);
+    assert_eq!(lo.mtu, 65536);
+
+    let eth0 = find(&interfaces, "eth0");
+    assert_eq!(eth0.kind, InterfaceKind::Wired);
+    assert!(eth0.is_active);
+    assert_eq!(eth0.link_speed_mbps, Some(1000));
+    assert_eq!(eth0.mac_address.as_deref(), Some("3c:7c:3f:aa:bb:cc"));
+    assert_eq!(eth0.bytes_received, 918273645);
+    assert_eq!(eth0.packets_received, 812345);
+    assert_eq!(eth0.errors_in, 12);
+    assert_eq!(eth0.drops_in, 40);
+    assert_eq!(eth0.bytes_sent, 123456789);
+    assert_eq!(eth0.errors_out, 3);
+    assert_eq!(eth0.drops_out, 7);
+
+    let wlan0 = find(&interfaces, "wlan0");
+    assert_eq!(wlan0.kind, InterfaceKind::Wireless);
+    assert!(!wlan0.is_active);
+    assert_eq!(wlan0.link_speed_mbps, None);
+
+    assert_eq!(find(&interfaces, "veth1").kind, InterfaceKind::Virtual);
+
+    let tun0 = find(&interfaces, "tun0");
+    assert_eq!(tun0.kind, InterfaceKind::Virtual);
+    assert!(tun0.is_active);
+    assert_eq!(tun0.mac_address, None);
+    assert_eq!(tun0.bytes_received, 4096);
+    assert_eq!(tun0.packets_sent, 17);
+}
+
+#[test]
+fn test_utilization_percent() {
+    let (proc_root, sys_root) = fake_roots();
+    let interfaces = SysfsInterfaceSource::with_roots(proc_root.path(), sys_root.path()).collect();
+
+    // 25 MB/s down on a 1 Gbit/s link
+    let eth0 = find(&interfaces, "eth0");
+    assert_eq!(eth0.utilization_percent(1_000_000, 25_000_000), Some(20.0));
+    assert_eq!(find(&interfaces, "wlan0").utilization_percent(1_000_000, 0), None);
+}
+
+#[test]
+fn test_live_interfaces() {
+    let interfaces = SysfsInterfaceSource::new().collect();
+    assert!(interfaces.iter().any(|i| i.kind == InterfaceKind::Loopback));
+}